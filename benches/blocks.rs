@@ -0,0 +1,85 @@
+// 匹配很少的输入上按块搜索与逐行搜索的对比，以及 is_match 与 search 找到第一个结果的对比：
+//     cargo bench --bench blocks
+// 给出 last_line 时搜索器只能逐行调用正则；取一个比输入行数大的值，结果与按块搜索相同
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use mrustgrep::search::{Options, Searcher};
+
+const LINES: usize = 200_000;
+
+// 约 12 MiB 的日志，每一万行只有一行匹配
+fn corpus() -> Vec<u8> {
+    (0..LINES)
+        .map(|i| match i % 10_000 {
+            0 => format!("{i:08} ERROR disk quota exceeded for user {}\n", i / 10_000),
+            _ => format!(
+                "{i:08} INFO request served in {} ms by worker {}\n",
+                i % 97,
+                i % 13
+            ),
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn low_match_density(c: &mut Criterion) {
+    let data = corpus();
+    let patterns = [("literal", "quota exceeded"), ("regex", r"ERROR \w+ quota")];
+    let modes = [
+        ("blocks", Options::default()),
+        (
+            "per line",
+            Options {
+                last_line: Some(LINES + 1),
+                ..Options::default()
+            },
+        ),
+    ];
+
+    let mut group = c.benchmark_group("low match density");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for (pattern_name, pattern) in patterns {
+        // 两种方式找到的行必须相同，否则对比没有意义
+        let counts: Vec<usize> = modes
+            .iter()
+            .map(|(_, opts)| {
+                let searcher = Searcher::new(pattern, opts.clone()).unwrap();
+                searcher.count(data.as_slice()).unwrap()
+            })
+            .collect();
+        assert_eq!(counts, [LINES / 10_000; 2]);
+        for (mode, opts) in &modes {
+            let searcher = Searcher::new(pattern, opts.clone()).unwrap();
+            group.bench_function(format!("search {pattern_name} {mode}"), |b| {
+                b.iter(|| searcher.search(data.as_slice()).count())
+            });
+            group.bench_function(format!("count {pattern_name} {mode}"), |b| {
+                b.iter(|| searcher.count(data.as_slice()).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+// -q 的快速路径：唯一一行匹配在输入的最后，is_match 要扫过整个输入，但不为任何一行构造结果
+fn match_at_the_end(c: &mut Criterion) {
+    let data = corpus();
+    let pattern = format!("for user {}$", LINES / 10_000 - 1);
+    let searcher = Searcher::new(&pattern, Options::default()).unwrap();
+    assert!(searcher.is_match(data.as_slice()).unwrap());
+    assert_eq!(searcher.count(data.as_slice()).unwrap(), 1);
+
+    let mut group = c.benchmark_group("match at the end");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("is_match", |b| {
+        b.iter(|| searcher.is_match(data.as_slice()).unwrap())
+    });
+    group.bench_function("search first result", |b| {
+        b.iter(|| searcher.search(data.as_slice()).next().is_some())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, low_match_density, match_at_the_end);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+// 大量固定字符串模式（-F -f 一个上万行的名单）的编译和搜索：
+//     cargo bench --bench literals
+// 全部是字面量时匹配器使用 Aho-Corasick；-i 时名单中含 k、s 的词会展开成多种写法，自动机更大
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use mrustgrep::search::{Options, Searcher};
+
+// 确定性的伪随机单词，保证每次运行的数据相同
+fn words(count: usize, seed: u64) -> Vec<String> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            let mut word = String::new();
+            let len = 6 + (state % 6) as usize;
+            for _ in 0..len {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                word.push((b'a' + ((state >> 33) % 26) as u8) as char);
+            }
+            word
+        })
+        .collect()
+}
+
+// 约 4 MiB 的语料，每一百行有一行含名单中的词
+fn corpus(dictionary: &[String]) -> Vec<u8> {
+    let filler = words(12, 7).join(" ");
+    let mut text = String::new();
+    for i in 0..50_000 {
+        match i % 100 {
+            0 => text.push_str(&format!(
+                "{i} {} {}\n",
+                filler,
+                dictionary[i % dictionary.len()]
+            )),
+            _ => text.push_str(&format!("{i} {}\n", filler)),
+        }
+    }
+    text.into_bytes()
+}
+
+fn many_literals(c: &mut Criterion) {
+    let dictionary = words(10_000, 42);
+    let patterns: Vec<&str> = dictionary.iter().map(String::as_str).collect();
+    let data = corpus(&dictionary);
+    let variants = [
+        (
+            "aho-corasick -F",
+            Options {
+                fixed_strings: true,
+                ..Options::default()
+            },
+        ),
+        (
+            "aho-corasick -i",
+            Options {
+                case_ignore: true,
+                ..Options::default()
+            },
+        ),
+        (
+            "aho-corasick -F -i",
+            Options {
+                fixed_strings: true,
+                case_ignore: true,
+                ..Options::default()
+            },
+        ),
+    ];
+
+    let mut group = c.benchmark_group("compile 10k literals");
+    group.sample_size(10);
+    for (name, opts) in &variants {
+        group.bench_function(*name, |b| {
+            b.iter(|| Searcher::with_patterns(&patterns, opts.clone()).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("search 10k literals");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for (name, opts) in &variants {
+        let searcher = Searcher::with_patterns(&patterns, opts.clone()).unwrap();
+        group.bench_function(*name, |b| {
+            b.iter(|| searcher.count(data.as_slice()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, many_literals);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+// 用 tokio 异步读取标准输入并逐行搜索，例如：
+//     printf 'foo\nbar\n' | cargo run --example async_stdin --features async -- foo
+use futures_util::StreamExt;
+use mrustgrep::search::{Options, Searcher};
+use tokio::io::BufReader;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let pattern = std::env::args().nth(1).unwrap_or_default();
+    let searcher = Searcher::new(&pattern, Options::default())?;
+    let mut results = std::pin::pin!(searcher.search_async(BufReader::new(tokio::io::stdin())));
+    while let Some(result) = results.next().await {
+        let result = result?;
+        println!("{}:{}", result.line_number, result.line);
+    }
+    Ok(())
+}
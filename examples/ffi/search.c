@@ -0,0 +1,49 @@
+/* 通过 C 接口搜索一段文本的小程序
+ *
+ *     cargo build --release --features ffi
+ *     cc examples/ffi/search.c -Iinclude -Ltarget/release -lmrustgrep -o target/search
+ *     LD_LIBRARY_PATH=target/release ./target/search
+ */
+#include <stdio.h>
+#include <string.h>
+
+#include "mrustgrep.h"
+
+static int print_line(void *userdata, size_t line_number, const uint8_t *line, size_t line_len,
+                      const MrgMatch *matches, size_t match_count) {
+    int *count = userdata;
+    *count += 1;
+    printf("%zu:%.*s (%zu matches, first at %zu)\n", line_number, (int)line_len,
+           (const char *)line, match_count, match_count > 0 ? matches[0].start : 0);
+    return 0;
+}
+
+int main(void) {
+    const char *text = "error: disk full\nok\nError: retry\nwarning\n";
+
+    MrgOptions options = {0};
+    options.case_ignore = true;
+    MrgSearcher *searcher = mrg_searcher_new("error", &options);
+    if (searcher == NULL) {
+        fprintf(stderr, "mrg_searcher_new: %s\n", mrg_last_error());
+        return 1;
+    }
+    int count = 0;
+    if (mrg_searcher_search_buffer(searcher, (const uint8_t *)text, strlen(text), print_line,
+                                   &count) != 0) {
+        fprintf(stderr, "mrg_searcher_search_buffer: %s\n", mrg_last_error());
+        mrg_searcher_free(searcher);
+        return 1;
+    }
+    mrg_searcher_free(searcher);
+
+    /* 非法的模式返回 NULL，并给出原因 */
+    MrgSearcher *bad = mrg_searcher_new("(", NULL);
+    if (bad != NULL || mrg_last_error() == NULL) {
+        fprintf(stderr, "expected an error for an invalid pattern\n");
+        return 1;
+    }
+    printf("invalid pattern: %s\n", mrg_last_error());
+
+    return count == 2 ? 0 : 1;
+}
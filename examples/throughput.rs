@@ -0,0 +1,30 @@
+// 在内存中的文本上搜索，用 SearchStats 打印读过的数据量和吞吐量，例如：
+//     cargo run --release --example throughput -- 'error \d+'
+use mrustgrep::search::{Options, Searcher};
+
+fn main() -> anyhow::Result<()> {
+    let pattern = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "error".to_string());
+    let searcher = Searcher::new(&pattern, Options::default())?;
+    let text: String = (0..200_000)
+        .map(|i| match i % 100 {
+            0 => format!("{i}: error {} while reading\n", i / 100),
+            _ => format!("{i}: everything is fine\n"),
+        })
+        .collect();
+
+    let mut results = searcher.search(text.as_bytes()).timed();
+    for result in results.by_ref() {
+        result?;
+    }
+    let stats = results.stats();
+    println!(
+        "{} lines, {} bytes read; {} lines matched, {} matches",
+        stats.lines_read, stats.bytes_read, stats.lines_matched, stats.matches
+    );
+    if let (Some(elapsed), Some(speed)) = (stats.elapsed, stats.bytes_per_second()) {
+        println!("{:?}, {:.1} MiB/s", elapsed, speed / (1024.0 * 1024.0));
+    }
+    Ok(())
+}
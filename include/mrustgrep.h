@@ -0,0 +1,63 @@
+/* 由 cbindgen 根据 src/ffi.rs 生成，不要手工修改
+ *
+ * 所有权规则：
+ * - mrg_searcher_new 返回的搜索器归调用方所有，用完交给 mrg_searcher_free 释放；
+ * - pattern、options、data 只在调用期间被读取；
+ * - 回调收到的 line 和 matches 只在这次回调期间有效，line 不以 \0 结尾；
+ * - mrg_last_error 返回的消息归库所有，在同一线程下一次调用之前有效。
+ * 回调返回 0 继续搜索，返回其他值停止；出错的函数返回 NULL 或 -1。
+ */
+
+#ifndef MRUSTGREP_H
+#define MRUSTGREP_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct MrgSearcher MrgSearcher;
+
+typedef struct MrgOptions {
+  bool case_ignore;
+  bool fixed_strings;
+  bool invert_match;
+  bool anchored;
+  bool overlapping;
+} MrgOptions;
+
+typedef struct MrgMatch {
+  size_t start;
+  size_t end;
+  size_t pattern;
+} MrgMatch;
+
+typedef int (*MrgCallback)(void *userdata,
+                           size_t line_number,
+                           const uint8_t *line,
+                           size_t line_len,
+                           const struct MrgMatch *matches,
+                           size_t match_count);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct MrgSearcher *mrg_searcher_new(const char *pattern, const struct MrgOptions *options);
+
+int mrg_searcher_search_buffer(const struct MrgSearcher *searcher,
+                               const uint8_t *data,
+                               size_t len,
+                               MrgCallback callback,
+                               void *userdata);
+
+void mrg_searcher_free(struct MrgSearcher *searcher);
+
+const char *mrg_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MRUSTGREP_H */
@@ -0,0 +1,108 @@
+// 二进制输入：判断输入是不是二进制，以及 --binary-offsets 的十六进制输出
+//
+// 与 grep 一样，输入开头（第一次读到的缓冲区）里有 NUL 字节就当作二进制。
+// 每个匹配输出一条记录：匹配起点在输入中的字节位置，以及匹配前后各 window 个字节的十六进制和 ASCII，
+// 不可打印的字节在 ASCII 部分显示为 '.'，输出中不会出现原始的控制字符。
+// 没有颜色时匹配的字节用 [ ] 括起来，有颜色时按匹配的颜色高亮。
+use std::io::{self, Write};
+
+use crate::color::{Colors, Style};
+use crate::matcher::Match;
+use crate::printer::write_label;
+
+// 很长的匹配只显示开头这么多字节，后面用 .. 表示省略
+const MAX_MATCH_BYTES: usize = 64;
+
+// 输入开头的这些字节是否说明它是二进制
+pub fn looks_binary(prefix: &[u8]) -> bool {
+    first_nul(prefix).is_some()
+}
+
+// 输入开头第一个 NUL 字节的位置
+pub fn first_nul(prefix: &[u8]) -> Option<usize> {
+    memchr::memchr(0, prefix)
+}
+
+// 输出 data 中的一个匹配，label 不为 None 时以它（文件名）开头
+pub fn write_dump<W: Write + ?Sized>(
+    writer: &mut W,
+    label: Option<&str>,
+    data: &[u8],
+    m: &Match,
+    window: usize,
+    colors: Option<&Colors>,
+) -> io::Result<()> {
+    if let Some(label) = label {
+        write_label(writer, label, ":", colors)?;
+    }
+    match colors {
+        Some(colors) => write!(
+            writer,
+            "{}{}{}:",
+            colors.line.start(),
+            m.start,
+            colors.line.end()
+        )?,
+        None => write!(writer, "{}:", m.start)?,
+    }
+    let before = &data[m.start.saturating_sub(window)..m.start];
+    let shown_end = m.end.min(m.start + MAX_MATCH_BYTES);
+    let matched = &data[m.start..shown_end];
+    let truncated = shown_end < m.end;
+    let after = &data[m.end..(m.end + window).min(data.len())];
+    let style = colors.map(|colors| colors.pattern(m.pattern));
+
+    // 十六进制部分
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut parts = Vec::new();
+    if !before.is_empty() {
+        parts.push(hex(before));
+    }
+    let mut matched_hex = hex(matched);
+    if truncated {
+        matched_hex.push_str(" ..");
+    }
+    parts.push(highlight(&matched_hex, style));
+    if !after.is_empty() {
+        parts.push(hex(after));
+    }
+    write!(writer, "{}  |", parts.join(" "))?;
+
+    // ASCII 部分
+    let mut matched_ascii = ascii(matched);
+    if truncated {
+        matched_ascii.push_str("..");
+    }
+    writeln!(
+        writer,
+        "{}{}{}|",
+        ascii(before),
+        highlight(&matched_ascii, style),
+        ascii(after)
+    )
+}
+
+// 可打印的 ASCII 原样输出，其余字节显示为 '.'
+fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x20..=0x7e => b as char,
+            _ => '.',
+        })
+        .collect()
+}
+
+// 匹配部分：有颜色时高亮，否则用 [ ] 括起来
+fn highlight(text: &str, style: Option<Style>) -> String {
+    match style {
+        Some(style) => format!("{}{}{}", style.start(), text, style.end()),
+        None => format!("[{}]", text),
+    }
+}
@@ -0,0 +1,1167 @@
+use std::{
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+};
+
+use clap::{
+    Arg, ArgAction, ArgGroup, Command, ValueHint,
+    builder::{PossibleValue, StringValueParser, TypedValueParser},
+    error::{ContextKind, ContextValue},
+    value_parser,
+}; // 命令行参数解析库
+
+use mrustgrep::color::Colors;
+use mrustgrep::printer::{ColumnUnit, Format, LineNumberWidth};
+use mrustgrep::search::WordBoundary;
+use mrustgrep::terminal::{ColorChoice, InteractiveStdin};
+use mrustgrep::types;
+use mrustgrep::walk::{DeviceAction, DirectoryAction, SortBy};
+
+// 可以通过环境变量提供默认值的参数，命令行中显式给出的值总是优先
+const ENV_HELP: &str = "\
+Environment variables:
+  MRUSTGREP_CONFIG       Path of the configuration file
+  MRUSTGREP_COLOR        Default for --color
+  MRUSTGREP_COLORS       Default for --colors (specs separated by ';')
+  MRUSTGREP_IGNORE       Default for --global-ignore-file
+  MRUSTGREP_MAX_COLUMNS  Default for --max-columns
+  MRUSTGREP_THREADS      Default for --threads
+  NO_COLOR               Disables --color=auto when set to a non-empty value";
+
+// 以 - 开头的模式可能被当成参数，"-foo" 这种正好能拆成短参数的写法尤其如此
+const DASH_HELP: &str = "\
+Patterns starting with '-':
+  Put the pattern after '--' (mrustgrep -- -v FILE) or pass it with -e (mrustgrep -e -v FILE).";
+
+// 构建命令行参数解析器
+// 同一个参数出现多次时以最后一次为准，这样命令行可以覆盖配置文件里的默认值
+pub fn build() -> Command {
+    Command::new("mrustgrep")
+        .version("0.1.0")
+        .author("Your Name")
+        .about("A simple Rust implementation of grep")
+        .args_override_self(true)
+        .after_help(format!(
+            "{}\n\n{}\n\nExit status is 0 if any line matched, 1 if none matched, and 2 if an error occurred.",
+            DASH_HELP, ENV_HELP
+        ))
+        .arg(
+            Arg::new("pattern")
+                .required_unless_present_any(["regexp", "file", "between", "generate-completions"])
+                .index(1)
+                .allow_hyphen_values(true)
+                .help("The pattern to search for (a file path when -e, -f or --between is given)"), // 需要查找的模式
+        )
+        .arg(
+            Arg::new("regexp")
+                .short('e')
+                .long("regexp")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .help("A pattern to search for (repeatable); any of them may match"), // 追加模式
+        )
+        .arg(
+            Arg::new("file")
+                .short('f')
+                .long("file")
+                .value_name("PATTERNFILE")
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath)
+                .help("Read patterns from PATTERNFILE, one per line (repeatable)"), // 从文件读取模式
+        )
+        .arg(
+            Arg::new("file-pattern-comments")
+                .long("file-pattern-comments")
+                .action(ArgAction::SetTrue)
+                .requires("file")
+                .help("In -f files, skip blank lines and lines starting with # (after optional whitespace), and join a line ending in an unescaped \\ with the next one"), // 模式文件中的注释
+        )
+        .arg(
+            Arg::new("not")
+                .long("not")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .help("Drop lines matching PATTERN even if another pattern matched (repeatable; uses the same -i/-F; needs at least one normal pattern)"), // 排除模式
+        )
+        .arg(
+            Arg::new("paths")
+                .index(2)
+                .num_args(0..)
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::AnyPath)
+                .help("Files to search; reads stdin when none are given or for '-' (at any position, at most once)"), // 需要搜索的文件
+        )
+        .arg(
+            Arg::new("ignore-case")
+                .short('i')
+                .long("ignore-case")
+                .action(ArgAction::SetTrue)
+                .overrides_with_all(["smart-case", "case-sensitive"])
+                .help("Match case-insensitively"), // 忽略大小写
+        )
+        .arg(
+            Arg::new("smart-case")
+                .short('S')
+                .long("smart-case")
+                .action(ArgAction::SetTrue)
+                .overrides_with_all(["ignore-case", "case-sensitive"])
+                .help("Match case-insensitively unless a pattern contains an uppercase letter (escaped characters such as \\S do not count)"), // 智能大小写
+        )
+        .arg(
+            Arg::new("case-sensitive")
+                .long("case-sensitive")
+                .action(ArgAction::SetTrue)
+                .overrides_with_all(["ignore-case", "smart-case"])
+                .help("Match case-sensitively (the default); of -i, -S and --case-sensitive the last one given wins, and the command line beats the config file"), // 区分大小写
+        )
+        .arg(
+            Arg::new("fixed-strings")
+                .short('F')
+                .long("fixed-strings")
+                .action(ArgAction::SetTrue)
+                .help("Treat patterns as literal strings; with -i, full Unicode case folding applies (ß matches SS)"), // 固定字符串
+        )
+        .arg(
+            Arg::new("glob-pattern")
+                .short('G')
+                .long("glob-pattern")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["fixed-strings", "fuzzy"])
+                .help("Treat patterns as shell wildcards (* any text, ? one character, [...] a class, \\ escapes); they match anywhere in the line"), // 通配符模式
+        )
+        .arg(
+            Arg::new("posix-basic")
+                .long("posix-basic")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["fixed-strings", "glob-pattern", "fuzzy", "posix-extended"])
+                .help("Treat patterns as POSIX basic regular expressions, as in grep: \\( \\) group, \\{n,m\\} repeats, plain + ? | ( ) { } are literal"), // POSIX 基本正则
+        )
+        .arg(
+            Arg::new("posix-extended")
+                .long("posix-extended")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["fixed-strings", "glob-pattern", "fuzzy"])
+                .help("Treat patterns as POSIX extended regular expressions, as in grep -E; back-references are not supported"), // POSIX 扩展正则
+        )
+        .arg(
+            Arg::new("fuzzy")
+                .long("fuzzy")
+                .value_name("K")
+                .value_parser(value_parser!(usize))
+                .requires("fixed-strings")
+                .help("Approximate matching: allow up to K insertions, deletions or substitutions (requires -F; -o prints the matched text from the line)"), // 近似匹配
+        )
+        .arg(
+            Arg::new("invert-match")
+                .short('v')
+                .long("invert-match")
+                .action(ArgAction::SetTrue)
+                .help("Select lines that do not match; cannot be combined with -o"), // 反向匹配
+        )
+        .arg(
+            Arg::new("only-matching")
+                .short('o')
+                .long("only-matching")
+                .action(ArgAction::SetTrue)
+                // 反向选出的行里没有任何匹配可以输出，与其静默输出空结果不如直接拒绝
+                .conflicts_with("invert-match")
+                .help("Print only the matched parts of each line"), // 只输出匹配部分
+        )
+        .arg(
+            Arg::new("join-matches")
+                .long("join-matches")
+                .value_name("SEP")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(" ")
+                .value_parser(parse_escapes)
+                .requires("only-matching")
+                .help("With -o, print all matches of a line as one record joined by SEP (written --join-matches=SEP; default a space; \\t, \\n, \\0 and \\\\ are escapes), with the prefixes once per line"), // 一行的匹配合成一条记录
+        )
+        .arg(
+            Arg::new("snippet")
+                .long("snippet")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .conflicts_with_all(["only-matching", "invert-match"])
+                .help("For each match print only it and up to N characters before and after, with … where text was cut (one record per match like -o; matches whose windows overlap share one record; ignores --max-columns)"), // 匹配附近的片段
+        )
+        .arg(
+            Arg::new("replace")
+                .long("replace")
+                .value_name("TEXT")
+                .conflicts_with("invert-match")
+                .help("Print each match replaced by TEXT ($1 / ${1} for capture groups, ${name} for named groups, $0 for the whole match, $$ for '$'); files are not changed"), // 替换输出
+        )
+        .arg(
+            Arg::new("write")
+                .long("write")
+                .action(ArgAction::SetTrue)
+                .requires("replace")
+                .conflicts_with_all(["paragraph", "follow", "watch", "pre", "parallel-file", "max-count", "max-total-matches", "counting", "files-with-matches", "files-without-match", "quiet", "after-context", "before-context", "context"])
+                .help("Apply --replace to the files themselves (atomic rename, permissions kept) and print how many replacements were made per file; refuses stdin, binary or non-UTF-8 files and symbolic links"), // 写回文件
+        )
+        .arg(
+            Arg::new("write-symlinks")
+                .long("write-symlinks")
+                .action(ArgAction::SetTrue)
+                .requires("write")
+                .help("With --write, rewrite the target of symbolic links (the link itself is kept)"), // 改写符号链接的目标
+        )
+        .arg(
+            Arg::new("line-number")
+                .short('n')
+                .long("line-number")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-line-number")
+                .help("Prefix each output record with its line number (the default except with -o)"), // 输出行号
+        )
+        .arg(
+            Arg::new("no-line-number")
+                .short('N')
+                .long("no-line-number")
+                .action(ArgAction::SetTrue)
+                .overrides_with("line-number")
+                .help("Do not prefix output records with line numbers"), // 不输出行号
+        )
+        .arg(
+            Arg::new("label")
+                .long("label")
+                .value_name("LABEL")
+                .default_value("(standard input)")
+                .help("Name shown for standard input ('-' or no file arguments) in prefixed output"), // 标准输入的名字
+        )
+        .arg(
+            Arg::new("heading")
+                .long("heading")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-heading")
+                .help("Print the file name once above its results instead of before every line (a blank line separates files)"), // 文件名作为标题
+        )
+        .arg(
+            Arg::new("no-heading")
+                .long("no-heading")
+                .action(ArgAction::SetTrue)
+                .overrides_with("heading")
+                .help("Print the file name before every line (default)"), // 不输出标题
+        )
+        .arg(
+            Arg::new("pretty")
+                .short('p')
+                .long("pretty")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-pretty")
+                .help("Shorthand for --color=always --heading --line-number, e.g. for piping into 'less -R' (explicit flags override it)"), // 便于阅读的输出
+        )
+        .arg(
+            Arg::new("no-pretty")
+                .long("no-pretty")
+                .action(ArgAction::SetTrue)
+                .overrides_with("pretty")
+                .help("Cancel a --pretty given earlier (e.g. in the configuration file)"), // 取消 --pretty
+        )
+        .arg(
+            Arg::new("with-filename")
+                .short('H')
+                .long("with-filename")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-filename")
+                .help("Prefix each output record with the file name, even for a single input"), // 总是输出文件名
+        )
+        .arg(
+            Arg::new("no-filename")
+                .long("no-filename")
+                .action(ArgAction::SetTrue)
+                .overrides_with("with-filename")
+                .help("Never prefix output records with the file name"), // 不输出文件名
+        )
+        .arg(
+            Arg::new("byte-offset")
+                .short('b')
+                .long("byte-offset")
+                .action(ArgAction::SetTrue)
+                .help("Prefix each output record with its 0-based byte offset in the input (of the match itself with -o)"), // 输出字节位置
+        )
+        .arg(
+            Arg::new("column")
+                .long("column")
+                .action(ArgAction::SetTrue)
+                .help("Prefix each output record with the 1-based column of its first match (of the match itself with -o), after the line number; lines without a match (context, -v) get none; see --column-unit"), // 输出列号
+        )
+        .arg(
+            Arg::new("count")
+                .short('c')
+                .long("count")
+                .action(ArgAction::SetTrue)
+                .help("Only print the number of matching lines for each input"), // 只输出匹配行数
+        )
+        .arg(
+            Arg::new("count-matches")
+                .long("count-matches")
+                .action(ArgAction::SetTrue)
+                .help("Like -c, but count every match instead of matching lines"), // 统计匹配次数
+        )
+        .group(
+            ArgGroup::new("counting")
+                .args(["count", "count-matches"])
+                .multiple(true),
+        )
+        .arg(
+            Arg::new("files-with-matches")
+                .short('l')
+                .long("files-with-matches")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("counting")
+                .help("Only print the paths of inputs that contain a match (stops reading each input at its first match)"), // 只输出有匹配的文件
+        )
+        .arg(
+            Arg::new("files-without-match")
+                .short('L')
+                .long("files-without-match")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["counting", "files-with-matches", "sort-by-count"])
+                .help("Only print the paths of inputs that contain no match (stops reading each input at its first match; exit status is 0 if any path was printed)"), // 只输出没有匹配的文件
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .visible_alias("silent")
+                .action(ArgAction::SetTrue)
+                .help("Print nothing; stop at the first selected line and exit with 0 if there was one (even if an input failed), otherwise 1, or 2 on error; overrides -c, -l and -L"), // 只返回退出码
+        )
+        .arg(
+            Arg::new("include-zero")
+                .long("include-zero")
+                .action(ArgAction::SetTrue)
+                .requires("counting")
+                .help("With -c, also print inputs that have zero matching lines"), // -c 时输出计数为 0 的文件
+        )
+        .arg(
+            Arg::new("sort-by-count")
+                .long("sort-by-count")
+                .action(ArgAction::SetTrue)
+                .help("With -c or -l, print inputs sorted by match count, highest first (ties by path)"), // 按匹配数排序输出
+        )
+        .arg(
+            Arg::new("all-match")
+                .long("all-match")
+                .action(ArgAction::SetTrue)
+                .help("Require every pattern to match: on the same line, or anywhere in the file with -l/-L"), // 所有模式都要匹配
+        )
+        .arg(
+            Arg::new("word-regexp")
+                .short('w')
+                .long("word-regexp")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("fuzzy")
+                .help("Only match whole words: a match must not be preceded or followed by a word character"), // 整词匹配
+        )
+        .arg(
+            Arg::new("line-regexp")
+                .short('x')
+                .long("line-regexp")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("fuzzy")
+                .help("Only match whole lines: a pattern must match from the start to the end of the line (overrides -w)"), // 整行匹配
+        )
+        .arg(
+            Arg::new("word-boundary")
+                .long("word-boundary")
+                .value_name("KIND")
+                .value_parser(value_parser!(WordBoundary))
+                .default_value("unicode")
+                .help("What counts as a word character for -w: unicode (default; letters such as é and 日 too) or ascii ([0-9A-Za-z_] only); only the boundaries change, \\w and \\d in patterns keep their meaning"), // 单词边界的字符集
+        )
+        .arg(
+            Arg::new("max-matches-per-line")
+                .long("max-matches-per-line")
+                .value_name("N")
+                .value_parser(parse_positive)
+                .help("Report at most the first N matches of each line (-o, highlighting, --count-matches, --replace); whether the line matches is unchanged"), // 每行最多的匹配数
+        )
+        .arg(
+            Arg::new("anchored")
+                .long("anchored")
+                .action(ArgAction::SetTrue)
+                .help("Only accept matches that start at the beginning of the line (applies to every alternative)"), // 锚定行首
+        )
+        .arg(
+            Arg::new("stop-on-nonmatch")
+                .long("stop-on-nonmatch")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("invert-match")
+                .help("Stop reading an input at the first non-matching line after a match"), // 匹配段结束即停止
+        )
+        .arg(
+            Arg::new("paragraph")
+                .long("paragraph")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("stop-on-nonmatch")
+                .help("Print the whole blank-line-delimited paragraph around each selected line, separating paragraphs with '--'"), // 段落上下文
+        )
+        .arg(
+            Arg::new("after-context")
+                .short('A')
+                .long("after-context")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("paragraph")
+                .help("Print NUM lines after each selected line, separating groups that are not adjacent with '--'"), // 之后的上下文
+        )
+        .arg(
+            Arg::new("before-context")
+                .short('B')
+                .long("before-context")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("paragraph")
+                .help("Print NUM lines before each selected line, separating groups that are not adjacent with '--'"), // 之前的上下文
+        )
+        .arg(
+            Arg::new("context")
+                .short('C')
+                .long("context")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("paragraph")
+                .help("Print NUM lines before and after each selected line; -A and -B take precedence"), // 前后的上下文
+        )
+        .arg(
+            Arg::new("overlapping")
+                .long("overlapping")
+                .action(ArgAction::SetTrue)
+                .help("Report overlapping matches by searching again after every match start; slower on match-dense input"), // 报告重叠匹配
+        )
+        .arg(
+            Arg::new("max-count")
+                .short('m')
+                .long("max-count")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .help("Stop reading each input after NUM selected lines (trailing context after the last one is still printed; -c counts at most NUM)"), // 每个输入的匹配行数上限
+        )
+        .arg(
+            Arg::new("max-total-matches")
+                .long("max-total-matches")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .help("Stop the whole run, including the directory walk, after NUM matching lines across all inputs"), // 全局匹配行数上限
+        )
+        .arg(
+            Arg::new("count-per-pattern")
+                .long("count-per-pattern")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("invert-match")
+                .help("After searching, print to stderr how many lines and matches each pattern produced; with --json they also go into the final summary object"), // 按模式统计
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(value_parser!(Format))
+                .default_value("standard")
+                .conflicts_with_all(["counting", "files-with-matches", "files-without-match"])
+                .help("Output format for matching lines; csv prints a header and one row per match (path, line, column, match_text, line_text); sarif prints one SARIF 2.1.0 document at the end, a rule per pattern and a result per match; json prints one JSON object per match (path, line_number, line, submatches with the byte start/end of the match in line; bytes that are not valid UTF-8 are replaced with U+FFFD first, which can shift later offsets), then a final {\"summary\": ...} object"), // 输出格式
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["format", "counting", "files-with-matches", "files-without-match"])
+                .help("Print results as JSON Lines, one object per match and a final summary object (same as --format json)"), // JSON Lines 输出
+        )
+        .arg(
+            Arg::new("column-unit")
+                .long("column-unit")
+                .value_name("UNIT")
+                .value_parser(value_parser!(ColumnUnit))
+                .default_value("bytes")
+                .help("Unit of reported column numbers: bytes (default, as ripgrep) or chars (Unicode scalar values; a combining mark counts as its own column); applies to --column and --format csv; -b offsets are always bytes"), // 列号单位
+        )
+        .arg(
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .action(ArgAction::SetTrue)
+                .help("Search directories recursively (the current directory when no path is given); symlinks inside directories are not followed"), // 递归搜索
+        )
+        .arg(
+            Arg::new("directories")
+                .long("directories")
+                .value_name("ACTION")
+                .value_parser(value_parser!(DirectoryAction))
+                .default_value("read")
+                .help("What to do with directory arguments without -r: read (report an error), recurse or skip"), // 目录参数
+        )
+        .arg(
+            Arg::new("devices")
+                .long("devices")
+                .value_name("ACTION")
+                .value_parser(value_parser!(DeviceAction))
+                .default_value("skip")
+                .help("What to do with devices, FIFOs and sockets: read or skip"), // 特殊文件
+        )
+        .arg(
+            Arg::new("one-file-system")
+                .long("one-file-system")
+                .action(ArgAction::SetTrue)
+                .help("Do not descend into directories on a different file system than the starting directory"), // 不跨文件系统
+        )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("When recursing, only search files matching GLOB; a leading ! skips matching files and directories instead (repeatable, later globs among --include, --exclude and --iglob win; .gitignore syntax, case-sensitive; overrides ignore files)"), // 包含的文件
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("When recursing, skip files and directories matching GLOB; same as --include '!GLOB'"), // 排除的文件
+        )
+        .arg(
+            Arg::new("iglob")
+                .long("iglob")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Like --include, but ignoring case in the whole path (a leading ! skips, like --exclude)"), // 不区分大小写的通配符
+        )
+        .arg(
+            Arg::new("type")
+                .short('t')
+                .long("type")
+                .value_name("TYPE")
+                .value_parser(TypeName)
+                .hide_possible_values(true)
+                .action(ArgAction::Append)
+                .help(format!("When recursing, only search files of TYPE (repeatable, any of them); files included by --include or --iglob are searched regardless. Types: {}", types::TYPES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "))), // 文件类型
+        )
+        .arg(
+            Arg::new("type-not")
+                .short('T')
+                .long("type-not")
+                .value_name("TYPE")
+                .value_parser(TypeName)
+                .hide_possible_values(true)
+                .action(ArgAction::Append)
+                .help("When recursing, skip files of TYPE (repeatable); a file of both a --type and a --type-not type is skipped"), // 排除文件类型
+        )
+        .arg(
+            Arg::new("no-ignore")
+                .long("no-ignore")
+                .action(ArgAction::SetTrue)
+                .overrides_with("ignore")
+                .help("Do not skip files matched by .ignore, .gitignore, git's global excludes file or the global ignore file when recursing"), // 不使用忽略文件
+        )
+        .arg(
+            Arg::new("ignore")
+                .long("ignore")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-ignore")
+                .help("Use ignore files (the default; undoes an earlier --no-ignore or -u)"), // 使用忽略文件
+        )
+        .arg(
+            Arg::new("hidden")
+                .long("hidden")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-hidden")
+                .help("When recursing, also search hidden files and directories (names starting with '.'), which are skipped by default; paths on the command line are always searched"), // 搜索隐藏文件
+        )
+        .arg(
+            Arg::new("no-hidden")
+                .long("no-hidden")
+                .action(ArgAction::SetTrue)
+                .overrides_with("hidden")
+                .help("Skip hidden files and directories when recursing (the default; undoes an earlier --hidden or -uu)"), // 跳过隐藏文件
+        )
+        .arg(
+            Arg::new("unrestricted")
+                .short('u')
+                .long("unrestricted")
+                .action(ArgAction::Count)
+                .help("Reduce filtering: -u is --no-ignore, -uu is --no-ignore --hidden, -uuu is --no-ignore --hidden --text. Later explicit flags such as --ignore, --no-hidden or --no-text win"), // 减少过滤
+        )
+        .arg(
+            Arg::new("no-ignore-vcs")
+                .long("no-ignore-vcs")
+                .action(ArgAction::SetTrue)
+                .help("Do not read .gitignore files or git's global excludes file (core.excludesFile, default ~/.config/git/ignore); .ignore and the global ignore file still apply"), // 不使用 .gitignore
+        )
+        .arg(
+            Arg::new("global-ignore-file")
+                .long("global-ignore-file")
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath)
+                .env("MRUSTGREP_IGNORE")
+                .help("Ignore file applied to every recursive search, relative to each starting directory (lowest precedence, below .gitignore and .ignore)"), // 全局忽略文件
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("SORTBY")
+                .value_parser(value_parser!(SortBy))
+                .conflicts_with("sortr")
+                .help("Search files in ascending order of SORTBY (collects the file list before searching)"), // 排序
+        )
+        .arg(
+            Arg::new("sortr")
+                .long("sortr")
+                .value_name("SORTBY")
+                .value_parser(value_parser!(SortBy))
+                .help("Like --sort, in descending order (newest or largest first)"), // 倒序
+        )
+        .arg(
+            Arg::new("skip-bytes")
+                .long("skip-bytes")
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .help("Start searching after the first SIZE bytes of each input, at the next line start (skipped data is read but not searched; line numbers stay absolute)"), // 跳过字节
+        )
+        .arg(
+            Arg::new("skip-lines")
+                .long("skip-lines")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Start searching after the first N lines of each input (after --skip-bytes)"), // 跳过行
+        )
+        .arg(
+            Arg::new("line-range")
+                .long("line-range")
+                .value_name("RANGE")
+                .value_parser(parse_line_range)
+                .conflicts_with_all(["skip-bytes", "skip-lines"])
+                .help("Only search lines A through B of each input (A-B, A- or -B; line numbers still count from the start, reading stops after line B)"), // 行号区间
+        )
+        .arg(
+            Arg::new("between")
+                .long("between")
+                .num_args(2)
+                .value_names(["START", "END"])
+                .allow_hyphen_values(true)
+                .help("Only select lines in regions running from a line matching START through the next line matching END, both inclusive; START inside a region does not nest, a line matching both is a one-line region, an unterminated region runs to the end of input. Without -e/-f every line in a region is selected and -e patterns filter them; -c counts selected lines"), // 区间
+        )
+        .arg(
+            Arg::new("follow")
+                .long("follow")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["counting", "files-with-matches", "files-without-match", "quiet", "recursive", "stop-on-nonmatch"])
+                .help("Keep searching a single file as it grows, like tail -f | grep (implies --line-buffered; Ctrl-C to stop)"), // 跟踪文件增长
+        )
+        .arg(
+            Arg::new("progress")
+                .long("progress")
+                .action(ArgAction::SetTrue)
+                .help("When a search runs longer than a second, show files searched, matched lines and the current path on one updating stderr line (only when stderr is a terminal; erased before results and at exit; off with --debug, -q and --json)"), // 进度
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["follow", "quiet"])
+                .help("After the first search, watch the given files and directories and search changed files again, after a separator line (not for stdin or -q; with --output every round goes to the same file; Ctrl-C to stop)"), // 监视变化
+        )
+        .arg(
+            Arg::new("text")
+                .short('a')
+                .long("text")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-text")
+                .help("Search binary inputs (a NUL byte near the start) as text and print their lines. By default binary files found while recursing are skipped (see --debug), and binary files named on the command line and stdin print a single 'binary file matches' line; --binary-offsets dumps them instead"), // 二进制文件当作文本
+        )
+        .arg(
+            Arg::new("no-text")
+                .long("no-text")
+                .action(ArgAction::SetTrue)
+                .overrides_with("text")
+                .help("Skip binary inputs (the default; undoes an earlier --text or -uuu)"), // 跳过二进制文件
+        )
+        .arg(
+            Arg::new("binary-offsets")
+                .long("binary-offsets")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["invert-match", "fuzzy", "anchored", "write"])
+                .help("For binary inputs (a NUL byte near the start), search the raw bytes and print each match's byte offset with a hex and ASCII dump around it instead of lines (-c counts matches)"), // 二进制文件的匹配位置
+        )
+        .arg(
+            Arg::new("binary-window")
+                .long("binary-window")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .default_value("16")
+                .requires("binary-offsets")
+                .help("Bytes shown on each side of a match with --binary-offsets"), // 前后显示的字节数
+        )
+        .arg(
+            Arg::new("pre")
+                .long("pre")
+                .value_name("COMMAND")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::CommandName)
+                .conflicts_with("follow")
+                .help("Search the standard output of 'COMMAND FILE' instead of each file's contents (not for stdin)"), // 预处理命令
+        )
+        .arg(
+            Arg::new("pre-glob")
+                .long("pre-glob")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .requires("pre")
+                .help("Only run --pre for files whose name (or whole path, if GLOB contains '/') matches GLOB (repeatable; same globs as --include, a leading ! excludes)"), // 预处理的文件
+        )
+        .arg(
+            Arg::new("parallel-file")
+                .long("parallel-file")
+                .action(ArgAction::SetTrue)
+                .help("Split each regular file into line-aligned chunks and search them on all CPUs, printing results in order (results of a file are held in memory until it is done; stdin, -q, --follow, --paragraph, --stop-on-nonmatch and --skip-* search sequentially)"), // 单文件并行
+        )
+        .arg(
+            Arg::new("mmap")
+                .long("mmap")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["follow", "write", "parallel-file", "binary-offsets"])
+                .help("Memory-map regular files and run the regex over the mapped bytes, counting newlines only to number matching lines (faster on very large files; -v, -A/-B/-C, --paragraph, --stop-on-nonmatch, --between and -c/-l read normally; results are undefined if a file changes while searched)"), // 内存映射
+        )
+        .arg(
+            Arg::new("threads")
+                .short('j')
+                .long("threads")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .env("MRUSTGREP_THREADS")
+                .default_value("1")
+                .help("Search up to NUM files at once on worker threads (0 means one per CPU; 1 searches sequentially). Each file's results are buffered and printed whole, in the same order as a sequential search; stdin and --pre files are searched on the main thread. Cannot be given with --follow, --write, --parallel-file or --binary-offsets; a value from MRUSTGREP_THREADS is ignored with them"), // 多文件并行
+        )
+        .arg(
+            Arg::new("no-dedupe")
+                .long("no-dedupe")
+                .action(ArgAction::SetTrue)
+                .help("Search a file again when it is reached through another path (by default each file, including hard links and repeated arguments, is searched once)"), // 不去重
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .value_parser(value_parser!(ColorChoice))
+                .env("MRUSTGREP_COLOR")
+                .default_value("auto")
+                .help("When to highlight matches (auto honors NO_COLOR and TERM=dumb)"), // 颜色输出策略
+        )
+        .arg(
+            Arg::new("colors")
+                .long("colors")
+                .value_name("SPEC")
+                .action(ArgAction::Append)
+                .value_delimiter(';')
+                .env("MRUSTGREP_COLORS")
+                .value_parser(|spec: &str| {
+                    Colors::with_specs([spec])
+                        .map(|_| spec.to_string())
+                        .map_err(|e| e.to_string())
+                })
+                .help("Color spec such as 'match:fg:green', 'match2:fg:blue' (the second -e pattern) or 'path:style:bold' (repeatable); without matchN specs each pattern's matches rotate through the foreground colors starting from the match color"), // 自定义配色
+        )
+        .arg(
+            Arg::new("color-groups")
+                .long("color-groups")
+                .action(ArgAction::SetTrue)
+                .help("Highlight the capture groups inside each match in their own styles (see groupN in --colors)"), // 捕获组单独高亮
+        )
+        .arg(
+            Arg::new("max-columns")
+                .short('M')
+                .long("max-columns")
+                .value_name("NUM")
+                .value_parser(value_parser!(usize))
+                .env("MRUSTGREP_MAX_COLUMNS")
+                .help("Do not print lines longer than NUM bytes, only a note about them"), // 长行截断
+        )
+        .arg(
+            Arg::new("field-match-separator")
+                .long("field-match-separator")
+                .value_name("SEP")
+                .default_value(":")
+                .allow_hyphen_values(true)
+                .help("Separator between the path, line number and text of matching lines"), // 匹配行字段分隔符
+        )
+        .arg(
+            Arg::new("field-context-separator")
+                .long("field-context-separator")
+                .value_name("SEP")
+                .default_value("-")
+                .allow_hyphen_values(true)
+                .help("Separator between the path, line number and text of context lines"), // 上下文行字段分隔符
+        )
+        .arg(
+            Arg::new("null-data-out")
+                .long("null-data-out")
+                .action(ArgAction::SetTrue)
+                .help("End every output record (matched and context lines, -o matches, --heading and -- separator lines) with a NUL byte instead of a newline; -c/-l lines, --binary-offsets dumps and --format csv stay newline-delimited"), // 输出记录以 NUL 结尾
+        )
+        .arg(
+            Arg::new("absolute-path")
+                .long("absolute-path")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("relative")
+                .help("Print paths as absolute, canonical paths (display only)"), // 绝对路径
+        )
+        .arg(
+            Arg::new("relative")
+                .long("relative")
+                .action(ArgAction::SetTrue)
+                .help("Print absolute paths under the current directory relative to it (display only)"), // 相对路径
+        )
+        .arg(
+            Arg::new("path-separator")
+                .long("path-separator")
+                .value_name("CHAR")
+                .value_parser(parse_path_separator)
+                .help("Print paths with CHAR as the directory separator (display only; default is the platform separator)"), // 路径分隔符
+        )
+        .arg(
+            Arg::new("line-number-width")
+                .long("line-number-width")
+                .value_name("N|auto")
+                .value_parser(LineNumberWidth::parse)
+                .help("Right-align line numbers to N columns; auto pads to the largest line number printed for each file (buffers that file's output)"), // 行号对齐
+        )
+        .arg(
+            Arg::new("line-buffered")
+                .long("line-buffered")
+                .action(ArgAction::SetTrue)
+                .help("Flush output after every line, even when not writing to a terminal"), // 强制按行刷新
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath)
+                .help("Write results to PATH (created or truncated) instead of stdout; '-' means stdout"), // 结果输出文件
+        )
+        .arg(
+            Arg::new("interactive-stdin")
+                .long("interactive-stdin")
+                .value_name("ACTION")
+                .value_parser(value_parser!(InteractiveStdin))
+                .default_value("warn")
+                .help("What to do when no files are given and stdin is a terminal with --no-default-recurse (pass '-' to read it silently)"), // stdin 是终端时的处理方式
+        )
+        .arg(
+            Arg::new("no-default-recurse")
+                .long("no-default-recurse")
+                .action(ArgAction::SetTrue)
+                .help("Without path arguments, read stdin even when it is a terminal, instead of searching the current directory recursively"), // 终端上不默认递归
+        )
+        .arg(
+            Arg::new("buffer-size")
+                .long("buffer-size")
+                .value_name("SIZE")
+                .value_parser(parse_buffer_size)
+                .help("Capacity of the read buffer for files and standard input, e.g. 256K (suffixes K, M, G; lines longer than the buffer still work)"), // 读缓冲区大小
+        )
+        .arg(
+            Arg::new("max-line-length")
+                .long("max-line-length")
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .default_value("256M")
+                .help("Skip lines longer than SIZE bytes with a warning instead of holding them in memory; skipped lines never match, count for -c or make the exit status 0"), // 最长的行
+        )
+        .arg(
+            Arg::new("regex-size-limit")
+                .long("regex-size-limit")
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .help("Upper limit on the compiled size of the regex, e.g. 10M (suffixes K, M, G)"), // 正则体积上限
+        )
+        .arg(
+            Arg::new("dfa-size-limit")
+                .long("dfa-size-limit")
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .help("Upper limit on the regex engine's DFA cache, e.g. 2M (suffixes K, M, G)"), // DFA 缓存上限
+        )
+        .arg(
+            Arg::new("match-timeout")
+                .long("match-timeout")
+                .value_name("MS")
+                .value_parser(value_parser!(u64))
+                .help("Give up matching a line after MS milliseconds and treat it as not matching; no effect with the current regex and literal engines, which run in linear time"), // 单行匹配超时
+        )
+        .arg(
+            Arg::new("no-messages")
+                .short('s')
+                .long("no-messages")
+                .action(ArgAction::SetTrue)
+                .help("Suppress error messages about nonexistent or unreadable files (the exit status is still 2)"), // 不输出文件错误
+        )
+        .arg(
+            Arg::new("debug")
+                .long("debug")
+                .action(ArgAction::SetTrue)
+                .help("Print the final pattern, engine choices, settings sources and timings to stderr"), // 调试信息
+        )
+        .arg(
+            Arg::new("no-config")
+                .long("no-config")
+                .action(ArgAction::SetTrue)
+                .help("Do not read the configuration file (MRUSTGREP_CONFIG or $XDG_CONFIG_HOME/mrustgrep/config)"), // 跳过配置文件
+        )
+        // 生成 shell 补全脚本，打包时使用，不在帮助中列出
+        // --color、--sort 等取值固定的参数，补全脚本中带有全部可选值
+        .arg(
+            Arg::new("generate-completions")
+                .long("generate-completions")
+                .value_name("SHELL")
+                .value_parser(value_parser!(clap_complete::Shell))
+                .hide(true)
+                .help("Print a completion script for SHELL (bash, zsh, fish, powershell, elvish) and exit"), // 生成补全脚本
+        )
+}
+
+// 把短参数后面紧跟着的值拆成单独的参数：-m2 变成 -m 2，-c -j2 中的 -j2 变成 -j 2
+// 模式参数允许以 - 开头，clap 遇到一组短参数中有不是短参数的字符（-m2 中的 2）时会把整组当成模式，
+// 拆开之后 -m 照常解析；其余参数原样保留，作为其他参数的值的（-e -m2）以及 "--" 之后的不拆
+pub fn split_attached_values(args: Vec<OsString>) -> Vec<OsString> {
+    let mut command = build();
+    command.build();
+    // 参数还要跟着几个单独的值
+    let values = |arg: &Arg| {
+        arg.get_num_args()
+            .map_or(0, |range| range.min_values())
+            .max(usize::from(arg.get_action().takes_values()))
+    };
+    let mut split = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    split.extend(args.next());
+    let mut pending: usize = 0;
+    for arg in args.by_ref() {
+        let text = match arg.to_str() {
+            Some(text) if pending == 0 => text,
+            _ => {
+                pending = pending.saturating_sub(1);
+                split.push(arg);
+                continue;
+            }
+        };
+        if text == "--" {
+            split.push(arg);
+            break;
+        }
+        if let Some(long) = text.strip_prefix("--") {
+            if !long.contains('=') {
+                pending = command
+                    .get_arguments()
+                    .find(|candidate| {
+                        candidate.get_long() == Some(long)
+                            || candidate
+                                .get_visible_aliases()
+                                .is_some_and(|aliases| aliases.contains(&long))
+                    })
+                    .map_or(0, values);
+            }
+            split.push(arg);
+            continue;
+        }
+        let Some(shorts) = text.strip_prefix('-') else {
+            split.push(arg);
+            continue;
+        };
+        // 逐个字符找短参数，遇到要值的参数时后面的部分就是它的值
+        let mut attached = None;
+        for (at, c) in shorts.char_indices() {
+            let Some(option) = command
+                .get_arguments()
+                .find(|candidate| candidate.get_short() == Some(c))
+            else {
+                break;
+            };
+            if values(option) > 0 {
+                let end = 1 + at + c.len_utf8();
+                match &text[end..] {
+                    "" => pending = values(option),
+                    value => attached = Some((end, value.strip_prefix('=').unwrap_or(value))),
+                }
+                break;
+            }
+        }
+        match attached {
+            Some((end, value)) => {
+                split.push(OsString::from(&text[..end]));
+                split.push(OsString::from(value));
+            }
+            None => split.push(arg),
+        }
+    }
+    split.extend(args);
+    split
+}
+
+// 参数解析失败时，判断出错的值是否来自环境变量，是则返回变量名
+// 只有当环境变量的值正好是出错的值时才归咎于它，避免误判命令行中的同名参数
+pub fn blame_env(err: &clap::Error) -> Option<String> {
+    let invalid_arg = match err.get(ContextKind::InvalidArg)? {
+        ContextValue::String(arg) => arg.split([' ', '=']).next()?.to_string(),
+        _ => return None,
+    };
+    let invalid_value = match err.get(ContextKind::InvalidValue)? {
+        ContextValue::String(value) => value.clone(),
+        _ => return None,
+    };
+
+    let command = build();
+    let arg = command.get_arguments().find(|arg| {
+        arg.get_long()
+            .is_some_and(|long| format!("--{}", long) == invalid_arg)
+    })?;
+    let var = arg.get_env()?.to_str()?.to_string();
+    let value = std::env::var(&var).ok()?;
+    // 带分隔符的参数会被拆开解析，只要变量里包含出错的那一段即可
+    let matched = match arg.get_value_delimiter() {
+        Some(delimiter) => value.split(delimiter).any(|part| part == invalid_value),
+        None => value == invalid_value,
+    };
+    matched.then_some(var)
+}
+
+// --type/--type-not 的取值：可能的取值是内置的类型名，供补全脚本使用；
+// 这里不检查，未知的类型由 FileTypes::new 报错并列出可用的类型
+#[derive(Debug, Clone, Copy)]
+struct TypeName;
+
+impl TypedValueParser for TypeName {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<String, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            types::TYPES
+                .iter()
+                .map(|(name, _)| PossibleValue::new(*name)),
+        ))
+    }
+}
+
+// 解析 --path-separator：必须正好是一个字符
+pub fn parse_path_separator(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(separator), None) => Ok(separator),
+        _ => Err(format!(
+            "invalid path separator {:?} (expected a single character such as '/')",
+            value
+        )),
+    }
+}
+
+// 解析带 K/M/G 后缀（1024 进制）的字节数，例如 "512K"、"10M"
+pub fn parse_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let (digits, shift) = match value.char_indices().last() {
+        Some((idx, 'K' | 'k')) => (&value[..idx], 10),
+        Some((idx, 'M' | 'm')) => (&value[..idx], 20),
+        Some((idx, 'G' | 'g')) => (&value[..idx], 30),
+        _ => (value, 0),
+    };
+    let number: usize = digits.parse().map_err(|_| {
+        format!(
+            "invalid size {:?} (expected a number with an optional K, M or G suffix)",
+            value
+        )
+    })?;
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("size {:?} is too large", value))
+}
+
+// --line-range 的行号区间，两端都包含在内，行号从 1 开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub first: usize,
+    // None 表示到输入结尾
+    pub last: Option<usize>,
+}
+
+// 解析 "A-B"、"A-"（从 A 到结尾）和 "-B"（从开头到 B）
+pub fn parse_line_range(value: &str) -> Result<LineRange, String> {
+    let invalid = || format!("invalid line range {:?} (expected A-B, A- or -B)", value);
+    let (first, last) = value.trim().split_once('-').ok_or_else(invalid)?;
+    let number = |text: &str| match text {
+        "" => Ok(None),
+        _ => match text.parse::<usize>() {
+            Ok(0) => Err("line numbers start at 1".to_string()),
+            Ok(n) => Ok(Some(n)),
+            Err(_) => Err(invalid()),
+        },
+    };
+    let range = match (number(first)?, number(last)?) {
+        (None, None) => return Err(invalid()),
+        (first, last) => LineRange {
+            first: first.unwrap_or(1),
+            last,
+        },
+    };
+    match range.last {
+        Some(last) if last < range.first => {
+            Err(format!("line range {:?} ends before it starts", value))
+        }
+        _ => Ok(range),
+    }
+}
+
+// 解析 --buffer-size：与 parse_size 相同的写法，但不接受 0
+pub fn parse_buffer_size(value: &str) -> Result<usize, String> {
+    match parse_size(value)? {
+        0 => Err("buffer size must be greater than zero".to_string()),
+        size => Ok(size),
+    }
+}
+
+// 解析不接受 0 的个数
+pub fn parse_positive(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be greater than zero".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// 解析分隔符中的 \t、\n、\0 和 \\，其余反斜杠原样保留
+pub fn parse_escapes(value: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,209 @@
+use anyhow::{Result, bail};
+
+// 终端的 8 种基本颜色
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// 单个输出元素的样式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    // 前景色（COLOR_NAMES 中的下标）
+    pub fg: Option<u8>,
+    // 背景色
+    pub bg: Option<u8>,
+    pub bold: bool,
+    pub underline: bool,
+    // 使用高亮（亮色）版本的前景色
+    pub intense: bool,
+}
+
+impl Style {
+    fn fg(color: u8) -> Style {
+        Style {
+            fg: Some(color),
+            ..Style::default()
+        }
+    }
+
+    // 生成开启该样式的 ANSI 转义序列，样式为空时返回空串
+    pub fn start(&self) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.underline {
+            codes.push("4".to_string());
+        }
+        if let Some(fg) = self.fg {
+            let base = if self.intense { 90 } else { 30 };
+            codes.push((base + fg).to_string());
+        }
+        if let Some(bg) = self.bg {
+            codes.push((40 + bg).to_string());
+        }
+        match codes.is_empty() {
+            true => String::new(),
+            false => format!("\x1b[{}m", codes.join(";")),
+        }
+    }
+
+    // 结束样式的转义序列，与 start() 成对使用
+    pub fn end(&self) -> &'static str {
+        match *self == Style::default() {
+            true => "",
+            false => "\x1b[0m",
+        }
+    }
+}
+
+// matchN、groupN 规则中 N 的上限
+const MAX_NUMBERED_STYLES: usize = 255;
+
+// 各类输出元素的样式集合（对应 --colors 参数）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Colors {
+    pub path: Style,
+    pub line: Style,
+    pub matched: Style,
+    // 由 matchN 规则单独指定的各个模式的匹配样式，下标是模式编号（N - 1）
+    pub patterns: Vec<Option<Style>>,
+    // 由 groupN 规则单独指定的第 N 个捕获组的样式，下标是 N - 1
+    pub groups: Vec<Option<Style>>,
+}
+
+// 默认配色与 ripgrep 一致：路径紫色、行号绿色、匹配加粗红色
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            path: Style::fg(5),
+            line: Style::fg(2),
+            matched: Style {
+                bold: true,
+                ..Style::fg(1)
+            },
+            patterns: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl Colors {
+    // 第 pattern 个模式（从 0 开始）的匹配样式
+    // 没有单独指定时按模式编号轮换 match 样式的前景色（红、绿、黄、蓝、紫、青），第一个模式就是 match 样式本身；
+    // match 样式没有前景色或者是黑色、白色时不轮换
+    pub fn pattern(&self, pattern: usize) -> Style {
+        match self.patterns.get(pattern) {
+            Some(Some(style)) => *style,
+            _ => rotate(self.matched, pattern),
+        }
+    }
+
+    // 匹配样式为 matched 的匹配中，第 group 个捕获组（从 1 开始）的样式
+    // 没有单独指定时在匹配样式的基础上加下划线，并把前景色按分组编号轮换
+    pub fn group(&self, matched: Style, group: usize) -> Style {
+        match self.groups.get(group - 1) {
+            Some(Some(style)) => *style,
+            _ => Style {
+                underline: true,
+                ..rotate(matched, group)
+            },
+        }
+    }
+
+    // 依次应用若干条 "{类型}:{属性}:{值}" 形式的配色规则
+    // 类型：path / line / match / matchN（第 N 个模式的匹配，N 从 1 开始）/ groupN（第 N 个捕获组）；
+    // 属性：fg / bg / style；"{类型}:none" 清空该类型的样式；
+    // matchN 的规则在该模式当前的样式上修改，groupN 的规则在第一个模式中该分组当前的样式上修改
+    pub fn with_specs<'a>(specs: impl IntoIterator<Item = &'a str>) -> Result<Colors> {
+        let mut colors = Colors::default();
+        for spec in specs {
+            let spec = spec.trim();
+            if spec.is_empty() {
+                continue;
+            }
+            colors.apply(spec)?;
+        }
+        Ok(colors)
+    }
+
+    fn apply(&mut self, spec: &str) -> Result<()> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let style = match parts[0] {
+            "path" => &mut self.path,
+            "line" => &mut self.line,
+            "match" => &mut self.matched,
+            other => match numbered(other) {
+                Some(("match", n)) => {
+                    let current = self.pattern(n - 1);
+                    slot(&mut self.patterns, n, current)
+                }
+                Some(("group", n)) => {
+                    let current = self.group(self.pattern(0), n);
+                    slot(&mut self.groups, n, current)
+                }
+                _ => bail!(
+                    "invalid color spec {:?}: unknown type {:?} (expected path, line, match, match1 to match{n} or group1 to group{n})",
+                    spec,
+                    other,
+                    n = MAX_NUMBERED_STYLES
+                ),
+            },
+        };
+
+        match parts[1..] {
+            ["none"] => *style = Style::default(),
+            ["fg", color] => style.fg = Some(parse_color(spec, color)?),
+            ["bg", color] => style.bg = Some(parse_color(spec, color)?),
+            ["style", "bold"] => style.bold = true,
+            ["style", "nobold"] => style.bold = false,
+            ["style", "underline"] => style.underline = true,
+            ["style", "nounderline"] => style.underline = false,
+            ["style", "intense"] => style.intense = true,
+            ["style", "nointense"] => style.intense = false,
+            _ => bail!(
+                "invalid color spec {:?}: expected {{type}}:fg|bg:{{color}}, {{type}}:style:{{style}} or {{type}}:none",
+                spec
+            ),
+        }
+        Ok(())
+    }
+}
+
+// 把前景色在红、绿、黄、蓝、紫、青之间轮换 by 步；没有前景色或者是黑色、白色时不变
+fn rotate(mut style: Style, by: usize) -> Style {
+    if let Some(fg @ 1..=6) = style.fg {
+        style.fg = Some(1 + ((fg as usize - 1 + by) % 6) as u8);
+    }
+    style
+}
+
+// 拆开 "match3"、"group2" 这样的类型，N 在 1 到 MAX_NUMBERED_STYLES 之间
+fn numbered(kind: &str) -> Option<(&'static str, usize)> {
+    ["match", "group"].into_iter().find_map(|prefix| {
+        let n = kind.strip_prefix(prefix)?.parse().ok()?;
+        (1..=MAX_NUMBERED_STYLES)
+            .contains(&n)
+            .then_some((prefix, n))
+    })
+}
+
+// 第 n 个（从 1 开始）单独指定的样式，还没有指定过时从 current 开始
+fn slot(styles: &mut Vec<Option<Style>>, n: usize, current: Style) -> &mut Style {
+    if styles.len() < n {
+        styles.resize(n, None);
+    }
+    styles[n - 1].get_or_insert(current)
+}
+
+fn parse_color(spec: &str, name: &str) -> Result<u8> {
+    match COLOR_NAMES.iter().position(|&color| color == name) {
+        Some(idx) => Ok(idx as u8),
+        None => bail!(
+            "invalid color spec {:?}: unknown color {:?} (expected one of {})",
+            spec,
+            name,
+            COLOR_NAMES.join(", ")
+        ),
+    }
+}
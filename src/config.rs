@@ -0,0 +1,165 @@
+use std::{
+    ffi::OsString,
+    fs, iter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::error::{ContextKind, ContextValue};
+
+// 指定配置文件路径的环境变量
+pub const CONFIG_ENV: &str = "MRUSTGREP_CONFIG";
+
+// 配置文件中的一个参数，记录所在行号以便报错
+#[derive(Debug, Clone)]
+pub struct ConfigArg {
+    pub line: usize,
+    pub value: String,
+}
+
+// 已加载的配置文件：每行一个参数，# 开头的行是注释
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub args: Vec<ConfigArg>,
+}
+
+impl ConfigFile {
+    // 解析配置文件内容
+    // 每个非空、非注释行就是一个完整的参数，带值的参数要写成 --flag=value
+    pub fn parse(path: &Path, contents: &str) -> Result<ConfigFile> {
+        let mut args = Vec::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // 配置文件只能放参数，不能放 pattern 或文件路径
+            if !line.starts_with('-') {
+                bail!(
+                    "error in config file {} line {}: expected a flag starting with '-', found {:?}",
+                    path.display(),
+                    idx + 1,
+                    line
+                );
+            }
+            args.push(ConfigArg {
+                line: idx + 1,
+                value: line.to_string(),
+            });
+        }
+
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            args,
+        })
+    }
+
+    // 找出导致 clap 解析失败的那个配置参数
+    pub fn blame(&self, err: &clap::Error) -> Option<&ConfigArg> {
+        let invalid = match err.get(ContextKind::InvalidArg)? {
+            ContextValue::String(arg) => arg,
+            _ => return None,
+        };
+        // clap 报告的形式可能是 "--color <WHEN>"，只比较参数名部分
+        let invalid = invalid.split([' ', '=']).next()?;
+        self.args.iter().find(|arg| {
+            let name = arg.value.split('=').next().unwrap_or_default();
+            name == invalid
+        })
+    }
+
+    // 单独解析配置文件中的参数时被当成模式的那个参数（多半是拼错的参数名）
+    // 模式允许以 - 开头，所以 clap 不会把未知的参数报告成错误
+    pub fn stray_pattern(&self) -> Option<&ConfigArg> {
+        let args = iter::once("mrustgrep")
+            .chain(self.args.iter().map(|arg| arg.value.as_str()))
+            .map(OsString::from)
+            .collect();
+        let matches = crate::cli::build()
+            .ignore_errors(true)
+            .try_get_matches_from(crate::cli::split_attached_values(args))
+            .ok()?;
+        let pattern = matches.get_one::<String>("pattern")?;
+        self.args.iter().find(|arg| &arg.value == pattern)
+    }
+}
+
+// 命令行中是否出现了 --no-config（在 "--" 之后的不算）
+// 必须在 clap 解析之前判断，因为配置内容本身要参与解析
+pub fn skip_requested(args: &[OsString]) -> bool {
+    args.iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--no-config")
+}
+
+// 配置文件路径：优先使用 MRUSTGREP_CONFIG，否则是 $XDG_CONFIG_HOME/mrustgrep/config
+// （未设置 XDG_CONFIG_HOME 时为 ~/.config/mrustgrep/config）
+fn config_path() -> Option<(PathBuf, bool)> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Some((PathBuf::from(path), true));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some((base.join("mrustgrep").join("config"), false))
+}
+
+// 加载配置文件
+// 通过环境变量显式指定的文件读不到时报错，默认位置的文件不存在则视为没有配置
+pub fn load() -> Result<Option<ConfigFile>> {
+    let Some((path, explicit)) = config_path() else {
+        return Ok(None);
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read config file {}", path.display()));
+        }
+    };
+
+    ConfigFile::parse(&path, &contents).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_one_flag_per_line_skipping_comments() {
+        let contents =
+            "# defaults\n\n  --smart-case\n--color=auto  \n  # indented comment\n--heading\n";
+        let config = ConfigFile::parse(Path::new("config"), contents).unwrap();
+        let args: Vec<(usize, &str)> = config
+            .args
+            .iter()
+            .map(|arg| (arg.line, arg.value.as_str()))
+            .collect();
+        assert_eq!(
+            args,
+            [(3, "--smart-case"), (4, "--color=auto"), (6, "--heading")]
+        );
+    }
+
+    #[test]
+    fn non_flag_line_names_file_and_line() {
+        let err = ConfigFile::parse(Path::new("/etc/mrg"), "--heading\nTODO\n").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("/etc/mrg line 2"), "{}", message);
+        assert!(message.contains("\"TODO\""), "{}", message);
+    }
+
+    #[test]
+    fn no_config_before_double_dash_only() {
+        let args = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+        assert!(skip_requested(&args(&["mrustgrep", "--no-config", "x"])));
+        assert!(!skip_requested(&args(&["mrustgrep", "--", "--no-config"])));
+        // 第 0 个是程序名，不算参数
+        assert!(!skip_requested(&args(&["--no-config", "x"])));
+    }
+}
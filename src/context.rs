@@ -0,0 +1,217 @@
+// 带上下文的搜索：每个选中的行附带它之前和之后若干行（Options 的 before_context / after_context）
+//
+// 上下文的分配与 grep 的 -B / -A 相同：
+// - 选中的行本身不会作为其他结果的上下文，而是单独交出；
+// - 两个结果离得很近时，中间的行只出现一次，优先作为前一个结果之后的上下文，其余的作为后一个结果之前的上下文；
+// - 结果要等之后的上下文读够（或者遇到下一个选中的行、输入结束）才交出。
+// 之前的上下文用一个最多 before_context 行的队列保存，内存只与上下文的行数有关。
+// 只有调用 search_with_context（或者展开成逐行结果的 search_context_lines）才会保存上下文，
+// search 等其他方法不受这两个选项影响。
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use anyhow::Result;
+
+use crate::search::{LineRecord, LongLine, Scan, SearchResult, Searcher};
+
+// 一行上下文
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextLine {
+    pub line_number: usize,
+    pub byte_offset: u64,
+    pub byte_len: usize,
+    // 去掉行终止符之后的内容
+    pub line: String,
+}
+
+// 附带前后上下文的搜索结果
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextedResult {
+    pub result: SearchResult,
+    // 按行号从小到大排列
+    pub before: Vec<ContextLine>,
+    pub after: Vec<ContextLine>,
+}
+
+impl Searcher {
+    // 逐行搜索 reader，每个选中的行连同前后的上下文一起交出
+    // 超过 max_line_length 的行交出 LongLine 错误，它两侧的行不再互为上下文
+    // --paragraph 的上下文就是段落本身，此时结果直接来自 search，before / after 为空
+    pub fn search_with_context<'a, R: BufRead + 'a>(
+        &'a self,
+        reader: R,
+    ) -> Box<dyn Iterator<Item = Result<ContextedResult>> + 'a> {
+        if self.options().paragraph {
+            return Box::new(self.search(reader).map(|result| {
+                result.map(|result| ContextedResult {
+                    result,
+                    before: Vec::new(),
+                    after: Vec::new(),
+                })
+            }));
+        }
+        Box::new(ContextIter::new(self, reader))
+    }
+
+    // search_with_context 按行号顺序展开成逐行的结果，上下文行的 context 为 true
+    // 与 --paragraph 的结果形式相同，可以直接交给输出器（不相邻的组之间由输出器加分隔行）
+    pub fn search_context_lines<'a, R: BufRead + 'a>(
+        &'a self,
+        reader: R,
+    ) -> Box<dyn Iterator<Item = Result<SearchResult>> + 'a> {
+        Box::new(
+            self.search_with_context(reader)
+                .flat_map(|result| match result {
+                    Ok(result) => result.into_lines().into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                }),
+        )
+    }
+}
+
+impl ContextedResult {
+    // 之前的上下文、结果本身和之后的上下文，按行号顺序排列
+    pub fn into_lines(self) -> Vec<SearchResult> {
+        let context = |line: ContextLine| SearchResult {
+            line_number: line.line_number,
+            byte_offset: line.byte_offset,
+            byte_len: line.byte_len,
+            line: line.line,
+            matches: Vec::new(),
+            groups: Vec::new(),
+            context: true,
+        };
+        let mut lines: Vec<SearchResult> = self.before.into_iter().map(context).collect();
+        lines.push(self.result);
+        lines.extend(self.after.into_iter().map(context));
+        lines
+    }
+}
+
+struct ContextIter<'a, R> {
+    searcher: &'a Searcher,
+    // 读完或者因 --stop-on-nonmatch 停下之后为 None
+    lines: Option<Scan<'a, R>>,
+    // 最近读过、还没有分配给任何结果的行
+    before: VecDeque<ContextLine>,
+    // 还在等待之后的上下文的结果
+    current: Option<ContextedResult>,
+    // 已经可以交出的结果和错误
+    pending: VecDeque<Result<ContextedResult>>,
+    seen_match: bool,
+    // 已经交出的结果数（Options::max_count）
+    selected: usize,
+}
+
+impl<'a, R: BufRead + 'a> ContextIter<'a, R> {
+    fn new(searcher: &'a Searcher, reader: R) -> Self {
+        ContextIter {
+            searcher,
+            lines: Some(searcher.scan(reader)),
+            before: VecDeque::new(),
+            current: None,
+            pending: VecDeque::new(),
+            seen_match: false,
+            selected: 0,
+        }
+    }
+
+    // 结果是否已经够 max_count 个
+    fn full(&self) -> bool {
+        self.searcher
+            .options()
+            .max_count
+            .is_some_and(|max| self.selected >= max)
+    }
+
+    // 交出还在等待的结果
+    fn finish_current(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.pending.push_back(Ok(current));
+        }
+    }
+
+    // 处理读到的一行，返回 false 表示不用再读了（--stop-on-nonmatch，或者结果够数并且读完了之后的上下文）
+    // 够数之后选中的行只作为最后一个结果之后的上下文，与 grep 的 -m 相同
+    fn feed(&mut self, record: LineRecord) -> bool {
+        let opts = self.searcher.options();
+        if record.selected && !self.full() {
+            self.seen_match = true;
+            self.selected += 1;
+            self.finish_current();
+            let result = ContextedResult {
+                result: SearchResult::from(record),
+                before: self.before.drain(..).collect(),
+                after: Vec::new(),
+            };
+            match opts.after_context {
+                0 => self.pending.push_back(Ok(result)),
+                _ => self.current = Some(result),
+            }
+            return self.current.is_some() || !self.full();
+        }
+        if !record.selected && opts.stop_on_nonmatch && self.seen_match {
+            return false;
+        }
+
+        let context = ContextLine {
+            line_number: record.line_number,
+            byte_offset: record.byte_offset,
+            byte_len: record.byte_len,
+            line: record.line,
+        };
+        match &mut self.current {
+            Some(current) => {
+                current.after.push(context);
+                if current.after.len() >= opts.after_context {
+                    self.finish_current();
+                }
+            }
+            None if opts.before_context > 0 => {
+                if self.before.len() == opts.before_context {
+                    self.before.pop_front();
+                }
+                self.before.push_back(context);
+            }
+            None => {}
+        }
+        self.current.is_some() || !self.full()
+    }
+}
+
+impl<'a, R: BufRead + 'a> Iterator for ContextIter<'a, R> {
+    type Item = Result<ContextedResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            match self.lines.as_mut()?.next() {
+                Some(Ok(record)) => {
+                    if !self.feed(record) {
+                        self.lines = None;
+                        self.finish_current();
+                    }
+                }
+                // 过长的行两侧不再互为上下文，之后还可以继续读
+                Some(Err(e)) if e.is::<LongLine>() => {
+                    self.finish_current();
+                    self.before.clear();
+                    self.pending.push_back(Err(e));
+                }
+                Some(Err(e)) => {
+                    self.lines = None;
+                    self.finish_current();
+                    self.pending.push_back(Err(e));
+                }
+                None => {
+                    self.lines = None;
+                    self.finish_current();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// C 接口（需要开启 ffi 功能），头文件是 include/mrustgrep.h（由 cbindgen 根据本文件生成）
+//
+// 所有权规则：
+// - mrg_searcher_new 返回的搜索器归调用方所有，用完必须交给 mrg_searcher_free 释放，释放之后不能再使用；
+// - 传入的 pattern、options、data 只在调用期间被读取，函数返回后不再保留；
+// - 回调收到的行和匹配区间指向库内部或者 data 中的内存，只在这次回调期间有效，需要保存时自行复制；
+// - mrg_last_error 返回的错误消息归库所有，在同一线程下一次调用本接口之前有效。
+// Rust 中的 panic 不会越过接口边界，会被捕获并当作错误返回。
+// 各函数的安全要求统一写在这里，不再逐个函数重复
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char, c_int, c_void},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::search::{Options, Searcher};
+
+// 搜索器，对 C 来说是不透明的类型
+pub struct MrgSearcher {
+    searcher: Searcher,
+}
+
+// 搜索选项，可以传 NULL 使用默认值
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MrgOptions {
+    // 忽略大小写
+    pub case_ignore: bool,
+    // 模式是固定字符串
+    pub fixed_strings: bool,
+    // 选出不匹配的行
+    pub invert_match: bool,
+    // 只接受从行首开始的匹配
+    pub anchored: bool,
+    // 报告重叠的匹配
+    pub overlapping: bool,
+}
+
+// 一处匹配：行内的字节区间 [start, end)，以及匹配的是第几个模式
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MrgMatch {
+    pub start: usize,
+    pub end: usize,
+    pub pattern: usize,
+}
+
+// 每个选中的行调用一次，返回 0 继续搜索，返回其他值停止
+// line 不包含行终止符，也不以 \0 结尾；不是合法 UTF-8 的字节已经替换成 U+FFFD
+// 在 Rust 这一侧是 Option，C 传入 NULL 时能报告错误而不是崩溃
+pub type MrgCallback = Option<
+    extern "C" fn(
+        userdata: *mut c_void,
+        line_number: usize,
+        line: *const u8,
+        line_len: usize,
+        matches: *const MrgMatch,
+        match_count: usize,
+    ) -> c_int,
+>;
+
+thread_local! {
+    // 本线程最近一次出错的消息
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).expect("no NUL bytes left");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+// 在接口边界捕获 panic，panic 当作错误处理
+fn guard<T>(on_error: T, f: impl FnOnce() -> Result<T, String>) -> T {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_error(message);
+            on_error
+        }
+        Err(_) => {
+            set_error("mrustgrep panicked".to_string());
+            on_error
+        }
+    }
+}
+
+// 编译一个模式（以 \0 结尾的 UTF-8），options 可以是 NULL；失败时返回 NULL，原因通过 mrg_last_error 取得
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mrg_searcher_new(
+    pattern: *const c_char,
+    options: *const MrgOptions,
+) -> *mut MrgSearcher {
+    guard(ptr::null_mut(), || {
+        if pattern.is_null() {
+            return Err("pattern is NULL".to_string());
+        }
+        let pattern = unsafe { CStr::from_ptr(pattern) }
+            .to_str()
+            .map_err(|_| "pattern is not valid UTF-8".to_string())?;
+        let options = match options.is_null() {
+            true => MrgOptions::default(),
+            false => unsafe { *options },
+        };
+        let opts = Options {
+            case_ignore: options.case_ignore,
+            fixed_strings: options.fixed_strings,
+            invert_match: options.invert_match,
+            anchored: options.anchored,
+            overlapping: options.overlapping,
+            ..Options::default()
+        };
+        let searcher = Searcher::new(pattern, opts).map_err(|e| format!("{e:#}"))?;
+        Ok(Box::into_raw(Box::new(MrgSearcher { searcher })))
+    })
+}
+
+// 逐行搜索 data 中的 len 个字节，每个选中的行调用一次 callback，userdata 原样传给 callback
+// 返回 0 表示搜索完成（或者被 callback 停止），-1 表示出错
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mrg_searcher_search_buffer(
+    searcher: *const MrgSearcher,
+    data: *const u8,
+    len: usize,
+    callback: MrgCallback,
+    userdata: *mut c_void,
+) -> c_int {
+    guard(-1, || {
+        let searcher = unsafe { searcher.as_ref() }.ok_or("searcher is NULL")?;
+        let callback = callback.ok_or("callback is NULL")?;
+        let data = match (data.is_null(), len) {
+            (_, 0) => &[][..],
+            (true, _) => return Err("data is NULL".to_string()),
+            (false, _) => unsafe { slice::from_raw_parts(data, len) },
+        };
+        for result in searcher.searcher.search_slice_ref(data) {
+            let result = result.map_err(|e| format!("{e:#}"))?;
+            let matches: Vec<MrgMatch> = result
+                .matches
+                .iter()
+                .map(|m| MrgMatch {
+                    start: m.start,
+                    end: m.end,
+                    pattern: m.pattern,
+                })
+                .collect();
+            let stop = callback(
+                userdata,
+                result.line_number,
+                result.line.as_ptr(),
+                result.line.len(),
+                matches.as_ptr(),
+                matches.len(),
+            );
+            if stop != 0 {
+                break;
+            }
+        }
+        Ok(0)
+    })
+}
+
+// 释放 mrg_searcher_new 返回的搜索器，传入 NULL 时什么也不做
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mrg_searcher_free(searcher: *mut MrgSearcher) {
+    if !searcher.is_null() {
+        drop(unsafe { Box::from_raw(searcher) });
+    }
+}
+
+// 本线程最近一次调用失败的原因，没有出错时返回 NULL
+#[unsafe(no_mangle)]
+pub extern "C" fn mrg_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
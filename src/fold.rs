@@ -0,0 +1,278 @@
+// 固定字符串在忽略大小写时的 Unicode 大小写折叠
+//
+// 正则引擎的 case_insensitive 只做简单折叠（一个字符对应一个字符），
+// 像 ß 和 ss 这种字符数会变化的完整折叠（CaseFolding.txt 中状态为 F 的条目）需要另外处理。
+// 这里把字面量翻译成正则：遇到这类字符或与其折叠结果相同的字符序列时展开成两者的分支，
+// 其余字符照常转义，剩下的简单折叠交给引擎。
+//
+// 语义遵循 Unicode 默认（与语言无关）的折叠规则：
+// - straße 与 STRASSE、STRAẞE 相互匹配
+// - İ（U+0130）匹配 i̇（i 加 U+0307 组合点），不匹配普通的 i；无点的 ı 只匹配自身
+// - Σ、σ、ς 三者相互匹配（由引擎的简单折叠完成）
+// 序列的识别是从左到右贪心的，例如 "sss" 会被当作 "ss" + "s"；
+// 同一位置先试较长的序列，"ffi" 对应 ﬃ 而不是 ﬀ + i。
+
+use std::cmp::Reverse;
+
+use regex_syntax::hir::{ClassUnicode, ClassUnicodeRange};
+
+// 完整折叠表：字符及其折叠后的字符序列
+const FULL_FOLDS: &[(char, &str)] = &[
+    ('ß', "ss"),
+    ('ẞ', "ss"),
+    ('İ', "i\u{307}"),
+    ('ŉ', "\u{2bc}n"),
+    ('ǰ', "j\u{30c}"),
+    ('ﬀ', "ff"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('ﬃ', "ffi"),
+    ('ﬄ', "ffl"),
+    ('ﬅ', "st"),
+    ('ﬆ', "st"),
+    ('և', "եւ"),
+];
+
+// 把字面量翻译成配合 case_insensitive 使用的正则
+pub fn case_insensitive_literal(literal: &str) -> String {
+    segments(literal)
+        .iter()
+        .map(|spellings| match spellings.as_slice() {
+            [text] => regex::escape(text),
+            spellings => format!(
+                "(?:{})",
+                spellings
+                    .iter()
+                    .map(|text| regex::escape(text))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            ),
+        })
+        .collect()
+}
+
+// 在只做 ASCII 大小写折叠的比较下（Aho-Corasick 的 ascii_case_insensitive），
+// 与忽略大小写的字面量等价所需的全部写法：非 ASCII 的简单折叠（k 与开尔文符号 K、s 与 ſ、é 与 É）
+// 逐一展开，full 为 true 时（-F）还展开 case_insensitive_literal 中的完整折叠分支。
+// 写法的顺序与正则分支的优先顺序一致；超过 limit 种时返回 None
+pub fn ascii_case_variants(literal: &str, full: bool, limit: usize) -> Option<Vec<String>> {
+    let segments = match full {
+        true => segments(literal),
+        false => literal.chars().map(|c| vec![c.to_string()]).collect(),
+    };
+    let mut variants = vec![String::new()];
+    for spellings in segments {
+        let mut expanded = Vec::new();
+        for spelling in spellings {
+            let mut partial = vec![String::new()];
+            for c in spelling.chars() {
+                let partners: Vec<String> = simple_fold_partners(c)
+                    .iter()
+                    .map(char::to_string)
+                    .collect();
+                partial = product(&partial, &partners, limit)?;
+            }
+            expanded.extend(partial);
+        }
+        variants = product(&variants, &expanded, limit)?;
+    }
+    Some(variants)
+}
+
+// 字面量按完整折叠切成的片段，每个片段是可以互相替代的几种写法（第一种是原文）
+fn segments(literal: &str) -> Vec<Vec<String>> {
+    let chars: Vec<char> = literal.chars().collect();
+    // 排序是稳定的，折叠结果相同的字符（ß 与 ẞ）仍按表中的顺序
+    let mut sequences: Vec<&(char, &str)> = FULL_FOLDS.iter().collect();
+    sequences.sort_by_key(|(_, folded)| Reverse(folded.chars().count()));
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    'outer: while i < chars.len() {
+        let c = chars[i];
+
+        // 字符本身有完整折叠：匹配它自己或折叠后的序列
+        if let Some((_, folded)) = FULL_FOLDS.iter().find(|(ch, _)| *ch == c) {
+            out.push(vec![c.to_string(), folded.to_string()]);
+            i += 1;
+            continue;
+        }
+
+        // 一段字符序列与某个字符的折叠结果相同：匹配这段序列或那个字符
+        for (ch, folded) in &sequences {
+            let len = folded.chars().count();
+            if i + len <= chars.len() && folds_to(&chars[i..i + len], folded) {
+                out.push(vec![chars[i..i + len].iter().collect(), ch.to_string()]);
+                i += len;
+                continue 'outer;
+            }
+        }
+
+        out.push(vec![c.to_string()]);
+        i += 1;
+    }
+
+    out
+}
+
+// 与 c 互为简单折叠、而 ASCII 大小写比较覆盖不到的字符，c 自己排在最前
+// ASCII 字母的另一种大小写由比较本身处理，不再列出
+fn simple_fold_partners(c: char) -> Vec<char> {
+    let mut class = ClassUnicode::new([ClassUnicodeRange::new(c, c)]);
+    class.case_fold_simple();
+    let mut partners = vec![c];
+    for other in class
+        .ranges()
+        .iter()
+        .flat_map(|range| range.start()..=range.end())
+    {
+        let covered = partners.iter().any(|partner| match other.is_ascii() {
+            true => partner.eq_ignore_ascii_case(&other),
+            false => *partner == other,
+        });
+        if !covered {
+            partners.push(other);
+        }
+    }
+    partners
+}
+
+// 两组写法首尾相接的所有组合，超过 limit 种时返回 None
+fn product(prefixes: &[String], suffixes: &[String], limit: usize) -> Option<Vec<String>> {
+    if prefixes.len() * suffixes.len() > limit {
+        return None;
+    }
+    Some(
+        prefixes
+            .iter()
+            .flat_map(|prefix| {
+                suffixes
+                    .iter()
+                    .map(move |suffix| format!("{prefix}{suffix}"))
+            })
+            .collect(),
+    )
+}
+
+// 逐个字符转小写后是否等于给定的折叠序列
+fn folds_to(chars: &[char], folded: &str) -> bool {
+    let lowered: String = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    lowered == folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按 -F -i 的方式编译字面量，判断它是否在 haystack 中出现
+    fn finds(literal: &str, haystack: &str) -> bool {
+        regex::RegexBuilder::new(&case_insensitive_literal(literal))
+            .case_insensitive(true)
+            .build()
+            .unwrap()
+            .is_match(haystack)
+    }
+
+    #[test]
+    fn german_sharp_s() {
+        for haystack in ["straße", "STRASSE", "Strasse", "STRAẞE"] {
+            assert!(finds("straße", haystack), "straße vs {}", haystack);
+            assert!(finds("STRASSE", haystack), "STRASSE vs {}", haystack);
+            assert!(finds("STRAẞE", haystack), "STRAẞE vs {}", haystack);
+        }
+        assert!(!finds("straße", "strase"));
+    }
+
+    #[test]
+    fn turkish_dotted_and_dotless_i() {
+        assert!(finds("İstanbul", "İSTANBUL"));
+        assert!(finds("İstanbul", "i\u{307}stanbul"));
+        assert!(!finds("İstanbul", "istanbul"));
+        assert!(!finds("İstanbul", "ISTANBUL"));
+        assert!(!finds("ılık", "ILIK"));
+        assert!(finds("ılık", "ılık"));
+        assert!(!finds("ılık", "ilik"));
+    }
+
+    #[test]
+    fn greek_sigma_forms() {
+        for pattern in ["Σ", "σ", "ς"] {
+            for haystack in ["Σ", "σ", "ς"] {
+                assert!(finds(pattern, haystack), "{} vs {}", pattern, haystack);
+            }
+        }
+        assert!(finds("ΟΔΟΣ", "οδος"));
+        assert!(finds("οδος", "ΟΔΟΣ"));
+    }
+
+    #[test]
+    fn ligatures() {
+        assert!(finds("ﬁle", "FILE"));
+        assert!(finds("file", "ﬁle"));
+        assert!(finds("oﬃce", "OFFICE"));
+        assert!(finds("office", "oﬃce"));
+        assert!(finds("ﬆ", "ST"));
+        assert!(!finds("ﬁle", "fle"));
+    }
+
+    #[test]
+    fn metacharacters_stay_literal() {
+        assert!(finds("a.b*", "A.B*"));
+        assert!(!finds("a.b*", "axbbb"));
+    }
+
+    #[test]
+    fn ascii_variants_spell_out_non_ascii_folds() {
+        let variants = |literal, full| ascii_case_variants(literal, full, 64).unwrap();
+        assert_eq!(variants("foo-BAR_42", true), ["foo-BAR_42"]);
+        assert_eq!(variants("k", false), ["k", "\u{212a}"]);
+        assert_eq!(
+            variants("Ask", false),
+            ["Ask", "As\u{212a}", "A\u{17f}k", "A\u{17f}\u{212a}"]
+        );
+        assert_eq!(variants("é", false), ["é", "É"]);
+        assert_eq!(variants("ß", false), ["ß", "ẞ"]);
+        assert_eq!(
+            variants("ss", true),
+            ["ss", "s\u{17f}", "\u{17f}s", "\u{17f}\u{17f}", "ß", "ẞ"]
+        );
+        assert_eq!(variants("fi", true), ["fi", "ﬁ"]);
+        assert_eq!(variants("fi", false), ["fi"]);
+        assert_eq!(ascii_case_variants("kkkkkkk", false, 64), None);
+    }
+
+    #[test]
+    fn ascii_variants_agree_with_the_regex() {
+        let haystacks = [
+            "STRASSE",
+            "STRAẞE",
+            "stra\u{17f}\u{17f}e",
+            "\u{212a}ILN",
+            "ﬁle",
+            "CAFÉ",
+        ];
+        for literal in ["straße", "Strasse", "kiln", "file", "café"] {
+            let automaton = aho_corasick::AhoCorasick::builder()
+                .ascii_case_insensitive(true)
+                .build(ascii_case_variants(literal, true, 64).unwrap())
+                .unwrap();
+            for haystack in haystacks {
+                assert_eq!(
+                    automaton.is_match(haystack),
+                    finds(literal, haystack),
+                    "{} vs {}",
+                    literal,
+                    haystack
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_matching_is_greedy_left_to_right() {
+        assert_eq!(case_insensitive_literal("sss"), "(?:ss|ß)s");
+        assert_eq!(case_insensitive_literal("ß"), "(?:ß|ss)");
+        assert_eq!(case_insensitive_literal("a+"), r"a\+");
+        assert_eq!(case_insensitive_literal("ffi"), "(?:ffi|ﬃ)");
+    }
+}
@@ -0,0 +1,98 @@
+// --follow：搜索完已有内容后继续等待文件增长（相当于 tail -f 加 grep）
+//
+// FollowReader 读到文件末尾时不返回 EOF，而是定期检查文件是否有新数据，
+// 因此上层的逐行搜索不需要任何改动；不完整的最后一行会一直等到换行符出现才交出。
+// 文件被截断或被替换（日志轮转）时按文件名重新打开，从头读取新内容；行号继续累加。
+// stop 被置位（例如收到 Ctrl-C）后，下一次读到末尾时返回 EOF，搜索照常结束。
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::visited::FileId;
+
+// 两次检查文件之间的等待时间
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// 检测到的日志轮转
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    // 文件变短了，从头重新读取
+    Truncated,
+    // 同名文件换成了另一个文件，重新打开
+    Replaced,
+}
+
+// 持续读取一个不断增长的文件
+pub struct FollowReader {
+    path: PathBuf,
+    file: File,
+    id: Option<FileId>,
+    // 已经读到的位置
+    position: u64,
+    stop: Arc<AtomicBool>,
+    // 检测到轮转时的通知（用于提示用户）
+    on_rotate: Box<dyn FnMut(Rotation)>,
+}
+
+impl FollowReader {
+    pub fn open(
+        path: &Path,
+        stop: Arc<AtomicBool>,
+        on_rotate: impl FnMut(Rotation) + 'static,
+    ) -> io::Result<FollowReader> {
+        let file = File::open(path)?;
+        Ok(FollowReader {
+            path: path.to_path_buf(),
+            file,
+            id: FileId::of(path).ok().flatten(),
+            position: 0,
+            stop,
+            on_rotate: Box::new(on_rotate),
+        })
+    }
+
+    // 读到末尾时检查文件是否被截断或替换
+    // 文件暂时不存在（轮转的中间状态）时什么也不做，下次再看
+    fn check_rotation(&mut self) -> io::Result<()> {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(());
+        };
+        let id = FileId::of(&self.path).ok().flatten();
+        if id != self.id {
+            self.file = File::open(&self.path)?;
+            self.id = id;
+            self.position = 0;
+            (self.on_rotate)(Rotation::Replaced);
+        } else if metadata.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            (self.on_rotate)(Rotation::Truncated);
+        }
+        Ok(())
+    }
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 {
+                self.position += n as u64;
+                return Ok(n);
+            }
+            if self.stop.load(Ordering::SeqCst) {
+                return Ok(0);
+            }
+            self.check_rotation()?;
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
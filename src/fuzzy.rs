@@ -0,0 +1,116 @@
+// 近似匹配（--fuzzy）：允许至多 k 处编辑（插入、删除、替换）的固定字符串搜索
+//
+// 使用 Sellers 的动态规划：逐个字符扫描文本，维护模式每个前缀在当前位置结束时的最小编辑距离，
+// 同时记录取得这个距离的匹配起点。复杂度是 O(模式长度 × 文本长度)，适合名字、标识符这类短模式。
+// 第一次出现距离不超过 k 的结束位置之后继续向后看，直到距离超过 k，取其中距离最小的一个，
+// 这样 "hello" 在 "hello world" 中匹配完整的 "hello"，而不是少一个字符的 "hell"。
+// 距离相同时选择起点更靠后（更短）的匹配；长度为零的匹配不报告。
+// 忽略大小写时逐个字符比较小写形式（简单折叠）。
+
+// 一处近似匹配：字节区间 [start, end) 和编辑距离
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub start: usize,
+    pub end: usize,
+    pub distance: usize,
+}
+
+// 动态规划中的一格：编辑距离和匹配起点（字节位置）
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    cost: usize,
+    start: usize,
+}
+
+impl Cell {
+    // 距离更小的优先，距离相同时起点更靠后的优先
+    fn better(self, other: Cell) -> Cell {
+        match (self.cost, other.cost) {
+            (a, b) if a < b => self,
+            (a, b) if a > b => other,
+            _ if self.start >= other.start => self,
+            _ => other,
+        }
+    }
+}
+
+fn fold(c: char, case_ignore: bool) -> char {
+    match case_ignore {
+        true => c.to_lowercase().next().unwrap_or(c),
+        false => c,
+    }
+}
+
+// 把模式转换成逐字符比较用的形式
+pub fn prepare(pattern: &str, case_ignore: bool) -> Vec<char> {
+    pattern.chars().map(|c| fold(c, case_ignore)).collect()
+}
+
+// 在 text 中从字节位置 pos 开始寻找第一处编辑距离不超过 max_edits 的匹配
+// anchored 为 true 时匹配必须从 pos 开始
+pub fn find(
+    pattern: &[char],
+    text: &str,
+    pos: usize,
+    max_edits: usize,
+    anchored: bool,
+    case_ignore: bool,
+) -> Option<FuzzyMatch> {
+    // 还没有读入文本字符时，前缀 i 只能靠 i 次插入得到
+    let mut column: Vec<Cell> = (0..=pattern.len())
+        .map(|cost| Cell { cost, start: pos })
+        .collect();
+    let mut next = column.clone();
+    let mut best: Option<FuzzyMatch> = None;
+
+    for (offset, c) in text[pos..].char_indices() {
+        let end = pos + offset + c.len_utf8();
+        let c = fold(c, case_ignore);
+        // 空前缀：不锚定时可以从这个字符之后开始，锚定时只能把这个字符删掉
+        next[0] = match anchored {
+            true => Cell {
+                cost: column[0].cost + 1,
+                start: pos,
+            },
+            false => Cell {
+                cost: 0,
+                start: end,
+            },
+        };
+        for i in 1..=pattern.len() {
+            let substitute = Cell {
+                cost: column[i - 1].cost + usize::from(pattern[i - 1] != c),
+                start: column[i - 1].start,
+            };
+            let delete = Cell {
+                cost: column[i].cost + 1,
+                start: column[i].start,
+            };
+            let insert = Cell {
+                cost: next[i - 1].cost + 1,
+                start: next[i - 1].start,
+            };
+            next[i] = substitute.better(delete).better(insert);
+        }
+        std::mem::swap(&mut column, &mut next);
+
+        let last = column[pattern.len()];
+        match (last.cost <= max_edits && last.start < end, best) {
+            (true, Some(found)) if last.cost >= found.distance => {}
+            (true, _) => {
+                best = Some(FuzzyMatch {
+                    start: last.start,
+                    end,
+                    distance: last.cost,
+                })
+            }
+            (false, Some(_)) => break,
+            (false, None) => {}
+        }
+        // 锚定时所有前缀的距离都已经超过上限，之后不可能再匹配
+        if anchored && best.is_none() && column.iter().all(|cell| cell.cost > max_edits) {
+            break;
+        }
+    }
+    best
+}
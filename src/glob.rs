@@ -0,0 +1,126 @@
+// 通配符模式（-G）：把 shell 风格的通配符翻译成等价的正则，之后的匹配、-o、高亮都与正则模式相同
+//
+// 支持的写法：
+// - *        任意个字符
+// - ?        一个字符
+// - [abc]    其中任意一个字符，[a-z] 表示范围，[!abc] 或 [^abc] 表示取反，第一个位置的 ] 是普通字符
+// - \x       字符 x 本身，用来写出字面的 *、?、[ 和 \
+// 其余字符都是普通字符。没有闭合的 [ 按普通字符处理。
+// 与 grep 的正则一样，模式可以出现在行内的任何位置，不要求匹配整行。
+
+// 把通配符模式翻译成正则
+pub fn to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            '\\' => match chars.get(i + 1) {
+                Some(&next) => {
+                    push_literal(&mut out, next);
+                    i += 1;
+                }
+                None => push_literal(&mut out, '\\'),
+            },
+            '[' => match class(&chars[i + 1..]) {
+                Some((regex, len)) => {
+                    out.push_str(&regex);
+                    i += len;
+                }
+                None => push_literal(&mut out, '['),
+            },
+            c => push_literal(&mut out, c),
+        }
+        i += 1;
+    }
+    out
+}
+
+pub(crate) fn push_literal(out: &mut String, c: char) {
+    out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+}
+
+// 翻译 [ 之后的字符类，返回对应的正则和消耗的字符数（包括结尾的 ]）；没有闭合时返回 None
+// 类中的每个字符都单独转义，避免与正则字符类中的 &&、--、~~ 和嵌套的 [ 冲突
+pub(crate) fn class(chars: &[char]) -> Option<(String, usize)> {
+    let mut i = 0;
+    let mut out = String::from("[");
+    if let Some('!' | '^') = chars.first() {
+        out.push('^');
+        i += 1;
+    }
+    let start = i;
+    loop {
+        let c = *chars.get(i)?;
+        // 第一个位置的 ] 是普通字符
+        if c == ']' && i > start {
+            out.push(']');
+            return Some((out, i + 1));
+        }
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                push_literal(&mut out, c);
+                out.push('-');
+                push_literal(&mut out, end);
+                i += 3;
+            }
+            _ => {
+                push_literal(&mut out, c);
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, text: &str) -> bool {
+        regex::Regex::new(&to_regex(glob)).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn escaped_wildcards_are_literal() {
+        assert_eq!(to_regex(r"a\*b"), r"a\*b");
+        assert!(matches(r"a\*b", "a*b"));
+        assert!(!matches(r"a\*b", "axb"));
+        assert_eq!(to_regex(r"\?\[\\"), r"\?\[\\");
+        // 结尾单独的 \ 是它本身
+        assert_eq!(to_regex(r"a\"), r"a\\");
+    }
+
+    #[test]
+    fn negated_classes() {
+        assert_eq!(to_regex("[!a]"), "[^a]");
+        assert_eq!(to_regex("[^a]"), "[^a]");
+        assert!(matches("x[!a]y", "xby"));
+        assert!(!matches("x[!a]y", "xay"));
+    }
+
+    #[test]
+    fn leading_bracket_is_a_class_member() {
+        assert_eq!(to_regex("[]a]"), r"[\]a]");
+        assert!(matches("x[]a]", "x]"));
+        assert!(matches("x[!]]", "xb"));
+        assert!(!matches("x[!]]", "x]"));
+    }
+
+    #[test]
+    fn unclosed_bracket_is_literal() {
+        assert_eq!(to_regex("[ab"), r"\[ab");
+        assert!(matches("x[ab", "x[ab"));
+        assert!(!matches("x[ab", "xa"));
+        assert_eq!(to_regex("[]"), r"\[\]");
+    }
+
+    #[test]
+    fn ranges_and_wildcards() {
+        assert_eq!(to_regex("a*[0-9]?"), "a.*[0-9].");
+        assert!(matches("f[a-c]-", "fb-"));
+        // 结尾的 - 不构成范围
+        assert!(matches("[a-]", "-"));
+    }
+}
@@ -0,0 +1,448 @@
+// 递归搜索时的忽略文件：.ignore、.gitignore、git 的全局排除文件和全局忽略文件，写法与 .gitignore 相同
+//
+// 每行一条规则，空行和 # 开头的行忽略，\# 和 \! 表示字面的 # 和 !：
+// - ! 开头的规则重新包含之前被忽略的路径；
+// - / 结尾的规则只匹配目录；
+// - 除结尾之外还含有 / 的规则相对于忽略文件所在的目录匹配整个相对路径（开头的 / 可以省略），
+//   否则只与文件名比较，在任何一层都生效；
+// - * 和 ? 不匹配 /，**/ 匹配任意层目录，结尾的 /** 匹配目录中的一切，[...] 与 -G 的写法相同。
+// 同一个文件中后面的规则优先。被忽略的目录不会进入，其中的文件无法再用 ! 重新包含。
+// 写错的规则（例如无法翻译的字符类）直接跳过，与 git 一样不报错。
+//
+// 各层之间的优先级（Walker 按这个顺序查找，第一个有结论的规则生效）：
+// .ignore > .gitignore > git 的全局排除文件 > 全局忽略文件；同一层中更深的目录里的文件优先。
+// 只读取遍历起点及其下各个目录中的忽略文件，起点之上的目录不读取。
+// git 的全局排除文件（core.excludesFile）与全局忽略文件一样，规则相对于每个遍历起点。
+//
+// 命令行上的通配符（--include、--exclude 和 --iglob，Overrides）写法相同，但意思相反：不以 ! 开头的是要搜索的文件，
+// 以 ! 开头的是要跳过的文件和目录；它们优先于所有忽略文件，同样是后面的优先。
+// 给出了至少一个不以 ! 开头的通配符时，与哪个都不匹配的文件被跳过（目录照常进入）。
+// --exclude GLOB 相当于 --include !GLOB；--iglob 与 --include 相同，只是整个路径都不区分大小写。
+use std::{
+    env, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use regex::{Regex, RegexBuilder};
+
+use crate::glob;
+
+// 一条规则
+#[derive(Debug, Clone)]
+struct Rule {
+    regex: Regex,
+    // ! 开头，匹配时表示不忽略
+    negated: bool,
+    // / 结尾，只匹配目录
+    dir_only: bool,
+    // 与整个相对路径比较，否则只与文件名比较
+    anchored: bool,
+}
+
+// 一个忽略文件中的全部规则
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    // 解析忽略文件的内容
+    pub fn parse(contents: &str) -> IgnoreFile {
+        IgnoreFile {
+            rules: contents
+                .lines()
+                .filter_map(|line| parse_rule(line, false)?.ok())
+                .collect(),
+        }
+    }
+
+    // 读取并解析一个忽略文件，文件不存在时返回 None
+    pub fn load(path: &Path) -> io::Result<Option<IgnoreFile>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(IgnoreFile::parse(&contents))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // relative 是相对于忽略文件所在目录的路径
+    // 返回 Some(true) 表示忽略，Some(false) 表示被 ! 规则重新包含，None 表示没有规则匹配
+    pub fn matched(&self, relative: &Path, is_dir: bool) -> Option<bool> {
+        let whole = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let name = whole.rsplit('/').next().unwrap_or_default();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && rule
+                        .regex
+                        .is_match(if rule.anchored { &whole } else { name })
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+// dir 所在的 git 工作树的根目录：dir 或它最近的一个含有 .git（目录或者工作树的 .git 文件）的上级目录
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+// git 的全局排除文件的位置：用户级 git 配置中的 core.excludesFile，
+// 没有配置时是 $XDG_CONFIG_HOME/git/ignore（默认 ~/.config/git/ignore）；不检查文件是否存在
+// 与 git 一样先读 $XDG_CONFIG_HOME/git/config，再读 ~/.gitconfig，后读到的设置优先
+pub fn git_excludes_file() -> Option<PathBuf> {
+    let home = env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from);
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+    let configs = [
+        config_home.as_ref().map(|dir| dir.join("git/config")),
+        home.as_ref().map(|home| home.join(".gitconfig")),
+    ];
+    let configured = configs
+        .into_iter()
+        .rev()
+        .flatten()
+        .find_map(|path| core_excludes_file(&fs::read_to_string(path).ok()?));
+    match configured {
+        Some(path) => match path.strip_prefix("~/") {
+            Some(rest) => home.map(|home| home.join(rest)),
+            None => Some(PathBuf::from(path)),
+        },
+        None => config_home.map(|dir| dir.join("git/ignore")),
+    }
+}
+
+// git 配置文件中 [core] 一节的 excludesFile，节名和键名不区分大小写
+// 只处理常见的写法：一行一个 key = value，值可以用双引号括起来，# 和 ; 开头的行是注释
+fn core_excludes_file(contents: &str) -> Option<String> {
+    let mut in_core = false;
+    let mut found = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            let name = section.split(']').next().unwrap_or_default().trim();
+            in_core = name.eq_ignore_ascii_case("core");
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if in_core && key.trim().eq_ignore_ascii_case("excludesfile") {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            found = Some(value.to_string());
+        }
+    }
+    found
+}
+
+// 命令行上的通配符
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    globs: IgnoreFile,
+    // 是否有不以 ! 开头的通配符
+    has_include: bool,
+}
+
+impl Overrides {
+    // 添加一个通配符，case_insensitive 时整个路径（包括目录部分）不区分大小写
+    pub fn add(&mut self, glob: &str, case_insensitive: bool) -> Result<()> {
+        let rule = parse_rule(glob, case_insensitive)
+            .ok_or_else(|| anyhow!("invalid glob {:?}: matches nothing", glob))?
+            .with_context(|| format!("invalid glob {:?}", glob))?;
+        self.has_include |= !rule.negated;
+        self.globs.rules.push(rule);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globs.rules.is_empty()
+    }
+
+    // relative 是相对于遍历起点的路径
+    // 返回 Some(true) 表示跳过，Some(false) 表示搜索（不再查看忽略文件），None 表示交给忽略文件决定
+    pub fn matched(&self, relative: &Path, is_dir: bool) -> Option<bool> {
+        match self.globs.matched(relative, is_dir) {
+            // 对于忽略文件，不以 ! 开头的规则表示忽略，这里正好相反
+            Some(ignored) => Some(!ignored),
+            None if self.has_include && !is_dir => Some(true),
+            None => None,
+        }
+    }
+}
+
+// 解析一行规则，空行和注释返回 None，翻译出的正则无效时返回错误
+fn parse_rule(line: &str, case_insensitive: bool) -> Option<Result<Rule, regex::Error>> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    // 结尾的空格忽略，用反斜杠转义的最后一个空格保留（由 translate 按字面处理）
+    let trimmed = line.trim_end_matches(' ');
+    let line = match trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        true => &line[..trimmed.len() + 1],
+        false => trimmed,
+    };
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // \# 和 \! 不是注释和否定，由 translate 按字面处理
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    if line.is_empty() {
+        return None;
+    }
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    let regex = RegexBuilder::new(&format!("^(?:{})$", translate(line)))
+        .case_insensitive(case_insensitive)
+        .build();
+    Some(regex.map(|regex| Rule {
+        regex,
+        negated,
+        dir_only,
+        anchored,
+    }))
+}
+
+// 把一条规则翻译成正则，* 和 ? 不跨越 /
+fn translate(glob_text: &str) -> String {
+    let chars: Vec<char> = glob_text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let at_component_start = i == 0 || chars[i - 1] == '/';
+        match chars[i] {
+            '*' if at_component_start && chars.get(i + 1) == Some(&'*') => {
+                match chars.get(i + 2) {
+                    // **/ 匹配零到多层目录
+                    Some('/') => {
+                        out.push_str("(?:.*/)?");
+                        i += 3;
+                    }
+                    // 结尾的 ** 匹配其中的一切
+                    None => {
+                        out.push_str(".*");
+                        i += 2;
+                    }
+                    // 其他位置的 ** 与 * 相同
+                    Some(_) => {
+                        out.push_str("[^/]*");
+                        i += 2;
+                    }
+                }
+                continue;
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '\\' => match chars.get(i + 1) {
+                Some(&next) => {
+                    glob::push_literal(&mut out, next);
+                    i += 1;
+                }
+                None => glob::push_literal(&mut out, '\\'),
+            },
+            '[' => match glob::class(&chars[i + 1..]) {
+                Some((regex, len)) => {
+                    out.push_str(&regex);
+                    i += len;
+                }
+                None => glob::push_literal(&mut out, '['),
+            },
+            c => glob::push_literal(&mut out, c),
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(contents: &str, path: &str, is_dir: bool) -> Option<bool> {
+        IgnoreFile::parse(contents).matched(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn rules_without_a_slash_match_the_name_at_any_depth() {
+        let rules = "*.log\n";
+        assert_eq!(ignored(rules, "a.log", false), Some(true));
+        assert_eq!(ignored(rules, "deep/down/b.log", false), Some(true));
+        assert_eq!(ignored(rules, "a.log.txt", false), None);
+    }
+
+    #[test]
+    fn rules_with_a_slash_are_anchored() {
+        let rules = "/build\ndocs/*.md\n";
+        assert_eq!(ignored(rules, "build", true), Some(true));
+        assert_eq!(ignored(rules, "src/build", true), None);
+        assert_eq!(ignored(rules, "docs/a.md", false), Some(true));
+        // * 不跨越 /
+        assert_eq!(ignored(rules, "docs/sub/a.md", false), None);
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_directories() {
+        let rules = "**/gen/*.rs\nvendor/**\n";
+        assert_eq!(ignored(rules, "gen/a.rs", false), Some(true));
+        assert_eq!(ignored(rules, "x/y/gen/a.rs", false), Some(true));
+        assert_eq!(ignored(rules, "vendor/a/b/c.c", false), Some(true));
+        assert_eq!(ignored(rules, "vendor", true), None);
+    }
+
+    #[test]
+    fn trailing_slash_matches_only_directories() {
+        let rules = "out/\n";
+        assert_eq!(ignored(rules, "out", true), Some(true));
+        assert_eq!(ignored(rules, "out", false), None);
+    }
+
+    #[test]
+    fn later_rules_win_and_negation_reincludes() {
+        let rules = "*.txt\n!keep.txt\n";
+        assert_eq!(ignored(rules, "a.txt", false), Some(true));
+        assert_eq!(ignored(rules, "keep.txt", false), Some(false));
+        let rules = "!keep.txt\n*.txt\n";
+        assert_eq!(ignored(rules, "keep.txt", false), Some(true));
+    }
+
+    #[test]
+    fn comments_blanks_and_escapes() {
+        let rules = "# comment\n\n\\#hash\n\\!bang\ntrailing   \nspace\\ \n";
+        assert_eq!(ignored(rules, "# comment", false), None);
+        assert_eq!(ignored(rules, "#hash", false), Some(true));
+        assert_eq!(ignored(rules, "!bang", false), Some(true));
+        assert_eq!(ignored(rules, "trailing", false), Some(true));
+        assert_eq!(ignored(rules, "space ", false), Some(true));
+        assert_eq!(ignored(rules, "space", false), None);
+    }
+
+    #[test]
+    fn crlf_rules_are_accepted() {
+        assert_eq!(ignored("*.o\r\nlib/\r\n", "a.o", false), Some(true));
+        assert_eq!(ignored("*.o\r\nlib/\r\n", "lib", true), Some(true));
+    }
+
+    #[test]
+    fn bad_rules_are_skipped() {
+        // 无法翻译的字符类只跳过这一条
+        let rules = "[[:nope:]]\n*.bak\n";
+        assert_eq!(ignored(rules, "a.bak", false), Some(true));
+    }
+
+    // globs 是 (通配符, 是否不区分大小写)
+    fn overridden(globs: &[(&str, bool)], path: &str, is_dir: bool) -> Option<bool> {
+        let mut overrides = Overrides::default();
+        for (glob, case_insensitive) in globs {
+            overrides.add(glob, *case_insensitive).unwrap();
+        }
+        overrides.matched(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn include_globs_select_files_and_skip_the_rest() {
+        let globs = [("*.rs", false)];
+        assert_eq!(overridden(&globs, "src/main.rs", false), Some(false));
+        assert_eq!(overridden(&globs, "README.md", false), Some(true));
+        // 目录照常进入
+        assert_eq!(overridden(&globs, "src", true), None);
+        // 区分大小写
+        assert_eq!(overridden(&globs, "MAIN.RS", false), Some(true));
+    }
+
+    #[test]
+    fn exclude_globs_skip_files_and_directories() {
+        // --exclude GLOB 就是 !GLOB
+        let globs = [("!*.min.js", false), ("!vendor", false)];
+        assert_eq!(overridden(&globs, "a.min.js", false), Some(true));
+        assert_eq!(overridden(&globs, "vendor", true), Some(true));
+        // 只有排除时其余的交给忽略文件决定
+        assert_eq!(overridden(&globs, "a.js", false), None);
+    }
+
+    #[test]
+    fn later_globs_win_across_include_and_exclude() {
+        let globs = [("*.rs", false), ("!gen_*.rs", false)];
+        assert_eq!(overridden(&globs, "gen_a.rs", false), Some(true));
+        assert_eq!(overridden(&globs, "a.rs", false), Some(false));
+        let globs = [("!gen_*.rs", false), ("*.rs", false)];
+        assert_eq!(overridden(&globs, "gen_a.rs", false), Some(false));
+    }
+
+    #[test]
+    fn globs_that_match_nothing_are_errors() {
+        assert!(Overrides::default().add("!", false).is_err());
+        assert!(Overrides::default().add("/", false).is_err());
+    }
+
+    #[test]
+    fn iglob_ignores_case_in_the_whole_path() {
+        let globs = [("docs/*makefile", true)];
+        assert_eq!(overridden(&globs, "docs/Makefile", false), Some(false));
+        assert_eq!(overridden(&globs, "DOCS/GNUmakefile", false), Some(false));
+        assert_eq!(overridden(&globs, "Docs/sub/makefile", false), Some(true));
+        let globs = [("!BUILD/", true)];
+        assert_eq!(overridden(&globs, "build", true), Some(true));
+        assert_eq!(overridden(&globs, "Build", true), Some(true));
+    }
+
+    #[test]
+    fn iglob_and_include_share_one_ordered_layer() {
+        // 后面的优先，不论哪一个区分大小写
+        let globs = [("readme*", true), ("!README.old", false)];
+        assert_eq!(overridden(&globs, "README.old", false), Some(true));
+        assert_eq!(overridden(&globs, "readme.old", false), Some(false));
+        let globs = [("!readme.old", false), ("README*", true)];
+        assert_eq!(overridden(&globs, "readme.old", false), Some(false));
+    }
+
+    #[test]
+    fn iglob_matches_paths_built_with_the_platform_separator() {
+        // Windows 上路径用 \ 分隔，大小写也可能与通配符不同；路径按组成部分比较，与分隔符无关
+        let path: PathBuf = ["SRC", "Sub", "Main.rs"].iter().collect();
+        let globs = [("src/**/*.RS", true)];
+        assert_eq!(
+            overridden(&globs, path.to_str().unwrap(), false),
+            Some(false)
+        );
+        let globs = [("src/**/*.RS", false)];
+        assert_eq!(
+            overridden(&globs, path.to_str().unwrap(), false),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn core_excludes_file_reads_the_core_section() {
+        let config = "[user]\n\texcludesFile = wrong\n[Core]\n\t# comment\n\tExcludesFile = \"~/my ignore\"\n";
+        assert_eq!(core_excludes_file(config).as_deref(), Some("~/my ignore"));
+        assert_eq!(core_excludes_file("[core]\n\teditor = vi\n"), None);
+        // 后面的设置优先
+        let config = "[core]\nexcludesfile = a\n[core]\nexcludesfile = b\n";
+        assert_eq!(core_excludes_file(config).as_deref(), Some("b"));
+    }
+}
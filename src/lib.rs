@@ -0,0 +1,34 @@
+// mrustgrep 的库部分：搜索核心与输出相关的公共组件，命令行程序建立在它之上
+pub mod binary;
+pub mod color;
+pub mod context;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fold;
+#[cfg(feature = "fs")]
+pub mod follow;
+pub mod fuzzy;
+pub mod glob;
+#[cfg(feature = "fs")]
+pub mod ignore;
+pub mod matcher;
+#[cfg(feature = "fs")]
+pub mod parallel;
+pub mod posix;
+#[cfg(feature = "fs")]
+pub mod preprocess;
+pub mod printer;
+#[cfg(feature = "fs")]
+pub mod rewrite;
+pub mod search;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod terminal;
+#[cfg(feature = "fs")]
+pub mod types;
+#[cfg(feature = "fs")]
+pub mod visited;
+#[cfg(feature = "fs")]
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,78 +1,1830 @@
 use std::{
-    io::{self, Write}, // 导入标准输入输出相关模块
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, Write}, // 导入标准输入输出相关模块
+    iter,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result}; // 错误处理库
-use clap::{Arg, Command}; // 命令行参数解析库
+use clap::parser::ValueSource;
 
-use crate::search::Searcher;
+use mrustgrep::binary;
+use mrustgrep::color::Colors;
+use mrustgrep::follow::{FollowReader, Rotation};
+use mrustgrep::ignore::{self, IgnoreFile, Overrides};
+use mrustgrep::parallel::ParallelSearcher;
+use mrustgrep::posix;
+use mrustgrep::preprocess::Preprocessor;
+use mrustgrep::printer::{
+    ColumnUnit, CsvPrinter, Format, InputErrorSummary, JsonPrinter, LineNumberWidth,
+    PatternSummary, Printer, SarifPrinter, StandardPrinter, Summary, write_label,
+};
+use mrustgrep::rewrite;
+use mrustgrep::search::{
+    self, LongLine, MatchTimeout, PatternError, PrintStyle, SearchResult, Searcher, WordBoundary,
+};
+use mrustgrep::terminal::{ColorChoice, InteractiveStdin, Terminal};
+use mrustgrep::types::FileTypes;
+use mrustgrep::visited::Visited;
+use mrustgrep::walk::{self, DeviceAction, DirectoryAction, SortBy, WalkItem, WalkOptions, Walker};
 
-mod search;
+use crate::cli::LineRange;
+use crate::patterns::{PatternSource, Patterns};
+use crate::prefetch::{Searched, Work};
+use crate::progress::Progress;
+use crate::watch::{Changes, Watch};
+
+mod cli;
+mod config;
+mod patterns;
+mod prefetch;
+mod progress;
+mod watch;
 
 fn main() -> Result<()> {
-    // 构建命令行参数解析器
-    let matches = Command::new("mrustgrep")
-        .version("0.1.0")
-        .author("Your Name")
-        .about("A simple Rust implementation of grep")
-        .arg(
-            Arg::new("pattern")
-                .required(true)
-                .index(1)
-                .help("The pattern to search for"), // 需要查找的模式
-        )
-        .get_matches();
-
-    // 获取命令行参数中的 pattern
-    let pattern = matches
-        .get_one::<String>("pattern")
-        .context("Failed to get pattern")?;
+    // 配置文件中的参数放在命令行参数之前，命令行中的同名参数会覆盖它们
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let config = match config::skip_requested(&args) {
+        true => None,
+        false => match config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Application error: {e:#}");
+                std::process::exit(2);
+            }
+        },
+    };
+    let mut full_args = args[..1].to_vec();
+    if let Some(config) = &config {
+        full_args.extend(config.args.iter().map(|arg| OsString::from(&arg.value)));
+    }
+    full_args.extend(args[1..].iter().cloned());
+
+    let matches = match cli::build().try_get_matches_from(cli::split_attached_values(full_args)) {
+        Ok(matches) => matches,
+        Err(err) => {
+            // 出错的值来自环境变量时，指出是哪个变量
+            if let Some(var) = cli::blame_env(&err) {
+                eprintln!("mrustgrep: invalid value in environment variable {}", var);
+            }
+            // 出错的参数来自配置文件时，指出是哪个文件的哪一行
+            else if let Some(arg) = config.as_ref().and_then(|config| config.blame(&err)) {
+                eprintln!(
+                    "mrustgrep: error in config file {} line {}: {}",
+                    config.as_ref().unwrap().path.display(),
+                    arg.line,
+                    arg.value
+                );
+            }
+            err.exit();
+        }
+    };
+    // 模式参数可以以 - 开头，配置文件里拼错的参数名会被当成模式，这里当作未知参数报错
+    if let Some((config, arg)) = config
+        .as_ref()
+        .and_then(|config| Some((config, config.stray_pattern()?)))
+    {
+        eprintln!(
+            "mrustgrep: error in config file {} line {}: unexpected argument {}",
+            config.path.display(),
+            arg.line,
+            arg.value
+        );
+        std::process::exit(2);
+    }
+
+    // 只生成补全脚本，不做搜索
+    if let Some(&shell) = matches.get_one::<clap_complete::Shell>("generate-completions") {
+        clap_complete::generate(shell, &mut cli::build(), "mrustgrep", &mut io::stdout());
+        return Ok(());
+    }
+
+    // 给出了 -e / -f 时，第一个位置参数其实是要搜索的文件
+    // 只给出 --between 时同样如此，模式是空串，区间里的每一行都被选中
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let between = patterns::between(&matches);
+    let mut patterns = match patterns::collect(&matches) {
+        Ok(Some(patterns)) => {
+            paths.extend(matches.get_one::<String>("pattern").map(PathBuf::from));
+            patterns
+        }
+        Ok(None) if between.is_some() => {
+            paths.extend(matches.get_one::<String>("pattern").map(PathBuf::from));
+            Patterns {
+                texts: vec![String::new()],
+                sources: vec![PatternSource::Positional],
+                ..Patterns::default()
+            }
+        }
+        Ok(None) => {
+            let pattern = matches
+                .get_one::<String>("pattern")
+                .context("Failed to get pattern")?;
+            Patterns {
+                texts: vec![pattern.clone()],
+                sources: vec![PatternSource::Positional],
+                ..Patterns::default()
+            }
+        }
+        Err(e) => {
+            eprintln!("Application error: {e:#}");
+            std::process::exit(2);
+        }
+    };
+    patterns.excluded = patterns::excluded(&matches);
+
+    // 排序需要先拿到每个文件的计数，只对 -c / -l 有意义
+    if matches.get_flag("sort-by-count")
+        && !matches.contains_id("counting")
+        && !matches.get_flag("files-with-matches")
+    {
+        eprintln!("Application error: --sort-by-count requires --count or --files-with-matches");
+        std::process::exit(2);
+    }
+
+    paths.extend(
+        matches
+            .get_many::<PathBuf>("paths")
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+    // Windows 的 shell 不展开通配符，由程序自己展开；其他平台上 shell 已经展开过，参数原样使用
+    if cfg!(windows) {
+        let mut expanded = Vec::new();
+        for path in paths {
+            match walk::expand_wildcards(&path, Path::exists, true) {
+                Ok(Some(files)) => expanded.extend(files),
+                Ok(None) => expanded.push(path),
+                Err(e) => {
+                    eprintln!("Application error: {e:#}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        paths = expanded;
+    }
+    // 标准输入只能读一次
+    if paths.iter().filter(|path| path.as_os_str() == "-").count() > 1 {
+        eprintln!("Application error: '-' (standard input) can be given only once");
+        std::process::exit(2);
+    }
+
+    // -q 不输出任何内容，-c、-l、-L 及其修饰参数都不再起作用
+    let quiet = matches.get_flag("quiet");
+    let reported = |id: &str| !quiet && matches.get_flag(id);
+    let report = ReportConfig {
+        quiet,
+        count: !quiet && matches.contains_id("counting"),
+        count_matches: reported("count-matches"),
+        files_with_matches: reported("files-with-matches"),
+        files_without_match: reported("files-without-match"),
+        include_zero: reported("include-zero"),
+        sort_by_count: reported("sort-by-count"),
+        count_per_pattern: reported("count-per-pattern"),
+        all_match: matches.get_flag("all-match"),
+        max_total_matches: matches
+            .get_one::<usize>("max-total-matches")
+            .copied()
+            .filter(|_| !quiet),
+    };
+
+    // 输出文件，"-" 等同于不指定（写到 stdout）
+    let path = matches
+        .get_one::<PathBuf>("output")
+        .filter(|path| path.as_os_str() != "-")
+        .cloned();
+
+    let mut terminal = Terminal::detect();
+
+    // 没有文件参数又没有管道输入时，进程会一直等键盘输入，看起来像卡死了
+    // 默认改为递归搜索当前目录（与 -r 不带路径相同）；--no-default-recurse 时照旧读终端，按 --interactive-stdin 提示
+    // 显式写出 "-" 说明用户确实想从终端输入，不做提示
+    let recursive = matches.get_flag("recursive")
+        || matches.get_one::<DirectoryAction>("directories") == Some(&DirectoryAction::Recurse);
+    let default_recurse = paths.is_empty()
+        && !recursive
+        && terminal.stdin_tty
+        && !matches.get_flag("no-default-recurse");
+    let recursive = recursive || default_recurse;
+    if paths.is_empty() && !recursive && terminal.stdin_tty {
+        match matches
+            .get_one::<InteractiveStdin>("interactive-stdin")
+            .unwrap()
+        {
+            InteractiveStdin::Warn => eprintln!(
+                "mrustgrep: reading from terminal; did you mean to pass a file or pipe input? Ctrl-D to end"
+            ),
+            InteractiveStdin::Error => {
+                eprintln!(
+                    "Application error: no input files given and stdin is a terminal; pass '-' to read from it"
+                );
+                std::process::exit(2);
+            }
+            InteractiveStdin::Allow => {}
+        }
+    }
+
+    // 根据终端环境推导出 auto 默认值，显式参数总是优先
+    // 写入文件时 stdout 是否为终端已经无关紧要，颜色只能由 --color=always 打开
+    if path.is_some() {
+        terminal.stdout_tty = false;
+    }
+    // --pretty 只提供默认值，命令行（包括配置文件）中显式给出的参数优先
+    let pretty = matches.get_flag("pretty");
+    let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let color_choice = match pretty && !explicit("color") {
+        true => ColorChoice::Always,
+        false => *matches.get_one::<ColorChoice>("color").unwrap(),
+    };
+    let color = terminal.color(color_choice);
+    let colors = match color && terminal.enable_ansi() {
+        true => {
+            let specs = matches.get_many::<String>("colors").into_iter().flatten();
+            // 每条规则在参数解析阶段已经校验过
+            Some(Colors::with_specs(specs.map(String::as_str))?)
+        }
+        false => None,
+    };
+    let json =
+        matches.get_flag("json") || matches.get_one::<Format>("format") == Some(&Format::Json);
+    let output = OutputConfig {
+        with_filename: match (
+            matches.get_flag("with-filename"),
+            matches.get_flag("no-filename"),
+        ) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+        heading: match (matches.get_flag("heading"), matches.get_flag("no-heading")) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => pretty,
+        },
+        path,
+        path_form: match (
+            matches.get_flag("absolute-path"),
+            matches.get_flag("relative"),
+        ) {
+            (true, _) => PathForm::Absolute,
+            (false, true) => match std::env::current_dir() {
+                Ok(cwd) => PathForm::Relative(cwd),
+                Err(_) => PathForm::AsGiven,
+            },
+            (false, false) => PathForm::AsGiven,
+        },
+        path_separator: matches.get_one::<char>("path-separator").copied(),
+        stdin_label: matches.get_one::<String>("label").unwrap().clone(),
+        replace: matches.get_one::<String>("replace").cloned(),
+        format: match matches.get_flag("json") {
+            true => Format::Json,
+            false => *matches.get_one::<Format>("format").unwrap(),
+        },
+        column_unit: *matches.get_one::<ColumnUnit>("column-unit").unwrap(),
+        line_number_width: matches
+            .get_one::<LineNumberWidth>("line-number-width")
+            .copied(),
+        style: PrintStyle {
+            colors,
+            max_columns: matches.get_one::<usize>("max-columns").copied(),
+            match_separator: matches
+                .get_one::<String>("field-match-separator")
+                .unwrap()
+                .clone(),
+            context_separator: matches
+                .get_one::<String>("field-context-separator")
+                .unwrap()
+                .clone(),
+            join_matches: matches.get_one::<String>("join-matches").cloned(),
+            column_unit: *matches.get_one::<ColumnUnit>("column-unit").unwrap(),
+            record_terminator: match matches.get_flag("null-data-out") {
+                true => '\0',
+                false => '\n',
+            },
+            ..PrintStyle::default()
+        },
+        line_buffered: matches.get_flag("line-buffered")
+            || matches.get_flag("follow")
+            || terminal.line_buffered(),
+        // --debug 的诊断信息同样输出在 stderr 上，不再显示进度；-q 没有输出，--json 的输出由程序读取，也不显示
+        progress: (matches.get_flag("progress")
+            && terminal.progress()
+            && !matches.get_flag("debug")
+            && !matches.get_flag("quiet")
+            && !json)
+            .then(|| terminal.stdout_tty && !matches.contains_id("output")),
+    };
 
     // 执行主逻辑，处理错误
-    match run(pattern) {
-        Ok(count) => {
-            eprintln!("Total matched lines: {}", count);
-            Ok(())
+    // 退出码与 grep 保持一致：0 有匹配，1 无匹配，2 出错
+    let debug = matches.get_flag("debug");
+    if debug {
+        debug_sources(config.as_ref(), config::skip_requested(&args), &matches);
+        if paths.is_empty() {
+            eprintln!(
+                "mrustgrep[debug]: no path given: {}",
+                match (recursive, terminal.stdin_tty) {
+                    _ if default_recurse => {
+                        "stdin is a terminal, searching the current directory recursively"
+                    }
+                    (true, _) => "searching the current directory recursively",
+                    (false, true) =>
+                        "reading standard input from the terminal (--no-default-recurse)",
+                    (false, false) => "reading standard input",
+                }
+            );
+        }
+        if pretty {
+            let overridden: Vec<&str> = [
+                ("color", "--color=always"),
+                ("heading", "--heading"),
+                ("no-heading", "--heading"),
+                ("line-number", "--line-number"),
+                ("no-line-number", "--line-number"),
+            ]
+            .into_iter()
+            .filter(|(id, _)| explicit(id))
+            .map(|(_, implied)| implied)
+            .collect();
+            eprintln!(
+                "mrustgrep[debug]: --pretty implies --color=always --heading --line-number{}",
+                match overridden.is_empty() {
+                    true => String::new(),
+                    false => format!("; overridden by explicit flags: {}", overridden.join(" ")),
+                }
+            );
+        }
+    }
+
+    // 搜索选项
+    let opts = search::Options {
+        // 行号默认只在输出整行时显示，-n / -N 明确指定时以它们为准
+        show_line_number: match (
+            matches.get_flag("line-number"),
+            matches.get_flag("no-line-number"),
+        ) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => pretty || !matches.get_flag("only-matching"),
+        },
+        show_byte_offset: matches.get_flag("byte-offset"),
+        show_column: matches.get_flag("column"),
+        count_only: report.per_input(),
+        case_ignore: patterns::case_ignore(
+            &matches,
+            patterns
+                .texts
+                .iter()
+                .chain(&patterns.excluded)
+                .chain(between.iter().flat_map(|(start, end)| [start, end]))
+                .map(String::as_str),
+        ),
+        match_only: matches.get_flag("only-matching"),
+        snippet: matches.get_one::<usize>("snippet").copied(),
+        json,
+        invert_match: matches.get_flag("invert-match"),
+        fixed_strings: matches.get_flag("fixed-strings"),
+        glob: matches.get_flag("glob-pattern"),
+        posix: match (
+            matches.get_flag("posix-basic"),
+            matches.get_flag("posix-extended"),
+        ) {
+            (true, _) => Some(posix::Syntax::Basic),
+            (_, true) => Some(posix::Syntax::Extended),
+            _ => None,
+        },
+        fuzzy: matches.get_one::<usize>("fuzzy").copied(),
+        overlapping: matches.get_flag("overlapping"),
+        anchored: matches.get_flag("anchored"),
+        max_matches_per_line: matches.get_one::<usize>("max-matches-per-line").copied(),
+        word: matches
+            .get_flag("word-regexp")
+            .then(|| *matches.get_one::<WordBoundary>("word-boundary").unwrap()),
+        line_regexp: matches.get_flag("line-regexp"),
+        stop_on_nonmatch: matches.get_flag("stop-on-nonmatch"),
+        max_count: matches.get_one::<usize>("max-count").copied(),
+        paragraph: matches.get_flag("paragraph"),
+        skip_bytes: matches
+            .get_one::<usize>("skip-bytes")
+            .map_or(0, |bytes| *bytes as u64),
+        skip_lines: match matches.get_one::<LineRange>("line-range") {
+            Some(range) => range.first - 1,
+            None => matches.get_one::<usize>("skip-lines").copied().unwrap_or(0),
+        },
+        last_line: matches
+            .get_one::<LineRange>("line-range")
+            .and_then(|range| range.last),
+        between,
+        all_match: report.all_match && !report.all_match_per_file(),
+        buffer_size: matches.get_one::<usize>("buffer-size").copied(),
+        max_line_length: matches.get_one::<usize>("max-line-length").copied(),
+        // -A / -B 优先于 -C
+        before_context: matches
+            .get_one::<usize>("before-context")
+            .or(matches.get_one::<usize>("context"))
+            .copied()
+            .unwrap_or(0),
+        after_context: matches
+            .get_one::<usize>("after-context")
+            .or(matches.get_one::<usize>("context"))
+            .copied()
+            .unwrap_or(0),
+        // 分组只用于高亮，不输出颜色时不必计算
+        capture_groups: matches.get_flag("color-groups") && output.style.colors.is_some(),
+        regex_size_limit: matches.get_one::<usize>("regex-size-limit").copied(),
+        dfa_size_limit: matches.get_one::<usize>("dfa-size-limit").copied(),
+        match_timeout: matches
+            .get_one::<u64>("match-timeout")
+            .map(|ms| Duration::from_millis(*ms)),
+    };
+
+    // --follow 只跟踪单个文件，Ctrl-C 结束跟踪后照常汇报结果
+    let follow = match matches.get_flag("follow") {
+        true => {
+            if paths.len() != 1 || paths[0].as_os_str() == "-" {
+                eprintln!("Application error: --follow requires exactly one file");
+                std::process::exit(2);
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            let handler_stop = stop.clone();
+            if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst)) {
+                eprintln!("Application error: Failed to install Ctrl-C handler: {e}");
+                std::process::exit(2);
+            }
+            Some(stop)
+        }
+        false => None,
+    };
+
+    let pre = match matches.get_one::<PathBuf>("pre") {
+        Some(command) => {
+            let globs: Vec<&String> = matches
+                .get_many::<String>("pre-glob")
+                .into_iter()
+                .flatten()
+                .collect();
+            match Preprocessor::new(command, &globs) {
+                Ok(pre) => Some(pre),
+                Err(e) => {
+                    eprintln!("Application error: {e:#}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // -u 展开成 --no-ignore，-uu 再加上 --hidden，-uuu 再加上 --text，和直接给出这些开关一样
+    // 出现在最后一个 -u 之后的反向开关（--ignore、--no-hidden、--no-text）仍然优先
+    let unrestricted = matches.get_count("unrestricted");
+    // 没有给出的开关也有默认值和位置，只比较命令行（包括配置文件）上出现过的
+    let last_index = |id: &str| {
+        explicit(id)
+            .then(|| matches.indices_of(id).and_then(|indices| indices.max()))
+            .flatten()
+    };
+    let mut expanded = Vec::new();
+    let mut undone = Vec::new();
+    let [no_ignore, hidden, text] = [
+        (1, "no-ignore", "ignore"),
+        (2, "hidden", "no-hidden"),
+        (3, "text", "no-text"),
+    ]
+    .map(|(level, flag, undo)| {
+        let by_unrestricted = unrestricted >= level;
+        if by_unrestricted {
+            expanded.push(format!("--{flag}"));
+        }
+        let later_undo = by_unrestricted && last_index(undo) > last_index("unrestricted");
+        if later_undo {
+            undone.push(format!("--{undo}"));
         }
+        matches.get_flag(flag) || (by_unrestricted && !later_undo)
+    });
+    if debug && unrestricted > 0 {
+        eprintln!(
+            "mrustgrep[debug]: -{} expands to {}{}",
+            "u".repeat(unrestricted.into()),
+            expanded.join(" "),
+            match undone.is_empty() {
+                true => String::new(),
+                false => format!(", overridden by a later {}", undone.join(" ")),
+            }
+        );
+    }
+
+    // git 的全局排除文件与 .gitignore 一样只在使用 VCS 忽略文件时读取，默认位置上通常没有文件，不存在时不提示
+    let ignore_vcs = !no_ignore && !matches.get_flag("no-ignore-vcs");
+    let git_excludes = match ignore::git_excludes_file() {
+        Some(path) if ignore_vcs => match IgnoreFile::load(&path) {
+            Ok(file) => {
+                if debug {
+                    eprintln!(
+                        "mrustgrep[debug]: git excludes file {}{}",
+                        path.display(),
+                        if file.is_some() { "" } else { " (not found)" }
+                    );
+                }
+                file.map(Arc::new)
+            }
+            Err(e) => {
+                eprintln!("mrustgrep: Failed to read {}: {e}", path.display());
+                None
+            }
+        },
+        _ => None,
+    };
+
+    // 全局忽略文件不存在时只警告，与没有给出一样
+    let global_ignore = match matches.get_one::<PathBuf>("global-ignore-file") {
+        Some(path) if !no_ignore => match IgnoreFile::load(path) {
+            Ok(Some(file)) => Some(Arc::new(file)),
+            Ok(None) => {
+                eprintln!(
+                    "mrustgrep: global ignore file {} does not exist",
+                    path.display()
+                );
+                None
+            }
+            Err(e) => {
+                eprintln!("Application error: Failed to read {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        },
+        _ => None,
+    };
+
+    // --include、--exclude 和 --iglob 共用一组通配符，按它们在命令行上出现的顺序添加，后面的优先
+    let mut globs: Vec<(usize, &str, String, bool)> = Vec::new();
+    for id in ["include", "exclude", "iglob"] {
+        let (Some(values), Some(indices)) =
+            (matches.get_many::<String>(id), matches.indices_of(id))
+        else {
+            continue;
+        };
+        for (glob, index) in values.zip(indices) {
+            let glob = match id {
+                "exclude" => format!("!{glob}"),
+                _ => glob.clone(),
+            };
+            globs.push((index, id, glob, id == "iglob"));
+        }
+    }
+    globs.sort_by_key(|&(index, ..)| index);
+    let mut overrides = Overrides::default();
+    for (_, id, glob, case_insensitive) in &globs {
+        if let Err(e) = overrides.add(glob, *case_insensitive) {
+            eprintln!("Application error: --{id}: {e:#}");
+            std::process::exit(2);
+        }
+    }
+    let names = |id: &str| -> Vec<&str> {
+        matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    };
+    let types = match FileTypes::new(&names("type"), &names("type-not")) {
+        Ok(types) => types,
         Err(e) => {
-            eprintln!("Application error: {e}");
-            std::process::exit(1);
+            eprintln!("Application error: {e:#}");
+            std::process::exit(2);
+        }
+    };
+
+    // --watch 要在第一次搜索之前开始监视，否则搜索期间的修改会漏掉
+    let watch = match matches.get_flag("watch") {
+        true => {
+            let roots = match (paths.is_empty(), recursive) {
+                (true, true) => vec![PathBuf::from(".")],
+                (true, false) => Vec::new(),
+                (false, _) => paths.clone(),
+            };
+            if roots.is_empty() || roots.iter().any(|path| path.as_os_str() == "-") {
+                eprintln!("Application error: --watch cannot watch stdin");
+                std::process::exit(2);
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            let handler_stop = stop.clone();
+            if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst)) {
+                eprintln!("Application error: Failed to install Ctrl-C handler: {e}");
+                std::process::exit(2);
+            }
+            match Watch::new(&roots, stop) {
+                Ok(watch) => Some(watch),
+                Err(e) => {
+                    eprintln!("Application error: {e:#}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        false => None,
+    };
+
+    let write = matches.get_flag("write");
+    if write
+        && ((paths.is_empty() && !recursive) || paths.iter().any(|path| path.as_os_str() == "-"))
+    {
+        eprintln!("Application error: --write cannot rewrite stdin");
+        std::process::exit(2);
+    }
+
+    // -j 只适用于逐个文件搜索；与这些参数一起在命令行上给出时报错，来自 MRUSTGREP_THREADS 的值则不起作用
+    let sequential = ["follow", "write", "parallel-file", "binary-offsets"]
+        .into_iter()
+        .find(|id| matches.get_flag(id));
+    if let Some(id) = sequential {
+        if explicit("threads") {
+            eprintln!("Application error: --threads cannot be used with --{}", id);
+            std::process::exit(2);
+        }
+        if debug && matches.value_source("threads") == Some(ValueSource::EnvVariable) {
+            eprintln!(
+                "mrustgrep[debug]: MRUSTGREP_THREADS ignored: --{} searches sequentially",
+                id
+            );
+        }
+    }
+
+    let mut input_config = InputConfig {
+        follow,
+        pre,
+        write,
+        write_symlinks: matches.get_flag("write-symlinks"),
+        binary_offsets: matches
+            .get_flag("binary-offsets")
+            .then(|| *matches.get_one::<usize>("binary-window").unwrap()),
+        skip_binary: !text && !matches.get_flag("binary-offsets"),
+        changed: None,
+        overrides: (!overrides.is_empty()).then(|| Arc::new(overrides)),
+        types: (!types.is_empty()).then(|| Arc::new(types)),
+        walk: WalkOptions {
+            recursive,
+            directories: *matches.get_one::<DirectoryAction>("directories").unwrap(),
+            devices: *matches.get_one::<DeviceAction>("devices").unwrap(),
+            one_file_system: matches.get_flag("one-file-system"),
+            ignore_dot: !no_ignore,
+            ignore_vcs,
+            hidden,
+        },
+        git_excludes,
+        global_ignore,
+        sort: match (
+            matches.get_one::<SortBy>("sort"),
+            matches.get_one::<SortBy>("sortr"),
+        ) {
+            (Some(sort), _) => Some((*sort, false)),
+            (None, Some(sort)) => Some((*sort, true)),
+            (None, None) => None,
+        },
+        dedupe: !matches.get_flag("no-dedupe"),
+        parallel_file: matches.get_flag("parallel-file"),
+        mmap: matches.get_flag("mmap"),
+        threads: match *matches.get_one::<usize>("threads").unwrap() {
+            _ if sequential.is_some() => 1,
+            threads => threads,
+        },
+        no_messages: matches.get_flag("no-messages"),
+    };
+
+    // --watch 的各轮都写到同一个输出中，输出文件只在这里创建（截断）一次
+    let mut writer = match open_output(&output) {
+        Ok(writer) => io::BufWriter::new(writer),
+        Err(e) => exit_with_error(&patterns, e),
+    };
+    // 输出文件在被监视的目录中时，写入结果不算变化，否则每写一次都会触发新的一轮
+    let watch = match (watch, &output.path) {
+        (Some(watch), Some(path)) => Some(watch.with_ignored(path)),
+        (watch, _) => watch,
+    };
+    let mut result = run(
+        &patterns,
+        opts.clone(),
+        &paths,
+        &input_config,
+        &report,
+        &output,
+        &mut writer,
+        debug,
+    );
+    // --watch 时退出码汇总所有轮次：有过匹配即为 0，有过输入出错即为 2
+    let (mut matched, mut failed) = (false, false);
+    loop {
+        let summary = match result {
+            Ok(summary) => summary,
+            Err(e) => exit_with_error(&patterns, e),
+        };
+        if !summary.errors.is_empty() && !input_config.no_messages {
+            // 没有单独路径的错误用错误消息本身代替
+            let labels: Vec<String> = summary
+                .errors
+                .iter()
+                .map(|err| err.label.clone().unwrap_or_else(|| err.error.to_string()))
+                .collect();
+            eprintln!(
+                "mrustgrep: {} input(s) could not be searched: {}",
+                labels.len(),
+                labels.join(", ")
+            );
         }
+        if !report.quiet {
+            eprintln!("Total matched lines: {}", summary.matched);
+        }
+        // -L 与 grep 一样，列出了路径才算成功
+        matched |= match report.files_without_match {
+            true => summary.listed > 0,
+            false => summary.matched > 0,
+        };
+        failed |= !summary.errors.is_empty();
+
+        let Some(changes) = watch
+            .as_ref()
+            .and_then(|watch| next_changes(watch, &output))
+        else {
+            break;
+        };
+        let changed: Vec<String> = changes
+            .files
+            .iter()
+            .chain(&changes.dirs)
+            .map(|path| display_path(path, &output))
+            .collect();
+        let separated = writeln!(writer, "--- changed: {} ---", changed.join(", "));
+        if let Err(e) = separated {
+            exit_with_error(
+                &patterns,
+                anyhow::Error::from(e).context("Failed to write output"),
+            );
+        }
+        let roots = changes.roots.clone();
+        input_config.changed = Some(changes);
+        result = run(
+            &patterns,
+            opts.clone(),
+            &roots,
+            &input_config,
+            &report,
+            &output,
+            &mut writer,
+            debug,
+        );
+    }
+    // 与 grep 一样，有输入出错时即使有匹配也返回 2，只有 -q 时有匹配就返回 0
+    let code = match (failed, matched) {
+        (_, true) if report.quiet => 0,
+        (true, _) => 2,
+        (false, true) => 0,
+        (false, false) => 1,
+    };
+    std::process::exit(code);
+}
+
+// 等待下一批需要重新搜索的变化，只有删除的变化报告之后继续等待；Ctrl-C 时返回 None
+fn next_changes(watch: &Watch, output: &OutputConfig) -> Option<Changes> {
+    loop {
+        let changes = watch.wait()?;
+        for path in &changes.removed {
+            eprintln!("mrustgrep: {} was removed", display_path(path, output));
+        }
+        if !changes.roots.is_empty() {
+            return Some(changes);
+        }
+    }
+}
+
+// run 本身出错（而不是某个输入出错）时结束程序
+fn exit_with_error(patterns: &Patterns, e: anyhow::Error) -> ! {
+    // 模式编译错误同时指出是哪一个模式
+    if let Some(err) = e.downcast_ref::<PatternError>() {
+        eprintln!(
+            "Application error in {}:\n{}",
+            patterns.source(err.index),
+            err
+        );
+    } else {
+        eprintln!("Application error: {e:#}");
+    }
+    std::process::exit(2);
+}
+
+// 如何处理输入文件
+struct InputConfig {
+    // 目录遍历选项
+    walk: WalkOptions,
+    // git 的全局排除文件，--no-ignore 和 --no-ignore-vcs 时为 None
+    git_excludes: Option<Arc<IgnoreFile>>,
+    // --global-ignore-file，--no-ignore 时为 None
+    global_ignore: Option<Arc<IgnoreFile>>,
+    // --include、--exclude 和 --iglob
+    overrides: Option<Arc<Overrides>>,
+    // --type / --type-not
+    types: Option<Arc<FileTypes>>,
+    // 搜索前对文件排序的依据，以及是否倒序
+    sort: Option<(SortBy, bool)>,
+    // 同一个文件（按设备号和 inode 判断）只搜索一次
+    dedupe: bool,
+    // --follow 时持续跟踪文件增长，置位后停止跟踪
+    follow: Option<Arc<AtomicBool>>,
+    // --pre 的预处理命令
+    pre: Option<Preprocessor>,
+    // --write：把替换写回文件而不是输出结果
+    write: bool,
+    // --binary-offsets 时二进制输入中每个匹配前后显示的字节数
+    binary_offsets: Option<usize>,
+    // 不搜索二进制输入：没有 -a/--text，也没有 --binary-offsets
+    skip_binary: bool,
+    // --write-symlinks
+    write_symlinks: bool,
+    // --watch 重新搜索时只搜索这些变化
+    changed: Option<Changes>,
+    // 把单个文件切块后多线程搜索
+    parallel_file: bool,
+    // --mmap：用内存映射搜索普通文件
+    mmap: bool,
+    // -j/--threads：同时搜索的文件数，0 表示每个 CPU 一个（见 ParallelSearcher::new），1 表示在主线程上顺序搜索
+    threads: usize,
+    // 不输出单个输入出错的消息（-s），出错的输入仍然会影响退出码
+    no_messages: bool,
+}
+
+// 没能搜索（或者没有搜索完）的输入，出错之后继续搜索其余的输入
+struct InputError {
+    // 输入的显示路径，遍历目录时出现的错误没有单独的路径（包含在错误消息中）
+    label: Option<String>,
+    // 打开或读取时的错误，最内层是操作系统给出的错误
+    error: anyhow::Error,
+}
+
+impl InputError {
+    // 交给输出器的形式（JSON 输出的 summary 对象中的 errors）
+    fn summary(&self) -> InputErrorSummary {
+        InputErrorSummary {
+            path: self.label.clone(),
+            message: format!("{:#}", self.error),
+            os_error: self
+                .error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<io::Error>())
+                .map(io::Error::to_string),
+        }
+    }
+}
+
+// 一次运行的结果
+struct RunSummary {
+    // 匹配的总行数
+    matched: usize,
+    // -L 列出的输入数
+    listed: usize,
+    errors: Vec<InputError>,
+}
+
+// 记录一个输入的错误，没有 -s 时立即输出
+fn input_failed(
+    errors: &mut Vec<InputError>,
+    input_config: &InputConfig,
+    label: Option<String>,
+    error: anyhow::Error,
+) {
+    let failed = InputError { label, error };
+    if !input_config.no_messages {
+        progress::erase();
+        eprintln!("mrustgrep: {:#}", failed.error);
     }
+    errors.push(failed);
 }
 
-// 主运行逻辑，接收正则模式，返回匹配的行数
-fn run(pattern: &str) -> Result<usize> {
+// 输出相关的最终配置（已经结合了终端探测结果）
+struct OutputConfig {
+    // 结果输出文件，None 表示 stdout
+    path: Option<PathBuf>,
+    // 是否输出文件名（-H / --no-filename），None 表示有多个输入或递归搜索时才输出
+    with_filename: Option<bool>,
+    // 文件名作为标题输出（--heading）
+    heading: bool,
+    // 输出路径的形式
+    path_form: PathForm,
+    // 输出路径时使用的目录分隔符，None 表示保持原样
+    path_separator: Option<char>,
+    // 标准输入在输出中的名字（--label）
+    stdin_label: String,
+    // --replace 的替换文本
+    replace: Option<String>,
+    // 逐行结果的输出格式
+    format: Format,
+    // 列号的单位
+    column_unit: ColumnUnit,
+    // 行号右对齐的宽度
+    line_number_width: Option<LineNumberWidth>,
+    // 颜色、长行截断等外观设置
+    style: PrintStyle,
+    // 是否每输出一行就刷新
+    line_buffered: bool,
+    // --progress 且 stderr 是终端，以及结果是否同样输出到终端
+    progress: Option<bool>,
+}
+
+// 按文件汇报的模式（-c / -l 及其修饰参数）
+struct ReportConfig {
+    // -q：不输出任何内容，读到第一个选中的行就结束整个运行
+    quiet: bool,
+    // 只输出每个输入的匹配行数
+    count: bool,
+    // -c 统计的是匹配次数而不是行数（--count-matches）
+    count_matches: bool,
+    // 只输出有匹配的输入路径
+    files_with_matches: bool,
+    // 只输出没有匹配的输入路径（-L）
+    files_without_match: bool,
+    // -c 时是否输出计数为 0 的输入
+    include_zero: bool,
+    // 是否缓存所有计数，结束后按计数从高到低输出
+    sort_by_count: bool,
+    // 结束时按模式汇报各自匹配的行数和次数
+    count_per_pattern: bool,
+    // --all-match 和 -l 一起使用：所有模式都在文件中出现过才列出该文件
+    all_match: bool,
+    // 所有输入一共最多汇报这么多匹配行，够数后整个运行停止（不再遍历目录、打开新的输入）
+    max_total_matches: Option<usize>,
+}
+
+impl ReportConfig {
+    // 是否处于按文件汇报的模式（不逐行输出匹配内容）
+    fn per_input(&self) -> bool {
+        self.quiet || self.count || self.lists_files()
+    }
+
+    // 是否只列出路径（-l / -L）
+    fn lists_files(&self) -> bool {
+        self.files_with_matches || self.files_without_match
+    }
+
+    // -q、-l、-L 只需要知道输入中有没有选中的行，读到第一个就可以停下
+    // --sort-by-count 要按行数排序，仍然要数完
+    fn first_match_only(&self) -> bool {
+        self.quiet || (self.lists_files() && !self.sort_by_count)
+    }
+
+    // --all-match 是否按整个文件判断（只有 -l / -L 是这样，其余情况按行判断）
+    fn all_match_per_file(&self) -> bool {
+        self.all_match && self.lists_files()
+    }
+
+    // 是否只需要每个输入的选中行数（-q、-c、-l），这时可以用 Searcher::is_match / count，不构造每一行的结果
+    fn lines_only(&self) -> bool {
+        self.per_input()
+            && !self.count_matches
+            && !self.count_per_pattern
+            && !self.all_match_per_file()
+            && self.max_total_matches.is_none()
+    }
+
+    // 已经汇报了 total 行之后还能汇报多少行
+    fn remaining(&self, total: usize) -> Option<usize> {
+        self.max_total_matches.map(|max| max.saturating_sub(total))
+    }
+}
+
+// 主运行逻辑，接收所有模式和输入文件，返回匹配的总行数和出错的输入
+// 单个输入打不开或者读取出错时只记录下来，继续搜索其余的输入；输出出错时整体失败
+// 结果写到 writer，--watch 的各轮共用同一个 writer
+#[allow(clippy::too_many_arguments)]
+fn run(
+    patterns: &Patterns,
+    opts: search::Options,
+    paths: &[PathBuf],
+    input_config: &InputConfig,
+    report: &ReportConfig,
+    output: &OutputConfig,
+    writer: &mut dyn Write,
+    debug: bool,
+) -> Result<RunSummary> {
+    let started = Instant::now();
     // 创建搜索器
-    let searcher = Searcher::new(
-        pattern,
-        search::Options {
-            show_line_number: true,
-            count_only: false,
-            case_ignore: false,
-            match_only: false,
-        },
-    )?;
+    let texts: Vec<&str> = patterns.texts.iter().map(String::as_str).collect();
+    let match_timeout = opts.match_timeout;
+    let paragraph = opts.paragraph;
+    let context = opts.has_context();
+    let excluded: Vec<&str> = patterns.excluded.iter().map(String::as_str).collect();
+    let searcher = Arc::new(Searcher::with_excluded(&texts, &excluded, opts)?);
+
+    if debug {
+        let info = searcher.matcher_info();
+        eprintln!("mrustgrep[debug]: final pattern: {}", info.pattern);
+        eprintln!("mrustgrep[debug]: engine: {}", info.engine);
+        eprintln!(
+            "mrustgrep[debug]: case-insensitive: {}",
+            info.case_insensitive
+        );
+        match &info.prefilter {
+            // 忽略大小写时字面量组合可能很多，只展示前几个
+            Some(literals) if literals.len() > 8 => eprintln!(
+                "mrustgrep[debug]: literal prefilter: {:?} ... ({} literals)",
+                &literals[..8],
+                literals.len()
+            ),
+            Some(literals) => eprintln!("mrustgrep[debug]: literal prefilter: {:?}", literals),
+            None => eprintln!("mrustgrep[debug]: literal prefilter: none"),
+        }
+        if let Some(timeout) = match_timeout {
+            eprintln!(
+                "mrustgrep[debug]: match timeout {:?} ignored: the {} engine runs in linear time",
+                timeout, info.engine
+            );
+        }
+    }
+
+    let mut timings = Timings::default();
+    let mut progress = Progress::new(output.progress.is_some(), output.progress == Some(true));
+
+    // 多个输入或递归搜索时，每行输出都带上文件名
+    let with_filename = output
+        .with_filename
+        .unwrap_or(paths.len() > 1 || input_config.walk.recursive);
+    let mut total = 0;
+    let mut listed = 0;
+    // --sort-by-count 时先收集 (标签, 计数)，遍历结束后统一排序输出
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    // 逐行输出的格式由输出器负责
+    let mut printer: Box<dyn Printer> = match output.format {
+        Format::Standard => Box::new(StandardPrinter::new(
+            searcher.output_format(),
+            output.style.clone(),
+            with_filename,
+            output.heading,
+            paragraph || context,
+            output.line_number_width,
+        )),
+        Format::Csv => Box::new(CsvPrinter::new(output.column_unit)),
+        Format::Sarif => {
+            // 位置相对于 git 仓库的根目录，不在仓库中时相对于当前目录
+            let printer = SarifPrinter::new(patterns.texts.clone());
+            match std::env::current_dir().and_then(std::fs::canonicalize) {
+                Ok(cwd) => {
+                    let root = ignore::repo_root(&cwd).unwrap_or_else(|| cwd.clone());
+                    Box::new(printer.with_source_root(&root, &cwd))
+                }
+                Err(_) => Box::new(printer),
+            }
+        }
+        Format::Json => Box::new(JsonPrinter::new(searcher.output_format())),
+    };
+    if !report.per_input() {
+        printer.begin(writer).context("Failed to write output")?;
+    }
+    // --count-per-pattern 的统计，下标就是模式编号，结束时再填上模式的文本
+    let mut pattern_stats = vec![PatternSummary::default(); patterns.texts.len()];
+    let mut errors: Vec<InputError> = Vec::new();
+
+    // 没有给出文件时从标准输入读取，递归搜索时则搜索当前目录
+    let walker = |roots: &[PathBuf]| {
+        let mut walker = Walker::new(roots, input_config.walk);
+        if let Some(excludes) = &input_config.git_excludes {
+            walker = walker.with_git_excludes(excludes.clone());
+        }
+        if let Some(global) = &input_config.global_ignore {
+            walker = walker.with_global_ignore(global.clone());
+        }
+        if let Some(overrides) = &input_config.overrides {
+            walker = walker.with_overrides(overrides.clone());
+        }
+        if let Some(types) = &input_config.types {
+            walker = walker.with_types(types.clone());
+        }
+        walker
+    };
+    let mut inputs: Box<dyn Iterator<Item = Result<WalkItem>>> =
+        match (paths.is_empty(), input_config.walk.recursive) {
+            (true, false) => Box::new(std::iter::once(Ok(WalkItem::File(PathBuf::from("-"))))),
+            (true, true) => Box::new(walker(&[PathBuf::from(".")])),
+            (false, _) => Box::new(walker(paths)),
+        };
+    // 排序需要先拿到完整的文件列表，遍历不再是边走边搜
+    if let Some((sort, reverse)) = input_config.sort {
+        if debug {
+            eprintln!(
+                "mrustgrep[debug]: --{} {:?}: collecting the file list before searching (no streaming)",
+                if reverse { "sortr" } else { "sort" },
+                sort
+            );
+        }
+        let mut items = Vec::new();
+        for input in inputs {
+            match input {
+                Ok(item) => items.push(item),
+                Err(e) => input_failed(&mut errors, input_config, None, e),
+            }
+        }
+        inputs = Box::new(walk::sort_items(items, sort, reverse).into_iter().map(Ok));
+    }
+
+    // -j 时后面的文件提前交给工作线程搜索，标准输入和需要预处理的文件仍在这里打开
+    let inputs: Box<dyn Iterator<Item = (Result<WalkItem>, Option<prefetch::Pending>)>> =
+        match input_config.threads {
+            1 => Box::new(inputs.map(|input| (input, None))),
+            threads => {
+                let pool = ParallelSearcher::new(searcher.clone(), threads);
+                if debug {
+                    eprintln!(
+                        "mrustgrep[debug]: searching files on {} threads",
+                        pool.threads()
+                    );
+                }
+                // 命令行上给出的二进制文件要在主线程上判断怎么输出，-a/--text 之外都不交给工作线程
+                let eligible = |path: &Path| {
+                    path.as_os_str() != "-"
+                        && !(input_config.skip_binary && paths.iter().any(|given| given == path))
+                        && !input_config
+                            .pre
+                            .as_ref()
+                            .is_some_and(|pre| pre.applies(path))
+                        && input_config
+                            .changed
+                            .as_ref()
+                            .is_none_or(|changed| changed.contains(path))
+                };
+                Box::new(prefetch::prefetch(
+                    &pool,
+                    inputs,
+                    eligible,
+                    match (report.lines_only(), report.first_match_only()) {
+                        (true, true) => Work::IsMatch,
+                        (true, false) => Work::Count,
+                        (false, _) => Work::Search {
+                            mmap: input_config.mmap,
+                        },
+                    },
+                    input_config.skip_binary,
+                ))
+            }
+        };
+
+    // 通过不同路径到达的同一个文件只搜索一次
+    let mut visited = Visited::new();
+
+    for (input, prefetched) in inputs {
+        // 够数之后不再取下一个输入，丢掉 inputs 时目录遍历也随之停止
+        if report.remaining(total) == Some(0) {
+            if debug {
+                eprintln!("mrustgrep[debug]: --max-total-matches reached, stopping");
+            }
+            break;
+        }
+        let input = match input {
+            Ok(input) => input,
+            Err(e) => {
+                input_failed(&mut errors, input_config, None, e);
+                continue;
+            }
+        };
+        let path = match input {
+            WalkItem::File(path)
+                if input_config
+                    .changed
+                    .as_ref()
+                    .is_some_and(|changed| !changed.contains(&path)) =>
+            {
+                continue;
+            }
+            WalkItem::File(path) => path,
+            WalkItem::Recursing(path) => {
+                if debug {
+                    eprintln!(
+                        "mrustgrep[debug]: {}: directory, recursing",
+                        display_path(&path, output)
+                    );
+                }
+                continue;
+            }
+            WalkItem::Skipped(path, reason) => {
+                if debug {
+                    eprintln!(
+                        "mrustgrep[debug]: skipping {} ({})",
+                        display_path(&path, output),
+                        reason
+                    );
+                }
+                continue;
+            }
+        };
+        // 工作线程搜索出的结果，在下面代替逐行搜索
+        let mut searched = None;
+        let (label, mut reader): (String, Box<dyn BufRead>) = match &path {
+            path if path.as_os_str() != "-" => {
+                let label = display_path(path, output);
+                if input_config.dedupe && !visited.insert(path) {
+                    if debug {
+                        eprintln!("mrustgrep[debug]: skipping {} (already searched)", label);
+                    }
+                    continue;
+                }
+                if input_config.write {
+                    let replacement = output.replace.as_deref().unwrap_or_default();
+                    match rewrite::rewrite_file(
+                        &searcher,
+                        path,
+                        replacement,
+                        input_config.write_symlinks,
+                    ) {
+                        Ok(rewrite) if rewrite.lines > 0 => {
+                            total += rewrite.lines;
+                            progress.output();
+                            writeln!(
+                                writer,
+                                "{}: {} replacement(s) on {} line(s)",
+                                label, rewrite.replacements, rewrite.lines
+                            )
+                            .context("Failed to write output")?;
+                        }
+                        Ok(_) if debug => {
+                            eprintln!("mrustgrep[debug]: {}: no matches, unchanged", label)
+                        }
+                        Ok(_) => {}
+                        Err(e) => input_failed(&mut errors, input_config, Some(label), e),
+                    }
+                    continue;
+                }
+                let prefetched = prefetched.and_then(|pending| {
+                    let search_started = Instant::now();
+                    let received = pending.recv().ok();
+                    timings.search += search_started.elapsed();
+                    received
+                });
+                let opened: io::Result<Box<dyn BufRead>> = match (prefetched, &input_config.follow)
+                {
+                    (Some(Ok(result)), _) => {
+                        searched = Some(result);
+                        Ok(Box::new(io::empty()))
+                    }
+                    (Some(Err(e)), _) => Err(e),
+                    (None, Some(stop)) => {
+                        let warned = label.clone();
+                        FollowReader::open(path, stop.clone(), move |rotation| {
+                            let what = match rotation {
+                                Rotation::Truncated => "was truncated",
+                                Rotation::Replaced => "was replaced",
+                            };
+                            eprintln!("mrustgrep: {} {}; reading it from the start", warned, what);
+                        })
+                        .map(|follow| Box::new(searcher.buffered(follow)) as Box<dyn BufRead>)
+                    }
+                    (None, None) => match &input_config.pre {
+                        Some(pre) if pre.applies(path) => {
+                            if debug {
+                                eprintln!("mrustgrep[debug]: {}: running preprocessor", label);
+                            }
+                            pre.open(path)
+                                .map_err(|e| {
+                                    io::Error::new(e.kind(), format!("preprocessor: {}", e))
+                                })
+                                .map(|reader| {
+                                    Box::new(searcher.buffered(reader)) as Box<dyn BufRead>
+                                })
+                        }
+                        pre => {
+                            if debug && pre.is_some() {
+                                eprintln!(
+                                    "mrustgrep[debug]: {}: no --pre-glob matched, searching directly",
+                                    label
+                                );
+                            }
+                            File::open(path)
+                                .map(|file| Box::new(searcher.buffered(file)) as Box<dyn BufRead>)
+                        }
+                    },
+                };
+                match opened {
+                    Ok(reader) => (label, reader),
+                    Err(e) => {
+                        let e = anyhow::Error::from(e).context(format!("Failed to open {}", label));
+                        input_failed(&mut errors, input_config, Some(label), e);
+                        continue;
+                    }
+                }
+            }
+            _ => (
+                output.stdin_label.clone(),
+                Box::new(searcher.buffered(io::stdin().lock())),
+            ),
+        };
+
+        // 开头有 NUL 字节的输入：-a/--text 时当作文本，--binary-offsets 时在下面输出十六进制，
+        // 否则递归时找到的文件不搜索，命令行上给出的文件和标准输入照常搜索，但逐行输出时只报告一行 "binary file matches"
+        // 开头读不出来时留给搜索报告错误
+        let named = path.as_os_str() == "-" || paths.contains(&path);
+        let nul = match &searched {
+            Some(Searched::Binary) => Some(0),
+            _ if input_config.skip_binary => reader.fill_buf().ok().and_then(binary::first_nul),
+            _ => None,
+        };
+        if nul.is_some() && !named {
+            if debug {
+                eprintln!(
+                    "mrustgrep[debug]: skipping {} (binary, -a/--text to search it)",
+                    label
+                );
+            }
+            continue;
+        }
+        if debug && nul.is_some() {
+            eprintln!(
+                "mrustgrep[debug]: {}: binary, reporting only whether it matches (-a/--text to print the lines)",
+                label
+            );
+        }
+        let binary_notice = nul.filter(|_| output.format == Format::Standard);
+        if debug {
+            eprintln!("mrustgrep[debug]: searching {}", label);
+        }
+        progress.input(writer, &label, total);
+        printer.begin_input(&label);
+
+        let mut count = 0;
+        let mut match_count = 0;
+        // 这个输入最多还能汇报的匹配行数（--max-total-matches 剩下的和 -m 中较小的一个）
+        // 逐行搜索时 -m 由 searcher 自己停下，这里用于 --binary-offsets 和 --parallel-file
+        let remaining = report
+            .remaining(total)
+            .into_iter()
+            .chain(searcher.options().max_count)
+            .min();
+        // 按文件判断 --all-match 时记录文件中出现过的模式
+        let mut seen_patterns = vec![false; searcher.pattern_len()];
+
+        // --binary-offsets：开头有 NUL 字节的输入整个读入，直接在字节中找匹配，不再逐行搜索
+        // 开头读不出来时留给逐行搜索报告错误
+        let binary = input_config.binary_offsets.is_some()
+            && reader.fill_buf().is_ok_and(binary::looks_binary);
+        if let (true, Some(window)) = (binary, input_config.binary_offsets) {
+            if debug {
+                eprintln!("mrustgrep[debug]: {}: binary, searching raw bytes", label);
+            }
+            let search_started = Instant::now();
+            let mut data = Vec::new();
+            let read = reader.read_to_end(&mut data);
+            timings.search += search_started.elapsed();
+            match read {
+                Ok(_) => {
+                    for m in searcher.find_bytes(&data)? {
+                        if remaining.is_some_and(|remaining| count >= remaining) {
+                            break;
+                        }
+                        count += 1;
+                        match_count += 1;
+                        seen_patterns[m.pattern] = true;
+                        if report.count_per_pattern {
+                            pattern_stats[m.pattern].lines += 1;
+                            pattern_stats[m.pattern].matches += 1;
+                        }
+                        if report.per_input() {
+                            continue;
+                        }
+                        let output_started = Instant::now();
+                        progress.output();
+                        binary::write_dump(
+                            writer,
+                            with_filename.then_some(label.as_str()),
+                            &data,
+                            &m,
+                            window,
+                            output.style.colors.as_ref(),
+                        )
+                        .context("Failed to write output")?;
+                        timings.output += output_started.elapsed();
+                    }
+                }
+                Err(e) => {
+                    let e = anyhow::Error::from(e).context(format!("Failed to read {}", label));
+                    input_failed(&mut errors, input_config, Some(label.clone()), e);
+                }
+            }
+        }
+
+        // 只要行数时直接数出选中的行，不构造结果；--parallel-file 要切块搜索，仍然逐条处理，
+        // 只有 -q 例外：它读到第一个选中的行就停下，顺序读取比切块更快
+        let lines_only =
+            report.lines_only() && !binary && (report.quiet || !input_config.parallel_file);
+        if lines_only {
+            if debug && report.quiet && input_config.parallel_file {
+                eprintln!("mrustgrep[debug]: {}: searching sequentially (-q)", label);
+            }
+            let mut warn = |long: LongLine| {
+                progress::erase();
+                eprintln!("mrustgrep: {}: {}", label, long);
+            };
+            let search_started = Instant::now();
+            let counted = match searched.take() {
+                Some(Searched::Count(counted, long_lines)) => {
+                    long_lines.into_iter().for_each(&mut warn);
+                    counted
+                }
+                // -q、-l、-L 时这个输入的选中行数只会是 0 或 1
+                _ if report.first_match_only() => searcher
+                    .is_match_with(&mut reader, &mut warn)
+                    .map(usize::from),
+                _ => searcher.count_with(&mut reader, &mut warn),
+            };
+            timings.search += search_started.elapsed();
+            match counted {
+                Ok(counted) => count = counted,
+                Err(e) => {
+                    let e = e.context(format!("Failed to read or search {}", label));
+                    input_failed(&mut errors, input_config, Some(label.clone()), e);
+                }
+            }
+        }
+
+        // 使用迭代器模式，逐行搜索
+        // 读取和匹配都发生在 next() 里，输出时间单独累计
+        // --parallel-file 时整个文件先在多个线程上搜索完，再逐条交出结果；--mmap 时在文件的内存映射上搜索
+        let mut results: Box<dyn Iterator<Item = Result<SearchResult>>> = match (
+            searched,
+            parallel_threads(&path, &searcher, input_config, debug),
+        ) {
+            _ if binary || lines_only => Box::new(iter::empty()),
+            (Some(Searched::Results(results)), _) => Box::new(results.into_iter()),
+            (_, Some(threads)) => {
+                let search_started = Instant::now();
+                let searched = searcher.search_file_parallel(&path, threads, remaining);
+                timings.search += search_started.elapsed();
+                match searched {
+                    Ok(results) => Box::new(results.into_iter()),
+                    Err(e) => Box::new(iter::once(Err(e))),
+                }
+            }
+            // -A / -B / -C 时逐条交出选中的行和它们前后的上下文行
+            (_, None) if context => Box::new(searcher.search_context_lines(reader)),
+            (_, None) if mapped(&path, input_config) => match searcher.search_mmap(&path) {
+                Ok(results) => results,
+                Err(e) => Box::new(iter::once(Err(e.into()))),
+            },
+            (_, None) => Box::new(searcher.search(reader)),
+        };
+        loop {
+            let search_started = Instant::now();
+            let next = results.next();
+            timings.search += search_started.elapsed();
+            let Some(result) = next else { break };
+
+            // 超长的行和匹配超时的行只给出警告，继续搜索后面的行
+            if let Err(e) = &result
+                && let Some(skipped) = e
+                    .downcast_ref::<LongLine>()
+                    .map(|long| long.to_string())
+                    .or_else(|| e.downcast_ref::<MatchTimeout>().map(|t| t.to_string()))
+            {
+                progress::erase();
+                eprintln!("mrustgrep: {}: {}", label, skipped);
+                continue;
+            }
+            let search_result = match result {
+                Ok(search_result) => search_result,
+                // 读到一半出错时，已经读过的部分照常汇报
+                Err(e) => {
+                    let e = e.context(format!("Failed to read or search {}", label));
+                    input_failed(&mut errors, input_config, Some(label.clone()), e);
+                    break;
+                }
+            };
+            // 上下文行只输出，不计入匹配
+            if !search_result.context {
+                // 已经够数时这一行不再汇报，也不再读后面的行
+                if remaining.is_some_and(|remaining| count >= remaining) {
+                    break;
+                }
+                count += 1;
+                // 反向匹配选出的行没有匹配区间，按行计数
+                match_count += search_result.matches.len().max(1);
+            }
 
-    // 获取输出格式的枚举类型
-    let format = searcher.output_format();
+            if report.all_match_per_file() {
+                for pattern in searcher.matching_patterns(&search_result.line) {
+                    seen_patterns[pattern] = true;
+                }
+            }
 
-    // 从标准输入读取数据
-    let stdin = io::stdin();
-    let reader = io::BufReader::new(stdin.lock());
-    let mut writer = io::BufWriter::new(io::stdout());
+            if report.count_per_pattern {
+                // 一行同时匹配多个模式时，每个模式都记一行
+                for pattern in searcher.matching_patterns(&search_result.line) {
+                    pattern_stats[pattern].lines += 1;
+                }
+                for m in &search_result.matches {
+                    pattern_stats[m.pattern].matches += 1;
+                }
+            }
 
-    let mut count = 0;
+            if report.per_input() {
+                continue;
+            }
 
-    // 使用迭代器模式，逐行搜索
-    for result in searcher.search(reader) {
-        let search_result = result.context("Failed to read or search line")?;
-        count += 1;
+            let output_started = Instant::now();
 
-        // 使用枚举 match
-        search_result.format_to(&mut writer, &format)?;
+            // 二进制输入不输出行的内容，第一个选中的行换成一行提示，然后不再读后面的部分
+            if let Some(offset) = binary_notice {
+                if search_result.context {
+                    continue;
+                }
+                progress.output();
+                if with_filename {
+                    write!(writer, "{}: ", label).context("Failed to write output")?;
+                }
+                writeln!(
+                    writer,
+                    "binary file matches (found \"\\0\" byte around offset {})",
+                    offset
+                )
+                .context("Failed to write output")?;
+                timings.output += output_started.elapsed();
+                break;
+            }
+
+            let search_result = match &output.replace {
+                Some(replacement) => searcher.replace(search_result, replacement),
+                None => search_result,
+            };
+            progress.output();
+            printer
+                .print(writer, &label, &search_result)
+                .context("Failed to write output")?;
+            if output.line_buffered {
+                writer.flush().context("Failed to write output")?;
+            }
+            timings.output += output_started.elapsed();
+        }
+
+        let output_started = Instant::now();
+        progress.output();
+        printer
+            .end_input(writer)
+            .context("Failed to write output")?;
+        if output.line_buffered {
+            writer.flush().context("Failed to write output")?;
+        }
+        timings.output += output_started.elapsed();
+
+        // 有模式从未出现的文件不列出，也不计入匹配总数
+        if report.all_match_per_file() && !seen_patterns.iter().all(|&seen| seen) {
+            count = 0;
+            match_count = 0;
+        }
+
+        total += count;
+        if report.files_without_match && count == 0 {
+            listed += 1;
+        }
+        let reported = if report.count_matches {
+            match_count
+        } else {
+            count
+        };
+        if report.quiet {
+            if total > 0 {
+                if debug {
+                    eprintln!(
+                        "mrustgrep[debug]: -q: {}: found a selected line, stopping",
+                        label
+                    );
+                }
+                break;
+            }
+        } else if report.sort_by_count {
+            counts.push((label, reported));
+        } else {
+            progress.output();
+            write_report(writer, report, &label, reported, with_filename, output)
+                .context("Failed to write output")?;
+        }
+    }
+
+    progress::erase();
+    // 计数从高到低，计数相同时按路径排序，保证输出稳定
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (label, count) in &counts {
+        write_report(writer, report, label, *count, with_filename, output)
+            .context("Failed to write output")?;
+    }
+    let pattern_stats: Vec<PatternSummary> = match report.count_per_pattern {
+        true => patterns
+            .texts
+            .iter()
+            .zip(pattern_stats)
+            .map(|(pattern, stats)| PatternSummary {
+                pattern: pattern.clone(),
+                ..stats
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    if !report.per_input() {
+        let summary = Summary {
+            matched_lines: total,
+            patterns: pattern_stats.clone(),
+            errors: errors.iter().map(InputError::summary).collect(),
+        };
+        printer
+            .finish(writer, &summary)
+            .context("Failed to write output")?;
+    }
+
+    writer.flush().context("Failed to write output")?;
+
+    for stats in &pattern_stats {
+        eprintln!(
+            "{}: {} lines, {} matches",
+            stats.pattern, stats.lines, stats.matches
+        );
+    }
+
+    if debug {
+        eprintln!(
+            "mrustgrep[debug]: timing: search {:?}, output {:?}, total {:?}",
+            timings.search,
+            timings.output,
+            started.elapsed()
+        );
+    }
+    Ok(RunSummary {
+        matched: total,
+        listed,
+        errors,
+    })
+}
+
+// --debug 用的分阶段耗时
+#[derive(Default)]
+struct Timings {
+    // 读取与匹配
+    search: Duration,
+    // 格式化与写出
+    output: Duration,
+}
+
+// --debug 时说明哪些设置来自配置文件和环境变量
+fn debug_sources(config: Option<&config::ConfigFile>, skipped: bool, matches: &clap::ArgMatches) {
+    match config {
+        Some(config) => eprintln!(
+            "mrustgrep[debug]: config file {} ({} args)",
+            config.path.display(),
+            config.args.len()
+        ),
+        None if skipped => eprintln!("mrustgrep[debug]: config file skipped (--no-config)"),
+        None => eprintln!("mrustgrep[debug]: no config file"),
+    }
+    for arg in cli::build().get_arguments() {
+        if matches.value_source(arg.get_id().as_str()) != Some(ValueSource::EnvVariable) {
+            continue;
+        }
+        let var = arg.get_env().unwrap_or_default().to_string_lossy();
+        let value = std::env::var(&*var).unwrap_or_default();
+        eprintln!(
+            "mrustgrep[debug]: --{} = {:?} (from environment variable {})",
+            arg.get_long().unwrap_or(arg.get_id().as_str()),
+            value,
+            var
+        );
     }
+}
+
+// 这个输入是否用 --mmap 搜索：标准输入和经过 --pre 的输出不是文件，不能映射
+fn mapped(path: &Path, input_config: &InputConfig) -> bool {
+    input_config.mmap
+        && path.as_os_str() != "-"
+        && !input_config
+            .pre
+            .as_ref()
+            .is_some_and(|pre| pre.applies(path))
+}
+
+// 对这个输入使用 --parallel-file 时的线程数，None 表示顺序搜索
+// 只有能随机访问的普通文件才能切块；跟踪文件增长和依赖顺序读取的选项都退回顺序搜索
+fn parallel_threads(
+    path: &Path,
+    searcher: &Searcher,
+    input_config: &InputConfig,
+    debug: bool,
+) -> Option<usize> {
+    if !input_config.parallel_file || path.as_os_str() == "-" {
+        return None;
+    }
+    let reason = match () {
+        _ if input_config.follow.is_some() => "--follow",
+        _ if input_config
+            .pre
+            .as_ref()
+            .is_some_and(|pre| pre.applies(path)) =>
+        {
+            "--pre"
+        }
+        _ if searcher.options().has_context() => "-A/-B/-C",
+        _ if !searcher.supports_parallel_file() => {
+            "--paragraph, --stop-on-nonmatch or --skip-* is in effect"
+        }
+        _ if !std::fs::metadata(path).is_ok_and(|m| m.is_file()) => "not a regular file",
+        _ => {
+            return Some(thread::available_parallelism().map_or(1, |n| n.get()));
+        }
+    };
+    if debug {
+        eprintln!(
+            "mrustgrep[debug]: {}: searching sequentially ({})",
+            path.display(),
+            reason
+        );
+    }
+    None
+}
+
+// 输出单个输入的汇报行（-c 的 "路径:计数" 或 -l / -L 的路径）
+fn write_report(
+    writer: &mut dyn Write,
+    report: &ReportConfig,
+    label: &str,
+    count: usize,
+    with_filename: bool,
+    output: &OutputConfig,
+) -> io::Result<()> {
+    if report.lists_files() {
+        if (count > 0) == report.files_with_matches {
+            write_label(writer, label, "\n", output.style.colors.as_ref())?;
+        }
+    } else if report.count && (count > 0 || report.include_zero || !with_filename) {
+        // 只有一个输入时和 grep 一样只输出数字，且计数为 0 也照样输出
+        if with_filename {
+            write_label(
+                writer,
+                label,
+                &output.style.match_separator,
+                output.style.colors.as_ref(),
+            )?;
+        }
+        writeln!(writer, "{}", count)?;
+    }
+    if output.line_buffered {
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// 输出路径的形式
+enum PathForm {
+    // 与给出时一致（递归搜索时是起点路径加上相对部分）
+    AsGiven,
+    // 绝对路径（--absolute-path），能解析时解析掉符号链接和 ".."
+    Absolute,
+    // 位于当前目录之下的绝对路径改写成相对路径（--relative），记录的是当前目录
+    Relative(PathBuf),
+}
 
-    writer.flush()?;
-    Ok(count)
+// 输出用的路径文本：先按 PathForm 改写，再把目录分隔符替换成 --path-separator 指定的字符
+// 只影响显示，打开文件时仍然使用原来的路径
+fn display_path(path: &Path, output: &OutputConfig) -> String {
+    let path = match &output.path_form {
+        PathForm::AsGiven => path.to_path_buf(),
+        // 文件不存在等情况下无法解析，仍然拼成绝对路径，由打开文件的地方报错
+        PathForm::Absolute => std::fs::canonicalize(path)
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        // current_dir 是解析过符号链接的物理路径，直接比较不上时用解析后的路径再比较一次
+        PathForm::Relative(cwd) => match path.strip_prefix(cwd) {
+            Ok(relative) if path.is_absolute() => relative.to_path_buf(),
+            _ => std::fs::canonicalize(path)
+                .ok()
+                .filter(|_| path.is_absolute())
+                .and_then(|resolved| resolved.strip_prefix(cwd).ok().map(Path::to_path_buf))
+                .unwrap_or_else(|| path.to_path_buf()),
+        },
+    };
+    let text = path.display().to_string();
+    match output.path_separator {
+        Some(separator) => text
+            .chars()
+            .map(|c| match std::path::is_separator(c) {
+                true => separator,
+                false => c,
+            })
+            .collect(),
+        None => text,
+    }
+}
+
+// 打开结果输出目标：指定了文件则创建（或截断）该文件，否则使用 stdout
+fn open_output(output: &OutputConfig) -> Result<Box<dyn Write>> {
+    match &output.path {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create output file {}", path.display()))?;
+            Ok(Box::new(file))
+        }
+        None => Ok(Box::new(io::stdout())),
+    }
 }
@@ -0,0 +1,1104 @@
+use std::{fmt, sync::OnceLock};
+
+use aho_corasick::{AhoCorasick, StartKind};
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use regex_automata::{
+    Anchored, Input, MatchKind, PatternID, PatternSet, meta,
+    util::{captures::Captures, interpolate, syntax},
+};
+use regex_syntax::hir::{
+    Capture, Class, ClassBytes, ClassBytesRange, ClassUnicode, ClassUnicodeRange, Hir, HirKind,
+    Look, Repetition,
+    literal::{ExtractKind, Extractor},
+};
+
+use crate::fold;
+use crate::fuzzy;
+use crate::glob;
+use crate::posix;
+use crate::search::Options;
+
+// -w 的单词边界按哪种字符集判断（对应 --word-boundary 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WordBoundary {
+    // Unicode 的字母、数字和下划线都是单词字符：é、日 与 ASCII 字母一样
+    #[default]
+    Unicode,
+    // 只有 [0-9A-Za-z_] 是单词字符，其他字符都算边界（naïve 中的 na 是一个单词）
+    Ascii,
+}
+
+impl WordBoundary {
+    // 要求模式前后都是单词边界：用半边界断言，模式以非单词字符开头或结尾时同样成立（与 grep -w 一致）
+    // 只有这两个断言带上 ASCII 标志，模式中的 \w、\d 仍然按原来的设置解释
+    fn wrap(self, pattern: &str) -> String {
+        match self {
+            WordBoundary::Unicode => format!(r"\b{{start-half}}(?:{})\b{{end-half}}", pattern),
+            WordBoundary::Ascii => {
+                format!(r"(?-u:\b{{start-half}})(?:{})(?-u:\b{{end-half}})", pattern)
+            }
+        }
+    }
+}
+
+// 一处匹配：在行内的字节区间 [start, end)，以及是第几个模式匹配上的（从 0 开始）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub pattern: usize,
+    // 与模式之间的编辑距离，只有 --fuzzy 时才可能不为 0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub distance: usize,
+}
+
+// 匹配中一个捕获组的区间，总在所属的匹配之内；没有参与匹配的可选分组不会出现
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    // 所属的匹配在 matches 中的下标
+    pub matched: usize,
+    // 分组在模式中的编号（从 1 开始，与 \1 的写法一致）
+    pub group: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// -i 时一个字面量模式最多展开成多少种写法交给 Aho-Corasick
+const MAX_LITERAL_VARIANTS: usize = 64;
+
+// 模式编译错误，记录出错的模式及其中的位置，显示时用 ^ 标出出错的区间
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    // 出错的是第几个模式（从 0 开始）
+    pub index: usize,
+    // 用户给出的原始模式
+    pub pattern: String,
+    // 出错区间在原始模式中的字符位置 [start, end)
+    pub start: usize,
+    pub end: usize,
+    // 引擎给出的错误描述
+    pub message: String,
+}
+
+impl PatternError {
+    fn new(index: usize, pattern: &str, err: &regex_syntax::Error) -> PatternError {
+        let (span, message) = match err {
+            regex_syntax::Error::Parse(e) => (Some(*e.span()), e.kind().to_string()),
+            regex_syntax::Error::Translate(e) => (Some(*e.span()), e.kind().to_string()),
+            e => (None, e.to_string()),
+        };
+        // span 中的 column 从 1 开始且按字符计数，正好对应终端上的显示位置
+        let (start, end) = match span {
+            Some(span) => (span.start.column - 1, span.end.column - 1),
+            None => (0, pattern.chars().count()),
+        };
+        PatternError {
+            index,
+            pattern: pattern.to_string(),
+            start,
+            end,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.end.saturating_sub(self.start).max(1);
+        writeln!(f, "regex parse error:")?;
+        writeln!(f, "    {}", self.pattern)?;
+        writeln!(f, "    {}{}", " ".repeat(self.start), "^".repeat(width))?;
+        write!(f, "error: {}", self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+// 用与最终编译相同的语法选项校验单个模式，错误位置相对于用户输入的原文
+fn check_pattern(
+    index: usize,
+    pattern: &str,
+    opts: &Options,
+) -> std::result::Result<(), PatternError> {
+    regex_syntax::ParserBuilder::new()
+        .case_insensitive(opts.case_ignore)
+        .build()
+        .parse(pattern)
+        .map(|_| ())
+        .map_err(|err| PatternError::new(index, pattern, &err))
+}
+
+// 匹配器构造过程中做出的决策，供 --debug 输出
+#[derive(Debug, Clone)]
+pub struct MatcherInfo {
+    // 经过所有变换之后的模式（多个模式以分支的形式展示）
+    pub pattern: String,
+    // 选用的匹配引擎
+    pub engine: &'static str,
+    // 是否以忽略大小写的方式编译（由构建器设置，不体现在 pattern 中）
+    pub case_insensitive: bool,
+    // 从模式中提取出的字面量前缀（正则引擎会用它做预过滤）
+    pub prefilter: Option<Vec<String>>,
+}
+
+impl MatcherInfo {
+    fn new(pattern: &str, engine: &'static str, opts: &Options) -> MatcherInfo {
+        MatcherInfo {
+            pattern: pattern.to_string(),
+            engine,
+            case_insensitive: opts.case_ignore,
+            prefilter: extract_prefilter(pattern, opts),
+        }
+    }
+}
+
+// 提取模式必须以之开头的字面量集合，无法提取（或有空字面量）时返回 None
+fn extract_prefilter(pattern: &str, opts: &Options) -> Option<Vec<String>> {
+    let hir = regex_syntax::ParserBuilder::new()
+        .case_insensitive(opts.case_ignore)
+        .build()
+        .parse(pattern)
+        .ok()?;
+    let seq = Extractor::new().kind(ExtractKind::Prefix).extract(&hir);
+    let literals = seq.literals()?;
+    if literals.is_empty() || literals.iter().any(|lit| lit.as_bytes().is_empty()) {
+        return None;
+    }
+    Some(
+        literals
+            .iter()
+            .map(|lit| String::from_utf8_lossy(lit.as_bytes()).into_owned())
+            .collect(),
+    )
+}
+
+// Aho-Corasick 自动机需要在构建时决定是否支持锚定搜索
+fn start_kind(anchored: bool) -> StartKind {
+    match anchored {
+        true => StartKind::Anchored,
+        false => StartKind::Unanchored,
+    }
+}
+
+// 实际执行匹配的引擎
+enum Engine {
+    // 正则引擎，多个模式一起编译，匹配结果自带模式编号
+    Regex {
+        regex: meta::Regex,
+        // 统计每个模式命中情况用的 MatchKind::All 版本（相当于 RegexSet），第一次用到时再构建
+        set: OnceLock<meta::Regex>,
+        // 每个模式单独编译的版本，--all-match 需要每个模式各自的匹配区间，第一次用到时再构建
+        singles: OnceLock<Vec<meta::Regex>>,
+        // 整块缓冲区上搜索候选行用的多行版本，第一次用到时再构建；模式不适合整块搜索时为 None
+        buffer: OnceLock<Option<meta::Regex>>,
+        builder: meta::Builder,
+        regexes: Vec<String>,
+    },
+    // 全部模式都是字面量时使用 Aho-Corasick 自动机，编译和匹配大量字面量都快得多
+    Literals {
+        automaton: AhoCorasick,
+        // 统计每个模式命中情况时才需要的可重叠自动机，第一次用到时再构建
+        overlapping: OnceLock<AhoCorasick>,
+        // 交给自动机的各个写法，以及每个写法属于哪个模式；不忽略大小写时写法就是模式本身
+        variants: Vec<String>,
+        owners: Vec<usize>,
+        pattern_len: usize,
+        case_ignore: bool,
+    },
+    // --fuzzy 的近似匹配，模式都是固定字符串
+    Fuzzy {
+        patterns: Vec<Vec<char>>,
+        max_edits: usize,
+        case_ignore: bool,
+    },
+}
+
+// 匹配器：封装模式的变换、引擎的选择和匹配
+pub struct Matcher {
+    engine: Engine,
+    info: MatcherInfo,
+    // 只接受从行首开始的匹配（通过引擎的锚定搜索实现，而不是在模式前加 ^）
+    anchored: bool,
+}
+
+impl Matcher {
+    // 编译一组模式，任意一个模式匹配即算匹配
+    // 每个模式先单独校验，出错时能准确指出是哪个模式的哪个位置
+    pub fn new(patterns: &[&str], opts: &Options) -> Result<Matcher> {
+        // 近似匹配只支持固定字符串，正则与近似匹配的组合暂不支持
+        if let Some(max_edits) = opts.fuzzy {
+            if !opts.fixed_strings {
+                bail!("--fuzzy only works with fixed-string patterns (-F)");
+            }
+            let display = patterns.join("|");
+            return Ok(Matcher {
+                engine: Engine::Fuzzy {
+                    patterns: patterns
+                        .iter()
+                        .map(|pattern| fuzzy::prepare(pattern, opts.case_ignore))
+                        .collect(),
+                    max_edits,
+                    case_ignore: opts.case_ignore,
+                },
+                info: MatcherInfo {
+                    prefilter: None,
+                    ..MatcherInfo::new(&regex::escape(&display), "fuzzy", opts)
+                },
+                anchored: opts.anchored,
+            });
+        }
+
+        // 固定字符串、通配符和 POSIX 模式下先把每个模式转成等价的正则
+        let regexes: Vec<String> = patterns
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| {
+                Ok(match (opts.fixed_strings, opts.case_ignore) {
+                    _ if opts.glob => glob::to_regex(pattern),
+                    _ if let Some(syntax) = opts.posix => posix::translate(index, pattern, syntax)?,
+                    (true, true) => fold::case_insensitive_literal(pattern),
+                    (true, false) => regex::escape(pattern),
+                    (false, _) => pattern.to_string(),
+                })
+            })
+            .collect::<std::result::Result<_, PatternError>>()?;
+        for (index, pattern) in regexes.iter().enumerate() {
+            check_pattern(index, pattern, opts)?;
+        }
+        // 校验之后再加上单词边界或整行锚点，报错的位置仍然对应用户的模式
+        // 模式放在非捕获分组里，其中的分支不会被锚点拆开；匹配的行不含行终止符，^ 和 $ 就是行首和行尾
+        let regexes: Vec<String> = match opts.word {
+            _ if opts.line_regexp => regexes
+                .iter()
+                .map(|pattern| format!("^(?:{})$", pattern))
+                .collect(),
+            Some(word) => regexes.iter().map(|pattern| word.wrap(pattern)).collect(),
+            None => regexes,
+        };
+
+        // 调试输出中展示的形式：单个模式保持原样，多个模式合并成分组的分支
+        let display = match regexes.as_slice() {
+            [pattern] => pattern.to_string(),
+            patterns => patterns
+                .iter()
+                .map(|pattern| format!("(?:{})", pattern))
+                .collect::<Vec<_>>()
+                .join("|"),
+        };
+
+        // 多个模式且全部是字面量时改用 Aho-Corasick
+        // 自动机只支持 ASCII 的大小写折叠，-i 下把 ASCII 比较覆盖不到的折叠展开成模式的多种写法
+        // （kiln 还要匹配开尔文符号开头的 \u{212a}iln，-F 时 strasse 还要匹配 straße），结果与正则引擎相同
+        let literal = |pattern: &&str| {
+            opts.fixed_strings
+                || (!opts.glob && opts.posix.is_none() && regex::escape(pattern) == *pattern)
+        };
+        // 自动机不支持单词边界和行锚点，-w、-x 时总是使用正则引擎
+        let use_literals = patterns.len() > 1
+            && opts.word.is_none()
+            && !opts.line_regexp
+            && patterns.iter().all(literal);
+        if use_literals && let Some((variants, owners)) = literal_variants(patterns, opts) {
+            // LeftmostFirst 与正则分支的语义一致：同一位置先列出的模式（和写法）优先
+            let automaton = AhoCorasick::builder()
+                .match_kind(aho_corasick::MatchKind::LeftmostFirst)
+                .ascii_case_insensitive(opts.case_ignore)
+                .start_kind(start_kind(opts.anchored))
+                .build(&variants)?;
+            // 自动机本身就是字面量搜索，不需要再提取预过滤
+            let info = MatcherInfo {
+                prefilter: None,
+                ..MatcherInfo::new(&display, "aho-corasick", opts)
+            };
+            return Ok(Matcher {
+                engine: Engine::Literals {
+                    automaton,
+                    overlapping: OnceLock::new(),
+                    variants,
+                    owners,
+                    pattern_len: patterns.len(),
+                    case_ignore: opts.case_ignore,
+                },
+                info,
+                anchored: opts.anchored,
+            });
+        }
+
+        // 大小写通过语法配置设置，不改动用户的模式文本
+        let mut config = meta::Config::new();
+        if let Some(limit) = opts.regex_size_limit {
+            config = config.nfa_size_limit(Some(limit));
+        }
+        if let Some(limit) = opts.dfa_size_limit {
+            config = config.hybrid_cache_capacity(limit);
+        }
+        let mut builder = meta::Builder::new();
+        builder
+            .configure(config)
+            .syntax(syntax::Config::new().case_insensitive(opts.case_ignore));
+        let regex = builder
+            .build_many(&regexes)
+            .map_err(|err| match err.size_limit() {
+                // 超出编译体积上限时说明是哪个上限、怎么放宽，而不是只给出一个数字
+                Some(limit) => anyhow!(
+                    "compiled regex exceeds the size limit of {} bytes (raise it with --regex-size-limit)",
+                    limit
+                ),
+                None => anyhow!(err).context("Failed to compile regex pattern"),
+            })?;
+
+        let info = MatcherInfo::new(&display, "regex", opts);
+        Ok(Matcher {
+            engine: Engine::Regex {
+                regex,
+                set: OnceLock::new(),
+                singles: OnceLock::new(),
+                buffer: OnceLock::new(),
+                builder,
+                regexes,
+            },
+            info,
+            anchored: opts.anchored,
+        })
+    }
+
+    // 构造时记录的引擎相关信息
+    pub fn info(&self) -> &MatcherInfo {
+        &self.info
+    }
+
+    // 模式的个数
+    pub fn pattern_len(&self) -> usize {
+        match &self.engine {
+            Engine::Regex { regex, .. } => regex.pattern_len(),
+            Engine::Literals { pattern_len, .. } => *pattern_len,
+            Engine::Fuzzy { patterns, .. } => patterns.len(),
+        }
+    }
+
+    // 行内是否有任意匹配
+    pub fn is_match(&self, line: &str) -> bool {
+        self.find_at(line, 0).is_some()
+    }
+
+    // 找出行内所有不重叠的匹配
+    pub fn find_iter<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = Match> + 'a> {
+        // 锚定在行首时最多只有一个匹配
+        if self.anchored {
+            return Box::new(self.find_at(line, 0).into_iter());
+        }
+        match &self.engine {
+            Engine::Fuzzy { .. } => Box::new(self.fuzzy_iter(line)),
+            Engine::Regex { regex, .. } => Box::new(regex.find_iter(line).map(|m| Match {
+                start: m.start(),
+                end: m.end(),
+                pattern: m.pattern().as_usize(),
+                distance: 0,
+            })),
+            Engine::Literals {
+                automaton, owners, ..
+            } => Box::new(automaton.find_iter(line).map(|m| Match {
+                start: m.start(),
+                end: m.end(),
+                pattern: owners[m.pattern().as_usize()],
+                distance: 0,
+            })),
+        }
+    }
+
+    // 在任意字节（不要求是 UTF-8，也不按行切分）中找出所有不重叠的匹配，用于二进制文件
+    // 近似匹配按字符工作，不支持，返回 None；锚定（--anchored）在这里没有行首可言，同样不支持
+    pub fn find_bytes<'a>(
+        &'a self,
+        haystack: &'a [u8],
+    ) -> Option<Box<dyn Iterator<Item = Match> + 'a>> {
+        if self.anchored {
+            return None;
+        }
+        match &self.engine {
+            Engine::Fuzzy { .. } => None,
+            Engine::Regex { regex, .. } => {
+                Some(Box::new(regex.find_iter(haystack).map(|m| Match {
+                    start: m.start(),
+                    end: m.end(),
+                    pattern: m.pattern().as_usize(),
+                    distance: 0,
+                })))
+            }
+            Engine::Literals {
+                automaton, owners, ..
+            } => Some(Box::new(automaton.find_iter(haystack).map(|m| Match {
+                start: m.start(),
+                end: m.end(),
+                pattern: owners[m.pattern().as_usize()],
+                distance: 0,
+            }))),
+        }
+    }
+
+    // 近似匹配的所有不重叠的匹配，每个匹配之后从它的终点继续
+    fn fuzzy_iter<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut pos = 0;
+        std::iter::from_fn(move || {
+            let m = self.find_at(line, pos)?;
+            pos = m.end;
+            Some(m)
+        })
+    }
+
+    // 正则引擎的搜索输入，按需锚定
+    fn input<'h>(&self, line: &'h str) -> Input<'h> {
+        match self.anchored {
+            true => Input::new(line).anchored(Anchored::Yes),
+            false => Input::new(line),
+        }
+    }
+
+    // Aho-Corasick 的搜索输入，按需锚定
+    fn literal_input<'h>(&self, line: &'h str) -> aho_corasick::Input<'h> {
+        aho_corasick::Input::new(line).anchored(match self.anchored {
+            true => aho_corasick::Anchored::Yes,
+            false => aho_corasick::Anchored::No,
+        })
+    }
+
+    // 从每个匹配的起点之后（下一个字符）重新搜索，得到所有可能重叠的匹配
+    // 最坏情况下每个位置都要重新搜索一次，匹配密集的输入上会明显变慢
+    // 零宽匹配同样只前进一个字符，不会死循环
+    pub fn find_overlapping(&self, line: &str) -> Vec<Match> {
+        if self.anchored {
+            return self.find_at(line, 0).into_iter().collect();
+        }
+        let mut matches = Vec::new();
+        let mut pos = 0;
+        while pos <= line.len() {
+            let Some(m) = self.find_at(line, pos) else {
+                break;
+            };
+            matches.push(m);
+            pos = m.start + line[m.start..].chars().next().map_or(1, char::len_utf8);
+        }
+        matches
+    }
+
+    // 从 pos 开始搜索第一个匹配，pos 之前的内容仍作为上下文（例如 \b 的判断）
+    fn find_at(&self, line: &str, pos: usize) -> Option<Match> {
+        let input = self.input(line).range(pos..);
+        match &self.engine {
+            Engine::Regex { regex, .. } => regex.search(&input).map(|m| Match {
+                start: m.start(),
+                end: m.end(),
+                pattern: m.pattern().as_usize(),
+                distance: 0,
+            }),
+            Engine::Literals {
+                automaton, owners, ..
+            } => {
+                let input = self.literal_input(line).range(pos..);
+                automaton.find(input).map(|m| Match {
+                    start: m.start(),
+                    end: m.end(),
+                    pattern: owners[m.pattern().as_usize()],
+                    distance: 0,
+                })
+            }
+            // 各模式中起点最靠前的匹配，起点相同时先列出的模式优先
+            Engine::Fuzzy {
+                patterns,
+                max_edits,
+                case_ignore,
+            } => patterns
+                .iter()
+                .enumerate()
+                .filter_map(|(pattern, chars)| {
+                    let m = fuzzy::find(chars, line, pos, *max_edits, self.anchored, *case_ignore)?;
+                    Some(Match {
+                        start: m.start,
+                        end: m.end,
+                        pattern,
+                        distance: m.distance,
+                    })
+                })
+                .min_by_key(|m| (m.start, m.pattern)),
+        }
+    }
+
+    // 近似匹配时每个模式各自从行首开始的第一个匹配
+    fn fuzzy_each<'a>(
+        &'a self,
+        line: &'a str,
+        patterns: &'a [Vec<char>],
+        max_edits: usize,
+        case_ignore: bool,
+    ) -> impl Iterator<Item = Match> + 'a {
+        patterns
+            .iter()
+            .enumerate()
+            .flat_map(move |(pattern, chars)| {
+                // 锚定时每个模式最多一个匹配
+                let mut pos = Some(0);
+                std::iter::from_fn(move || {
+                    let m = fuzzy::find(chars, line, pos?, max_edits, self.anchored, case_ignore)?;
+                    pos = (!self.anchored).then_some(m.end);
+                    Some(Match {
+                        start: m.start,
+                        end: m.end,
+                        pattern,
+                        distance: m.distance,
+                    })
+                })
+            })
+    }
+
+    // 能否在整块缓冲区（多行）上用 find_candidate 搜索
+    // 锚定搜索只能在行首开始；正则中有 \A、\z 这类整段文本的锚点，或者用内联标志改了 \r\n 的语义时，
+    // 整块搜索的结果与逐行搜索对不上，只能逐行搜索
+    pub fn supports_buffer(&self) -> bool {
+        if self.anchored {
+            return false;
+        }
+        match &self.engine {
+            Engine::Regex {
+                buffer,
+                builder,
+                regexes,
+                ..
+            } => buffer
+                .get_or_init(|| buffer_regex(builder, regexes, self.info.case_insensitive))
+                .is_some(),
+            Engine::Literals { .. } => true,
+            Engine::Fuzzy { .. } => false,
+        }
+    }
+
+    // 在多行文本中从 pos 开始寻找第一个可能的匹配，返回其起点
+    // 结果只是候选：逐行搜索能匹配的行一定包含或者排在某个候选之后，候选所在的行仍要逐行确认
+    // 需要先确认 supports_buffer() 为 true
+    pub fn find_candidate(&self, text: &str, pos: usize) -> Option<usize> {
+        match &self.engine {
+            Engine::Regex { buffer, .. } => {
+                let regex = buffer.get()?.as_ref()?;
+                regex
+                    .search(&Input::new(text).range(pos..))
+                    .map(|m| m.start())
+            }
+            Engine::Literals { automaton, .. } => automaton
+                .find(aho_corasick::Input::new(text).range(pos..))
+                .map(|m| m.start()),
+            Engine::Fuzzy { .. } => None,
+        }
+    }
+
+    // 行内出现过的所有模式编号（升序），与 find_iter 不同，被其他模式的匹配覆盖住的模式也算
+    pub fn matching_patterns(&self, line: &str) -> Vec<usize> {
+        match &self.engine {
+            Engine::Regex {
+                regex,
+                set,
+                builder,
+                regexes,
+                ..
+            } => {
+                // 默认的 LeftmostFirst 语义下重叠搜索会提前结束，必须用 MatchKind::All 编译
+                let all = set.get_or_init(|| {
+                    builder
+                        .clone()
+                        .configure(meta::Config::new().match_kind(MatchKind::All))
+                        .build_many(regexes)
+                        .expect("patterns already compiled once")
+                });
+                let mut set = PatternSet::new(regex.pattern_len());
+                all.which_overlapping_matches(&self.input(line), &mut set);
+                set.iter().map(|pattern| pattern.as_usize()).collect()
+            }
+            Engine::Literals {
+                overlapping,
+                variants,
+                owners,
+                case_ignore,
+                ..
+            } => {
+                // 可重叠的搜索不支持锚定，锚定时直接比较各模式是否为行的前缀
+                let mut found: Vec<usize> = match self.anchored {
+                    true => literal_prefixes(line, variants, owners, *case_ignore)
+                        .map(|m| m.pattern)
+                        .collect(),
+                    false => overlapping_automaton(overlapping, variants, *case_ignore)
+                        .find_overlapping_iter(line)
+                        .map(|m| owners[m.pattern().as_usize()])
+                        .collect(),
+                };
+                found.sort_unstable();
+                found.dedup();
+                found
+            }
+            Engine::Fuzzy {
+                patterns,
+                max_edits,
+                case_ignore,
+            } => patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, chars)| {
+                    fuzzy::find(chars, line, 0, *max_edits, self.anchored, *case_ignore).is_some()
+                })
+                .map(|(pattern, _)| pattern)
+                .collect(),
+        }
+    }
+
+    // 一处匹配中各个捕获组的区间，matched 是这个匹配在 matches 中的下标
+    // 在匹配的区间内按匹配的模式重新做一次锚定搜索得到分组；只有正则引擎有捕获组，其余引擎返回空
+    pub fn groups(&self, line: &str, m: &Match, matched: usize) -> Vec<Group> {
+        let Some(caps) = self.captures(line, m) else {
+            return Vec::new();
+        };
+        (1..caps.group_len())
+            .filter_map(|group| {
+                let span = caps.get_group(group)?;
+                Some(Group {
+                    matched,
+                    group,
+                    start: span.start,
+                    end: span.end,
+                })
+            })
+            .collect()
+    }
+
+    // 把匹配 m 替换成 replacement 的展开结果追加到 dst
+    // $N / ${N} 是第 N 个捕获组，${name} 是命名分组，$0 是整个匹配，$$ 是字面的 $；不存在的分组展开为空
+    // 不是正则引擎（固定字符串、--fuzzy）时只有 $0
+    pub fn interpolate(&self, line: &str, m: &Match, replacement: &str, dst: &mut String) {
+        match self.captures(line, m) {
+            Some(caps) => caps.interpolate_string_into(line, replacement, dst),
+            None => interpolate::string(
+                replacement,
+                |group, dst| {
+                    if group == 0 {
+                        dst.push_str(&line[m.start..m.end]);
+                    }
+                },
+                |_| None,
+                dst,
+            ),
+        }
+    }
+
+    // 在匹配 m 的区间内重新匹配同一个模式，取得捕获组；不是正则引擎时返回 None
+    fn captures(&self, line: &str, m: &Match) -> Option<Captures> {
+        let Engine::Regex { regex, .. } = &self.engine else {
+            return None;
+        };
+        let mut caps = regex.create_captures();
+        let input = Input::new(line)
+            .range(m.start..m.end)
+            .anchored(Anchored::Pattern(PatternID::must(m.pattern)));
+        regex.search_captures(&input, &mut caps);
+        Some(caps)
+    }
+
+    // 每个模式各自在行内的所有匹配，按起点排序
+    // 不同模式的匹配之间可能重叠，用于 --all-match 时高亮所有模式的匹配区间
+    pub fn find_each(&self, line: &str) -> Vec<Match> {
+        let mut matches: Vec<Match> = match &self.engine {
+            Engine::Regex {
+                singles,
+                builder,
+                regexes,
+                ..
+            } => {
+                let singles = singles.get_or_init(|| {
+                    regexes
+                        .iter()
+                        .map(|pattern| {
+                            builder
+                                .build(pattern)
+                                .expect("patterns already compiled once")
+                        })
+                        .collect()
+                });
+                let mut matches = Vec::new();
+                for (pattern, regex) in singles.iter().enumerate() {
+                    let found: Vec<regex_automata::Match> = match self.anchored {
+                        true => regex.search(&self.input(line)).into_iter().collect(),
+                        false => regex.find_iter(line).collect(),
+                    };
+                    matches.extend(found.into_iter().map(|m| Match {
+                        start: m.start(),
+                        end: m.end(),
+                        pattern,
+                        distance: 0,
+                    }));
+                }
+                matches
+            }
+            Engine::Literals {
+                overlapping,
+                variants,
+                owners,
+                case_ignore,
+                ..
+            } => match self.anchored {
+                true => literal_prefixes(line, variants, owners, *case_ignore).collect(),
+                false => overlapping_automaton(overlapping, variants, *case_ignore)
+                    .find_overlapping_iter(line)
+                    .map(|m| Match {
+                        start: m.start(),
+                        end: m.end(),
+                        pattern: owners[m.pattern().as_usize()],
+                        distance: 0,
+                    })
+                    .collect(),
+            },
+            Engine::Fuzzy {
+                patterns,
+                max_edits,
+                case_ignore,
+            } => self
+                .fuzzy_each(line, patterns, *max_edits, *case_ignore)
+                .collect(),
+        };
+        matches.sort_by_key(|m| (m.start, m.end, m.pattern));
+        matches
+    }
+}
+
+// 整块搜索用的正则：^ 和 $ 匹配每一行的边界（\r\n 也算行尾），字符类中去掉 \n，因此匹配不会跨行
+// 在不含孤立 \r 的文本中，逐行搜索能找到的匹配在整块文本中同样是匹配，多出来的候选由逐行确认排除
+fn buffer_regex(
+    builder: &meta::Builder,
+    regexes: &[String],
+    case_ignore: bool,
+) -> Option<meta::Regex> {
+    if regexes.iter().any(|pattern| changes_line_flags(pattern)) {
+        return None;
+    }
+    let mut hirs = Vec::new();
+    for pattern in regexes {
+        let hir = regex_syntax::ParserBuilder::new()
+            .case_insensitive(case_ignore)
+            .multi_line(true)
+            .crlf(true)
+            .build()
+            .parse(pattern)
+            .ok()?;
+        let looks = hir.properties().look_set();
+        if looks.contains(Look::Start) || looks.contains(Look::End) {
+            return None;
+        }
+        hirs.push(without_newlines(&hir));
+    }
+    builder.build_many_from_hir(&hirs).ok()
+}
+
+// 去掉模式中所有能匹配 \n 的部分：字符类减去 \n，含有 \n 的字面量永远不匹配
+// 逐行搜索时行内没有 \n，这些部分本来就不可能匹配上
+fn without_newlines(hir: &Hir) -> Hir {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => hir.clone(),
+        HirKind::Literal(literal) => match literal.0.contains(&b'\n') {
+            true => Hir::fail(),
+            false => hir.clone(),
+        },
+        HirKind::Class(Class::Unicode(class)) => {
+            let mut class = class.clone();
+            class.difference(&ClassUnicode::new([ClassUnicodeRange::new('\n', '\n')]));
+            Hir::class(Class::Unicode(class))
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let mut class = class.clone();
+            class.difference(&ClassBytes::new([ClassBytesRange::new(b'\n', b'\n')]));
+            Hir::class(Class::Bytes(class))
+        }
+        HirKind::Repetition(repetition) => Hir::repetition(Repetition {
+            sub: Box::new(without_newlines(&repetition.sub)),
+            ..repetition.clone()
+        }),
+        HirKind::Capture(capture) => Hir::capture(Capture {
+            sub: Box::new(without_newlines(&capture.sub)),
+            ..capture.clone()
+        }),
+        HirKind::Concat(subs) => Hir::concat(subs.iter().map(without_newlines).collect()),
+        HirKind::Alternation(subs) => Hir::alternation(subs.iter().map(without_newlines).collect()),
+    }
+}
+
+// 模式中是否有改动 R 标志（\r\n 是否算行尾）的内联标志组，例如 (?-R) 或 (?R:...)
+// (?-m) 会变成 \A、\z 这类锚点，已经另外判断；只看文本形式，\(?R 这样的写法也会被当作标志组，结果只是多了一次不必要的逐行搜索
+fn changes_line_flags(pattern: &str) -> bool {
+    pattern.match_indices("(?").any(|(idx, _)| {
+        pattern[idx + 2..]
+            .split([':', ')'])
+            .next()
+            .is_some_and(|flags| flags.contains('R'))
+    })
+}
+
+// 交给 Aho-Corasick 的各个写法及其所属的模式编号
+// -i 时某个模式的写法超过 MAX_LITERAL_VARIANTS 种（例如很多个 k 和 s）就返回 None，改用正则引擎
+fn literal_variants(patterns: &[&str], opts: &Options) -> Option<(Vec<String>, Vec<usize>)> {
+    let mut variants = Vec::new();
+    let mut owners = Vec::new();
+    for (index, pattern) in patterns.iter().enumerate() {
+        let spellings = match opts.case_ignore {
+            true => fold::ascii_case_variants(pattern, opts.fixed_strings, MAX_LITERAL_VARIANTS)?,
+            false => vec![pattern.to_string()],
+        };
+        owners.extend(std::iter::repeat_n(index, spellings.len()));
+        variants.extend(spellings);
+    }
+    Some((variants, owners))
+}
+
+// 统计每个模式命中情况用的可重叠自动机，第一次用到时构建
+fn overlapping_automaton<'a>(
+    cell: &'a OnceLock<AhoCorasick>,
+    patterns: &[String],
+    case_ignore: bool,
+) -> &'a AhoCorasick {
+    cell.get_or_init(|| {
+        AhoCorasick::builder()
+            .ascii_case_insensitive(case_ignore)
+            .build(patterns)
+            .expect("literal patterns already compiled once")
+    })
+}
+
+// 锚定时作为行前缀出现的字面量模式（的某种写法）
+fn literal_prefixes<'a>(
+    line: &'a str,
+    variants: &'a [String],
+    owners: &'a [usize],
+    case_ignore: bool,
+) -> impl Iterator<Item = Match> + 'a {
+    variants
+        .iter()
+        .zip(owners)
+        .filter_map(move |(pattern, &owner)| {
+            let prefix = line.as_bytes().get(..pattern.len())?;
+            let found = match case_ignore {
+                true => prefix.eq_ignore_ascii_case(pattern.as_bytes()),
+                false => prefix == pattern.as_bytes(),
+            };
+            found.then_some(Match {
+                start: 0,
+                end: pattern.len(),
+                pattern: owner,
+                distance: 0,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 编译失败时取出其中的 PatternError
+    fn compile_error(patterns: &[&str], opts: &Options) -> PatternError {
+        match Matcher::new(patterns, opts) {
+            Ok(_) => panic!("{:?} should not compile", patterns),
+            Err(err) => err
+                .downcast::<PatternError>()
+                .expect("compile errors are PatternError"),
+        }
+    }
+
+    // 渲染结果中标出区间的那一行
+    fn caret_line(err: &PatternError) -> String {
+        err.to_string().lines().nth(2).unwrap().to_string()
+    }
+
+    #[test]
+    fn caret_marks_the_error_span() {
+        let cases = [
+            ("a(b", 1, 2, "     ^"),
+            ("x{2,1}", 1, 6, "     ^^^^^"),
+            ("[z-a]", 1, 4, "     ^^^"),
+            (r"\p{Nope}", 0, 8, "    ^^^^^^^^"),
+            ("ab)", 2, 3, "      ^"),
+        ];
+        for (pattern, start, end, carets) in cases {
+            let err = compile_error(&[pattern], &Options::default());
+            assert_eq!((err.start, err.end), (start, end), "{}", pattern);
+            assert_eq!(caret_line(&err), carets, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn rendering_shows_pattern_and_message() {
+        let err = compile_error(&["a(b"], &Options::default());
+        assert_eq!(
+            err.to_string(),
+            "regex parse error:\n    a(b\n     ^\nerror: unclosed group"
+        );
+    }
+
+    #[test]
+    fn case_insensitive_does_not_shift_offsets() {
+        let opts = Options {
+            case_ignore: true,
+            ..Options::default()
+        };
+        let err = compile_error(&["x{2,1}"], &opts);
+        assert_eq!((err.start, err.end), (1, 6));
+        assert_eq!(err.pattern, "x{2,1}");
+    }
+
+    #[test]
+    fn offsets_count_characters_not_bytes() {
+        let err = compile_error(&["日本(x"], &Options::default());
+        assert_eq!((err.start, err.end), (2, 3));
+        assert_eq!(caret_line(&err), "      ^");
+    }
+
+    #[test]
+    fn word_and_line_wrapping_do_not_shift_offsets() {
+        for opts in [
+            Options {
+                word: Some(WordBoundary::Unicode),
+                ..Options::default()
+            },
+            Options {
+                line_regexp: true,
+                ..Options::default()
+            },
+        ] {
+            let err = compile_error(&["[z-a]"], &opts);
+            assert_eq!(err.pattern, "[z-a]");
+            assert_eq!((err.start, err.end), (1, 4));
+        }
+    }
+
+    // -w 下 pattern 在 line 中匹配到的文本
+    fn words<'a>(pattern: &str, boundary: WordBoundary, line: &'a str) -> Vec<&'a str> {
+        let opts = Options {
+            word: Some(boundary),
+            ..Options::default()
+        };
+        let matcher = Matcher::new(&[pattern], &opts).unwrap();
+        matcher
+            .find_iter(line)
+            .map(|m| &line[m.start..m.end])
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn unicode_word_boundaries_treat_accented_letters_as_word_characters() {
+        use WordBoundary::*;
+        assert!(words("na", Unicode, "naïve").is_empty());
+        assert_eq!(words("na", Ascii, "naïve"), ["na"]);
+        assert_eq!(words("café", Unicode, "un café noir"), ["café"]);
+        assert!(words("café", Unicode, "cafés").is_empty());
+        // ASCII 边界下 é 不是单词字符，caf 是一个完整的单词
+        assert!(words("caf", Unicode, "café").is_empty());
+        assert_eq!(words("caf", Ascii, "café"), ["caf"]);
+    }
+
+    #[test]
+    fn cjk_text_is_one_word_unless_boundaries_are_ascii() {
+        use WordBoundary::*;
+        assert!(words("日本", Unicode, "日本語").is_empty());
+        assert_eq!(words("日本", Unicode, "日本 語"), ["日本"]);
+        assert_eq!(words("日本", Ascii, "日本語"), ["日本"]);
+        assert!(words("語", Ascii, "日本語x").is_empty());
+    }
+
+    #[test]
+    fn underscores_and_digits_are_word_characters_in_both_modes() {
+        for boundary in [WordBoundary::Unicode, WordBoundary::Ascii] {
+            assert!(words("na", boundary, "na_1").is_empty());
+            assert!(words("na", boundary, "na1 1na").is_empty());
+            assert_eq!(words("na", boundary, "na-1 (na)"), ["na", "na"]);
+            assert_eq!(words("_1", boundary, "na _1"), ["_1"]);
+        }
+    }
+
+    #[test]
+    fn ascii_boundaries_leave_the_pattern_unicode_aware() {
+        // ٣ 是阿拉伯-印度数字，Unicode 的 \d 匹配它；只有边界断言是 ASCII 的
+        assert_eq!(words(r"\d", WordBoundary::Ascii, "x ٣ y"), ["٣"]);
+        assert_eq!(words(r"\w+", WordBoundary::Ascii, "été"), ["été"]);
+    }
+
+    #[test]
+    fn word_patterns_that_start_or_end_with_punctuation() {
+        // 半边界断言只看模式之外的字符
+        assert_eq!(words(r"-x", WordBoundary::Unicode, "a -x b"), ["-x"]);
+        assert_eq!(words(r"x\(", WordBoundary::Unicode, "x( y"), ["x("]);
+        assert!(words(r"-x", WordBoundary::Unicode, "a -xy").is_empty());
+    }
+
+    #[test]
+    fn error_names_the_failing_pattern() {
+        let err = compile_error(&["ok", "fine", "b("], &Options::default());
+        assert_eq!(err.index, 2);
+        assert_eq!(err.pattern, "b(");
+    }
+
+    #[test]
+    fn literals_cover_non_ascii_case_folds() {
+        let opts = Options {
+            case_ignore: true,
+            ..Options::default()
+        };
+        let matcher = Matcher::new(&["k", "zz"], &opts).unwrap();
+        assert_eq!(matcher.info().engine, "aho-corasick");
+        assert!(matcher.is_match("\u{212a}"));
+        let found = matcher.find_each("-\u{212a}-ZZ");
+        assert_eq!(found.iter().map(|m| m.pattern).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!((found[0].start, found[0].end), (1, 4));
+        let matcher = Matcher::new(&["as", "zz"], &opts).unwrap();
+        assert!(matcher.is_match("A\u{17f}"));
+        assert_eq!(matcher.matching_patterns("A\u{17f} zZ"), [0, 1]);
+        let matcher = Matcher::new(&["café", "zz"], &opts).unwrap();
+        assert_eq!(matcher.info().engine, "aho-corasick");
+        assert!(matcher.is_match("CAFÉ"));
+
+        let fixed = Options {
+            fixed_strings: true,
+            ..opts.clone()
+        };
+        let matcher = Matcher::new(&["office", "strasse"], &fixed).unwrap();
+        assert_eq!(matcher.info().engine, "aho-corasick");
+        assert_eq!(matcher.pattern_len(), 2);
+        assert!(matcher.is_match("THE OFFICE") && matcher.is_match("the oﬃce"));
+        assert_eq!(matcher.matching_patterns("STRAẞE"), [1]);
+
+        let anchored = Options {
+            anchored: true,
+            ..opts
+        };
+        let matcher = Matcher::new(&["kiln", "zz"], &anchored).unwrap();
+        assert_eq!(matcher.matching_patterns("\u{212a}ILN fired"), [0]);
+        assert!(!matcher.is_match("a kiln"));
+    }
+
+    #[test]
+    fn literals_with_too_many_variants_use_the_regex() {
+        let opts = Options {
+            case_ignore: true,
+            ..Options::default()
+        };
+        let matcher = Matcher::new(&["kkkkkkk", "zz"], &opts).unwrap();
+        assert_eq!(matcher.info().engine, "regex");
+        assert!(matcher.is_match("kk\u{212a}kKkk"));
+    }
+
+    #[test]
+    fn case_ignore_is_a_builder_option() {
+        let opts = Options {
+            case_ignore: true,
+            ..Options::default()
+        };
+        let matcher = Matcher::new(&["straße"], &opts).unwrap();
+        assert_eq!(matcher.info().pattern, "straße");
+        assert!(matcher.info().case_insensitive);
+        assert!(matcher.is_match("STRAẞE"));
+        assert!(matcher.is_match("Straße"));
+    }
+}
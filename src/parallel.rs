@@ -0,0 +1,359 @@
+// 多线程搜索多个文件的工作池
+//
+// 文件放在共享的队列里，每个工作线程取出一个文件、完整地搜索完，再取下一个。
+// 每个工作线程有自己的 Sink（由调用方给出的工厂函数创建），同一个文件的结果总是连续地交给同一个 Sink，
+// 不同文件之间的先后顺序不固定。
+// 设置了 max_matches 时，所有工作线程从同一个计数器中预留名额，交给 Sink 的结果一共恰好是这么多条
+// （输入中的结果不够时则是全部），名额用完后各线程停止搜索当前文件，也不再取新的文件。
+// 每个工作线程累计自己搜索过的文件的 SearchStats，全部搜索完之后交给 Sink::finished。
+// search_ordered 是同一个工作池的另一种用法：提前搜索后面的文件，结果按输入的顺序交回调用方的线程。
+use std::{
+    collections::VecDeque,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+
+use crate::search::{SearchResultRef, SearchStats, Searcher};
+
+// Searcher 要在线程之间共享，编译时保证它是 Send + Sync
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Searcher>();
+};
+
+// 接收一个工作线程的搜索结果
+pub trait Sink {
+    // 开始搜索一个文件
+    fn begin_file(&mut self, _path: &Path) {}
+
+    // 文件中的一条结果，返回 false 时不再搜索这个文件剩下的部分
+    fn matched(&mut self, path: &Path, result: SearchResultRef<'_>) -> bool;
+
+    // 文件搜索完毕，或者打开、读取时出错
+    fn end_file(&mut self, _path: &Path, _result: Result<()>) {}
+
+    // 这个工作线程不再取新的文件，stats 是它搜索过的所有文件的统计之和
+    fn finished(&mut self, _stats: &SearchStats) {}
+}
+
+// 在多个线程上用同一个 Searcher 搜索一批文件
+pub struct ParallelSearcher {
+    searcher: Arc<Searcher>,
+    threads: usize,
+    // 一次 search_files 最多交给 Sink 的结果条数
+    max_matches: Option<usize>,
+    // 是否给每个工作线程计时
+    timing: bool,
+}
+
+impl ParallelSearcher {
+    // threads 为 0 时使用全部 CPU；searcher 可以是已经与别处共享的 Arc<Searcher>
+    pub fn new(searcher: impl Into<Arc<Searcher>>, threads: usize) -> ParallelSearcher {
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        ParallelSearcher {
+            searcher: searcher.into(),
+            threads,
+            max_matches: None,
+            timing: false,
+        }
+    }
+
+    // 限制每次 search_files 交给 Sink 的结果总条数
+    pub fn with_max_matches(mut self, max_matches: usize) -> ParallelSearcher {
+        self.max_matches = Some(max_matches);
+        self
+    }
+
+    // 给每个工作线程计时，交给 Sink::finished 的 elapsed 是它从开始到取不到新文件的时间
+    pub fn with_timing(mut self) -> ParallelSearcher {
+        self.timing = true;
+        self
+    }
+
+    pub fn searcher(&self) -> &Searcher {
+        &self.searcher
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    // 搜索 paths 中的所有文件，每个工作线程调用一次 sink_factory 得到自己的 Sink
+    // 全部文件搜索完之后返回各个工作线程的 Sink，顺序与线程编号一致
+    pub fn search_files<I, P, F, S>(&self, paths: I, sink_factory: F) -> Vec<S>
+    where
+        I: IntoIterator<Item = P>,
+        I::IntoIter: Send,
+        P: AsRef<Path>,
+        F: Fn() -> S + Sync,
+        S: Sink + Send,
+    {
+        let queue = Mutex::new(paths.into_iter());
+        // 已经预留的名额
+        let reserved = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let started = self.timing.then(Instant::now);
+                        let mut sink = sink_factory();
+                        let mut stats = SearchStats::default();
+                        loop {
+                            if self.exhausted(&reserved) {
+                                break;
+                            }
+                            // 只在取下一个文件时持有锁，搜索期间不阻塞其他线程
+                            let next = queue.lock().unwrap().next();
+                            let Some(path) = next else { break };
+                            let path = path.as_ref();
+                            sink.begin_file(path);
+                            let result = self.search_file(path, &mut sink, &reserved, &mut stats);
+                            sink.end_file(path, result);
+                        }
+                        stats.elapsed = started.map(|started| started.elapsed());
+                        sink.finished(&stats);
+                        sink
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("search thread panicked"))
+                .collect()
+        })
+    }
+
+    // 在工作线程上提前搜索 inputs 中后面的文件，迭代器按 inputs 的顺序交出每个输入
+    // job 给出一个输入要交给工作线程的文件，work 在工作线程上搜索它，结果从与输入一起交出的通道取回；
+    // job 返回 None 的输入不带通道，由调用方自己处理。提前搜索的文件最多是线程数的两倍，
+    // 它们的结果都留在内存中。不受 max_matches 限制，也不计时。
+    // 丢弃迭代器后工作线程不再开始新的文件，搜索完手上的文件就退出，不等待它们结束
+    pub fn search_ordered<I, F, W, T>(&self, inputs: I, job: F, work: W) -> Ordered<I, F, T>
+    where
+        I: Iterator,
+        F: FnMut(&I::Item) -> Option<PathBuf>,
+        W: Fn(&Searcher, &Path) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let (jobs, queue) = mpsc::channel::<Job<T>>();
+        let queue = Arc::new(Mutex::new(queue));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let work = Arc::new(work);
+        for _ in 0..self.threads {
+            let queue = queue.clone();
+            let stopped = stopped.clone();
+            let searcher = self.searcher.clone();
+            let work = work.clone();
+            thread::spawn(move || {
+                loop {
+                    // 只在取下一个文件时持有锁
+                    let next = queue.lock().unwrap().recv();
+                    let Ok(job) = next else { break };
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let _ = job.done.send(work(&searcher, &job.path));
+                }
+            });
+        }
+        Ordered {
+            inputs,
+            job,
+            jobs: Some(jobs),
+            stopped,
+            pending: VecDeque::new(),
+            window: self.threads * 2,
+        }
+    }
+
+    // 搜索单个文件，结果交给 sink，统计加到 stats 上；每条结果先预留一个名额，预留不到时停止
+    fn search_file<S: Sink>(
+        &self,
+        path: &Path,
+        sink: &mut S,
+        reserved: &AtomicUsize,
+        stats: &mut SearchStats,
+    ) -> Result<()> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        self.searcher
+            .search_with_stats(
+                self.searcher.buffered(file),
+                |result| {
+                    self.reserve(reserved)
+                        && sink.matched(path, result)
+                        && !self.exhausted(reserved)
+                },
+                stats,
+            )
+            .with_context(|| format!("Failed to read or search {}", path.display()))
+    }
+
+    // 预留一个名额，名额已经用完时返回 false
+    fn reserve(&self, reserved: &AtomicUsize) -> bool {
+        let Some(max) = self.max_matches else {
+            return true;
+        };
+        reserved
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    // 名额是否已经用完
+    fn exhausted(&self, reserved: &AtomicUsize) -> bool {
+        self.max_matches
+            .is_some_and(|max| reserved.load(Ordering::Relaxed) >= max)
+    }
+}
+
+// 交给工作线程的一个文件
+struct Job<T> {
+    path: PathBuf,
+    done: Sender<T>,
+}
+
+// ParallelSearcher::search_ordered 返回的迭代器
+pub struct Ordered<I: Iterator, F, T> {
+    inputs: I,
+    job: F,
+    jobs: Option<Sender<Job<T>>>,
+    // 丢弃时置位，工作线程不再开始新的文件
+    stopped: Arc<AtomicBool>,
+    pending: VecDeque<(I::Item, Option<Receiver<T>>)>,
+    window: usize,
+}
+
+impl<I, F, T> Iterator for Ordered<I, F, T>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> Option<PathBuf>,
+{
+    // 输入，以及交给工作线程时取结果的通道
+    type Item = (I::Item, Option<Receiver<T>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.len() < self.window {
+            let Some(input) = self.inputs.next() else {
+                break;
+            };
+            let result = (self.job)(&input).and_then(|path| {
+                let (done, result) = mpsc::channel();
+                let jobs = self.jobs.as_ref().expect("jobs are closed only on drop");
+                jobs.send(Job { path, done }).ok().map(|_| result)
+            });
+            self.pending.push_back((input, result));
+        }
+        self.pending.pop_front()
+    }
+}
+
+impl<I: Iterator, F, T> Drop for Ordered<I, F, T> {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.jobs = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Options;
+
+    // 只累计统计的 Sink
+    #[derive(Default)]
+    struct Stats(SearchStats);
+
+    impl Sink for Stats {
+        fn matched(&mut self, _path: &Path, _result: SearchResultRef<'_>) -> bool {
+            true
+        }
+
+        fn finished(&mut self, stats: &SearchStats) {
+            self.0 = *stats;
+        }
+    }
+
+    #[test]
+    fn worker_stats_add_up_to_the_sequential_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        let mut expected = SearchStats::default();
+        let searcher = Searcher::new("err", Options::default()).unwrap();
+        for n in 0..8 {
+            let path = dir.path().join(format!("{n}.txt"));
+            let contents = "err\nok\n".repeat(n + 1) + "err err";
+            std::fs::write(&path, &contents).unwrap();
+            searcher
+                .search_with_stats(contents.as_bytes(), |_| true, &mut expected)
+                .unwrap();
+            paths.push(path);
+        }
+        let sinks = ParallelSearcher::new(searcher, 3)
+            .with_timing()
+            .search_files(&paths, Stats::default);
+        let mut total = SearchStats::default();
+        for sink in &sinks {
+            assert!(sink.0.elapsed.is_some());
+            total.add(&sink.0);
+        }
+        assert_eq!(
+            SearchStats {
+                elapsed: None,
+                ..total
+            },
+            expected
+        );
+    }
+
+    #[test]
+    fn ordered_results_follow_the_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let searcher = Searcher::new("err", Options::default()).unwrap();
+        let mut inputs = Vec::new();
+        for n in 0..12 {
+            let path = dir.path().join(format!("{n}.txt"));
+            // 前面的文件更大，往往比后面的文件晚搜索完
+            std::fs::write(&path, "ok\n".repeat((12 - n) * 1000) + &"err\n".repeat(n)).unwrap();
+            inputs.push(Some(path));
+            // None 的输入不交给工作线程
+            inputs.push(None);
+        }
+        let ordered = ParallelSearcher::new(searcher, 4).search_ordered(
+            inputs.clone().into_iter(),
+            |input| input.clone(),
+            |searcher, path| {
+                searcher
+                    .count(searcher.buffered(File::open(path).unwrap()))
+                    .unwrap()
+            },
+        );
+        let mut seen = Vec::new();
+        for (input, result) in ordered {
+            assert_eq!(input.is_some(), result.is_some());
+            seen.push((input, result.map(|result| result.recv().unwrap())));
+        }
+        let expected: Vec<_> = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                let count = input.is_some().then_some(idx / 2);
+                (input, count)
+            })
+            .collect();
+        assert_eq!(seen, expected);
+    }
+}
@@ -0,0 +1,235 @@
+use std::{fmt, fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+
+// 模式的来源，报错时用来告诉用户是哪一个模式写错了
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternSource {
+    // 第一个位置参数
+    Positional,
+    // 第 n 个 -e 参数（从 1 开始）
+    Regexp(usize),
+    // -f 文件中的某一行（从 1 开始）
+    File { path: PathBuf, line: usize },
+    // 第 n 个 --not 参数（从 1 开始）
+    Not(usize),
+    // --between 的起始模式（1）或结束模式（2）
+    Between(usize),
+}
+
+impl fmt::Display for PatternSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternSource::Positional => write!(f, "the pattern argument"),
+            PatternSource::Regexp(n) => write!(f, "-e pattern #{}", n),
+            PatternSource::File { path, line } => write!(f, "{} line {}", path.display(), line),
+            PatternSource::Not(n) => write!(f, "--not pattern #{}", n),
+            PatternSource::Between(1) => write!(f, "the --between start pattern"),
+            PatternSource::Between(_) => write!(f, "the --between end pattern"),
+        }
+    }
+}
+
+// 从命令行收集的所有模式
+#[derive(Debug, Clone, Default)]
+pub struct Patterns {
+    pub texts: Vec<String>,
+    pub sources: Vec<PatternSource>,
+    // --not 给出的排除模式
+    pub excluded: Vec<String>,
+}
+
+impl Patterns {
+    // 按编号找出模式的来源，排除模式编号排在所有普通模式之后，--between 的两个模式再排在最后
+    pub fn source(&self, index: usize) -> PatternSource {
+        match self.sources.get(index) {
+            Some(source) => source.clone(),
+            None if index - self.sources.len() < self.excluded.len() => {
+                PatternSource::Not(index - self.sources.len() + 1)
+            }
+            None => PatternSource::Between(index - self.sources.len() - self.excluded.len() + 1),
+        }
+    }
+
+    fn push(&mut self, text: String, source: PatternSource) {
+        self.texts.push(text);
+        self.sources.push(source);
+    }
+}
+
+// 收集 -e 和 -f 给出的模式
+// 两者都没有时返回 None，此时第一个位置参数才是模式
+pub fn collect(matches: &ArgMatches) -> Result<Option<Patterns>> {
+    if !matches.contains_id("regexp") && !matches.contains_id("file") {
+        return Ok(None);
+    }
+
+    let mut patterns = Patterns::default();
+    for (idx, text) in matches
+        .get_many::<String>("regexp")
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        patterns.push(text.clone(), PatternSource::Regexp(idx + 1));
+    }
+
+    // 模式文件每行一个模式，空文件表示没有任何模式（什么都不匹配）
+    let comments = matches.get_flag("file-pattern-comments");
+    for path in matches.get_many::<PathBuf>("file").into_iter().flatten() {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pattern file {}", path.display()))?;
+        let lines = match comments {
+            true => logical_lines(&contents),
+            false => contents
+                .lines()
+                .enumerate()
+                .map(|(idx, line)| (idx + 1, line.to_string()))
+                .collect(),
+        };
+        for (line, text) in lines {
+            let source = PatternSource::File {
+                path: path.clone(),
+                line,
+            };
+            patterns.push(text, source);
+        }
+    }
+
+    Ok(Some(patterns))
+}
+
+// --file-pattern-comments：去掉空行和注释，把以 \ 结尾的行与下一行拼起来
+// 返回每个模式和它第一行的行号，报错时仍然指向文件中原来的位置
+// 行尾有奇数个反斜杠才是续行，\\ 结尾的行是匹配反斜杠的模式
+fn logical_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (idx, line) in contents.lines().enumerate() {
+        if pending.is_none() && (line.trim().is_empty() || line.trim_start().starts_with('#')) {
+            continue;
+        }
+        let (first, mut text) = pending.take().unwrap_or((idx + 1, String::new()));
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        match backslashes % 2 {
+            1 => {
+                text.push_str(&line[..line.len() - 1]);
+                pending = Some((first, text));
+            }
+            _ => {
+                text.push_str(line);
+                lines.push((first, text));
+            }
+        }
+    }
+    // 最后一行也以 \ 结尾时，已经拼好的部分照常作为一个模式
+    lines.extend(pending);
+    lines
+}
+
+// 是否忽略大小写：-i、-S 和 --case-sensitive 互相覆盖（clap 的 overrides_with），只有最后给出的一个生效；
+// 配置文件的参数排在命令行之前，所以命令行上的总是优先。三者都没有时区分大小写。
+// patterns 是所有按这个设置编译的模式，-S 时用来判断有没有大写字母
+pub fn case_ignore<'a>(matches: &ArgMatches, patterns: impl IntoIterator<Item = &'a str>) -> bool {
+    matches.get_flag("ignore-case")
+        || (matches.get_flag("smart-case")
+            && smart_case(patterns, matches.get_flag("fixed-strings")))
+}
+
+// --smart-case：所有模式都没有大写字母时才忽略大小写
+// 正则中反斜杠之后的字符是转义（\S、\W 等）不算；-F 时模式按字面比较，每个字符都算
+pub fn smart_case<'a>(patterns: impl IntoIterator<Item = &'a str>, fixed_strings: bool) -> bool {
+    !patterns.into_iter().any(|pattern| {
+        let mut escaped = false;
+        pattern.chars().any(|c| {
+            let upper = !escaped && c.is_uppercase();
+            escaped = !escaped && !fixed_strings && c == '\\';
+            upper
+        })
+    })
+}
+
+// --between 给出的起始和结束模式
+pub fn between(matches: &ArgMatches) -> Option<(String, String)> {
+    let mut values = matches.get_many::<String>("between")?.cloned();
+    Some((values.next()?, values.next()?))
+}
+
+// 收集 --not 给出的排除模式
+pub fn excluded(matches: &ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("not")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLAGS: [Option<&str>; 4] = [None, Some("-i"), Some("-S"), Some("--case-sensitive")];
+
+    // 按 main 的做法把配置文件的参数放在命令行参数之前，再解析
+    fn resolve(config: &[&str], command_line: &[&str], pattern: &str) -> bool {
+        let args = ["mrustgrep"]
+            .into_iter()
+            .chain(config.iter().copied())
+            .chain(command_line.iter().copied())
+            .chain([pattern]);
+        let matches = crate::cli::build().try_get_matches_from(args).unwrap();
+        case_ignore(&matches, [pattern])
+    }
+
+    // 期望的结果：最后一个大小写参数决定，-S 看模式中有没有大写字母
+    fn expected(flags: &[&str], pattern: &str) -> bool {
+        match flags.last() {
+            Some(&"-i") => true,
+            Some(&"-S") => !pattern.chars().any(char::is_uppercase),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn last_case_flag_wins_and_the_command_line_beats_the_config_file() {
+        for pattern in ["needle", "Needle"] {
+            for config in FLAGS {
+                for first in FLAGS {
+                    for second in FLAGS {
+                        let config: Vec<&str> = config.into_iter().collect();
+                        let command_line: Vec<&str> =
+                            [first, second].into_iter().flatten().collect();
+                        let all: Vec<&str> = config.iter().chain(&command_line).copied().collect();
+                        assert_eq!(
+                            resolve(&config, &command_line, pattern),
+                            expected(&all, pattern),
+                            "config {config:?}, command line {command_line:?}, pattern {pattern:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_case_sensitive_beats_every_default() {
+        for config in [&["-i"][..], &["-S"], &["-i", "-S"], &["-S", "-i"]] {
+            assert!(!resolve(config, &["--case-sensitive"], "needle"));
+        }
+        // 与 -i 同时给出时看顺序
+        assert!(resolve(&[], &["--case-sensitive", "-i"], "needle"));
+        assert!(!resolve(&[], &["-i", "--case-sensitive"], "needle"));
+    }
+
+    #[test]
+    fn smart_case_ignores_escapes_except_with_fixed_strings() {
+        assert!(smart_case(["a\\Sb"], false));
+        assert!(!smart_case(["a\\Sb"], true));
+        assert!(!smart_case(["ok", "Upper"], false));
+        // 大小写不同的非 ASCII 字母同样算
+        assert!(!smart_case(["Éte"], false));
+        assert!(smart_case(["été"], false));
+    }
+}
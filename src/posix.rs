@@ -0,0 +1,364 @@
+// POSIX 正则兼容模式（--posix-basic / --posix-extended）：把 grep 的 BRE、ERE 写法翻译成 regex 的语法
+//
+// BRE（grep 默认）中 \( \) \{ \} \| \+ \? 是元字符，不带反斜杠的 ( ) { } | + ? 是普通字符；
+// ERE（grep -E）与 regex 的写法基本相同，区别在下面这些细节：
+// - 出现在开头（或分组、分支开头）的 * 是普通字符；
+// - BRE 中 ^ 只在开头、$ 只在结尾才是锚点，其他位置是普通字符；
+// - 方括号表达式中的反斜杠是普通字符，[]abc] 和 [^]abc] 中的 ] 是普通字符，[:alpha:] 这类字符类照常支持；
+// - ERE 中不是合法区间的 { 是普通字符，{,m} 写成 {0,m}；
+// - GNU 扩展 \< \> \b \B \w \W \s \S 照常支持。
+// 反向引用（\1 到 \9）和排序相关的 [=a=]、[.a.] 无法翻译，报告为模式错误。
+
+use crate::matcher::PatternError;
+
+// POSIX 正则的两种语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Syntax {
+    Basic,
+    Extended,
+}
+
+// 翻译过程中的状态
+struct Translator<'a> {
+    syntax: Syntax,
+    chars: Vec<char>,
+    pos: usize,
+    out: String,
+    // 出错时报告用
+    index: usize,
+    pattern: &'a str,
+}
+
+// 把第 index 个模式从 POSIX 语法翻译成 regex 的语法
+pub fn translate(index: usize, pattern: &str, syntax: Syntax) -> Result<String, PatternError> {
+    let mut translator = Translator {
+        syntax,
+        chars: pattern.chars().collect(),
+        pos: 0,
+        out: String::new(),
+        index,
+        pattern,
+    };
+    translator.run()?;
+    Ok(translator.out)
+}
+
+impl Translator<'_> {
+    fn error(&self, start: usize, end: usize, message: &str) -> PatternError {
+        PatternError {
+            index: self.index,
+            pattern: self.pattern.to_string(),
+            start,
+            end,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn literal(&mut self, c: char) {
+        self.out
+            .push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+    }
+
+    // 当前位置是否是一个子表达式的开头（此时 * 是普通字符，BRE 的 ^ 是锚点）
+    fn at_expression_start(&self) -> bool {
+        let before = &self.chars[..self.pos];
+        match self.syntax {
+            Syntax::Basic => {
+                before.is_empty()
+                    || before.ends_with(&['\\', '('])
+                    || before.ends_with(&['\\', '|'])
+                    || before == ['^']
+                    || before.ends_with(&['\\', '(', '^'])
+            }
+            Syntax::Extended => before.is_empty() || matches!(before.last(), Some('(' | '|' | '^')),
+        }
+    }
+
+    // BRE 中当前的 $ 是否在子表达式的结尾（因而是锚点）
+    fn at_expression_end(&self) -> bool {
+        let rest = &self.chars[self.pos + 1..];
+        rest.is_empty() || rest.starts_with(&['\\', ')']) || rest.starts_with(&['\\', '|'])
+    }
+
+    fn run(&mut self) -> Result<(), PatternError> {
+        while let Some(c) = self.peek(0) {
+            match (c, self.syntax) {
+                // 这几种写法自己移动位置
+                ('\\', _) => {
+                    self.escape()?;
+                    continue;
+                }
+                ('[', _) => {
+                    self.bracket()?;
+                    continue;
+                }
+                ('*', _) if self.at_expression_start() => self.literal('*'),
+                ('*' | '.', _) => self.out.push(c),
+                ('^', Syntax::Basic) if !self.at_expression_start() => self.literal('^'),
+                ('$', Syntax::Basic) if !self.at_expression_end() => self.literal('$'),
+                ('^' | '$', _) => self.out.push(c),
+                ('(' | ')' | '|' | '+' | '?', Syntax::Extended) => {
+                    if matches!(c, '+' | '?') && self.at_expression_start() {
+                        self.literal(c);
+                    } else {
+                        self.out.push(c);
+                    }
+                }
+                ('{', Syntax::Extended) => {
+                    // 合法的区间已经跳过，否则 { 是普通字符，照常前进一个字符
+                    if self.interval(1)? {
+                        continue;
+                    }
+                    self.literal('{');
+                }
+                _ => self.literal(c),
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    // 反斜杠开头的写法
+    fn escape(&mut self) -> Result<(), PatternError> {
+        let start = self.pos;
+        let Some(c) = self.peek(1) else {
+            return Err(self.error(start, start + 1, "trailing backslash"));
+        };
+        match (c, self.syntax) {
+            ('1'..='9', _) => {
+                return Err(self.error(start, start + 2, "back-references are not supported"));
+            }
+            ('(' | ')' | '|', Syntax::Basic) => self.out.push(c),
+            ('+' | '?', Syntax::Basic) => match self.at_expression_start() {
+                true => self.literal(c),
+                false => self.out.push(c),
+            },
+            ('{', Syntax::Basic) => {
+                if !self.interval(2)? {
+                    return Err(self.error(start, start + 2, "invalid interval"));
+                }
+                return Ok(());
+            }
+            ('<', _) => self.out.push_str(r"\b{start}"),
+            ('>', _) => self.out.push_str(r"\b{end}"),
+            ('b' | 'B' | 'w' | 'W' | 's' | 'S', _) => {
+                self.out.push('\\');
+                self.out.push(c);
+            }
+            _ => self.literal(c),
+        }
+        self.pos += 2;
+        Ok(())
+    }
+
+    // 从当前位置开始的区间：BRE 中写作 \{n,m\}（close 为 2），ERE 中写作 {n,m}（close 为 1）
+    // 是合法的区间时输出并跳过它，返回 true；否则不移动位置，返回 false
+    fn interval(&mut self, close: usize) -> Result<bool, PatternError> {
+        let start = self.pos;
+        let mut end = start + close;
+        let mut body = String::new();
+        loop {
+            match self.chars.get(end) {
+                Some(&c @ ('0'..='9' | ',')) => body.push(c),
+                Some('\\') if close == 2 && self.chars.get(end + 1) == Some(&'}') => break,
+                Some('}') if close == 1 => break,
+                _ => return Ok(false),
+            }
+            end += 1;
+        }
+        let (min, max) = match body.split_once(',') {
+            Some((min, max)) => (min, Some(max)),
+            None => (body.as_str(), None),
+        };
+        if (min.is_empty() && max.is_none_or(str::is_empty)) || max.is_some_and(|m| m.contains(','))
+        {
+            return Ok(false);
+        }
+        if self.at_expression_start() {
+            return Err(self.error(start, end + close, "interval with nothing to repeat"));
+        }
+        let min = if min.is_empty() { "0" } else { min };
+        match max {
+            None => self.out.push_str(&format!("{{{}}}", min)),
+            Some(max) => self.out.push_str(&format!("{{{},{}}}", min, max)),
+        }
+        self.pos = end + close;
+        Ok(true)
+    }
+
+    // 方括号表达式，每个字符单独转义，避免与 regex 字符类中的 &&、--、~~ 和嵌套的 [ 冲突
+    fn bracket(&mut self) -> Result<(), PatternError> {
+        let start = self.pos;
+        let mut i = self.pos + 1;
+        let mut out = String::from("[");
+        if self.chars.get(i) == Some(&'^') {
+            out.push('^');
+            i += 1;
+        }
+        let first = i;
+        loop {
+            let Some(&c) = self.chars.get(i) else {
+                return Err(self.error(start, self.chars.len(), "unclosed bracket expression"));
+            };
+            if c == ']' && i > first {
+                out.push(']');
+                break;
+            }
+            // [:alpha:] 这类字符类 regex 同样支持；[=a=] 和 [.a.] 无法翻译
+            if c == '[' && matches!(self.chars.get(i + 1), Some(':' | '=' | '.')) {
+                let kind = self.chars[i + 1];
+                let Some(len) = self.chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == [kind, ']'])
+                else {
+                    return Err(self.error(i, self.chars.len(), "unclosed character class"));
+                };
+                if kind != ':' {
+                    return Err(self.error(
+                        i,
+                        i + len + 4,
+                        "equivalence classes and collating symbols are not supported",
+                    ));
+                }
+                out.extend(&self.chars[i..i + len + 4]);
+                i += len + 4;
+                continue;
+            }
+            match (self.chars.get(i + 1), self.chars.get(i + 2)) {
+                (Some('-'), Some(&end)) if end != ']' => {
+                    out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                    out.push('-');
+                    out.push_str(&regex::escape(end.encode_utf8(&mut [0; 4])));
+                    i += 3;
+                }
+                _ => {
+                    out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                    i += 1;
+                }
+            }
+        }
+        self.out.push_str(&out);
+        self.pos = i + 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Syntax::{Basic, Extended};
+
+    fn matches(syntax: Syntax, pattern: &str, text: &str) -> bool {
+        let translated = translate(0, pattern, syntax).unwrap();
+        regex::Regex::new(&translated)
+            .unwrap_or_else(|e| panic!("{pattern:?} -> {translated:?}: {e}"))
+            .is_match(text)
+    }
+
+    // (语法, 模式, 文本, 是否匹配)，结果与 GNU grep -G / -E 相同
+    const CASES: &[(Syntax, &str, &str, bool)] = &[
+        // BRE 的分组、分支和重复要带反斜杠
+        (Basic, r"\(ab\)\{2\}", "abab", true),
+        (Basic, r"\(ab\)\{2\}", "ab", false),
+        (Basic, r"a\|b", "b", true),
+        (Basic, r"a\+", "aa", true),
+        (Basic, r"ab\?c", "ac", true),
+        (Basic, "(a)", "(a)", true),
+        (Basic, "(a)", "a", false),
+        (Basic, "a|b", "a|b", true),
+        (Basic, "a|b", "a", false),
+        (Basic, "a+", "a+", true),
+        (Basic, "a+", "aa", false),
+        (Basic, "a{2}", "a{2}", true),
+        (Basic, r"a\{,2\}b", "b", true),
+        // 开头的 * 是普通字符
+        (Basic, "*a", "*a", true),
+        (Basic, "*a", "a", false),
+        (Basic, r"\(*a\)", "*a", true),
+        (Basic, "^*a", "*a", true),
+        (Extended, "a|*b", "*b", true),
+        (Extended, "a|*b", "b", false),
+        // BRE 中不在两端的 ^ $ 是普通字符
+        (Basic, "a^b", "a^b", true),
+        (Basic, "a$b", "a$b", true),
+        (Basic, "^ab$", "ab", true),
+        (Basic, "^ab$", "xab", false),
+        (Basic, r"\(^a\)", "a", true),
+        (Basic, r"a$\|b", "xa", true),
+        // ERE 与 regex 基本相同
+        (Extended, "(ab){2}", "abab", true),
+        (Extended, "a|b", "b", true),
+        (Extended, "a+b?", "aa", true),
+        (Extended, "a{,2}b", "b", true),
+        (Extended, "a{1", "a{1", true),
+        (Extended, "a{x}", "a{x}", true),
+        (Extended, "a{}", "a{}", true),
+        (Extended, "+a", "+a", true),
+        (Extended, "(?a)", "?a", true),
+        // 方括号表达式
+        (Basic, r"[\]", r"\", true),
+        (Basic, r"[\n]", "n", true),
+        (Basic, "[]a]", "]", true),
+        (Basic, "[^]a]", "]", false),
+        (Basic, "[^]a]", "b", true),
+        (Extended, "[a-c]x", "bx", true),
+        (Extended, "[a-]", "-", true),
+        (Extended, "[[:digit:]]+", "42", true),
+        (Extended, "[[:alpha:]]", "1", false),
+        (Extended, "[a&&b]", "&", true),
+        (Extended, "[%--]", "+", true),
+        (Extended, "[[]", "[", true),
+        // GNU 扩展
+        (Basic, r"\<ab\>", "x ab y", true),
+        (Basic, r"\<ab\>", "xaby", false),
+        (Extended, r"\bab\b", "ab", true),
+        (Extended, r"\w\s\W", "a ,", true),
+        (Basic, r"\.", ".", true),
+        (Basic, r"\.", "a", false),
+        (Basic, ".", "a", true),
+    ];
+
+    #[test]
+    fn translated_patterns_match_like_grep() {
+        for &(syntax, pattern, text, expected) in CASES {
+            assert_eq!(
+                matches(syntax, pattern, text),
+                expected,
+                "{syntax:?} {pattern:?} on {text:?}"
+            );
+        }
+    }
+
+    // (语法, 模式, 报错区间, 消息开头)
+    const ERRORS: &[(Syntax, &str, (usize, usize), &str)] = &[
+        (Basic, r"\(a\)\1", (5, 7), "back-references"),
+        (Extended, r"(a)\1", (3, 5), "back-references"),
+        (Basic, r"ab\", (2, 3), "trailing backslash"),
+        (Basic, r"a\{x\}", (1, 3), "invalid interval"),
+        (Basic, r"\{2\}", (0, 5), "interval with nothing"),
+        (Extended, "{2}", (0, 3), "interval with nothing"),
+        (Extended, "[abc", (0, 4), "unclosed bracket"),
+        (Extended, "[[:alpha]", (1, 9), "unclosed character class"),
+        (Extended, "[[=a=]]", (1, 6), "equivalence classes"),
+        (Extended, "[[.a.]]", (1, 6), "equivalence classes"),
+    ];
+
+    #[test]
+    fn untranslatable_patterns_are_errors() {
+        for &(syntax, pattern, (start, end), message) in ERRORS {
+            let error = translate(3, pattern, syntax).unwrap_err();
+            assert_eq!(
+                (error.index, error.start, error.end),
+                (3, start, end),
+                "{syntax:?} {pattern:?}: {error:?}"
+            );
+            assert!(error.message.starts_with(message), "{pattern:?}: {error:?}");
+            assert_eq!(error.pattern, pattern);
+        }
+    }
+}
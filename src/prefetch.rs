@@ -0,0 +1,97 @@
+use std::{
+    fs::File,
+    io::{self, BufRead},
+    iter,
+    path::Path,
+    sync::mpsc::Receiver,
+};
+
+use anyhow::Result;
+use mrustgrep::binary;
+use mrustgrep::parallel::ParallelSearcher;
+use mrustgrep::search::{LongLine, SearchResult, Searcher};
+use mrustgrep::walk::WalkItem;
+
+// 工作线程搜索完一个文件的结果，整个文件的结果一起交给主线程，不同文件的输出不会交错
+pub enum Searched {
+    // 全部结果，读取出错时最后一条是那个错误
+    Results(Vec<Result<SearchResult>>),
+    // 只要行数时（-c、-l）选中的行数（IsMatch 时是 0 或 1），以及其间遇到的超长行
+    Count(Result<usize>, Vec<LongLine>),
+    // 二进制文件，没有搜索（见 prefetch 的 skip_binary）
+    Binary,
+}
+
+// 取回一个文件的搜索结果，打不开文件时是打开的错误
+pub type Pending = Receiver<io::Result<Searched>>;
+
+// 工作线程对每个文件做什么
+#[derive(Debug, Clone, Copy)]
+pub enum Work {
+    // 交出全部结果，mmap 时用 Searcher::search_mmap
+    Search { mmap: bool },
+    // 只数选中的行（Searcher::count_with），不构造结果
+    Count,
+    // 只看有没有选中的行（Searcher::is_match_with），读到第一个就停
+    IsMatch,
+}
+
+// -j/--threads：用 ParallelSearcher 的工作池提前搜索后面的文件，主线程仍按遍历的顺序逐个取出
+// 输出与顺序搜索完全相同；eligible 决定哪些文件交给工作线程，其余的输入由主线程照常打开和搜索
+// skip_binary 时开头有 NUL 字节的文件不搜索，交出 Searched::Binary
+pub fn prefetch<I, F>(
+    pool: &ParallelSearcher,
+    inputs: I,
+    mut eligible: F,
+    work: Work,
+    skip_binary: bool,
+) -> impl Iterator<Item = (Result<WalkItem>, Option<Pending>)> + use<I, F>
+where
+    I: Iterator<Item = Result<WalkItem>>,
+    F: FnMut(&Path) -> bool,
+{
+    pool.search_ordered(
+        inputs,
+        move |input| match input {
+            Ok(WalkItem::File(path)) if eligible(path) => Some(path.clone()),
+            _ => None,
+        },
+        move |searcher, path| search(searcher, path, work, skip_binary),
+    )
+}
+
+// 在工作线程上打开并搜索一个文件
+fn search(searcher: &Searcher, path: &Path, work: Work, skip_binary: bool) -> io::Result<Searched> {
+    let mut reader = searcher.buffered(File::open(path)?);
+    // 开头读不出来时留给下面的搜索报告错误
+    if skip_binary && reader.fill_buf().is_ok_and(binary::looks_binary) {
+        return Ok(Searched::Binary);
+    }
+    let mut long_lines = Vec::new();
+    let searched: Box<dyn Iterator<Item = Result<SearchResult>>> = match work {
+        Work::Count => {
+            let counted = searcher.count_with(reader, |long| long_lines.push(long));
+            return Ok(Searched::Count(counted, long_lines));
+        }
+        Work::IsMatch => {
+            let matched = searcher.is_match_with(reader, |long| long_lines.push(long));
+            return Ok(Searched::Count(matched.map(usize::from), long_lines));
+        }
+        _ if searcher.options().has_context() => searcher.search_context_lines(reader),
+        // 映射失败时与主线程上一样当作读取错误
+        Work::Search { mmap: true } => searcher
+            .search_mmap(path)
+            .unwrap_or_else(|e| Box::new(iter::once(Err(e.into())))),
+        Work::Search { mmap: false } => Box::new(searcher.search(reader)),
+    };
+    let mut results = Vec::new();
+    for result in searched {
+        // 超长的行只是警告，其他错误之后不再读
+        let failed = result.as_ref().is_err_and(|e| !e.is::<LongLine>());
+        results.push(result);
+        if failed {
+            break;
+        }
+    }
+    Ok(Searched::Results(results))
+}
@@ -0,0 +1,144 @@
+// --pre：搜索一个文件之前先用外部命令转换它的内容（例如把 PDF 转成文本），搜索的是命令的标准输出
+//
+// 命令以文件路径作为唯一的参数运行，标准输入为空；给出 --pre-glob 时只转换路径匹配其中某个通配符的文件，
+// 其余文件照常直接搜索。通配符与 --include 是同一套实现（ignore::Overrides，.gitignore 的写法，区分大小写）：
+// 不含 / 的通配符只与文件名比较，含 / 的与整个路径比较（路径中的 . 和 .. 不参与比较），
+// !GLOB 把前面的通配符选中的文件排除在外，后面的优先。命令行上给出的文件同样按通配符判断。
+// 命令以非零状态退出时，在读完它的输出之后作为读取错误报告，已经读到的结果照常输出。
+use std::{
+    ffi::OsString,
+    io::{self, Read},
+    path::Path,
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+
+use crate::ignore::Overrides;
+
+// 预处理命令和它适用的文件
+pub struct Preprocessor {
+    command: OsString,
+    // 为空时适用于所有文件
+    globs: Overrides,
+}
+
+impl Preprocessor {
+    pub fn new<S: AsRef<str>>(command: impl Into<OsString>, globs: &[S]) -> Result<Preprocessor> {
+        let mut overrides = Overrides::default();
+        for glob in globs {
+            overrides
+                .add(glob.as_ref(), false)
+                .context("invalid --pre-glob")?;
+        }
+        Ok(Preprocessor {
+            command: command.into(),
+            globs: overrides,
+        })
+    }
+
+    // 这个文件是否要经过预处理
+    pub fn applies(&self, path: &Path) -> bool {
+        self.globs.is_empty() || self.globs.matched(path, false) == Some(false)
+    }
+
+    // 启动预处理命令，返回读取它的输出的 reader
+    pub fn open(&self, path: &Path) -> io::Result<PreprocessedReader> {
+        let mut child = Command::new(&self.command)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(PreprocessedReader {
+            child,
+            stdout,
+            finished: false,
+        })
+    }
+}
+
+// 预处理命令的输出，读到结尾时等待命令退出并检查退出状态
+pub struct PreprocessedReader {
+    child: Child,
+    stdout: ChildStdout,
+    finished: bool,
+}
+
+impl Read for PreprocessedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stdout.read(buf)?;
+        if n == 0 && !buf.is_empty() && !self.finished {
+            self.finished = true;
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "preprocessor exited with {}",
+                    status
+                )));
+            }
+        }
+        Ok(n)
+    }
+}
+
+// 没有读完就不再需要输出时（例如 --stop-on-nonmatch）结束命令，避免留下僵尸进程
+impl Drop for PreprocessedReader {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applies(globs: &[&str], path: &str) -> bool {
+        Preprocessor::new("cat", globs)
+            .unwrap()
+            .applies(Path::new(path))
+    }
+
+    #[test]
+    fn without_globs_every_file_is_preprocessed() {
+        assert!(applies(&[], "a.txt"));
+        assert!(applies(&[], "deep/b.pdf"));
+    }
+
+    #[test]
+    fn globs_without_a_slash_match_the_file_name() {
+        let globs = ["*.pdf", "*.docx"];
+        assert!(applies(&globs, "a.pdf"));
+        assert!(applies(&globs, "./docs/deep/b.docx"));
+        assert!(!applies(&globs, "a.txt"));
+        assert!(!applies(&globs, "pdf/a.txt"));
+        // 与 --include 一样区分大小写
+        assert!(!applies(&globs, "A.PDF"));
+    }
+
+    #[test]
+    fn globs_with_a_slash_match_the_whole_path() {
+        let globs = ["docs/*.pdf"];
+        assert!(applies(&globs, "docs/a.pdf"));
+        assert!(applies(&globs, "./docs/a.pdf"));
+        assert!(!applies(&globs, "src/docs/a.pdf"));
+        assert!(!applies(&globs, "docs/sub/a.pdf"));
+        assert!(applies(&["docs/**/*.pdf"], "docs/sub/a.pdf"));
+    }
+
+    #[test]
+    fn negated_globs_exclude_and_later_globs_win() {
+        let globs = ["*.pdf", "!secret*.pdf"];
+        assert!(applies(&globs, "a.pdf"));
+        assert!(!applies(&globs, "secret-plan.pdf"));
+        assert!(applies(&["!secret*.pdf", "*.pdf"], "secret-plan.pdf"));
+    }
+
+    #[test]
+    fn globs_that_match_nothing_are_errors() {
+        assert!(Preprocessor::new("cat", &["!"]).is_err());
+    }
+}
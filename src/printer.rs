@@ -0,0 +1,772 @@
+use std::io::{self, Write};
+#[cfg(feature = "serde")]
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::color::Colors;
+use crate::search::{OutputFormat, PrintStyle, SearchResult};
+
+// 结果的输出格式（对应 --format 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    // grep 风格的文本输出
+    #[default]
+    Standard,
+    // 带表头的 CSV，每个匹配一行
+    Csv,
+    // SARIF 2.1.0 文档（代码扫描平台使用），所有结果在最后一次输出
+    Sarif,
+    // JSON Lines，每个匹配一个对象
+    Json,
+}
+
+// 列号的单位（对应 --column-unit 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColumnUnit {
+    // 字节，与 ripgrep 一致
+    #[default]
+    Bytes,
+    // Unicode 标量值：每个 char 算一列，组合字符单独算一列
+    Chars,
+}
+
+impl ColumnUnit {
+    // 从 line 的字节位置 start 算出列号（从 1 开始）
+    // 按字符计数需要扫描 start 之前的内容，所以只在真正输出的时候才计算
+    pub fn column(self, line: &str, start: usize) -> usize {
+        match self {
+            ColumnUnit::Bytes => start + 1,
+            ColumnUnit::Chars => line[..start].chars().count() + 1,
+        }
+    }
+}
+
+// 输出器：决定搜索结果以什么形式写出
+// 新的输出格式实现这个 trait 即可，搜索循环不需要知道具体格式
+pub trait Printer {
+    // 在所有结果之前调用一次（例如输出表头）
+    fn begin(&mut self, _writer: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+
+    // 开始搜索一个新的输入
+    fn begin_input(&mut self, _label: &str) {}
+
+    // 一个输入的结果已经全部交给 print（缓存了结果的输出器在这里写出）
+    fn end_input(&mut self, _writer: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+
+    // 输出一条搜索结果，label 是结果所在输入的名字
+    fn print(&mut self, writer: &mut dyn Write, label: &str, result: &SearchResult) -> Result<()>;
+
+    // 在所有结果之后调用一次（例如输出缓存的整个文档），summary 是整个运行的汇总
+    fn finish(&mut self, _writer: &mut dyn Write, _summary: &Summary) -> Result<()> {
+        Ok(())
+    }
+}
+
+// 一次运行结束时交给输出器的汇总，JSON 输出把它写成最后一个对象 {"summary": {...}}
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Summary {
+    // 选中的总行数
+    pub matched_lines: usize,
+    // 每个模式的命中统计（--count-per-pattern），按模式的顺序；没有要求时为空
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub patterns: Vec<PatternSummary>,
+    // 没能搜索（或者没有搜索完）的输入，没有时为空
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub errors: Vec<InputErrorSummary>,
+}
+
+// 一个出错的输入
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InputErrorSummary {
+    // 输入的显示路径；遍历目录时出现的错误没有单独的路径（包含在 message 中）
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub path: Option<String>,
+    // 完整的错误消息，包括出错时在做什么
+    pub message: String,
+    // 最内层的操作系统错误，例如 "Permission denied (os error 13)"，不是 I/O 错误时为 None
+    pub os_error: Option<String>,
+}
+
+// 单个模式的命中统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PatternSummary {
+    pub pattern: String,
+    // 出现该模式的行数，一行同时匹配多个模式时每个模式都算一行
+    pub lines: usize,
+    // 该模式贡献的（输出中的）匹配次数
+    pub matches: usize,
+}
+
+// 行号的宽度（对应 --line-number-width 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumberWidth {
+    // 右对齐到固定宽度，行号更长时照常输出
+    Fixed(usize),
+    // 对齐到每个输入中输出的最大行号，需要缓存该输入的全部结果
+    Auto,
+}
+
+impl LineNumberWidth {
+    // 解析 "auto" 或者一个宽度
+    pub fn parse(value: &str) -> Result<LineNumberWidth, String> {
+        match value {
+            "auto" => Ok(LineNumberWidth::Auto),
+            _ => value
+                .parse()
+                .map(LineNumberWidth::Fixed)
+                .map_err(|_| format!("expected a width or 'auto', found {:?}", value)),
+        }
+    }
+}
+
+// 默认的文本输出：可选的文件名前缀，加上 OutputFormat 决定的其余前缀和内容
+// heading 时文件名不放在每行前面，而是单独一行放在这个输入的结果之前，不同输入的结果之间空一行
+pub struct StandardPrinter {
+    format: OutputFormat,
+    style: PrintStyle,
+    // 是否在每行前面输出文件名
+    with_filename: bool,
+    // 文件名作为标题单独输出（with_filename 时才生效）
+    heading: bool,
+    // 是否已经输出过一个输入的标题
+    printed_heading: bool,
+    // 是否输出上下文行，此时不相邻的组之间输出分隔行
+    context: bool,
+    // 当前输入中上一个输出的行号
+    last_line: Option<usize>,
+    // 是否已经输出过一组
+    printed_group: bool,
+    // 行号宽度为 auto 时缓存的当前输入的结果
+    buffered: Option<(String, Vec<SearchResult>)>,
+}
+
+// 上下文输出中不相邻的两组之间的分隔行
+const GROUP_SEPARATOR: &str = "--";
+
+impl StandardPrinter {
+    pub fn new(
+        format: OutputFormat,
+        style: PrintStyle,
+        with_filename: bool,
+        heading: bool,
+        context: bool,
+        line_number_width: Option<LineNumberWidth>,
+    ) -> StandardPrinter {
+        let (line_number_width, buffered) = match line_number_width {
+            Some(LineNumberWidth::Fixed(width)) => (width, None),
+            Some(LineNumberWidth::Auto) => (0, Some((String::new(), Vec::new()))),
+            None => (0, None),
+        };
+        StandardPrinter {
+            format,
+            style: PrintStyle {
+                line_number_width,
+                ..style
+            },
+            with_filename,
+            heading,
+            printed_heading: false,
+            context,
+            last_line: None,
+            printed_group: false,
+            buffered,
+        }
+    }
+
+    fn write_result(
+        &mut self,
+        writer: &mut dyn Write,
+        label: &str,
+        result: &SearchResult,
+    ) -> Result<()> {
+        // 行号不连续就是新的一组，不同输入的组之间同样要分隔
+        let first_of_group = match self.last_line {
+            Some(last) => result.line_number != last + 1,
+            None => true,
+        };
+        let first_of_input = self.last_line.is_none();
+        self.last_line = Some(result.line_number);
+        let heading = self.with_filename && self.heading;
+        // 标题、空行和分隔行与结果记录使用同样的终止符
+        let terminator = self.style.record_terminator.to_string();
+        if heading && first_of_input {
+            if self.printed_heading {
+                write!(writer, "{}", terminator)?;
+            }
+            write_label(writer, label, &terminator, self.style.colors.as_ref())?;
+            self.printed_heading = true;
+            // 标题已经把不同输入分开，不再需要分隔行
+            self.printed_group = false;
+        }
+        if self.context && first_of_group {
+            if self.printed_group {
+                write!(writer, "{}{}", GROUP_SEPARATOR, terminator)?;
+            }
+            self.printed_group = true;
+        }
+
+        let label = (self.with_filename && !heading).then_some(label);
+        result.format_to(writer, label, &self.format, &self.style)
+    }
+}
+
+impl Printer for StandardPrinter {
+    fn begin_input(&mut self, _label: &str) {
+        self.last_line = None;
+    }
+
+    fn print(&mut self, writer: &mut dyn Write, label: &str, result: &SearchResult) -> Result<()> {
+        match &mut self.buffered {
+            Some((buffered_label, results)) => {
+                if results.is_empty() {
+                    *buffered_label = label.to_string();
+                }
+                results.push(result.clone());
+                Ok(())
+            }
+            None => self.write_result(writer, label, result),
+        }
+    }
+
+    fn end_input(&mut self, writer: &mut dyn Write) -> Result<()> {
+        let Some((label, results)) = self.buffered.take() else {
+            return Ok(());
+        };
+        // 结果按行号递增，最后一个行号最大
+        self.style.line_number_width = results
+            .last()
+            .map_or(0, |result| result.line_number.to_string().len());
+        for result in &results {
+            self.write_result(writer, &label, result)?;
+        }
+        self.buffered = Some((String::new(), Vec::new()));
+        Ok(())
+    }
+}
+
+// CSV 输出：表头之后每个匹配一行，列依次是 path, line, column, match_text, line_text
+// column 是匹配起点的列号（从 1 开始，单位由 ColumnUnit 决定）；反向匹配选出的行没有匹配，column 和 match_text 留空
+// 上下文行不是匹配，不输出
+#[derive(Debug, Default)]
+pub struct CsvPrinter {
+    column_unit: ColumnUnit,
+}
+
+impl CsvPrinter {
+    pub fn new(column_unit: ColumnUnit) -> CsvPrinter {
+        CsvPrinter { column_unit }
+    }
+}
+
+impl Printer for CsvPrinter {
+    fn begin(&mut self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(writer, "path,line,column,match_text,line_text")?;
+        Ok(())
+    }
+
+    fn print(&mut self, writer: &mut dyn Write, label: &str, result: &SearchResult) -> Result<()> {
+        if result.context {
+            return Ok(());
+        }
+        let line = result.text();
+        let line_number = result.line_number.to_string();
+        if result.matches.is_empty() {
+            write_csv_row(writer, &[label, &line_number, "", "", line])?;
+        }
+        for m in &result.matches {
+            let column = self.column_unit.column(line, m.start).to_string();
+            write_csv_row(
+                writer,
+                &[label, &line_number, &column, &line[m.start..m.end], line],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// 输出一行 CSV，含有逗号、引号或换行的字段加引号，字段内的引号写成两个
+fn write_csv_row(writer: &mut dyn Write, fields: &[&str]) -> io::Result<()> {
+    for (idx, field) in fields.iter().enumerate() {
+        if idx > 0 {
+            write!(writer, ",")?;
+        }
+        match field.contains([',', '"', '\n', '\r']) {
+            true => write!(writer, "\"{}\"", field.replace('"', "\"\""))?,
+            false => write!(writer, "{}", field)?,
+        }
+    }
+    writeln!(writer)
+}
+
+// SARIF 输出：一个 run，每个不同的模式是一条规则（id 就是模式文本，重复给出的模式只有一条），每个匹配是一个结果
+// 位置相对于源码根目录（uriBaseId 为 %SRCROOT%，见 with_source_root），根目录之外的文件写成绝对的 file: URI；
+// 列号按 Unicode 码位计，endColumn 不包含在匹配内；结果的消息是整行内容。
+// 反向匹配选出的行没有匹配区间，结果只有行号、不属于任何规则。上下文行不输出
+#[cfg(feature = "serde")]
+pub struct SarifPrinter {
+    // 去重之后的模式，也就是各条规则
+    rules: Vec<String>,
+    // 每个模式（Match::pattern）对应的规则编号
+    rule_of: Vec<usize>,
+    // 源码根目录和当前目录（都是绝对路径）；没有设置时相对路径原样作为相对于 %SRCROOT% 的 URI
+    source_root: Option<(PathBuf, PathBuf)>,
+    results: Vec<sarif::Result>,
+}
+
+#[cfg(feature = "serde")]
+impl SarifPrinter {
+    pub fn new(patterns: Vec<String>) -> SarifPrinter {
+        let mut rules: Vec<String> = Vec::new();
+        let rule_of = patterns
+            .into_iter()
+            .map(
+                |pattern| match rules.iter().position(|rule| *rule == pattern) {
+                    Some(index) => index,
+                    None => {
+                        rules.push(pattern);
+                        rules.len() - 1
+                    }
+                },
+            )
+            .collect();
+        SarifPrinter {
+            rules,
+            rule_of,
+            source_root: None,
+            results: Vec::new(),
+        }
+    }
+
+    // 位置写成相对于 root 的 URI（通常是 git 仓库的根目录），cwd 是相对路径的起点
+    // 两者都应当是解析过符号链接的绝对路径，与 --absolute-path 给出的路径一致
+    pub fn with_source_root(mut self, root: &Path, cwd: &Path) -> SarifPrinter {
+        self.source_root = Some((root.to_path_buf(), cwd.to_path_buf()));
+        self
+    }
+
+    // 输出中的路径对应的位置
+    fn location(&self, label: &str) -> sarif::ArtifactLocation {
+        let label = label.replace('\\', "/");
+        let path = Path::new(&label);
+        let relative = |uri| sarif::ArtifactLocation {
+            uri,
+            uri_base_id: Some(sarif::SRCROOT),
+        };
+        let absolute = |path: &Path| sarif::ArtifactLocation {
+            uri: file_uri(path),
+            uri_base_id: None,
+        };
+        let Some((root, cwd)) = &self.source_root else {
+            return match path.is_absolute() {
+                true => absolute(path),
+                false => relative(sarif_uri(path.strip_prefix("./").unwrap_or(path))),
+            };
+        };
+        let full = normalize(&cwd.join(path));
+        match full.strip_prefix(root) {
+            Ok(inside) => relative(sarif_uri(inside)),
+            Err(_) => absolute(&full),
+        }
+    }
+
+    fn push_result(
+        &mut self,
+        label: &str,
+        result: &SearchResult,
+        m: Option<&crate::search::Match>,
+    ) {
+        let line = result.text();
+        let unit = ColumnUnit::Chars;
+        let rule = m.and_then(|m| self.rule_of.get(m.pattern).copied());
+        self.results.push(sarif::Result {
+            rule_id: rule.map(|rule| self.rules[rule].clone()),
+            rule_index: rule,
+            level: "warning",
+            message: sarif::Message {
+                text: line.to_string(),
+            },
+            locations: [sarif::Location {
+                physical_location: sarif::PhysicalLocation {
+                    artifact_location: self.location(label),
+                    region: sarif::Region {
+                        start_line: result.line_number,
+                        start_column: m.map(|m| unit.column(line, m.start)),
+                        end_column: m.map(|m| unit.column(line, m.end.min(line.len()))),
+                    },
+                },
+            }],
+        });
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Printer for SarifPrinter {
+    fn print(&mut self, _writer: &mut dyn Write, label: &str, result: &SearchResult) -> Result<()> {
+        if result.context {
+            return Ok(());
+        }
+        if result.matches.is_empty() {
+            self.push_result(label, result, None);
+        }
+        for m in &result.matches {
+            self.push_result(label, result, Some(m));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, writer: &mut dyn Write, _summary: &Summary) -> Result<()> {
+        let log = sarif::Log {
+            schema: "https://json.schemastore.org/sarif-2.1.0.json",
+            version: "2.1.0",
+            runs: [sarif::Run {
+                tool: sarif::Tool {
+                    driver: sarif::Driver {
+                        name: "mrustgrep",
+                        version: env!("CARGO_PKG_VERSION"),
+                        rules: self
+                            .rules
+                            .iter()
+                            .map(|pattern| sarif::Rule {
+                                id: pattern,
+                                short_description: sarif::Message {
+                                    text: pattern.clone(),
+                                },
+                            })
+                            .collect(),
+                    },
+                },
+                original_uri_base_ids: self.source_root.as_ref().map(|(root, _)| {
+                    // 基准 URI 必须以 / 结尾
+                    let mut uri = file_uri(root);
+                    if !uri.ends_with('/') {
+                        uri.push('/');
+                    }
+                    [(
+                        sarif::SRCROOT,
+                        sarif::ArtifactLocation {
+                            uri,
+                            uri_base_id: None,
+                        },
+                    )]
+                    .into_iter()
+                    .collect()
+                }),
+                column_kind: "unicodeCodePoints",
+                results: &self.results,
+            }],
+        };
+        serde_json::to_writer(&mut *writer, &log)?;
+        writeln!(writer)?;
+        self.results.clear();
+        Ok(())
+    }
+}
+
+// SARIF 2.1.0 文档中用到的对象，字段名与规范一致
+#[cfg(feature = "serde")]
+mod sarif {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    // 相对路径的基准
+    pub const SRCROOT: &str = "%SRCROOT%";
+
+    #[derive(Serialize)]
+    pub struct Log<'a> {
+        #[serde(rename = "$schema")]
+        pub schema: &'static str,
+        pub version: &'static str,
+        pub runs: [Run<'a>; 1],
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Run<'a> {
+        pub tool: Tool<'a>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub original_uri_base_ids: Option<BTreeMap<&'static str, ArtifactLocation>>,
+        pub column_kind: &'static str,
+        pub results: &'a [Result],
+    }
+
+    #[derive(Serialize)]
+    pub struct Tool<'a> {
+        pub driver: Driver<'a>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Driver<'a> {
+        pub name: &'static str,
+        pub version: &'static str,
+        pub rules: Vec<Rule<'a>>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Rule<'a> {
+        pub id: &'a str,
+        pub short_description: Message,
+    }
+
+    #[derive(Serialize)]
+    pub struct Message {
+        pub text: String,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Result {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub rule_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub rule_index: Option<usize>,
+        pub level: &'static str,
+        pub message: Message,
+        pub locations: [Location; 1],
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Location {
+        pub physical_location: PhysicalLocation,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PhysicalLocation {
+        pub artifact_location: ArtifactLocation,
+        pub region: Region,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ArtifactLocation {
+        pub uri: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub uri_base_id: Option<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Region {
+        pub start_line: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub start_column: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub end_column: Option<usize>,
+    }
+}
+
+// JSON Lines 输出：每个匹配一行 JSON 对象，由 OutputFormat 的 Body::Json 写出（见 SearchResult::format_to），
+// 字段是 path、line_number、line 和 submatches；上下文行不是匹配，不输出。最后一行是 {"summary": Summary}
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct JsonPrinter {
+    format: OutputFormat,
+    style: PrintStyle,
+}
+
+#[cfg(feature = "serde")]
+impl JsonPrinter {
+    // format 通常来自 Options::output_format()，body 应当是 Body::Json
+    pub fn new(format: OutputFormat) -> JsonPrinter {
+        JsonPrinter {
+            format,
+            style: PrintStyle::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Printer for JsonPrinter {
+    fn print(&mut self, writer: &mut dyn Write, label: &str, result: &SearchResult) -> Result<()> {
+        result.format_to(writer, Some(label), &self.format, &self.style)
+    }
+
+    fn finish(&mut self, writer: &mut dyn Write, summary: &Summary) -> Result<()> {
+        #[derive(serde::Serialize)]
+        struct Record<'a> {
+            summary: &'a Summary,
+        }
+        serde_json::to_writer(&mut *writer, &Record { summary })?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+// 把相对路径写成 URI 引用：目录分隔符统一成 /，保留字符之外的字节按 %XX 编码
+#[cfg(feature = "serde")]
+fn sarif_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut out = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+// 绝对路径的 file: URI，Windows 上的盘符路径写成 file:///C:/...
+#[cfg(feature = "serde")]
+fn file_uri(path: &Path) -> String {
+    let uri = sarif_uri(path);
+    // 盘符后面的冒号不用编码
+    let uri = uri.replacen("%3A", ":", usize::from(!uri.starts_with('/')));
+    match uri.starts_with('/') {
+        true => format!("file://{uri}"),
+        false => format!("file:///{uri}"),
+    }
+}
+
+// 按字面去掉路径中的 . 和 ..，不访问文件系统
+#[cfg(feature = "serde")]
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// 输出文件名，后面紧跟分隔符
+pub fn write_label<W: Write + ?Sized>(
+    writer: &mut W,
+    label: &str,
+    separator: &str,
+    colors: Option<&Colors>,
+) -> io::Result<()> {
+    match colors {
+        Some(colors) => write!(
+            writer,
+            "{}{}{}{}",
+            colors.path.start(),
+            label,
+            colors.path.end(),
+            separator
+        ),
+        None => write!(writer, "{}{}", label, separator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // é 是一个字符，e 加上 U+0301 是两个字符，汉字是一个字符；它们都不止一个字节
+    const LINE: &str = "é e\u{301} 中 hit";
+
+    #[test]
+    fn byte_columns_count_bytes_before_the_match() {
+        let start = LINE.find("hit").unwrap();
+        assert_eq!(start, 11);
+        assert_eq!(ColumnUnit::Bytes.column(LINE, start), 12);
+        assert_eq!(ColumnUnit::Bytes.column(LINE, 0), 1);
+    }
+
+    #[test]
+    fn char_columns_count_scalars_and_combining_marks_separately() {
+        let start = LINE.find("hit").unwrap();
+        // é、空格、e、U+0301、空格、中、空格，共 7 个字符
+        assert_eq!(ColumnUnit::Chars.column(LINE, start), 8);
+        assert_eq!(ColumnUnit::Chars.column(LINE, 0), 1);
+        // 纯 ASCII 的行两种单位相同
+        assert_eq!(ColumnUnit::Chars.column("ab hit", 3), 4);
+        assert_eq!(ColumnUnit::Bytes.column("ab hit", 3), 4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_summary_is_the_last_object() {
+        let mut out = Vec::new();
+        let summary = Summary {
+            matched_lines: 3,
+            patterns: vec![PatternSummary {
+                pattern: "a\"b".to_string(),
+                lines: 2,
+                matches: 5,
+            }],
+            errors: vec![InputErrorSummary {
+                path: Some("locked.txt".to_string()),
+                message: "Failed to open locked.txt: Permission denied (os error 13)".to_string(),
+                os_error: Some("Permission denied (os error 13)".to_string()),
+            }],
+        };
+        let format = OutputFormat {
+            line_number: false,
+            byte_offset: false,
+            column: false,
+            body: crate::search::Body::Json,
+        };
+        JsonPrinter::new(format).finish(&mut out, &summary).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"summary":{"matched_lines":3,"patterns":[{"pattern":"a\"b","lines":2,"matches":5}],"#,
+                r#""errors":[{"path":"locked.txt","message":"Failed to open locked.txt: Permission denied (os error 13)","os_error":"Permission denied (os error 13)"}]}}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn patterns_are_left_out_when_not_counted() {
+        let summary = Summary {
+            matched_lines: 1,
+            ..Summary::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"matched_lines":1}"#
+        );
+    }
+
+    #[cfg(all(feature = "serde", unix))]
+    #[test]
+    fn sarif_locations_are_relative_to_the_source_root() {
+        let printer = SarifPrinter::new(vec!["a".to_string(), "b".to_string(), "a".to_string()])
+            .with_source_root(Path::new("/repo"), Path::new("/repo/sub"));
+        assert_eq!(printer.rules, ["a", "b"]);
+        assert_eq!(printer.rule_of, [0, 1, 0]);
+        let uri = |label| {
+            let location = printer.location(label);
+            (location.uri, location.uri_base_id)
+        };
+        assert_eq!(
+            uri("./x y.txt"),
+            ("sub/x%20y.txt".to_string(), Some(sarif::SRCROOT))
+        );
+        assert_eq!(
+            uri("../top.txt"),
+            ("top.txt".to_string(), Some(sarif::SRCROOT))
+        );
+        assert_eq!(
+            uri("/repo/sub/x.txt"),
+            ("sub/x.txt".to_string(), Some(sarif::SRCROOT))
+        );
+        assert_eq!(
+            uri("../../etc/passwd"),
+            ("file:///etc/passwd".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+// 搜索超过这么久还没结束才显示进度，很快完成的搜索不会闪一下
+const DELAY: Duration = Duration::from_secs(1);
+// 两次刷新之间至少间隔这么久
+const INTERVAL: Duration = Duration::from_millis(250);
+// 拿不到终端宽度（COLUMNS）时假定的宽度
+const DEFAULT_WIDTH: usize = 80;
+
+// 进度行是否正显示在 stderr 上；警告和错误信息输出之前要先擦掉它
+static SHOWN: AtomicBool = AtomicBool::new(false);
+
+// --progress：在 stderr 上原地刷新一行，显示已搜索的文件数、匹配行数和当前路径
+// 没有开启时每次调用只检查一个布尔值
+pub struct Progress {
+    enabled: bool,
+    // 结果也输出到终端（stdout），写出结果之前要擦掉进度行
+    shares_terminal: bool,
+    started: Instant,
+    last_draw: Option<Instant>,
+    files: usize,
+}
+
+impl Progress {
+    pub fn new(enabled: bool, shares_terminal: bool) -> Progress {
+        Progress {
+            enabled,
+            shares_terminal,
+            started: Instant::now(),
+            last_draw: None,
+            files: 0,
+        }
+    }
+
+    // 开始搜索一个输入，matched 是到目前为止的匹配行数
+    // 重画之前先把 writer 中缓存的结果写出，结果永远不会与进度行混在一起
+    pub fn input(&mut self, writer: &mut dyn Write, label: &str, matched: usize) {
+        if !self.enabled {
+            return;
+        }
+        self.files += 1;
+        let now = Instant::now();
+        if now.duration_since(self.started) < DELAY
+            || self
+                .last_draw
+                .is_some_and(|last| now.duration_since(last) < INTERVAL)
+        {
+            return;
+        }
+        self.last_draw = Some(now);
+        if writer.flush().is_err() {
+            return;
+        }
+        let status = format!("{} files, {} matched lines: ", self.files, matched);
+        let width = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH);
+        // 放不下时保留路径的结尾，最后一列空着，避免终端自动换行
+        let room = width.saturating_sub(status.chars().count() + 1);
+        let chars = label.chars().count();
+        let label: String = match chars > room {
+            true => label.chars().skip(chars - room).collect(),
+            false => label.to_string(),
+        };
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r{}{}\x1b[K", status, label);
+        let _ = stderr.flush();
+        SHOWN.store(true, Ordering::Relaxed);
+    }
+
+    // 向 stdout 输出结果之前调用
+    pub fn output(&self) {
+        if self.shares_terminal {
+            erase();
+        }
+    }
+}
+
+// 擦掉正在显示的进度行，之后可以正常输出
+pub fn erase() {
+    if SHOWN.swap(false, Ordering::Relaxed) {
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K");
+        let _ = stderr.flush();
+    }
+}
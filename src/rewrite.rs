@@ -0,0 +1,121 @@
+// --write：把 --replace 的替换直接写回文件
+//
+// 选中哪些行、替换哪些匹配与输出完全一致（同样经过 Searcher::search），只是结果写回文件而不是输出。
+// 替换后的内容先写到同一目录下的临时文件，再改名覆盖原文件，中途出错时原文件不受影响；
+// 新文件沿用原文件的权限位（所有者等其他属性不保留）。
+// 含有 NUL 字节或不是合法 UTF-8 的文件不改写：搜索时无效的字节被替换成 U+FFFD，写回会破坏文件。
+// 符号链接默认拒绝改写，follow_symlinks 时改写链接指向的文件，链接本身保持不变。
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::search::Searcher;
+
+// 改写一个文件的结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rewrite {
+    // 发生替换的行数
+    pub lines: usize,
+    // 替换的匹配个数
+    pub replacements: usize,
+}
+
+// 按 searcher 的搜索结果把 path 中的匹配替换成 replacement，没有匹配时不改动文件
+pub fn rewrite_file(
+    searcher: &Searcher,
+    path: &Path,
+    replacement: &str,
+    follow_symlinks: bool,
+) -> Result<Rewrite> {
+    let link = fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read metadata of {}", path.display()))?;
+    let target = match (link.file_type().is_symlink(), follow_symlinks) {
+        (false, _) => path.to_path_buf(),
+        (true, true) => fs::canonicalize(path)
+            .with_context(|| format!("Failed to resolve symbolic link {}", path.display()))?,
+        (true, false) => bail!(
+            "{} is a symbolic link (use --write-symlinks to rewrite its target)",
+            path.display()
+        ),
+    };
+    let permissions = fs::metadata(&target)
+        .with_context(|| format!("Failed to read metadata of {}", path.display()))?
+        .permissions();
+    let bytes = fs::read(&target).with_context(|| format!("Failed to read {}", path.display()))?;
+    if memchr::memchr(0, &bytes).is_some() {
+        bail!(
+            "{} looks like a binary file (contains NUL bytes)",
+            path.display()
+        );
+    }
+    let Ok(contents) = String::from_utf8(bytes) else {
+        bail!("{} is not valid UTF-8", path.display());
+    };
+
+    // 行号 -> 替换后的行（不含行终止符）
+    let mut replaced = HashMap::new();
+    let mut rewrite = Rewrite::default();
+    for result in searcher.search(contents.as_bytes()) {
+        let result = result.with_context(|| format!("Failed to search {}", path.display()))?;
+        if result.context || result.matches.is_empty() {
+            continue;
+        }
+        let result = searcher.replace(result, replacement);
+        rewrite.lines += 1;
+        rewrite.replacements += result.matches.len();
+        replaced.insert(result.line_number, result.text().to_string());
+    }
+    if replaced.is_empty() {
+        return Ok(rewrite);
+    }
+
+    let mut output = String::with_capacity(contents.len());
+    for (idx, line) in contents.split_inclusive('\n').enumerate() {
+        match replaced.get(&(idx + 1)) {
+            Some(text) => {
+                output.push_str(text);
+                output.push_str(terminator(line));
+            }
+            None => output.push_str(line),
+        }
+    }
+    replace_atomically(&target, output.as_bytes(), permissions)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(rewrite)
+}
+
+// 行末的 \n 或 \r\n，最后一行可能没有
+fn terminator(line: &str) -> &str {
+    let text = line.strip_suffix('\n').unwrap_or(line);
+    let text = text.strip_suffix('\r').unwrap_or(text);
+    &line[text.len()..]
+}
+
+// 在 target 所在目录写临时文件，设好权限后改名覆盖 target
+fn replace_atomically(target: &Path, contents: &[u8], permissions: fs::Permissions) -> Result<()> {
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let temp: PathBuf = dir.join(format!(".{}.mrustgrep-{}.tmp", name, process::id()));
+    let written = (|| -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("Failed to create temporary file {}", temp.display()))?;
+        file.write_all(contents)?;
+        file.set_permissions(permissions)?;
+        file.sync_all()?;
+        fs::rename(&temp, target)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
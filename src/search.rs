@@ -1,112 +1,858 @@
-use anyhow::{Context, Result};
-use regex::Regex;
+use anyhow::Result;
 use std::{
-    io::{BufRead, Write},
-    marker,
+    borrow::Cow,
+    collections::VecDeque,
+    fmt,
+    io::{self, BufRead, Read, Write},
+    iter, mem,
+    time::{Duration, Instant},
 };
+#[cfg(feature = "fs")]
+use std::{
+    fs::{self, File},
+    io::{Seek, SeekFrom},
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::color::{Colors, Style};
+use crate::matcher::Matcher;
+pub use crate::matcher::{Group, Match, MatcherInfo, PatternError, WordBoundary};
+use crate::printer::{ColumnUnit, write_label};
+
+// --snippet 中表示省略了前后内容的标记
+const ELLIPSIS: &str = "…";
 
+// 输出记录的主体
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum OutputFormat {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Body {
     // 只计数，不输出具体行
-    CountOnly,
-    // 只输出匹配的文本片段（类似 grep -o）
-    MatchOnly,
-    // 输出完整行，带行号（默认）
-    LineNumbered,
-    // 输出完整行，不带行号
-    FullLine,
+    Count,
+    // 每个结果输出一条记录，内容是完整的行（默认）
+    Line,
+    // 每个匹配输出一条记录，内容是匹配的文本片段（类似 grep -o）
+    Matches,
+    // 每个匹配输出一条记录，内容是匹配及其前后各 n 个字符（--snippet），前后窗口重叠的匹配合成一条
+    Snippet(usize),
+    // 每个匹配输出一个 JSON 对象（--json），不输出前缀，上下文行不输出；需要开启 serde 功能
+    #[cfg(feature = "serde")]
+    Json,
 }
 
-// 从用户选项转换为格式化策略
-// 优先级：count_only > match_only > show_line_number > full_line
+// 输出格式：每条记录前面的前缀加上记录的主体
+// 各个前缀互相独立，也与主体无关，-o 的每条记录同样带上文件名、行号和字节位置；
+// 文件名前缀由输出器决定（format_to 的 label 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputFormat {
+    pub line_number: bool,
+    // 记录开头在输入中的字节位置：整行时是行首，-o 时是匹配的起点（与 grep -b 相同）
+    pub byte_offset: bool,
+    // 记录开头的列号（从 1 开始，单位见 PrintStyle::column_unit）：整行时是第一个匹配的起点，
+    // -o 时是匹配的起点；没有匹配的行（上下文行、反向匹配选出的行）不输出列号，与 ripgrep 相同
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub column: bool,
+    pub body: Body,
+}
+
+// 从用户选项转换为格式化策略，count_only 优先于 match_only
 impl From<&Options> for OutputFormat {
     fn from(opts: &Options) -> Self {
-        if opts.count_only {
-            OutputFormat::CountOnly
-        } else if opts.match_only {
-            OutputFormat::MatchOnly
-        } else if opts.show_line_number {
-            OutputFormat::LineNumbered
-        } else {
-            OutputFormat::FullLine
+        let body = match (opts.count_only, opts.match_only, opts.snippet) {
+            (true, _, _) => Body::Count,
+            (false, true, _) => Body::Matches,
+            (false, false, Some(chars)) => Body::Snippet(chars),
+            (false, false, None) => Body::Line,
+        };
+        #[cfg(feature = "serde")]
+        let body = match opts.json && !opts.count_only {
+            true => Body::Json,
+            false => body,
+        };
+        OutputFormat {
+            line_number: opts.show_line_number,
+            byte_offset: opts.show_byte_offset,
+            column: opts.show_column,
+            body,
         }
     }
 }
 
 // 单次搜索的结果，包含行号、行内容和所有匹配位置
+// 开启 serde 功能时字段名就是序列化格式，改名会破坏已经保存的数据（Options、Match 同样如此）
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResult {
     pub line_number: usize,
+    // 行首在输入中的字节位置（从 0 开始，--skip-bytes 跳过的部分也计算在内）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub byte_offset: u64,
+    // 行在输入中占的字节数，包括行终止符
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub byte_len: usize,
     pub line: String,
-    pub matches: Vec<(usize, usize)>,
+    pub matches: Vec<Match>,
+    // 各个匹配中捕获组的区间，只有 Options::capture_groups 时才计算，否则为空
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<Group>,
+    // 是否只是作为上下文输出的行（例如 --paragraph 中同一段落里没有被选中的行）
+    pub context: bool,
 }
 
 impl SearchResult {
+    // 行号、文件名与内容之间的分隔符，匹配行和上下文行分别使用各自的分隔符
+    pub fn separator<'a>(&self, style: &'a PrintStyle) -> &'a str {
+        match self.context {
+            true => &style.context_separator,
+            false => &style.match_separator,
+        }
+    }
+
+    // 去掉行终止符之后的行内容
+    pub fn text(&self) -> &str {
+        strip_terminator(&self.line)
+    }
+
     // 获取所有匹配的文本片段
     pub fn match_texts(&self) -> Vec<&str> {
         self.matches
             .iter()
-            .map(|&(start, end)| &self.line[start..end])
+            .map(|m| &self.line[m.start..m.end])
             .collect()
     }
 
-    // 根据输出格式格式化到writer
-    // 使用 match 表达式替代 if-else，更清晰且易扩展
-    pub fn format_to<W: Write>(&self, writer: &mut W, format: &OutputFormat) -> Result<()> {
-        match format {
-            OutputFormat::CountOnly => {}
-            OutputFormat::MatchOnly => {
-                for match_text in self.match_texts() {
-                    writeln!(writer, "{}", match_text)?;
+    // 根据输出格式格式化到writer，label 不为 None 时每条记录以它（文件名）开头
+    pub fn format_to<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        label: Option<&str>,
+        format: &OutputFormat,
+        style: &PrintStyle,
+    ) -> Result<()> {
+        match format.body {
+            Body::Count => {}
+            Body::Line => {
+                let column = self.matches.first().map(|m| m.start);
+                self.write_prefix(writer, label, format, style, self.byte_offset, column)?;
+                self.write_line(writer, style)?;
+            }
+            Body::Matches if let Some(separator) = &style.join_matches => {
+                // 一行的所有匹配合成一条记录，前缀只输出一次，字节位置是第一个匹配的起点
+                let mut shown = self
+                    .matches
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, m)| m.end > m.start)
+                    .peekable();
+                let Some((_, first)) = shown.peek() else {
+                    return Ok(());
+                };
+                let offset = self.byte_offset + first.start as u64;
+                self.write_prefix(writer, label, format, style, offset, Some(first.start))?;
+                for (n, (idx, m)) in shown.enumerate() {
+                    if n > 0 {
+                        write!(writer, "{}", separator)?;
+                    }
+                    match &style.colors {
+                        Some(colors) => self.write_match(writer, colors, idx, m.start, m.end)?,
+                        None => write!(writer, "{}", &self.line[m.start..m.end])?,
+                    }
+                }
+                write!(writer, "{}", style.record_terminator)?;
+            }
+            Body::Matches => {
+                // 零宽匹配没有可输出的内容，跳过而不是输出空行
+                for (idx, m) in self.matches.iter().enumerate() {
+                    if m.end <= m.start {
+                        continue;
+                    }
+                    let offset = self.byte_offset + m.start as u64;
+                    self.write_prefix(writer, label, format, style, offset, Some(m.start))?;
+                    match &style.colors {
+                        Some(colors) => self.write_match(writer, colors, idx, m.start, m.end)?,
+                        None => write!(writer, "{}", &self.line[m.start..m.end])?,
+                    }
+                    write!(writer, "{}", style.record_terminator)?;
+                }
+            }
+            Body::Snippet(chars) => {
+                for (from, to) in self.snippets(chars) {
+                    let offset = self.byte_offset + from as u64;
+                    self.write_prefix(writer, label, format, style, offset, Some(from))?;
+                    if from > 0 {
+                        write!(writer, "{}", ELLIPSIS)?;
+                    }
+                    match &style.colors {
+                        Some(colors) => self.write_highlighted(writer, colors, from, to)?,
+                        None => write!(writer, "{}", &self.line[from..to])?,
+                    }
+                    if to < self.text().len() {
+                        write!(writer, "{}", ELLIPSIS)?;
+                    }
+                    write!(writer, "{}", style.record_terminator)?;
                 }
             }
-            OutputFormat::LineNumbered => {
-                writeln!(writer, "{}: {}", self.line_number, self.line.trim_end())?;
+            #[cfg(feature = "serde")]
+            Body::Json => self.write_json(writer, label)?,
+        }
+        Ok(())
+    }
+
+    // 每个匹配一个 JSON 对象，submatches 中只有这个匹配；反向匹配选出的行没有匹配，输出一个 submatches 为空的对象
+    // start / end 是 line 字段（UTF-8）中的字节位置：输入中不是合法 UTF-8 的字节已经替换成 U+FFFD（3 个字节），
+    // 这样的行中位置可能与原文件中的不同，例如 caf\xe9 foo 中 foo 的 start 是 7 而不是 5
+    #[cfg(feature = "serde")]
+    fn write_json<W: Write + ?Sized>(&self, writer: &mut W, label: Option<&str>) -> Result<()> {
+        if self.context {
+            return Ok(());
+        }
+        let submatches: Vec<Vec<JsonSubmatch>> = match self.matches.is_empty() {
+            true => vec![Vec::new()],
+            false => self
+                .matches
+                .iter()
+                .map(|m| {
+                    vec![JsonSubmatch {
+                        start: m.start,
+                        end: m.end,
+                    }]
+                })
+                .collect(),
+        };
+        for submatches in submatches {
+            let record = JsonMatch {
+                path: label,
+                line_number: self.line_number,
+                line: self.text(),
+                submatches,
+            };
+            serde_json::to_writer(&mut *writer, &record)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    // --snippet 的窗口：每个匹配向前后各扩展 chars 个字符（按 char 计，不会切开 UTF-8 序列），
+    // 与上一个窗口重叠或相接的合并；零宽匹配和落在行终止符里的匹配不产生窗口
+    fn snippets(&self, chars: usize) -> Vec<(usize, usize)> {
+        let line = self.text();
+        let mut windows: Vec<(usize, usize)> = Vec::new();
+        for m in &self.matches {
+            let end = m.end.min(line.len());
+            if end <= m.start {
+                continue;
+            }
+            let from = match chars {
+                0 => m.start,
+                _ => line[..m.start]
+                    .char_indices()
+                    .rev()
+                    .nth(chars - 1)
+                    .map_or(0, |(idx, _)| idx),
+            };
+            let to = line[end..]
+                .char_indices()
+                .nth(chars)
+                .map_or(line.len(), |(idx, _)| end + idx);
+            match windows.last_mut() {
+                Some(last) if from <= last.1 => last.1 = last.1.max(to),
+                _ => windows.push((from, to)),
+            }
+        }
+        windows
+    }
+
+    // 输出一条记录的前缀：文件名、行号、列号、字节位置，依次以分隔符结尾
+    // column 是记录开头在行中的字节位置，None 时不输出列号
+    fn write_prefix<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        label: Option<&str>,
+        format: &OutputFormat,
+        style: &PrintStyle,
+        byte_offset: u64,
+        column: Option<usize>,
+    ) -> Result<()> {
+        let separator = self.separator(style);
+        let colors = style.colors.as_ref();
+        if let Some(label) = label {
+            write_label(writer, label, separator, colors)?;
+        }
+        if format.line_number {
+            let number = format!(
+                "{:>width$}",
+                self.line_number,
+                width = style.line_number_width
+            );
+            write_field(writer, &number, separator, colors)?;
+        }
+        if let (true, Some(start)) = (format.column, column) {
+            let column = style.column_unit.column(&self.line, start);
+            write_field(writer, &column.to_string(), separator, colors)?;
+        }
+        if format.byte_offset {
+            write_field(writer, &byte_offset.to_string(), separator, colors)?;
+        }
+        Ok(())
+    }
+
+    // 输出整行内容，开启颜色时把每个匹配区间包在高亮转义中
+    fn write_line<W: Write + ?Sized>(&self, writer: &mut W, style: &PrintStyle) -> Result<()> {
+        let line = self.text();
+
+        // 超长的行只输出一条提示，避免压缩过的文件刷屏
+        if style.max_columns.is_some_and(|max| line.len() > max) {
+            write!(
+                writer,
+                "[Omitted long line with {} matches]{}",
+                self.matches.len(),
+                style.record_terminator
+            )?;
+            return Ok(());
+        }
+
+        let Some(colors) = &style.colors else {
+            write!(writer, "{}{}", line, style.record_terminator)?;
+            return Ok(());
+        };
+        self.write_highlighted(writer, colors, 0, line.len())?;
+        write!(writer, "{}", style.record_terminator)?;
+        Ok(())
+    }
+
+    // 输出行中 [from, to) 这一段，把其中的匹配包在高亮转义中；只有一部分落在这一段里的匹配只高亮这一部分
+    // 每个匹配按它的模式编号取样式；每一段高亮都单独开启、结束，相邻或嵌套的匹配也不会交错转义序列
+    fn write_highlighted<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        colors: &Colors,
+        from: usize,
+        to: usize,
+    ) -> Result<()> {
+        let mut last = from;
+        for (idx, &Match { start, end, .. }) in self.matches.iter().enumerate() {
+            // 落在这一段之后（包括行终止符里）的匹配不再高亮
+            if start >= to {
+                break;
+            }
+            // 重叠的部分按先开始的匹配高亮，已经输出过的部分不再重复
+            let start = start.max(last);
+            let end = end.min(to);
+            if end <= start {
+                continue;
             }
-            OutputFormat::FullLine => {
-                writeln!(writer, "{}", self.line.trim_end())?;
+            write!(writer, "{}", &self.line[last..start])?;
+            self.write_match(writer, colors, idx, start, end)?;
+            last = end;
+        }
+        write!(writer, "{}", &self.line[last..to])?;
+        Ok(())
+    }
+
+    // 高亮输出第 matched 个匹配中 [start, end) 这一段：捕获组用分组的样式，其余部分用匹配的样式
+    // 嵌套的分组以最内层的为准；样式每变化一次都先结束上一段的转义再开启新的，转义序列不会交错
+    fn write_match<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        colors: &Colors,
+        matched: usize,
+        start: usize,
+        end: usize,
+    ) -> Result<()> {
+        let base = colors.pattern(self.matches[matched].pattern);
+        let groups: Vec<&Group> = self
+            .groups
+            .iter()
+            .filter(|g| g.matched == matched && g.end > g.start)
+            .collect();
+        // 按所有分组的边界把这一段切开，每一小段取覆盖它的最内层分组（起点最靠后、编号最大）的样式
+        let mut bounds = vec![start, end];
+        for g in &groups {
+            bounds.push(g.start.clamp(start, end));
+            bounds.push(g.end.clamp(start, end));
+        }
+        bounds.sort_unstable();
+        bounds.dedup();
+        let mut segments: Vec<(Style, usize, usize)> = Vec::new();
+        for range in bounds.windows(2) {
+            let (from, to) = (range[0], range[1]);
+            let style = groups
+                .iter()
+                .filter(|g| g.start <= from && to <= g.end)
+                .max_by_key(|g| (g.start, g.group))
+                .map_or(base, |g| colors.group(base, g.group));
+            match segments.last_mut() {
+                // 样式相同的相邻小段合并输出
+                Some(last) if last.0 == style => last.2 = to,
+                _ => segments.push((style, from, to)),
             }
         }
+        for (style, from, to) in segments {
+            write!(
+                writer,
+                "{}{}{}",
+                style.start(),
+                &self.line[from..to],
+                style.end()
+            )?;
+        }
         Ok(())
     }
 }
 
+// 借用行内容的搜索结果（search_slice_ref 和 search_with 产生），字段与 SearchResult 一一对应
+// 行是合法 UTF-8 时直接借用输入或内部的行缓冲区，只有替换 U+FFFD 的行才是副本
+#[derive(Debug, Clone)]
+pub struct SearchResultRef<'a> {
+    pub line_number: usize,
+    pub byte_offset: u64,
+    pub byte_len: usize,
+    pub line: Cow<'a, str>,
+    pub matches: Vec<Match>,
+    pub groups: Vec<Group>,
+    pub context: bool,
+}
+
+impl SearchResultRef<'_> {
+    // 复制出一个拥有行内容的 SearchResult，可以在借用结束之后继续保存
+    pub fn to_owned(&self) -> SearchResult {
+        SearchResult {
+            line_number: self.line_number,
+            byte_offset: self.byte_offset,
+            byte_len: self.byte_len,
+            line: self.line.to_string(),
+            matches: self.matches.clone(),
+            groups: self.groups.clone(),
+            context: self.context,
+        }
+    }
+}
+
+impl SearchResult {
+    // 借用行内容的形式
+    pub fn as_borrowed(&self) -> SearchResultRef<'_> {
+        SearchResultRef {
+            line_number: self.line_number,
+            byte_offset: self.byte_offset,
+            byte_len: self.byte_len,
+            line: Cow::Borrowed(&self.line),
+            matches: self.matches.clone(),
+            groups: self.groups.clone(),
+            context: self.context,
+        }
+    }
+}
+
+impl From<SearchResult> for SearchResultRef<'static> {
+    fn from(result: SearchResult) -> Self {
+        SearchResultRef {
+            line_number: result.line_number,
+            byte_offset: result.byte_offset,
+            byte_len: result.byte_len,
+            line: Cow::Owned(result.line),
+            matches: result.matches,
+            groups: result.groups,
+            context: result.context,
+        }
+    }
+}
+
+// 输出行号、字节位置这类数字前缀，开启颜色时使用行号的样式
+fn write_field<W: Write + ?Sized>(
+    writer: &mut W,
+    field: &str,
+    separator: &str,
+    colors: Option<&Colors>,
+) -> io::Result<()> {
+    match colors {
+        Some(colors) => write!(
+            writer,
+            "{}{}{}{}",
+            colors.line.start(),
+            field,
+            colors.line.end(),
+            separator
+        ),
+        None => write!(writer, "{}{}", field, separator),
+    }
+}
+
+// 一行原始字节对应的文本：去掉行终止符，不是合法 UTF-8 的字节替换成 U+FFFD
+fn line_text(line: &[u8]) -> Cow<'_, str> {
+    match String::from_utf8_lossy(line) {
+        Cow::Borrowed(line) => Cow::Borrowed(strip_terminator(line)),
+        Cow::Owned(line) => Cow::Owned(strip_terminator(&line).to_string()),
+    }
+}
+
+// 超过 max_line_length 而被跳过的行；这个错误不会中止搜索，之后的行照常交出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongLine {
+    pub line_number: usize,
+    pub byte_offset: u64,
+    // 这一行的实际字节数（包括行终止符）
+    pub len: u64,
+    pub max: usize,
+}
+
+impl fmt::Display for LongLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} is {} bytes long, over the {} byte limit; skipped",
+            self.line_number, self.len, self.max
+        )
+    }
+}
+
+impl std::error::Error for LongLine {}
+
+// 匹配一行的时间超过 Options::match_timeout 而放弃的行，与 LongLine 一样不会中止搜索
+// 目前的正则和字面量引擎都是线性时间的，不会交出这个错误；给可能失控的引擎预留，调用方可以先按它处理
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchTimeout {
+    pub line_number: usize,
+    pub byte_offset: u64,
+    pub limit: Duration,
+}
+
+impl fmt::Display for MatchTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "matching line {} took longer than {:?}; skipped",
+            self.line_number, self.limit
+        )
+    }
+}
+
+impl std::error::Error for MatchTimeout {}
+
+// 排除模式的个数
+fn excluded_len(excluded: Option<&Matcher>) -> usize {
+    excluded.map_or(0, Matcher::pattern_len)
+}
+
+// --between 的区间状态，每个输入从区间之外开始
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Region {
+    open: bool,
+}
+
+impl Region {
+    // 读到一行之后更新状态，返回这一行是否在区间内（包括打开和关闭区间的行）；没有 between 时总在区间内
+    pub(crate) fn step(&mut self, searcher: &Searcher, line: &str) -> bool {
+        let Some((start, end)) = &searcher.between else {
+            return true;
+        };
+        if !self.open {
+            if !start.is_match(line) {
+                return false;
+            }
+            self.open = true;
+        }
+        if end.is_match(line) {
+            self.open = false;
+        }
+        true
+    }
+}
+
+// 一次搜索的统计：读过的行数和字节数从输入开头算起（包括 --skip-bytes / --skip-lines 跳过的部分），
+// 选中的行不包括 --paragraph 带出的上下文行；elapsed 只在要求计时时才有
+///
+/// ```
+/// use mrustgrep::search::{Options, Searcher};
+///
+/// let searcher = Searcher::new("err", Options::default())?;
+/// let text = "ok\nerror 1\nerr, err\nok\n".repeat(1000);
+/// let mut results = searcher.search(text.as_bytes()).timed();
+/// let selected = results.by_ref().collect::<anyhow::Result<Vec<_>>>()?;
+/// let stats = results.stats();
+/// assert_eq!(selected.len(), 2000);
+/// assert_eq!(stats.lines_read, 4000);
+/// assert_eq!(stats.bytes_read, text.len() as u64);
+/// assert_eq!(stats.lines_matched, 2000);
+/// assert_eq!(stats.matches, 3000);
+/// assert!(stats.elapsed.is_some());
+/// if let Some(rate) = stats.bytes_per_second() {
+///     println!("{:.1} MB/s", rate / 1e6);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    // 最后一行没有行终止符也算一行
+    pub lines_read: u64,
+    pub bytes_read: u64,
+    pub lines_matched: u64,
+    // 选中的行中的匹配个数
+    pub matches: u64,
+    pub elapsed: Option<Duration>,
+}
+
+impl SearchStats {
+    // 加上另一次搜索的统计，elapsed 累加（两者都没有计时时仍为 None）
+    pub fn add(&mut self, other: &SearchStats) {
+        self.lines_read += other.lines_read;
+        self.bytes_read += other.bytes_read;
+        self.lines_matched += other.lines_matched;
+        self.matches += other.matches;
+        self.elapsed = match (self.elapsed, other.elapsed) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+
+    // 每秒读过的字节数，没有计时或者用时为 0 时返回 None
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        let seconds = self.elapsed?.as_secs_f64();
+        (seconds > 0.0).then(|| self.bytes_read as f64 / seconds)
+    }
+
+    // 记下一个选中的行
+    fn record(&mut self, matches: usize) {
+        self.lines_matched += 1;
+        self.matches += matches as u64;
+    }
+}
+
+// Body::Json 输出的一个对象，path 是输出器给出的 label，没有时省略
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonMatch<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    line_number: usize,
+    line: &'a str,
+    submatches: Vec<JsonSubmatch>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonSubmatch {
+    start: usize,
+    end: usize,
+}
+
+// 去掉行尾的 \n 或 \r\n，其余尾部空白原样保留
+fn strip_terminator(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+// 输出外观设置，与 OutputFormat 一起决定最终输出的样子
+#[derive(Debug, Clone)]
+pub struct PrintStyle {
+    // 高亮配色，None 表示不输出颜色
+    pub colors: Option<Colors>,
+    // 超过该字节数的行不输出内容
+    pub max_columns: Option<usize>,
+    // 行号右对齐到的最小宽度，0 表示不补齐
+    pub line_number_width: usize,
+    // 匹配行的文件名、行号与内容之间的分隔符
+    pub match_separator: String,
+    // 上下文行的文件名、行号与内容之间的分隔符
+    pub context_separator: String,
+    // 每条记录（匹配行、上下文行、-o 的每个匹配）末尾的终止符，--null-data-out 时是 '\0'
+    pub record_terminator: char,
+    // -o 时把一行的所有匹配用这个分隔符连成一条记录（--join-matches），None 表示每个匹配一条记录
+    pub join_matches: Option<String>,
+    // 列号（OutputFormat::column）的单位
+    pub column_unit: ColumnUnit,
+}
+
+// 分隔符默认与 grep 的输出完全一致：匹配行 ':'，上下文行 '-'，后面都不带空格
+impl Default for PrintStyle {
+    fn default() -> Self {
+        PrintStyle {
+            colors: None,
+            max_columns: None,
+            line_number_width: 0,
+            match_separator: ":".to_string(),
+            context_separator: "-".to_string(),
+            record_terminator: '\n',
+            join_matches: None,
+            column_unit: ColumnUnit::Bytes,
+        }
+    }
+}
+
 // 用户配置选项（从命令行参数来）
 // 保留这个结构体用于配置管理，然后转换为 OutputFormat 使用
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// 缺少的字段取默认值，以前保存的选项在增加新选项之后仍然能读入
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Options {
     // 是否显示行号
     pub show_line_number: bool,
+    // 是否在每条输出记录前显示字节位置
+    pub show_byte_offset: bool,
+    // 是否在每条输出记录前显示第一个匹配的列号（--column）
+    pub show_column: bool,
     // 是否仅显示匹配数量（不输出具体行）
     pub count_only: bool,
     // 是否大小写不敏感
     pub case_ignore: bool,
     // 是否只输出匹配的部分
     pub match_only: bool,
+    // 每个匹配只输出匹配及其前后各这么多个字符（--snippet），与 match_only 一样每个匹配一条记录
+    pub snippet: Option<usize>,
+    // 是否把每个匹配输出为一个 JSON 对象（Body::Json，--json），比 match_only 和 snippet 优先；没有开启 serde 功能时不起作用
+    pub json: bool,
+    // 是否反向匹配（选出不匹配的行）
+    pub invert_match: bool,
+    // 是否把模式当作固定字符串（不解释正则元字符）
+    pub fixed_strings: bool,
+    // 是否要求所有模式都在行内出现才算匹配（默认任意一个即可）
+    pub all_match: bool,
+    // 是否把模式当作 shell 风格的通配符（*、?、[...]）
+    pub glob: bool,
+    // 把模式当作 POSIX 基本（BRE）或扩展（ERE）正则，翻译成 regex 的语法之后再编译
+    pub posix: Option<crate::posix::Syntax>,
+    // 近似匹配允许的最大编辑距离（--fuzzy），只能与固定字符串一起使用
+    pub fuzzy: Option<usize>,
+    // 是否报告重叠的匹配（每个匹配起点之后重新搜索）
+    pub overlapping: bool,
+    // 是否只接受从行首开始的匹配
+    pub anchored: bool,
+    // 只接受前后都是单词边界的匹配（-w），以及边界按哪种字符集判断
+    pub word: Option<WordBoundary>,
+    // 只接受覆盖整行的匹配（-x），比 word 优先
+    pub line_regexp: bool,
+    // 每行最多报告这么多个匹配（--max-matches-per-line），影响 -o、高亮和匹配计数，不影响这一行是否被选中
+    pub max_matches_per_line: Option<usize>,
+    // 出现过匹配行之后，遇到第一个不匹配的行就停止读取当前输入
+    pub stop_on_nonmatch: bool,
+    // 每个输入最多选出这么多行（-m/--max-count），够数之后不再读取，count 和 is_match 也最多数到这么多
+    // --paragraph 时当前段落剩下的行作为上下文交出；search_context_lines 时照常读完最后一个结果之后的上下文，
+    // 其中再被选中的行也只作为上下文；search_file_parallel 不看这个选项，由调用方通过 max_matches 限制
+    pub max_count: Option<usize>,
+    // 有行被选中时输出它所在的整个段落（以空行分隔）
+    pub paragraph: bool,
+    // 从输入开头跳过的字节数（之后再跳到下一个行首）和行数，行号仍从输入开头算起
+    pub skip_bytes: u64,
+    pub skip_lines: usize,
+    // 最后一个要搜索的行号（从输入开头算起），读过这一行就停止读取，None 表示读到结尾
+    // 与 skip_lines 一起可以只搜索一个行号区间
+    pub last_line: Option<usize>,
+    // 只在区间内选择行（--between START END）：匹配 START 的行打开区间，之后第一个匹配 END 的行（含）关闭区间
+    // 两个模式与普通模式使用相同的选项编译；区间已经打开时再遇到 START 不会嵌套，同一行同时匹配两者时区间只有这一行；
+    // 读到结尾还没有关闭的区间延续到结尾；每个输入重新开始
+    pub between: Option<(String, String)>,
+    // 读取输入时缓冲区的容量（字节），None 使用标准库的默认值；比最长的行小也没关系，读行时会按需增长
+    pub buffer_size: Option<usize>,
+    // 一行（包括行终止符）最多的字节数，更长的行不保存也不搜索，读到行尾就跳过，None 表示不限制
+    // search、search_async 和 search_file_parallel 把跳过的行作为 LongLine 错误交出，之后照常继续；
+    // search_slice、search_slice_ref 和 search_with 直接跳过
+    pub max_line_length: Option<usize>,
+    // search_with_context 和 search_context_lines 给每个结果附带的前后上下文行数，其他搜索方法不受影响
+    pub before_context: usize,
+    pub after_context: usize,
+    // 是否给每个结果计算匹配中捕获组的区间（SearchResult::groups），开启颜色时分组会单独高亮
+    pub capture_groups: bool,
+    // 编译后正则的体积上限（字节），None 使用引擎默认值
+    pub regex_size_limit: Option<usize>,
+    // 惰性 DFA 缓存的体积上限（字节），None 使用引擎默认值
+    pub dfa_size_limit: Option<usize>,
+    // 单行匹配的时间上限，超时的行交出 MatchTimeout 错误，按不匹配处理
+    // 目前的引擎都是线性时间的，不会失控，因此只记录不生效
+    pub match_timeout: Option<Duration>,
 }
 
 impl Options {
+    // 设置 match_timeout，便于在 Options::default() 之上链式构造
+    pub fn with_match_timeout(mut self, timeout: Duration) -> Options {
+        self.match_timeout = Some(timeout);
+        self
+    }
+
     // 获取对应的输出格式
     pub fn output_format(&self) -> OutputFormat {
         OutputFormat::from(self)
     }
+
+    // 是否要求了前后的上下文行
+    pub fn has_context(&self) -> bool {
+        self.before_context > 0 || self.after_context > 0
+    }
+
+    // 已经读过 line_number 行之后最多还能读的行数，None 表示不限制
+    fn lines_left(&self, line_number: usize) -> Option<usize> {
+        self.last_line.map(|last| last.saturating_sub(line_number))
+    }
 }
 
-// 搜索器，持有正则和配置选项，负责创建搜索迭代器
+// 搜索器，持有匹配器和配置选项，负责创建搜索迭代器
 pub struct Searcher {
-    regex: Regex,
+    matcher: Matcher,
+    // 排除模式，匹配其中任意一个的行不会被选中
+    excluded: Option<Matcher>,
+    // Options::between 的起始和结束模式
+    between: Option<(Matcher, Matcher)>,
     opts: Options,
 }
 
 impl Searcher {
     pub fn new(pattern: &str, opts: Options) -> Result<Searcher> {
-        let pattern = match opts.case_ignore {
-            true => format!("(?i){}", pattern),
-            false => pattern.to_string(),
+        Searcher::with_patterns(&[pattern], opts)
+    }
+
+    // 用多个模式创建搜索器，任意一个模式匹配即算匹配
+    pub fn with_patterns(patterns: &[&str], opts: Options) -> Result<Searcher> {
+        Searcher::with_excluded(patterns, &[], opts)
+    }
+
+    // 在普通模式之外再给出一组排除模式，两者使用相同的选项编译
+    // 排除模式出错时，PatternError 的编号接在普通模式之后，between 的起始和结束模式再排在排除模式之后
+    pub fn with_excluded(patterns: &[&str], excluded: &[&str], opts: Options) -> Result<Searcher> {
+        let matcher = Matcher::new(patterns, &opts)?;
+        let compile = |pattern: &[&str], index: usize| {
+            Matcher::new(pattern, &opts).map_err(|err| match err.downcast::<PatternError>() {
+                Ok(mut err) => {
+                    err.index += index;
+                    err.into()
+                }
+                Err(err) => err,
+            })
         };
+        let excluded = match excluded.is_empty() {
+            true => None,
+            false => Some(compile(excluded, patterns.len())?),
+        };
+        let first = patterns.len() + excluded_len(excluded.as_ref());
+        let between = match &opts.between {
+            Some((start, end)) => Some((compile(&[start], first)?, compile(&[end], first + 1)?)),
+            None => None,
+        };
+        Ok(Searcher {
+            matcher,
+            excluded,
+            between,
+            opts,
+        })
+    }
 
-        let regex = Regex::new(&pattern).context("Failed to compile regex pattern")?;
+    // 构造时给出的选项
+    pub fn options(&self) -> &Options {
+        &self.opts
+    }
+
+    // 构造时记录的引擎相关信息
+    pub fn matcher_info(&self) -> &MatcherInfo {
+        self.matcher.info()
+    }
 
-        Ok(Searcher { regex, opts })
+    // 按 buffer_size 给 reader 加上缓冲
+    pub fn buffered<R: Read>(&self, reader: R) -> io::BufReader<R> {
+        match self.opts.buffer_size {
+            Some(capacity) => io::BufReader::with_capacity(capacity, reader),
+            None => io::BufReader::new(reader),
+        }
     }
 
     // 创建一个搜索迭代器，从给定的reader中逐行搜索
@@ -114,66 +860,2088 @@ impl Searcher {
         SearchIter::new(self, reader)
     }
 
-    // 搜索单行（内部使用）
-    fn search_line(&self, line_number: usize, line: String) -> Option<SearchResult> {
-        let matches: Vec<(usize, usize)> = self
-            .regex
-            .find_iter(&line)
-            .map(|m| (m.start(), m.end()))
-            .collect();
+    // 逐行交出输入中的每一行，连同行内的匹配区间和是否被选中，用来展示带标注的完整文件
+    // 与 search 共用读行和匹配的逻辑（--skip-bytes / --skip-lines 跳过的行不交出），
+    // 但不受 --paragraph 和 --stop-on-nonmatch 影响；超过 max_line_length 的行交出 LongLine 错误
+    pub fn scan<'a, R: BufRead + 'a>(&'a self, mut reader: R) -> Scan<'a, R> {
+        match skip_prefix(&mut reader, &self.opts) {
+            Ok(skipped) => Scan::from_skipped(self, reader, skipped),
+            Err(e) => Scan {
+                searcher: self,
+                lines: None,
+                line_number: 0,
+                offset: 0,
+                error: Some(e.into()),
+                region: Region::default(),
+            },
+        }
+    }
 
-        if matches.is_empty() {
-            return None;
+    // 搜索已经在内存中的内容，行号从 1 开始，最后一行没有行终止符也会搜索
+    // 与 search 使用同一套逐行 / 按块的搜索逻辑，所有选项的效果相同；超过 max_line_length 的行直接跳过
+    // 从切片读取本身不会出错，但搜索仍可能失败，错误和 search 一样从迭代器交出
+    /// ```
+    /// use mrustgrep::search::{Options, Searcher};
+    ///
+    /// let searcher = Searcher::new("b+", Options::default())?;
+    /// let lines = searcher
+    ///     .search_slice(b"abc\nxyz\nbb")
+    ///     .map(|result| result.map(|result| (result.line_number, result.match_texts().join(","))))
+    ///     .collect::<anyhow::Result<Vec<_>>>()?;
+    /// assert_eq!(lines, [(1, "b".to_string()), (3, "bb".to_string())]);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn search_slice<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = Result<SearchResult>> + 'a {
+        self.search(data)
+            .filter(|result| !result.as_ref().is_err_and(|e| e.is::<LongLine>()))
+    }
+
+    // search_slice 的 &str 版本
+    /// ```
+    /// use mrustgrep::search::{Options, Searcher};
+    ///
+    /// let searcher = Searcher::new("error", Options::default())?;
+    /// for result in searcher.search_str("ok\nerror: disk full\nok") {
+    ///     let result = result?;
+    ///     assert_eq!(result.line_number, 2);
+    ///     assert_eq!(result.text(), "error: disk full");
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn search_str<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Result<SearchResult>> + 'a {
+        self.search_slice(text.as_bytes())
+    }
+
+    // search_slice 的借用版本：结果直接借用 data 中的行，不为每个匹配行分配
+    // --paragraph 需要缓存整个段落、between 需要跟踪区间，这时结果是 search_slice 的结果转换而来的
+    pub fn search_slice_ref<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> Box<dyn Iterator<Item = Result<SearchResultRef<'a>>> + 'a> {
+        if self.opts.paragraph || self.opts.between.is_some() {
+            return Box::new(
+                self.search_slice(data)
+                    .map(|result| result.map(SearchResultRef::from)),
+            );
+        }
+        let mut rest = data;
+        let skipped = match skip_prefix(&mut rest, &self.opts) {
+            Ok(skipped) => skipped,
+            Err(e) => return Box::new(iter::once(Err(e.into()))),
+        };
+        let stop_on_nonmatch = self.opts.stop_on_nonmatch;
+        let mut seen_match = false;
+        let mut offset = skipped.bytes;
+        let max = self.opts.max_line_length;
+        let lines_left = self.opts.lines_left(skipped.lines).unwrap_or(usize::MAX);
+        Box::new(
+            rest.split_inclusive(|&b| b == b'\n')
+                .take(lines_left)
+                .enumerate()
+                .map(move |(idx, line)| {
+                    let line_offset = offset;
+                    offset += line.len() as u64;
+                    match max.is_some_and(|max| line.len() > max) {
+                        true => None,
+                        false => self.search_line_ref(skipped.lines + idx + 1, line_offset, line),
+                    }
+                })
+                .take_while(move |result| match result {
+                    Some(_) => {
+                        seen_match = true;
+                        true
+                    }
+                    None => !(stop_on_nonmatch && seen_match),
+                })
+                .flatten()
+                .take(self.opts.max_count.unwrap_or(usize::MAX))
+                .map(Ok),
+        )
+    }
+
+    // 逐行搜索 reader，把每个结果交给 sink，sink 返回 false 时停止读取
+    // 结果借用同一个行缓冲区，读下一行之前必须处理完（需要保存时用 to_owned）
+    // --paragraph 需要缓存整个段落，这时结果来自 search
+    pub fn search_with<R: BufRead>(
+        &self,
+        reader: R,
+        sink: impl FnMut(SearchResultRef<'_>) -> bool,
+    ) -> Result<()> {
+        self.search_with_stats(reader, sink, &mut SearchStats::default())
+    }
+
+    // 与 search_with 相同，同时把这次搜索的统计加到 stats 上（不计时），出错时已经读过的部分也计入
+    pub fn search_with_stats<R: BufRead>(
+        &self,
+        mut reader: R,
+        mut sink: impl FnMut(SearchResultRef<'_>) -> bool,
+        stats: &mut SearchStats,
+    ) -> Result<()> {
+        if self.opts.paragraph {
+            let mut results = self.search(reader);
+            let searched = (|| {
+                for result in results.by_ref() {
+                    if !sink(result?.as_borrowed()) {
+                        break;
+                    }
+                }
+                Ok(())
+            })();
+            stats.add(&results.stats());
+            return searched;
+        }
+        let mut filter = LineFilter::new(self);
+        let mut buf = Vec::new();
+        loop {
+            if filter.finished() {
+                return Ok(());
+            }
+            buf.clear();
+            let len = read_line(&mut reader, &mut buf, self.opts.max_line_length)?;
+            if len == 0 {
+                return Ok(());
+            }
+            stats.lines_read += 1;
+            stats.bytes_read += len;
+            if self
+                .opts
+                .max_line_length
+                .is_some_and(|max| len > max as u64)
+            {
+                filter.feed_long(len);
+                continue;
+            }
+            match filter.feed(&buf) {
+                Step::Selected(result) => {
+                    stats.record(result.matches.len());
+                    if !sink(result) {
+                        return Ok(());
+                    }
+                }
+                Step::Skipped => {}
+                Step::Stop => return Ok(()),
+            }
+        }
+    }
+
+    // 输入中是否有被选中的行，读到第一个选中的行就返回，不构造结果
+    // 选中的行与 search 相同；超过 max_line_length 的行不算（search 对它们交出 LongLine）
+    pub fn is_match<R: BufRead>(&self, reader: R) -> Result<bool> {
+        Ok(self.tally(reader, Some(1), &mut |_| {})? > 0)
+    }
+
+    // 与 is_match 相同，读到第一个选中的行之前遇到的超过 max_line_length 的行依次交给 long_line
+    pub fn is_match_with<R: BufRead>(
+        &self,
+        reader: R,
+        mut long_line: impl FnMut(LongLine),
+    ) -> Result<bool> {
+        Ok(self.tally(reader, Some(1), &mut long_line)? > 0)
+    }
+
+    // 输入中被选中的行数，等于 search 交出的非上下文结果的条数
+    // 只判断每一行是否被选中，不计算匹配区间，也不为选中的行分配结果
+    pub fn count<R: BufRead>(&self, reader: R) -> Result<usize> {
+        self.tally(reader, None, &mut |_| {})
+    }
+
+    // 与 count 相同，超过 max_line_length 的行（search 交出的 LongLine）依次交给 long_line
+    pub fn count_with<R: BufRead>(
+        &self,
+        reader: R,
+        mut long_line: impl FnMut(LongLine),
+    ) -> Result<usize> {
+        self.tally(reader, None, &mut long_line)
+    }
+
+    // is_match 和 count 共用：数出选中的行，数到 limit 和 max_count 中较小的一个就停止
+    // 与 search 一样按选项选用按块或者逐行搜索，--paragraph 直接数 search 的结果
+    fn tally<R: BufRead>(
+        &self,
+        mut reader: R,
+        limit: Option<usize>,
+        long_line: &mut dyn FnMut(LongLine),
+    ) -> Result<usize> {
+        let limit = match (limit, self.opts.max_count) {
+            (Some(limit), Some(max)) => Some(limit.min(max)),
+            (limit, max) => limit.or(max),
+        };
+        let mut count = 0;
+        if self.opts.paragraph {
+            for result in self.search(reader) {
+                match result.map_err(|e| e.downcast::<LongLine>()) {
+                    Ok(result) if !result.context => count += 1,
+                    Ok(_) => {}
+                    Err(Ok(long)) => long_line(long),
+                    Err(Err(e)) => return Err(e),
+                }
+                if limit.is_some_and(|limit| count >= limit) {
+                    break;
+                }
+            }
+            return Ok(count);
+        }
+        if self.supports_buffer() {
+            let skipped = skip_prefix(&mut reader, &self.opts)?;
+            return Ok(Blocks::counting(self, reader, skipped).tally(limit, long_line)?);
+        }
+        let mut filter = LineFilter::new(self);
+        let mut buf = Vec::new();
+        while !(filter.finished() || limit.is_some_and(|limit| count >= limit)) {
+            buf.clear();
+            let len = read_line(&mut reader, &mut buf, self.opts.max_line_length)?;
+            if len == 0 {
+                break;
+            }
+            if self
+                .opts
+                .max_line_length
+                .is_some_and(|max| len > max as u64)
+            {
+                if let Some(long) = filter.feed_long(len) {
+                    long_line(long);
+                }
+                continue;
+            }
+            let text = line_text(&buf);
+            match filter.feed_with(&buf, |searcher, _, _| {
+                searcher.is_selected(&text).then_some(())
+            }) {
+                Step::Selected(()) => count += 1,
+                Step::Skipped => {}
+                Step::Stop => break,
+            }
         }
+        Ok(count)
+    }
 
+    // 搜索单行（内部使用），byte_offset 和 byte_len 是这一行在输入中的位置和长度
+    fn search_line(
+        &self,
+        line_number: usize,
+        byte_offset: u64,
+        byte_len: usize,
+        line: String,
+    ) -> Option<SearchResult> {
+        let matches = self.select(&line)?;
         Some(SearchResult {
             line_number,
+            byte_offset,
+            byte_len,
+            groups: self.groups(&line, &matches),
             line,
             matches,
+            context: false,
         })
     }
 
-    // 获取输出格式
-    pub fn output_format(&self) -> OutputFormat {
-        self.opts.output_format()
+    // 搜索单行，结果借用行内容（内部使用），line 是包含行终止符的原始字节
+    fn search_line_ref<'a>(
+        &self,
+        line_number: usize,
+        byte_offset: u64,
+        line: &'a [u8],
+    ) -> Option<SearchResultRef<'a>> {
+        let text = line_text(line);
+        let matches = self.select(&text)?;
+        Some(SearchResultRef {
+            line_number,
+            byte_offset,
+            byte_len: line.len(),
+            groups: self.groups(&text, &matches),
+            line: text,
+            matches,
+            context: false,
+        })
     }
-}
 
-// 搜索迭代器，实现Iterator trait
-// 每次迭代返回一个匹配的行
-// 使用迭代器链实现，而不是手动loop，更符合Rust习惯
-pub struct SearchIter<'a, R> {
-    inner: Box<dyn Iterator<Item = Result<SearchResult>> + 'a>,
-    _phantom: marker::PhantomData<R>,
-}
+    // 各个匹配中捕获组的区间，没有打开 capture_groups 时不计算
+    fn groups(&self, line: &str, matches: &[Match]) -> Vec<Group> {
+        if !self.opts.capture_groups {
+            return Vec::new();
+        }
+        matches
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, m)| self.matcher.groups(line, m, idx))
+            .collect()
+    }
 
-impl<'a, R: BufRead + 'a> SearchIter<'a, R> {
-    fn new(searcher: &'a Searcher, reader: R) -> Self {
-        // 使用迭代器链：lines() -> enumerate() -> filter_map()
-        let inner = Box::new(
-            reader
-                .lines()
-                .enumerate()
-                .filter_map(move |(idx, line_result)| {
-                    let line_number = idx + 1;
-                    match line_result {
-                        Ok(line) => searcher.search_line(line_number, line).map(Ok),
-                        Err(e) => Some(Err(e.into())),
-                    }
-                }),
-        );
+    // 在整个输入的原始字节中找出所有不重叠的匹配（--binary-offsets），区间是在 data 中的字节位置
+    // 不按行切分，排除模式、-v 等以行为单位的选项不适用；不支持 --fuzzy 和 --anchored
+    pub fn find_bytes<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> Result<Box<dyn Iterator<Item = Match> + 'a>> {
+        self.matcher
+            .find_bytes(data)
+            .ok_or_else(|| anyhow::anyhow!("--fuzzy and --anchored cannot search binary data"))
+    }
 
-        SearchIter {
-            inner,
-            _phantom: marker::PhantomData,
+    // 把结果中的每个匹配替换成 replacement 的展开结果（写法见 Matcher::interpolate）
+    // 新的匹配区间指向替换后的文本，-o 和高亮输出的就是替换的结果；捕获组区间不再适用，清空
+    // 重叠的匹配（--overlapping）只替换不与前一个重叠的那些
+    pub fn replace(&self, result: SearchResult, replacement: &str) -> SearchResult {
+        if result.matches.is_empty() {
+            return result;
+        }
+        let mut line = String::with_capacity(result.line.len());
+        let mut matches = Vec::with_capacity(result.matches.len());
+        let mut last = 0;
+        for m in &result.matches {
+            if m.start < last {
+                continue;
+            }
+            line.push_str(&result.line[last..m.start]);
+            let start = line.len();
+            self.matcher
+                .interpolate(&result.line, m, replacement, &mut line);
+            matches.push(Match {
+                start,
+                end: line.len(),
+                ..*m
+            });
+            last = m.end;
+        }
+        line.push_str(&result.line[last..]);
+        SearchResult {
+            line,
+            matches,
+            groups: Vec::new(),
+            ..result
         }
     }
-}
 
-impl<'a, R> Iterator for SearchIter<'a, R> {
-    type Item = Result<SearchResult>;
+    // 判断一行是否被选中，选中时返回要输出的匹配区间
+    pub(crate) fn select(&self, line: &str) -> Option<Vec<Match>> {
+        // 命中排除模式的行无论如何都不选中（反向匹配时也一样）
+        if self.is_excluded(line) {
+            return None;
+        }
+        let matches = self.find_matches(line);
+        // 反向匹配时选出的是没有匹配的行，这些结果的 matches 为空
+        if matches.is_empty() != self.opts.invert_match {
+            return None;
+        }
+        Some(matches)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    // 与 select(line).is_some() 相同，但只判断有没有匹配，不找出匹配区间
+    fn is_selected(&self, line: &str) -> bool {
+        if self.is_excluded(line) {
+            return false;
+        }
+        let matched = match self.opts.all_match {
+            true => self.matcher.matching_patterns(line).len() == self.matcher.pattern_len(),
+            false => self.matcher.is_match(line),
+        };
+        matched != self.opts.invert_match
+    }
+
+    // 行内的匹配区间和这一行是否被选中，scan 使用；与 select 的区别是不选中的行也给出匹配区间
+    fn evaluate(&self, line: &str) -> (Vec<Match>, bool) {
+        let matches = self.find_matches(line);
+        let selected = matches.is_empty() == self.opts.invert_match && !self.is_excluded(line);
+        (matches, selected)
+    }
+
+    fn is_excluded(&self, line: &str) -> bool {
+        self.excluded
+            .as_ref()
+            .is_some_and(|excluded| excluded.is_match(line))
+    }
+
+    // 按 --overlapping、--all-match 和 max_matches_per_line 找出行内要报告的匹配区间
+    fn find_matches(&self, line: &str) -> Vec<Match> {
+        let limit = self.opts.max_matches_per_line.unwrap_or(usize::MAX);
+        let mut matches: Vec<Match> = match self.opts.overlapping {
+            true => self.matcher.find_overlapping(line),
+            // 够数之后不再继续找
+            false if !self.opts.all_match => self.matcher.find_iter(line).take(limit).collect(),
+            false => self.matcher.find_iter(line).collect(),
+        };
+
+        // 所有模式都出现时，输出的区间是各模式匹配区间的并集，否则当作没有匹配
+        if self.opts.all_match && !matches.is_empty() {
+            matches = match self.matcher.matching_patterns(line).len() == self.matcher.pattern_len()
+            {
+                true => self.matcher.find_each(line),
+                false => Vec::new(),
+            };
+        }
+        matches.truncate(limit);
+        matches
+    }
+
+    // 能否把单个文件切块并行搜索
+    // 段落上下文、--stop-on-nonmatch、跳过开头、last_line 和 between 都依赖从头顺序读取，这些情况下只能顺序搜索
+    pub fn supports_parallel_file(&self) -> bool {
+        !self.opts.paragraph
+            && !self.opts.stop_on_nonmatch
+            && self.opts.skip_bytes == 0
+            && self.opts.skip_lines == 0
+            && self.opts.last_line.is_none()
+            && self.opts.between.is_none()
+    }
+
+    // 把一个普通文件按行边界切成 threads 块，在多个线程上分别搜索，再按顺序合并结果
+    // 每块先按块内的行号搜索，同时统计块内的换行符个数，合并时加上前面各块的行数得到全局行号
+    // 结果全部收集在内存中，适合匹配稀疏的大文件；其中的错误只有 LongLine，读取出错时整体返回错误
+    // max_matches 限制交出的结果条数：只保留文件中最前面的这么多条，
+    // 排在前面的各块已经找到足够的结果时，后面的块提前停止搜索
+    #[cfg(feature = "fs")]
+    pub fn search_file_parallel(
+        &self,
+        path: &Path,
+        threads: usize,
+        max_matches: Option<usize>,
+    ) -> Result<Vec<Result<SearchResult>>> {
+        let len = fs::metadata(path)?.len();
+        let threads = (threads as u64).clamp(1, len.max(1));
+        let mut bounds = vec![0];
+        for idx in 1..threads {
+            let bound = line_start_after(path, len * idx / threads)?;
+            if bound > *bounds.last().unwrap() && bound < len {
+                bounds.push(bound);
+            }
+        }
+        bounds.push(len);
+
+        // 每块目前找到的结果条数，只会增加，所以前面各块的和是它们最终结果条数的下界
+        let found: Vec<AtomicUsize> = bounds.windows(2).map(|_| AtomicUsize::new(0)).collect();
+        let chunks: Vec<Result<(Vec<Result<SearchResult>>, usize)>> = thread::scope(|scope| {
+            let handles: Vec<_> = bounds
+                .windows(2)
+                .enumerate()
+                .map(|(idx, range)| {
+                    let (start, end) = (range[0], range[1]);
+                    let limit = max_matches.map(|max| ChunkLimit {
+                        found: &found,
+                        idx,
+                        max,
+                    });
+                    scope.spawn(move || self.search_chunk(path, start, end, limit))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("search thread panicked"))
+                .collect()
+        });
+
+        let mut results = Vec::new();
+        let mut lines_before = 0;
+        let mut remaining = max_matches.unwrap_or(usize::MAX);
+        for chunk in chunks {
+            // 之后的块即使出错也不影响已经够数的结果
+            if remaining == 0 {
+                break;
+            }
+            let (chunk_results, newlines) = chunk?;
+            // 提前停止的块可能多找到几条，这里按文件中的顺序截到 max_matches 条
+            let chunk_results = chunk_results.into_iter().take_while(|result| {
+                let keep = remaining > 0;
+                if result.is_ok() {
+                    remaining = remaining.saturating_sub(1);
+                }
+                keep
+            });
+            results.extend(chunk_results.map(|result| match result {
+                Ok(mut result) => {
+                    result.line_number += lines_before;
+                    Ok(result)
+                }
+                Err(e) => match e.downcast::<LongLine>() {
+                    Ok(mut long) => {
+                        long.line_number += lines_before;
+                        Err(long.into())
+                    }
+                    Err(e) => Err(e),
+                },
+            }));
+            lines_before += newlines;
+        }
+        Ok(results)
+    }
+
+    // 搜索文件中 [start, end) 这一块，返回块内行号的结果（字节位置已经是文件中的位置）和块内的换行符个数
+    // 给出 limit 时，这一块和前面各块一共找到足够的结果后停止（之后的行不会被用到）
+    #[cfg(feature = "fs")]
+    fn search_chunk(
+        &self,
+        path: &Path,
+        start: u64,
+        end: u64,
+        limit: Option<ChunkLimit<'_>>,
+    ) -> Result<(Vec<Result<SearchResult>>, usize)> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut reader = self.buffered(CountingReader {
+            inner: file.take(end - start),
+            newlines: 0,
+            limit,
+        });
+        let mut results = Vec::new();
+        for result in SearchIter::new(self, &mut reader) {
+            match result {
+                Ok(mut result) => {
+                    result.byte_offset += start;
+                    results.push(Ok(result));
+                    if let Some(limit) = limit
+                        && limit.record()
+                    {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let mut long = e.downcast::<LongLine>()?;
+                    long.byte_offset += start;
+                    results.push(Err(long.into()));
+                }
+            }
+        }
+        Ok((results, reader.get_ref().newlines))
+    }
+
+    // 能否按块搜索：反向匹配和 --stop-on-nonmatch 需要逐行知道哪些行不匹配，只能逐行搜索
+    // 给出 last_line 时要在那一行之后停下，给出 between 时要逐行跟踪区间，同样逐行搜索
+    fn supports_buffer(&self) -> bool {
+        !self.opts.invert_match
+            && !self.opts.stop_on_nonmatch
+            && self.opts.last_line.is_none()
+            && self.opts.between.is_none()
+            && self.matcher.supports_buffer()
+    }
+
+    // search_mmap 能否直接在映射上按块搜索，否则退回逐行读取
+    #[cfg(feature = "fs")]
+    pub fn supports_mmap(&self) -> bool {
+        !self.opts.paragraph && self.supports_buffer()
+    }
+
+    // 用内存映射搜索一个文件：正则直接在映射的字节上寻找候选行，只有候选行才切出来确认，
+    // 其余的行只数换行符推进行号，既不逐行读取也不复制；结果与 search 相同
+    // 不能按块搜索的选项（见 supports_mmap）以及管道、设备这类不能映射的文件退回 search
+    // 映射期间文件被截断或改写时结果不确定（截断时进程可能收到 SIGBUS），适合只追加或不再变化的大文件
+    #[cfg(feature = "fs")]
+    pub fn search_mmap<'a>(
+        &'a self,
+        path: &Path,
+    ) -> io::Result<Box<dyn Iterator<Item = Result<SearchResult>> + 'a>> {
+        let file = File::open(path)?;
+        if !self.supports_mmap() || !file.metadata()?.is_file() {
+            return Ok(Box::new(self.search(self.buffered(file))));
+        }
+        // 映射只读；文件在别处被改写的风险见上面的说明
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let skipped = skip_prefix(&mut &map[..], &self.opts)?;
+        Ok(Box::new(Mapped {
+            pos: skipped.bytes as usize,
+            blocks: Blocks::new(self, io::empty(), skipped),
+            map,
+            left: self.opts.max_count.unwrap_or(usize::MAX),
+        }))
+    }
+
+    // 行内出现过的所有模式编号，用于按模式统计
+    pub fn matching_patterns(&self, line: &str) -> Vec<usize> {
+        self.matcher.matching_patterns(line)
+    }
+
+    // 模式的个数
+    pub fn pattern_len(&self) -> usize {
+        self.matcher.pattern_len()
+    }
+
+    // 获取输出格式
+    pub fn output_format(&self) -> OutputFormat {
+        self.opts.output_format()
+    }
+}
+
+// search_file_parallel 中一块的结果条数限制
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy)]
+struct ChunkLimit<'a> {
+    // 各块目前找到的结果条数
+    found: &'a [AtomicUsize],
+    // 这一块的编号
+    idx: usize,
+    max: usize,
+}
+
+#[cfg(feature = "fs")]
+impl ChunkLimit<'_> {
+    // 记下这一块新找到的一条结果，返回这一块及之前各块是否已经够 max 条
+    fn record(&self) -> bool {
+        self.found[self.idx].fetch_add(1, Ordering::Relaxed);
+        self.sum(self.idx + 1) >= self.max
+    }
+
+    // 之前各块是否已经够 max 条，这时这一块的结果都不会被用到
+    fn earlier_reached(&self) -> bool {
+        self.sum(self.idx) >= self.max
+    }
+
+    fn sum(&self, chunks: usize) -> usize {
+        self.found[..chunks]
+            .iter()
+            .map(|found| found.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+// scan 交出的一行
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineRecord {
+    pub line_number: usize,
+    pub byte_offset: u64,
+    pub byte_len: usize,
+    // 去掉行终止符之后的内容
+    pub line: String,
+    // 行内的匹配区间（按 --overlapping 和 --all-match 计算），没有匹配的行为空
+    pub matches: Vec<Match>,
+    // 各个匹配中捕获组的区间，只有 Options::capture_groups 时才计算
+    pub groups: Vec<Group>,
+    // search 是否会选中这一行（考虑了 -v 和排除模式）
+    pub selected: bool,
+}
+
+impl From<LineRecord> for SearchResult {
+    fn from(record: LineRecord) -> Self {
+        SearchResult {
+            line_number: record.line_number,
+            byte_offset: record.byte_offset,
+            byte_len: record.byte_len,
+            line: record.line,
+            matches: record.matches,
+            groups: record.groups,
+            context: false,
+        }
+    }
+}
+
+// scan 返回的迭代器
+pub struct Scan<'a, R> {
+    searcher: &'a Searcher,
+    // 读完或者出错之后为 None
+    lines: Option<LossyLines<R>>,
+    line_number: usize,
+    offset: u64,
+    // 跳过开头时出现的错误，第一次迭代时交出
+    error: Option<anyhow::Error>,
+    region: Region,
+}
+
+impl<'a, R: BufRead> Scan<'a, R> {
+    // 开头已经按 skipped 跳过的 reader
+    pub(crate) fn from_skipped(searcher: &'a Searcher, reader: R, skipped: Skipped) -> Self {
+        Scan {
+            searcher,
+            lines: Some(
+                LossyLines::new(reader, searcher.opts.max_line_length)
+                    .limit(searcher.opts.lines_left(skipped.lines)),
+            ),
+            line_number: skipped.lines,
+            offset: skipped.bytes,
+            error: None,
+            region: Region::default(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Scan<'_, R> {
+    type Item = Result<LineRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let (line, byte_len) = match self.lines.as_mut()?.next()? {
+            Ok(line) => line,
+            Err(e) => {
+                self.lines = None;
+                return Some(Err(e.into()));
+            }
+        };
+        self.line_number += 1;
+        let byte_offset = self.offset;
+        self.offset += byte_len;
+        let Some(line) = line else {
+            let long = LongLine {
+                line_number: self.line_number,
+                byte_offset,
+                len: byte_len,
+                max: self.searcher.opts.max_line_length.unwrap_or(usize::MAX),
+            };
+            return Some(Err(long.into()));
+        };
+        let (matches, selected) = match self.region.step(self.searcher, &line) {
+            true => self.searcher.evaluate(&line),
+            false => (Vec::new(), false),
+        };
+        Some(Ok(LineRecord {
+            line_number: self.line_number,
+            byte_offset,
+            byte_len: byte_len as usize,
+            groups: self.searcher.groups(&line, &matches),
+            line,
+            matches,
+            selected,
+        }))
+    }
+}
+
+// 搜索迭代器，实现Iterator trait
+// 每次迭代返回一个匹配的行，同时累计这次搜索的统计（stats）
+pub struct SearchIter<'a, R> {
+    inner: Inner<'a, R>,
+    // 已经交出的选中行和匹配个数
+    stats: SearchStats,
+    // 要求计时时开始计时的时刻
+    started: Option<Instant>,
+    // 最多交出的选中行数（Options::max_count）
+    max_count: Option<usize>,
+}
+
+// 按选项选用的搜索方式
+enum Inner<'a, R> {
+    // 跳过开头时出错，第一次迭代时交出错误
+    Failed(Option<io::Error>),
+    Lines(Lines<'a, R>),
+    Blocks(Blocks<'a, R>),
+    Paragraphs(Paragraphs<'a, R>),
+}
+
+impl<'a, R: BufRead + 'a> SearchIter<'a, R> {
+    fn new(searcher: &'a Searcher, mut reader: R) -> Self {
+        // 跳过的部分只读取不搜索，跳过的行数用来保持行号从文件开头算起
+        let inner = match skip_prefix(&mut reader, &searcher.opts) {
+            Err(e) => Inner::Failed(Some(e)),
+            Ok(skipped) => match (searcher.opts.paragraph, searcher.supports_buffer()) {
+                (true, _) => Inner::Paragraphs(Paragraphs::new(searcher, reader, skipped)),
+                (false, true) => Inner::Blocks(Blocks::new(searcher, reader, skipped)),
+                (false, false) => Inner::Lines(Lines::new(searcher, reader, skipped)),
+            },
+        };
+        SearchIter {
+            inner,
+            stats: SearchStats::default(),
+            started: None,
+            max_count: searcher.opts.max_count,
+        }
+    }
+
+    // 从现在开始计时，迭代结束时停止，stats 中的 elapsed 就是这段时间
+    pub fn timed(mut self) -> Self {
+        self.started = Some(Instant::now());
+        self
+    }
+
+    // 到目前为止的统计；迭代结束之后就是整个输入的统计
+    // --paragraph 会先读入整个段落，迭代结束之前读过的行可能比已经交出的结果多
+    pub fn stats(&self) -> SearchStats {
+        let (lines, bytes) = match &self.inner {
+            Inner::Failed(_) => (0, 0),
+            Inner::Lines(lines) => (lines.scan.line_number, lines.scan.offset),
+            Inner::Blocks(blocks) => (blocks.line_number, blocks.offset),
+            Inner::Paragraphs(paragraphs) => (paragraphs.line_number, paragraphs.offset),
+        };
+        SearchStats {
+            lines_read: lines as u64,
+            bytes_read: bytes,
+            elapsed: self
+                .stats
+                .elapsed
+                .or_else(|| self.started.map(|started| started.elapsed())),
+            ..self.stats
+        }
+    }
+}
+
+impl<'a, R: BufRead> Iterator for SearchIter<'a, R> {
+    type Item = Result<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 够数之后不再读取，--paragraph 时当前段落剩下的行都作为上下文交出
+        let full = self
+            .max_count
+            .is_some_and(|max| self.stats.lines_matched >= max as u64);
+        let next = match &mut self.inner {
+            Inner::Paragraphs(paragraphs) if full => paragraphs.pending.pop_front().map(|result| {
+                result.map(|result| SearchResult {
+                    matches: Vec::new(),
+                    groups: Vec::new(),
+                    context: true,
+                    ..result
+                })
+            }),
+            _ if full => None,
+            Inner::Failed(e) => e.take().map(|e| Err(e.into())),
+            Inner::Lines(lines) => lines.next(),
+            Inner::Blocks(blocks) => blocks.next(),
+            Inner::Paragraphs(paragraphs) => paragraphs.next(),
+        };
+        match &next {
+            Some(Ok(result)) if !result.context => self.stats.record(result.matches.len()),
+            Some(_) => {}
+            None => {
+                if self.stats.elapsed.is_none() {
+                    self.stats.elapsed = self.started.map(|started| started.elapsed());
+                }
+            }
+        }
+        next
+    }
+}
+
+// 逐行搜索：在 scan 给出的每一行中只留下选中的行
+struct Lines<'a, R> {
+    scan: Scan<'a, R>,
+    seen_match: bool,
+    // --stop-on-nonmatch 生效之后不再向 reader 要下一行
+    stopped: bool,
+}
+
+impl<'a, R: BufRead> Lines<'a, R> {
+    fn new(searcher: &'a Searcher, reader: R, skipped: Skipped) -> Self {
+        Lines {
+            scan: Scan::from_skipped(searcher, reader, skipped),
+            seen_match: false,
+            stopped: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<'_, R> {
+    type Item = Result<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 不选中的行也要经过这里，供 --stop-on-nonmatch 判断
+        while !self.stopped {
+            match self.scan.next()? {
+                Ok(record) if record.selected => {
+                    self.seen_match = true;
+                    return Some(Ok(record.into()));
+                }
+                Ok(_) => self.stopped = self.scan.searcher.opts.stop_on_nonmatch && self.seen_match,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+// 一行一行喂入输入时的搜索状态：行号、跳过开头（--skip-bytes / --skip-lines）、last_line 和 --stop-on-nonmatch
+// 由调用方负责读行，同步的 search_with 和异步的 search_async 共用这一部分
+// 跳过的效果与 skip_prefix 相同：起点落在前 skip_bytes 个字节之内的行都跳过，之后再跳过 skip_lines 行
+pub(crate) struct LineFilter<'a> {
+    searcher: &'a Searcher,
+    line_number: usize,
+    // 已经喂入的字节数，也就是下一行的起点
+    offset: u64,
+    // 还要跳过的整行数
+    skip_lines: usize,
+    seen_match: bool,
+    // 已经选中的行数，够 max_count 之后就不用再读了
+    selected: usize,
+    region: Region,
+}
+
+// 喂入一行之后的结果，T 是选中时的结果
+pub(crate) enum Step<T> {
+    // 选中，search_with 的结果借用喂入的行
+    Selected(T),
+    // 跳过或者没有选中
+    Skipped,
+    // --stop-on-nonmatch 生效，不用再读了
+    Stop,
+}
+
+impl<'a> LineFilter<'a> {
+    pub(crate) fn new(searcher: &'a Searcher) -> LineFilter<'a> {
+        LineFilter {
+            searcher,
+            line_number: 0,
+            offset: 0,
+            skip_lines: searcher.opts.skip_lines,
+            seen_match: false,
+            selected: 0,
+            region: Region::default(),
+        }
+    }
+
+    // 是否已经读过 last_line 或者选够了 max_count 行，不用再读了
+    pub(crate) fn finished(&self) -> bool {
+        self.searcher.opts.lines_left(self.line_number) == Some(0)
+            || self
+                .searcher
+                .opts
+                .max_count
+                .is_some_and(|max| self.selected >= max)
+    }
+
+    // 越过长度为 len 的一行，返回它是否落在要跳过的开头部分
+    fn advance(&mut self, len: u64) -> bool {
+        let start = self.offset;
+        self.offset += len;
+        self.line_number += 1;
+        if start < self.searcher.opts.skip_bytes {
+            return true;
+        }
+        if self.skip_lines > 0 {
+            self.skip_lines -= 1;
+            return true;
+        }
+        false
+    }
+
+    // 喂入一行超过 max_line_length 的行（只有长度），不在跳过的部分时返回对应的 LongLine
+    pub(crate) fn feed_long(&mut self, len: u64) -> Option<LongLine> {
+        let byte_offset = self.offset;
+        match self.advance(len) {
+            true => None,
+            false => Some(LongLine {
+                line_number: self.line_number,
+                byte_offset,
+                len,
+                max: self.searcher.opts.max_line_length.unwrap_or(usize::MAX),
+            }),
+        }
+    }
+
+    // line 是包含行终止符的一整行（最后一行可以没有）
+    pub(crate) fn feed<'l>(&mut self, line: &'l [u8]) -> Step<SearchResultRef<'l>> {
+        self.feed_with(line, |searcher, line_number, start| {
+            searcher.search_line_ref(line_number, start, line)
+        })
+    }
+
+    // 与 feed 相同，但选中的行由 select 判断并给出结果（参数是行号和行在输入中的位置）
+    pub(crate) fn feed_with<T>(
+        &mut self,
+        line: &[u8],
+        select: impl FnOnce(&Searcher, usize, u64) -> Option<T>,
+    ) -> Step<T> {
+        let start = self.offset;
+        if self.advance(line.len() as u64) {
+            return Step::Skipped;
+        }
+        if !self.region.step(self.searcher, &line_text(line)) {
+            return Step::Skipped;
+        }
+        match select(self.searcher, self.line_number, start) {
+            Some(result) => {
+                self.seen_match = true;
+                self.selected += 1;
+                Step::Selected(result)
+            }
+            None if self.searcher.opts.stop_on_nonmatch && self.seen_match => Step::Stop,
+            None => Step::Skipped,
+        }
+    }
+}
+
+// 文件中 pos 位置之后（含）第一个行首的位置：pos 本身是行首，或者 pos 之后第一个 \n 的下一个字节
+#[cfg(feature = "fs")]
+fn line_start_after(path: &Path, pos: u64) -> io::Result<u64> {
+    if pos == 0 {
+        return Ok(0);
+    }
+    let mut reader = io::BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(pos - 1))?;
+    let mut discard = Vec::new();
+    let n = reader.read_until(b'\n', &mut discard)?;
+    Ok(pos - 1 + n as u64)
+}
+
+// 统计读过的换行符个数的 reader
+// 给出 limit 时，前面各块已经找到足够的结果后提前读到结尾，这一块剩下的部分不用再搜索
+#[cfg(feature = "fs")]
+struct CountingReader<'a, R> {
+    inner: R,
+    newlines: usize,
+    limit: Option<ChunkLimit<'a>>,
+}
+
+#[cfg(feature = "fs")]
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.limit.is_some_and(|limit| limit.earlier_reached()) {
+            return Ok(0);
+        }
+        let n = self.inner.read(buf)?;
+        self.newlines += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        Ok(n)
+    }
+}
+
+// 跳过的输入开头：完整的行数和字节数，用来让行号和字节位置从输入开头算起
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Skipped {
+    pub(crate) lines: usize,
+    pub(crate) bytes: u64,
+}
+
+// 按 --skip-bytes 和 --skip-lines 跳过输入开头的部分
+// 先跳过字节：停在行的中间时继续跳到下一个 \n 之后，保证第一行是完整的；再跳过若干整行
+pub(crate) fn skip_prefix<R: BufRead>(reader: &mut R, opts: &Options) -> io::Result<Skipped> {
+    let mut skipped = Skipped::default();
+    let mut last = b'\n';
+    while skipped.bytes < opts.skip_bytes {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(skipped);
+        }
+        let remaining = opts.skip_bytes - skipped.bytes;
+        let n = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        skipped.lines += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        last = buf[n - 1];
+        reader.consume(n);
+        skipped.bytes += n as u64;
+    }
+
+    let mut discard = Vec::new();
+    let partial = usize::from(last != b'\n');
+    for _ in 0..opts.skip_lines + partial {
+        discard.clear();
+        let n = reader.read_until(b'\n', &mut discard)?;
+        if n == 0 {
+            break;
+        }
+        skipped.lines += 1;
+        skipped.bytes += n as u64;
+    }
+    Ok(skipped)
+}
+
+// 从 available 中取出当前行的一段追加到 buf，返回消耗的字节数和这一行是否已经结束
+// total 是这一行之前已经读过的字节数；加上这一段超过 max 时清空 buf，之后的部分也不再保存
+pub(crate) fn take_line_part(
+    available: &[u8],
+    buf: &mut Vec<u8>,
+    total: u64,
+    max: Option<usize>,
+) -> (usize, bool) {
+    let (n, done) = match memchr::memchr(b'\n', available) {
+        Some(i) => (i + 1, true),
+        None => (available.len(), false),
+    };
+    match max.is_some_and(|max| total + n as u64 > max as u64) {
+        true => buf.clear(),
+        false => buf.extend_from_slice(&available[..n]),
+    }
+    (n, done)
+}
+
+// 与 read_until(b'\n') 相同，但超过 max 字节的行不保存（buf 为空），只读到行尾为止
+// 返回这一行的字节数（包括行终止符），输入已经读完时返回 0
+fn read_line<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>, max: Option<usize>) -> io::Result<u64> {
+    let mut total = 0;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(total);
+        }
+        let (n, done) = take_line_part(available, buf, total, max);
+        reader.consume(n);
+        total += n as u64;
+        if done {
+            return Ok(total);
+        }
+    }
+}
+
+// 逐行读取，去掉行终止符，不是合法 UTF-8 的字节替换成 U+FFFD，而不是中止搜索
+// 同时给出这一行原本的字节数（包括行终止符）；超过 max_line_length 的行没有内容（None）
+pub(crate) struct LossyLines<R> {
+    reader: R,
+    max_line_length: Option<usize>,
+    // 还能读的行数（Options::last_line），None 表示读到结尾
+    remaining: Option<usize>,
+}
+
+impl<R> LossyLines<R> {
+    pub(crate) fn new(reader: R, max_line_length: Option<usize>) -> Self {
+        LossyLines {
+            reader,
+            max_line_length,
+            remaining: None,
+        }
+    }
+
+    // 最多再读 remaining 行
+    pub(crate) fn limit(mut self, remaining: Option<usize>) -> Self {
+        self.remaining = remaining;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for LossyLines<R> {
+    type Item = io::Result<(Option<String>, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.checked_sub(1)?;
+        }
+        let mut buf = Vec::new();
+        match read_line(&mut self.reader, &mut buf, self.max_line_length) {
+            Ok(0) => None,
+            Ok(n) if self.max_line_length.is_some_and(|max| n > max as u64) => Some(Ok((None, n))),
+            Ok(n) => {
+                let line = String::from_utf8_lossy(&buf);
+                Some(Ok((Some(strip_terminator(&line).to_string()), n)))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+// 按块搜索时每次至少读入的字节数，块总是截断在最后一个 \n 之后，比这一行更长的行会让块变大
+const BLOCK_SIZE: usize = 64 * 1024;
+
+// 读入下一块的结果
+enum Fill {
+    // 块的长度，0 表示输入已经读完
+    Block(usize),
+    // 块开头的一行超过了 max_line_length，已经整行读过丢弃，这是它的字节数
+    LongLine(u64),
+}
+
+// 按块搜索的迭代器：读入一大块完整的行，在整块上用正则寻找候选位置，
+// 只有候选所在的行才切出来逐行确认，其余的行只用 memchr 数换行符来推进行号
+// 匹配稀疏时绝大多数行既不分配也不单独调用正则
+// 不是合法 UTF-8 或者含有孤立 \r 的块退回逐行搜索（与 LossyLines 一样替换成 U+FFFD）
+struct Blocks<'a, R> {
+    searcher: &'a Searcher,
+    reader: R,
+    buf: Vec<u8>,
+    // 下一块开头那一行的行号减一
+    line_number: usize,
+    // 下一块开头在输入中的字节位置
+    offset: u64,
+    // 当前块中已经确定要交给调用方的行和 LongLine
+    pending: VecDeque<Result<SearchResult>>,
+    // 读取出错时先搜索已经读到的完整行，错误留到下一次 fill 交出
+    error: Option<io::Error>,
+    done: bool,
+    // 只数选中的行（Searcher::count），不构造结果，pending 中只有 LongLine
+    count_only: bool,
+    selected: usize,
+}
+
+impl<'a, R: BufRead> Blocks<'a, R> {
+    fn new(searcher: &'a Searcher, reader: R, skipped: Skipped) -> Self {
+        Blocks {
+            searcher,
+            reader,
+            buf: Vec::new(),
+            line_number: skipped.lines,
+            offset: skipped.bytes,
+            pending: VecDeque::new(),
+            error: None,
+            done: false,
+            count_only: false,
+            selected: 0,
+        }
+    }
+
+    // 只数选中的行的版本，用 tally 读取
+    fn counting(searcher: &'a Searcher, reader: R, skipped: Skipped) -> Self {
+        Blocks {
+            count_only: true,
+            ..Blocks::new(searcher, reader, skipped)
+        }
+    }
+
+    // 读完整个输入，返回选中的行数；给出 limit 时数到够数的那一块为止，超出的部分不算
+    fn tally(
+        mut self,
+        limit: Option<usize>,
+        long_line: &mut dyn FnMut(LongLine),
+    ) -> io::Result<usize> {
+        let limit = limit.unwrap_or(usize::MAX);
+        while !(self.done || self.selected >= limit) {
+            self.advance()?;
+            // count_only 时 pending 中只有 LongLine
+            for pending in self.pending.drain(..) {
+                if let Err(e) = pending
+                    && let Ok(long) = e.downcast::<LongLine>()
+                {
+                    long_line(long);
+                }
+            }
+        }
+        Ok(self.selected.min(limit))
+    }
+
+    // 读入下一块
+    // 上一块之后剩下的不完整的行留在 buf 开头，其中没有 \n，只需要在新读入的部分中找 \n
+    // 不完整的行超过 max_line_length 时先交出它之前的完整行，它本身在下一次调用时读到行尾丢弃，
+    // 因此 buf 不会比 max_line_length 加上一次读入的数据大太多
+    fn fill(&mut self) -> io::Result<Fill> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let max = self.searcher.opts.max_line_length;
+        let mut scanned = self.buf.len();
+        // buf 中最后一行（还不完整）的起点
+        let mut tail = 0;
+        loop {
+            if max.is_some_and(|max| self.buf.len() - tail > max) {
+                if tail > 0 {
+                    return Ok(Fill::Block(tail));
+                }
+                let partial = self.buf.len() as u64;
+                self.buf.clear();
+                let rest = read_line(&mut self.reader, &mut self.buf, Some(0))?;
+                return Ok(Fill::LongLine(partial + rest));
+            }
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if tail > 0 => {
+                    self.error = Some(e);
+                    return Ok(Fill::Block(tail));
+                }
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                return Ok(Fill::Block(self.buf.len()));
+            }
+            let n = available.len();
+            self.buf.extend_from_slice(available);
+            self.reader.consume(n);
+            if let Some(last) = memchr::memrchr(b'\n', &self.buf[scanned..]) {
+                tail = scanned + last + 1;
+            }
+            scanned = self.buf.len();
+            if self.buf.len() >= BLOCK_SIZE && tail > 0 {
+                return Ok(Fill::Block(tail));
+            }
+        }
+    }
+
+    // 读入并搜索下一块，输入读完或者出错时设置 done
+    fn advance(&mut self) -> io::Result<()> {
+        let fill = self.fill().inspect_err(|_| self.done = true)?;
+        match fill {
+            Fill::Block(0) => self.done = true,
+            Fill::Block(len) => {
+                // 块暂时从 self 中取出，搜索时需要 &mut self
+                let buf = mem::take(&mut self.buf);
+                self.search_block(&buf[..len]);
+                self.buf = buf;
+                self.buf.drain(..len);
+                self.offset += len as u64;
+            }
+            Fill::LongLine(len) => {
+                self.line_number += 1;
+                let long = self.long_line(self.line_number, self.offset, len);
+                self.pending.push_back(Err(long.into()));
+                self.offset += len;
+            }
+        }
+        Ok(())
+    }
+
+    fn long_line(&self, line_number: usize, byte_offset: u64, len: u64) -> LongLine {
+        LongLine {
+            line_number,
+            byte_offset,
+            len,
+            max: self.searcher.opts.max_line_length.unwrap_or(usize::MAX),
+        }
+    }
+
+    // 推进行号越过 block[from..to] 中的行（都没有候选），其中超过 max_line_length 的行记为 LongLine
+    // 输入末尾没有行终止符的最后一行也算一行（它不会超过 max_line_length，见 fill）
+    fn pass_lines(&mut self, block: &[u8], from: usize, to: usize) {
+        if from < to && block[to - 1] != b'\n' {
+            self.line_number += 1;
+        }
+        // 整段都不超过 max_line_length 时其中不可能有超长的行，只需要数换行符
+        let max = match self.searcher.opts.max_line_length {
+            Some(max) if to - from > max => max,
+            _ => {
+                self.line_number += memchr::memchr_iter(b'\n', &block[from..to]).count();
+                return;
+            }
+        };
+        let mut start = from;
+        for i in memchr::memchr_iter(b'\n', &block[from..to]) {
+            self.line_number += 1;
+            let end = from + i + 1;
+            if end - start > max {
+                let long = self.long_line(
+                    self.line_number,
+                    self.offset + start as u64,
+                    (end - start) as u64,
+                );
+                self.pending.push_back(Err(long.into()));
+            }
+            start = end;
+        }
+    }
+
+    // 搜索从输入中 offset 处开始的一块完整的行（最后一行可能没有行终止符）
+    fn search_block(&mut self, block: &[u8]) {
+        // 孤立的 \r（后面不是 \n）在整块搜索中会被当作行尾，与逐行搜索的语义不同
+        let lone_cr = memchr::memchr_iter(b'\r', block).any(|i| block.get(i + 1) != Some(&b'\n'));
+        if lone_cr || std::str::from_utf8(block).is_err() {
+            return self.search_lines(block);
+        }
+        let text = std::str::from_utf8(block).expect("checked above");
+        let len = block.len();
+        let max = self.searcher.opts.max_line_length;
+
+        let mut pos = 0;
+        while pos < len {
+            let Some(found) = self.searcher.matcher.find_candidate(text, pos) else {
+                break;
+            };
+            // 落在块末尾的零宽匹配属于下一行，留给下一块
+            if found >= len {
+                break;
+            }
+            let start =
+                memchr::memrchr(b'\n', &text.as_bytes()[pos..found]).map_or(pos, |i| pos + i + 1);
+            let end =
+                memchr::memchr(b'\n', &text.as_bytes()[found..]).map_or(len, |i| found + i + 1);
+            self.pass_lines(text.as_bytes(), pos, start);
+            self.line_number += 1;
+            let offset = self.offset + start as u64;
+            if max.is_some_and(|max| end - start > max) {
+                let long = self.long_line(self.line_number, offset, (end - start) as u64);
+                self.pending.push_back(Err(long.into()));
+            } else if self.count_only {
+                let line = strip_terminator(&text[start..end]);
+                self.selected += usize::from(self.searcher.is_selected(line));
+            } else {
+                let line = strip_terminator(&text[start..end]).to_string();
+                self.pending.extend(
+                    self.searcher
+                        .search_line(self.line_number, offset, end - start, line)
+                        .map(Ok),
+                );
+            }
+            pos = end;
+        }
+        self.pass_lines(text.as_bytes(), pos, len);
+    }
+
+    // 逐行搜索一块完整的行
+    fn search_lines(&mut self, block: &[u8]) {
+        let max = self.searcher.opts.max_line_length;
+        let mut offset = self.offset;
+        for line in block.split_inclusive(|&b| b == b'\n') {
+            self.line_number += 1;
+            let byte_len = line.len();
+            if max.is_some_and(|max| byte_len > max) {
+                let long = self.long_line(self.line_number, offset, byte_len as u64);
+                self.pending.push_back(Err(long.into()));
+            } else if self.count_only {
+                let line = String::from_utf8_lossy(line);
+                self.selected += usize::from(self.searcher.is_selected(strip_terminator(&line)));
+            } else {
+                let line = String::from_utf8_lossy(line);
+                let line = strip_terminator(&line).to_string();
+                self.pending.extend(
+                    self.searcher
+                        .search_line(self.line_number, offset, byte_len, line)
+                        .map(Ok),
+                );
+            }
+            offset += byte_len as u64;
+        }
+    }
+}
+
+impl<'a, R: BufRead> Iterator for Blocks<'a, R> {
+    type Item = Result<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            if let Err(e) = self.advance() {
+                return Some(Err(e.into()));
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+// 内存映射时每次搜索的块至少有这么多字节，同样截断在行尾
+#[cfg(feature = "fs")]
+const MAPPED_BLOCK_SIZE: usize = 1024 * 1024;
+
+// 在内存映射的整个文件上按块搜索（Searcher::search_mmap）
+// 块直接是映射中的切片，不经过 reader 也不复制到 buf；blocks 只用来搜索块和记录行号、位置
+#[cfg(feature = "fs")]
+struct Mapped<'a> {
+    blocks: Blocks<'a, io::Empty>,
+    map: memmap2::Mmap,
+    // 下一块在映射中的起点
+    pos: usize,
+    // 还能交出的选中行数（Options::max_count）
+    left: usize,
+}
+
+#[cfg(feature = "fs")]
+impl Iterator for Mapped<'_> {
+    type Item = Result<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left == 0 {
+            return None;
+        }
+        while self.blocks.pending.is_empty() && self.pos < self.map.len() {
+            let rest = &self.map[self.pos..];
+            let len = match rest.get(MAPPED_BLOCK_SIZE..) {
+                Some(after) => {
+                    memchr::memchr(b'\n', after).map_or(rest.len(), |i| MAPPED_BLOCK_SIZE + i + 1)
+                }
+                None => rest.len(),
+            };
+            self.blocks.search_block(&rest[..len]);
+            self.blocks.offset += len as u64;
+            self.pos += len;
+        }
+        let next = self.blocks.pending.pop_front();
+        if let Some(Ok(_)) = next {
+            self.left -= 1;
+        }
+        next
+    }
+}
+
+// --paragraph 的搜索迭代器：以空行为界缓存一个段落，
+// 段落中有行被选中时输出整个段落，选中的行之外的行标记为上下文
+// 分隔段落的空行本身不输出，文件末尾没有空行时最后一段同样处理
+// 超过 max_line_length 的行不属于任何输出，只交出 LongLine
+struct Paragraphs<'a, R> {
+    searcher: &'a Searcher,
+    lines: LossyLines<R>,
+    line_number: usize,
+    // 下一行在输入中的字节位置
+    offset: u64,
+    // 已经确定要交给调用方的行和 LongLine
+    pending: VecDeque<Result<SearchResult>>,
+    done: bool,
+    region: Region,
+}
+
+impl<'a, R: BufRead> Paragraphs<'a, R> {
+    fn new(searcher: &'a Searcher, reader: R, skipped: Skipped) -> Self {
+        Paragraphs {
+            searcher,
+            lines: LossyLines::new(reader, searcher.opts.max_line_length)
+                .limit(searcher.opts.lines_left(skipped.lines)),
+            line_number: skipped.lines,
+            offset: skipped.bytes,
+            pending: VecDeque::new(),
+            done: false,
+            region: Region::default(),
+        }
+    }
+
+    // 读入下一个段落，段落中有选中的行时放进 pending
+    fn read_paragraph(&mut self) -> Result<()> {
+        let mut paragraph: Vec<Result<SearchResult>> = Vec::new();
+        let mut selected = false;
+        for line in self.lines.by_ref() {
+            let (line, byte_len) = line?;
+            self.line_number += 1;
+            let byte_offset = self.offset;
+            self.offset += byte_len;
+            let Some(line) = line else {
+                paragraph.push(Err(LongLine {
+                    line_number: self.line_number,
+                    byte_offset,
+                    len: byte_len,
+                    max: self.searcher.opts.max_line_length.unwrap_or(usize::MAX),
+                }
+                .into()));
+                continue;
+            };
+            if line.trim().is_empty() {
+                match paragraph.is_empty() {
+                    // 连续的空行
+                    true => continue,
+                    false => break,
+                }
+            }
+            let in_region = self.region.step(self.searcher, &line);
+            let (matches, context) = match in_region.then(|| self.searcher.select(&line)).flatten()
+            {
+                Some(matches) => {
+                    selected = true;
+                    (matches, false)
+                }
+                None => (Vec::new(), true),
+            };
+            paragraph.push(Ok(SearchResult {
+                line_number: self.line_number,
+                byte_offset,
+                byte_len: byte_len as usize,
+                groups: self.searcher.groups(&line, &matches),
+                line,
+                matches,
+                context,
+            }));
+        }
+        if paragraph.is_empty() {
+            self.done = true;
+        }
+        // 没有选中的段落只留下 LongLine
+        if !selected {
+            paragraph.retain(Result::is_err);
+        }
+        self.pending.extend(paragraph);
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for Paragraphs<'_, R> {
+    type Item = Result<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Some(result);
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(e) = self.read_paragraph() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Colors;
+
+    // 搜索 input 并把所有结果按 opts 对应的输出格式渲染出来
+    fn render(pattern: &str, opts: Options, input: &str, style: &PrintStyle) -> String {
+        let searcher = Searcher::new(pattern, opts).unwrap();
+        let format = searcher.output_format();
+        let mut out = Vec::new();
+        for result in searcher.search_str(input) {
+            result
+                .unwrap()
+                .format_to(&mut out, None, &format, style)
+                .unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_body_writes_one_object_per_match() {
+        let opts = Options {
+            json: true,
+            // JSON 对象没有前缀，行号放在 line_number 字段里
+            show_line_number: true,
+            match_only: true,
+            ..Options::default()
+        };
+        assert_eq!(opts.output_format().body, Body::Json);
+        let out = render("o+", opts, "foo boo\nbar\n", &PrintStyle::default());
+        assert_eq!(
+            out,
+            concat!(
+                r#"{"line_number":1,"line":"foo boo","submatches":[{"start":1,"end":3}]}"#,
+                "\n",
+                r#"{"line_number":1,"line":"foo boo","submatches":[{"start":5,"end":7}]}"#,
+                "\n",
+            )
+        );
+        // 反向匹配选出的行没有匹配区间
+        let opts = Options {
+            json: true,
+            invert_match: true,
+            ..Options::default()
+        };
+        let out = render("o", opts, "foo\nbar\n", &PrintStyle::default());
+        assert_eq!(
+            out,
+            "{\"line_number\":2,\"line\":\"bar\",\"submatches\":[]}\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_offsets_point_into_the_decoded_line() {
+        let searcher = Searcher::new("foo", Options::default()).unwrap();
+        let format = OutputFormat {
+            line_number: false,
+            byte_offset: false,
+            column: false,
+            body: Body::Json,
+        };
+        let mut out = Vec::new();
+        for result in searcher.search_slice(b"caf\xe9 foo\n") {
+            result
+                .unwrap()
+                .format_to(&mut out, Some("a.txt"), &format, &PrintStyle::default())
+                .unwrap();
+        }
+        // \xe9 变成 3 个字节的 U+FFFD，foo 从第 7 个字节开始，切出来仍然是匹配的文本
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            "{\"path\":\"a.txt\",\"line_number\":1,\"line\":\"caf\u{fffd} foo\",\"submatches\":[{\"start\":7,\"end\":10}]}\n"
+        );
+        assert_eq!(&"caf\u{fffd} foo"[7..10], "foo");
+    }
+
+    #[test]
+    fn record_terminator_ends_every_record() {
+        let style = PrintStyle {
+            record_terminator: '\0',
+            ..PrintStyle::default()
+        };
+        let opts = Options {
+            show_line_number: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            render("o", opts, "foo\nbar\nboo\n", &style),
+            concat!("1:foo\0", "3:boo\0")
+        );
+        // -o 的每个匹配都是一条记录
+        let opts = Options {
+            match_only: true,
+            ..Options::default()
+        };
+        assert_eq!(render("o+", opts, "foo boo\n", &style), "oo\0oo\0");
+    }
+
+    #[test]
+    fn stats_agree_between_iterator_and_sink_searches() {
+        // 最后一行没有行终止符
+        let input = "err\nok\nerr err\n\nerr";
+        let expected = SearchStats {
+            lines_read: 5,
+            bytes_read: input.len() as u64,
+            lines_matched: 3,
+            matches: 4,
+            elapsed: None,
+        };
+        for opts in [
+            Options::default(),
+            // -v 需要逐行判断，走 Lines 而不是按块搜索
+            Options {
+                invert_match: true,
+                ..Options::default()
+            },
+        ] {
+            let invert = opts.invert_match;
+            let searcher = Searcher::new("err", opts).unwrap();
+            let mut results = searcher.search(input.as_bytes());
+            results.by_ref().for_each(drop);
+            let from_iter = results.stats();
+            let mut from_sink = SearchStats::default();
+            searcher
+                .search_with_stats(input.as_bytes(), |_| true, &mut from_sink)
+                .unwrap();
+            assert_eq!(from_iter, from_sink, "invert {invert}");
+            if !invert {
+                assert_eq!(from_iter, expected);
+            } else {
+                assert_eq!((from_iter.lines_read, from_iter.lines_matched), (5, 2));
+            }
+        }
+    }
+
+    #[test]
+    fn stats_add_sums_counters_and_elapsed() {
+        let one = SearchStats {
+            lines_read: 2,
+            bytes_read: 10,
+            lines_matched: 1,
+            matches: 3,
+            elapsed: Some(Duration::from_millis(5)),
+        };
+        let mut total = SearchStats::default();
+        total.add(&one);
+        total.add(&SearchStats {
+            elapsed: None,
+            ..one
+        });
+        assert_eq!(
+            (
+                total.lines_read,
+                total.bytes_read,
+                total.lines_matched,
+                total.matches
+            ),
+            (4, 20, 2, 6)
+        );
+        assert_eq!(total.elapsed, Some(Duration::from_millis(5)));
+        assert_eq!(SearchStats::default().bytes_per_second(), None);
+    }
+
+    // 交出 data 之后读取就出错的 reader
+    struct FailsAfter<'a>(&'a [u8]);
+
+    impl io::Read for FailsAfter<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.is_empty() {
+                true => Err(io::Error::other("disk on fire")),
+                false => self.0.read(buf),
+            }
+        }
+    }
+
+    #[test]
+    fn is_match_and_count_agree_with_search() {
+        let input = "err 1\nok\n\nerr err\nSTART\nok err\nEND\nx\n";
+        let options = [
+            Options::default(),
+            Options {
+                invert_match: true,
+                ..Options::default()
+            },
+            Options {
+                max_line_length: Some(5),
+                ..Options::default()
+            },
+            Options {
+                skip_lines: 2,
+                ..Options::default()
+            },
+            Options {
+                last_line: Some(4),
+                ..Options::default()
+            },
+            Options {
+                stop_on_nonmatch: true,
+                ..Options::default()
+            },
+            Options {
+                max_count: Some(2),
+                ..Options::default()
+            },
+            Options {
+                between: Some(("START".to_string(), "END".to_string())),
+                ..Options::default()
+            },
+            Options {
+                paragraph: true,
+                ..Options::default()
+            },
+        ];
+        for opts in options {
+            let described = format!("{opts:?}");
+            let searcher = Searcher::new("err", opts).unwrap();
+            let selected = searcher
+                .search(input.as_bytes())
+                .filter(|result| result.as_ref().is_ok_and(|result| !result.context))
+                .count();
+            assert_eq!(
+                searcher.count(input.as_bytes()).unwrap(),
+                selected,
+                "{described}"
+            );
+            assert_eq!(
+                searcher.is_match(input.as_bytes()).unwrap(),
+                selected > 0,
+                "{described}"
+            );
+        }
+        let searcher = Searcher::new("nowhere", Options::default()).unwrap();
+        assert!(!searcher.is_match(input.as_bytes()).unwrap());
+        assert_eq!(searcher.count(input.as_bytes()).unwrap(), 0);
+    }
+
+    #[test]
+    fn is_match_stops_at_the_first_selected_line() {
+        let searcher = Searcher::new("hit", Options::default()).unwrap();
+        // 读到选中的行之后不再读取，后面的读取错误不会出现
+        let reader = io::BufReader::new(FailsAfter(b"ok\nhit\n"));
+        assert!(searcher.is_match(reader).unwrap());
+        // count 要读完整个输入，遇到错误
+        let reader = io::BufReader::new(FailsAfter(b"ok\nhit\n"));
+        assert!(searcher.count(reader).is_err());
+        // 还没有选中的行时，错误照常交出
+        let reader = io::BufReader::new(FailsAfter(b"ok\n"));
+        assert!(searcher.is_match(reader).is_err());
+    }
+
+    #[test]
+    fn slices_skip_long_lines_without_failing() {
+        let opts = Options {
+            max_line_length: Some(4),
+            ..Options::default()
+        };
+        let searcher = Searcher::new("a", opts).unwrap();
+        let data = b"a\naaaaaa\nba";
+
+        let found = searcher
+            .search_slice(data)
+            .map(|result| result.map(|result| result.line_number))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(found, [1, 3]);
+        let found = searcher
+            .search_slice_ref(data)
+            .map(|result| result.map(|result| result.line_number))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(found, [1, 3]);
+        // search 把跳过的行作为错误交出
+        let errors: Vec<bool> = searcher
+            .search(&data[..])
+            .map(|result| result.is_err_and(|e| e.is::<LongLine>()))
+            .collect();
+        assert_eq!(errors, [false, true, false]);
+    }
+
+    #[test]
+    fn paragraph_marks_unselected_lines_as_context() {
+        let opts = Options {
+            paragraph: true,
+            ..Options::default()
+        };
+        let searcher = Searcher::new("hit", opts).unwrap();
+        let found: Vec<(usize, bool)> = searcher
+            .search_str("a\nb hit\n\nc\n\nd hit\ne")
+            .map(|result| result.unwrap())
+            .map(|result| (result.line_number, result.context))
+            .collect();
+        assert_eq!(found, [(1, true), (2, false), (6, false), (7, true)]);
+    }
+
+    #[test]
+    fn match_timeout_is_accepted_and_has_no_effect() {
+        let opts = Options::default().with_match_timeout(Duration::from_millis(5));
+        assert_eq!(opts.match_timeout, Some(Duration::from_millis(5)));
+        let searcher = Searcher::new("(a|aa)+$", opts).unwrap();
+        let input = format!("{}b\n{}\n", "a".repeat(5000), "a".repeat(5000));
+        let found: Vec<usize> = searcher
+            .search(input.as_bytes())
+            .map(|result| result.unwrap().line_number)
+            .collect();
+        assert_eq!(found, [2]);
+    }
+
+    #[test]
+    fn match_timeout_message() {
+        let timeout = MatchTimeout {
+            line_number: 7,
+            byte_offset: 120,
+            limit: Duration::from_millis(50),
+        };
+        assert_eq!(
+            timeout.to_string(),
+            "matching line 7 took longer than 50ms; skipped"
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_is_printed() {
+        let style = PrintStyle::default();
+        let opts = Options::default;
+        assert_eq!(render(" $", opts(), "a \nb\n", &style), "a \n");
+        assert_eq!(render("b", opts(), "b \t\r\nc\n", &style), "b \t\n");
+        assert_eq!(render("x", opts(), "x  ", &style), "x  \n");
+    }
+
+    #[test]
+    fn highlight_inside_trailing_whitespace() {
+        let style = PrintStyle {
+            colors: Some(Colors::default()),
+            ..PrintStyle::default()
+        };
+        let start = Colors::default().pattern(0).start();
+        let end = Colors::default().pattern(0).end();
+        assert_eq!(
+            render(" +$", Options::default(), "ab  \r\n", &style),
+            format!("ab{start}  {end}\n")
+        );
+        let only = Options {
+            match_only: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            render("\t+$", only, "a\t\t\n", &style),
+            format!("{start}\t\t{end}\n")
+        );
+    }
+
+    // 命令行的 -i、-c、-o、-n、-N 在 main.rs 中设置的 Options 字段对应的输出格式
+    #[test]
+    fn flags_map_to_output_formats() {
+        let format = |opts: Options| opts.output_format();
+        let plain = OutputFormat {
+            line_number: false,
+            byte_offset: false,
+            column: false,
+            body: Body::Line,
+        };
+        assert_eq!(format(Options::default()), plain);
+        // -i 只影响匹配，不影响输出格式
+        let ignore_case = Options {
+            case_ignore: true,
+            ..Options::default()
+        };
+        assert_eq!(format(ignore_case), plain);
+        // -n
+        let numbered = Options {
+            show_line_number: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            format(numbered.clone()),
+            OutputFormat {
+                line_number: true,
+                ..plain
+            }
+        );
+        // -o -n：前缀与主体互相独立
+        let only = Options {
+            match_only: true,
+            ..numbered.clone()
+        };
+        assert_eq!(
+            format(only.clone()),
+            OutputFormat {
+                line_number: true,
+                body: Body::Matches,
+                ..plain
+            }
+        );
+        // -o -N
+        let only_unnumbered = Options {
+            show_line_number: false,
+            ..only.clone()
+        };
+        assert_eq!(
+            format(only_unnumbered),
+            OutputFormat {
+                body: Body::Matches,
+                ..plain
+            }
+        );
+        // -c 比 -o 优先，行号保留在格式中但计数时不输出记录
+        let count = Options {
+            count_only: true,
+            ..only
+        };
+        assert_eq!(format(count).body, Body::Count);
+    }
+
+    #[test]
+    fn prefixes_compose_with_each_body() {
+        let prefixed = |body: Options| Options {
+            show_line_number: true,
+            show_byte_offset: true,
+            show_column: true,
+            ..body
+        };
+        let style = PrintStyle::default();
+        let input = "ab cab\nx\n";
+        assert_eq!(
+            render("ab", prefixed(Options::default()), input, &style),
+            "1:1:0:ab cab\n"
+        );
+        let matches = Options {
+            match_only: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            render("ab", prefixed(matches), input, &style),
+            "1:1:0:ab\n1:5:4:ab\n"
+        );
+        let snippet = Options {
+            snippet: Some(1),
+            ..Options::default()
+        };
+        assert_eq!(render("x", prefixed(snippet), input, &style), "2:1:7:x\n");
+    }
+
+    // 开启 capture_groups 和默认配色搜索 input
+    fn render_groups(pattern: &str, input: &str, match_only: bool) -> String {
+        let style = PrintStyle {
+            colors: Some(Colors::default()),
+            ..PrintStyle::default()
+        };
+        let opts = Options {
+            capture_groups: true,
+            match_only,
+            ..Options::default()
+        };
+        render(pattern, opts, input, &style)
+    }
+
+    // 用第 group 个分组的默认样式（0 表示匹配本身）包起 text
+    fn paint(group: usize, text: &str) -> String {
+        let colors = Colors::default();
+        let style = match group {
+            0 => colors.pattern(0),
+            group => colors.group(colors.pattern(0), group),
+        };
+        format!("{}{}{}", style.start(), text, style.end())
+    }
+
+    #[test]
+    fn groups_get_their_own_styles_inside_the_match() {
+        assert_eq!(
+            render_groups(r"(\w+)=(\S+)", "key=value rest\n", false),
+            format!(
+                "{}{}{} rest\n",
+                paint(1, "key"),
+                paint(0, "="),
+                paint(2, "value")
+            )
+        );
+    }
+
+    #[test]
+    fn nested_groups_use_the_innermost_style() {
+        assert_eq!(
+            render_groups("x((a)b)c", "xabcd\n", false),
+            format!(
+                "{}{}{}{}d\n",
+                paint(0, "x"),
+                paint(2, "a"),
+                paint(1, "b"),
+                paint(0, "c")
+            )
+        );
+    }
+
+    #[test]
+    fn adjacent_groups_close_and_reopen() {
+        assert_eq!(
+            render_groups("(a)(b)", "ab\n", false),
+            format!("{}{}\n", paint(1, "a"), paint(2, "b"))
+        );
+    }
+
+    #[test]
+    fn unmatched_optional_groups_are_skipped() {
+        assert_eq!(
+            render_groups("(x)?(a)(y)?", "a\n", false),
+            format!("{}\n", paint(2, "a"))
+        );
+    }
+
+    #[test]
+    fn groups_are_highlighted_in_only_matching_output() {
+        assert_eq!(
+            render_groups("(a)=", "a= b a=\n", true),
+            format!("{0}{1}\n{0}{1}\n", paint(1, "a"), paint(0, "="))
+        );
     }
 }
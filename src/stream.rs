@@ -0,0 +1,92 @@
+// 异步搜索：在 tokio 的 AsyncBufRead 上逐行搜索，结果以 Stream 的形式交出（需要开启 async 功能）
+//
+// 读行由异步的 read_line 完成（与 read_until 相同，但不保存超过 max_line_length 的行），跳过开头、--stop-on-nonmatch 和匹配都交给与同步路径共用的 LineFilter。
+// Stream 持有 reader 和行缓冲区，中途丢弃时它们一起释放；Searcher 只是被借用，之后可以继续使用。
+use anyhow::{Result, anyhow};
+use futures_util::stream::{self, Stream};
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt};
+
+use crate::search::{LineFilter, SearchResult, Searcher, Step, take_line_part};
+
+// 流的内部状态，读完或出错之后为 None
+struct State<'a, R> {
+    reader: R,
+    filter: LineFilter<'a>,
+    buf: Vec<u8>,
+}
+
+impl Searcher {
+    // 异步地逐行搜索 reader，每个选中的行交出一个 SearchResult，超过 max_line_length 的行交出 LongLine 错误
+    // --paragraph 需要缓存整个段落，异步搜索不支持，此时流只交出一个错误
+    pub fn search_async<'a, R: AsyncBufRead + Unpin + 'a>(
+        &'a self,
+        reader: R,
+    ) -> impl Stream<Item = Result<SearchResult>> + 'a {
+        let state = match self.options().paragraph {
+            true => None,
+            false => Some(State {
+                reader,
+                filter: LineFilter::new(self),
+                buf: Vec::new(),
+            }),
+        };
+        let unsupported = state.is_none();
+        let max = self.options().max_line_length;
+        stream::unfold(
+            (state, unsupported),
+            move |(state, unsupported)| async move {
+                if unsupported {
+                    let err = anyhow!("--paragraph is not supported by asynchronous search");
+                    return Some((Err(err), (None, false)));
+                }
+                let mut state = state?;
+                loop {
+                    if state.filter.finished() {
+                        return None;
+                    }
+                    state.buf.clear();
+                    match read_line(&mut state.reader, &mut state.buf, max).await {
+                        Ok(0) => return None,
+                        Ok(len) if max.is_some_and(|max| len > max as u64) => {
+                            if let Some(long) = state.filter.feed_long(len) {
+                                return Some((Err(long.into()), (Some(state), false)));
+                            }
+                            continue;
+                        }
+                        Ok(_) => {}
+                        Err(e) => return Some((Err(e.into()), (None, false))),
+                    }
+                    match state.filter.feed(&state.buf) {
+                        Step::Selected(result) => {
+                            let result = result.to_owned();
+                            return Some((Ok(result), (Some(state), false)));
+                        }
+                        Step::Skipped => {}
+                        Step::Stop => return None,
+                    }
+                }
+            },
+        )
+    }
+}
+
+// 同步版本 read_line 的异步形式：超过 max 字节的行不保存（buf 为空），返回这一行的字节数，读完时返回 0
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max: Option<usize>,
+) -> io::Result<u64> {
+    let mut total = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(total);
+        }
+        let (n, done) = take_line_part(available, buf, total, max);
+        reader.consume(n);
+        total += n as u64;
+        if done {
+            return Ok(total);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::IsTerminal;
+
+use clap::ValueEnum;
+
+// 颜色输出策略（对应 --color 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    // 根据终端环境自动决定
+    #[default]
+    Auto,
+    // 总是输出颜色
+    Always,
+    // 从不输出颜色
+    Never,
+}
+
+// stdin 是交互终端、又没有给出任何文件时的处理方式（对应 --interactive-stdin 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum InteractiveStdin {
+    // 在 stderr 上提示，然后照常从终端读取
+    #[default]
+    Warn,
+    // 直接以用法错误退出
+    Error,
+    // 不做任何提示，适合确实从终端喂输入的脚本
+    Allow,
+}
+
+// 终端环境探测结果
+// 所有 "auto" 类的默认行为都从这里推导，避免各处各自探测
+// 字段是公开的，方便调用方直接构造（例如注入测试用的探测结果）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Terminal {
+    // stdout 是否连接到终端
+    pub stdout_tty: bool,
+    // stdin 是否连接到终端（没有管道输入）
+    pub stdin_tty: bool,
+    // stderr 是否连接到终端
+    pub stderr_tty: bool,
+    // 是否设置了 NO_COLOR 环境变量（任意非空值）
+    pub no_color: bool,
+    // TERM 是否为 dumb
+    pub dumb: bool,
+}
+
+impl Terminal {
+    // 探测当前进程的真实终端环境
+    pub fn detect() -> Terminal {
+        Terminal {
+            stdout_tty: std::io::stdout().is_terminal(),
+            stdin_tty: std::io::stdin().is_terminal(),
+            stderr_tty: std::io::stderr().is_terminal(),
+            no_color: std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+            dumb: std::env::var_os("TERM").is_some_and(|v| v == "dumb"),
+        }
+    }
+
+    // 根据用户选择决定是否输出颜色
+    // 显式的 always/never 总是优先；auto 时要求 stdout 是终端，且没有 NO_COLOR 和 TERM=dumb
+    pub fn color(&self, choice: ColorChoice) -> bool {
+        match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => self.stdout_tty && !self.no_color && !self.dumb,
+        }
+    }
+
+    // 准备在终端上输出 ANSI 颜色，返回终端能否显示
+    // Windows 控制台需要先开启虚拟终端处理（旧版控制台会把转义序列原样显示成乱码），开启失败时不能输出颜色；
+    // 其他平台的终端以及重定向到文件、管道时不需要任何处理
+    pub fn enable_ansi(&self) -> bool {
+        if !self.stdout_tty {
+            return true;
+        }
+        anstyle_query::windows::enable_ansi_colors().unwrap_or(true)
+    }
+
+    // stderr 上能否显示原地刷新的进度行：要求是终端，且不是 TERM=dumb（不支持清除行的转义序列）
+    pub fn progress(&self) -> bool {
+        self.stderr_tty && !self.dumb
+    }
+
+    // 默认是否按行刷新输出：交互终端上希望立即看到结果，管道中则整块写出更快
+    pub fn line_buffered(&self) -> bool {
+        self.stdout_tty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // stdout、stderr 都是终端，没有 NO_COLOR 和 TERM=dumb
+    fn tty() -> Terminal {
+        Terminal {
+            stdout_tty: true,
+            stdin_tty: true,
+            stderr_tty: true,
+            no_color: false,
+            dumb: false,
+        }
+    }
+
+    #[test]
+    fn auto_color_needs_a_terminal() {
+        assert!(tty().color(ColorChoice::Auto));
+        let piped = Terminal {
+            stdout_tty: false,
+            ..tty()
+        };
+        assert!(!piped.color(ColorChoice::Auto));
+    }
+
+    #[test]
+    fn no_color_and_dumb_disable_auto_color() {
+        let no_color = Terminal {
+            no_color: true,
+            ..tty()
+        };
+        assert!(!no_color.color(ColorChoice::Auto));
+        let dumb = Terminal {
+            dumb: true,
+            ..tty()
+        };
+        assert!(!dumb.color(ColorChoice::Auto));
+    }
+
+    #[test]
+    fn explicit_choice_wins() {
+        let everything_off = Terminal {
+            stdout_tty: false,
+            no_color: true,
+            dumb: true,
+            ..tty()
+        };
+        assert!(everything_off.color(ColorChoice::Always));
+        assert!(!tty().color(ColorChoice::Never));
+    }
+
+    #[test]
+    fn progress_needs_a_capable_stderr() {
+        assert!(tty().progress());
+        let dumb = Terminal {
+            dumb: true,
+            ..tty()
+        };
+        assert!(!dumb.progress());
+        let redirected = Terminal {
+            stderr_tty: false,
+            ..tty()
+        };
+        assert!(!redirected.progress());
+    }
+
+    #[test]
+    fn line_buffering_follows_stdout() {
+        assert!(tty().line_buffered());
+        let piped = Terminal {
+            stdout_tty: false,
+            ..tty()
+        };
+        assert!(!piped.line_buffered());
+    }
+}
@@ -0,0 +1,137 @@
+// 递归搜索时按文件类型筛选（--type / --type-not）
+//
+// 每个类型是一组文件名通配符（写法与忽略文件相同，只与文件名比较）。
+// 给出 --type 时只搜索至少属于其中一个类型的文件，--type-not 的类型再从中去掉：
+// 同时属于选中类型和排除类型的文件不搜索。类型只筛选文件，目录照常进入；
+// --include 或 --iglob 明确包含的文件不受类型限制，命令行上直接给出的文件同样不受影响。
+use std::path::Path;
+
+use anyhow::{Result, bail};
+
+use crate::ignore::IgnoreFile;
+
+// 内置的文件类型：名字和对应的通配符
+pub const TYPES: &[(&str, &[&str])] = &[
+    ("c", &["*.c", "*.h"]),
+    // 与 ripgrep 一样，*.h 同时属于 c 和 cpp
+    (
+        "cpp",
+        &["*.cpp", "*.cc", "*.cxx", "*.h", "*.hpp", "*.hh", "*.hxx"],
+    ),
+    ("css", &["*.css", "*.scss"]),
+    ("go", &["*.go"]),
+    ("html", &["*.html", "*.htm"]),
+    ("java", &["*.java"]),
+    ("js", &["*.js", "*.mjs", "*.cjs", "*.jsx"]),
+    ("json", &["*.json"]),
+    ("md", &["*.md", "*.markdown"]),
+    ("py", &["*.py", "*.pyi"]),
+    ("rust", &["*.rs"]),
+    ("sh", &["*.sh", "*.bash", "*.zsh"]),
+    ("toml", &["*.toml", "Cargo.lock"]),
+    ("ts", &["*.ts", "*.tsx"]),
+    ("txt", &["*.txt"]),
+    ("yaml", &["*.yaml", "*.yml"]),
+];
+
+// 选中和排除的文件类型
+#[derive(Debug, Clone, Default)]
+pub struct FileTypes {
+    // 选中类型的通配符，None 表示没有给出 --type
+    selected: Option<IgnoreFile>,
+    // 排除类型的通配符
+    excluded: Option<IgnoreFile>,
+}
+
+impl FileTypes {
+    // 按名字选出类型，未知的名字报错并列出所有类型
+    pub fn new(selected: &[&str], excluded: &[&str]) -> Result<FileTypes> {
+        Ok(FileTypes {
+            selected: globs(selected)?,
+            excluded: globs(excluded)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_none() && self.excluded.is_none()
+    }
+
+    // 文件是否被类型筛掉
+    pub fn skipped(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let name = Path::new(name);
+        let matched = |types: &IgnoreFile| types.matched(name, false).unwrap_or(false);
+        self.selected.as_ref().is_some_and(|types| !matched(types))
+            || self.excluded.as_ref().is_some_and(matched)
+    }
+}
+
+// 把类型名换成它们的通配符，合成一个忽略文件
+fn globs(names: &[&str]) -> Result<Option<IgnoreFile>> {
+    if names.is_empty() {
+        return Ok(None);
+    }
+    let mut lines = Vec::new();
+    for name in names {
+        let Some((_, globs)) = TYPES.iter().find(|(type_name, _)| type_name == name) else {
+            let available: Vec<&str> = TYPES.iter().map(|(name, _)| *name).collect();
+            bail!(
+                "unknown file type {:?} (available: {})",
+                name,
+                available.join(", ")
+            );
+        };
+        lines.extend_from_slice(globs);
+    }
+    Ok(Some(IgnoreFile::parse(&lines.join("\n"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skipped(selected: &[&str], excluded: &[&str], path: &str) -> bool {
+        FileTypes::new(selected, excluded)
+            .unwrap()
+            .skipped(Path::new(path))
+    }
+
+    #[test]
+    fn type_not_alone_skips_only_its_globs() {
+        assert!(skipped(&[], &["md"], "docs/README.md"));
+        assert!(skipped(&[], &["md", "toml"], "Cargo.lock"));
+        assert!(!skipped(&[], &["md"], "src/main.rs"));
+        assert!(!skipped(&[], &["md"], "Makefile"));
+    }
+
+    #[test]
+    fn selected_types_minus_excluded_types() {
+        let (selected, excluded) = (&["c", "cpp"][..], &["c"][..]);
+        assert!(!skipped(selected, excluded, "src/a.cpp"));
+        // a.h 同时属于选中的 cpp 和排除的 c，不搜索
+        assert!(skipped(selected, excluded, "include/a.h"));
+        assert!(skipped(selected, excluded, "a.c"));
+        assert!(skipped(selected, excluded, "a.rs"));
+    }
+
+    #[test]
+    fn types_match_only_the_file_name() {
+        assert!(!skipped(&["rust"], &[], "deep/dir.md/lib.rs"));
+        assert!(skipped(&["rust"], &[], "src.rs/notes.txt"));
+    }
+
+    #[test]
+    fn unknown_types_list_the_available_ones() {
+        let err = FileTypes::new(&["rust"], &["nope"])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("unknown file type \"nope\" (available: c, cpp, "),
+            "{err}"
+        );
+        assert!(err.ends_with("yaml)"), "{err}");
+        assert!(FileTypes::new(&["Rust"], &[]).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+// 按文件身份（而不是路径）记录已经搜索过的文件
+//
+// 同一个文件可能通过不同的路径到达：重复的参数、"src" 和 "src/"、符号链接、硬链接。
+// Unix 上用 (设备号, inode) 标识文件，Windows 上用 (卷序列号, 文件索引)，
+// 两者都能识别硬链接；其他平台无法取得这类标识，不做去重。
+use std::{collections::HashSet, io, path::Path};
+
+// 文件的身份标识，符号链接取其指向的文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId {
+    pub device: u64,
+    pub index: u64,
+}
+
+impl FileId {
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> io::Result<Option<FileId>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = std::fs::metadata(path)?;
+        Ok(Some(FileId {
+            device: metadata.dev(),
+            index: metadata.ino(),
+        }))
+    }
+
+    #[cfg(windows)]
+    pub fn of(path: &Path) -> io::Result<Option<FileId>> {
+        let file = std::fs::File::open(path)?;
+        let info = winapi_util::file::information(&file)?;
+        Ok(Some(FileId {
+            device: info.volume_serial_number(),
+            index: info.file_index(),
+        }))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn of(_path: &Path) -> io::Result<Option<FileId>> {
+        Ok(None)
+    }
+}
+
+// 一次运行中已经搜索过的文件
+#[derive(Debug, Default)]
+pub struct Visited {
+    seen: HashSet<FileId>,
+}
+
+impl Visited {
+    pub fn new() -> Visited {
+        Visited::default()
+    }
+
+    // 记录即将搜索的文件，返回它是否是第一次出现
+    // 取不到文件身份时（文件不存在、没有权限等）当作第一次出现，由随后的打开操作报告错误
+    pub fn insert(&mut self, path: &Path) -> bool {
+        match FileId::of(path) {
+            Ok(Some(id)) => self.seen.insert(id),
+            Ok(None) | Err(_) => true,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_file_through_other_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a");
+        std::fs::write(&file, "x").unwrap();
+        std::fs::hard_link(&file, dir.path().join("hard")).unwrap();
+        std::os::unix::fs::symlink(&file, dir.path().join("sym")).unwrap();
+
+        let mut visited = Visited::new();
+        assert!(visited.insert(&file));
+        assert!(!visited.insert(&file));
+        assert!(!visited.insert(&dir.path().join("./a")));
+        assert!(!visited.insert(&dir.path().join("hard")));
+        assert!(!visited.insert(&dir.path().join("sym")));
+    }
+
+    #[test]
+    fn different_files_with_the_same_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "x").unwrap();
+        std::fs::write(dir.path().join("b"), "x").unwrap();
+        let mut visited = Visited::new();
+        assert!(visited.insert(&dir.path().join("a")));
+        assert!(visited.insert(&dir.path().join("b")));
+    }
+
+    #[test]
+    fn missing_files_are_always_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let mut visited = Visited::new();
+        assert!(visited.insert(&missing));
+        assert!(visited.insert(&missing));
+    }
+}
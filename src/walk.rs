@@ -0,0 +1,739 @@
+// 递归搜索时的目录遍历
+//
+// 命令行给出的文件原样交给调用方；目录在递归模式下深度优先展开，其中的普通文件逐个交出。
+// 遍历是流式的：读到一个文件就交出一个，不需要先收集整棵目录树。
+// 遍历中遇到的符号链接不跟随（命令行上直接给出的符号链接照常打开）。
+// 展开目录时按 --include、--exclude、--iglob、忽略文件和文件类型跳过其中的条目（规则和各层的优先级见 ignore 和 types 模块），
+// 名字以 . 开头的隐藏文件和目录默认也跳过（--hidden 时照常遍历），命令行上直接给出的路径不受影响。
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+
+use crate::ignore::{IgnoreFile, Overrides};
+use crate::types::FileTypes;
+
+// 命令行上给出目录时的处理方式（对应 --directories 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DirectoryAction {
+    // 当作普通文件读取，也就是报错并提示使用 -r
+    #[default]
+    Read,
+    // 递归搜索（等同于 -r）
+    Recurse,
+    // 跳过
+    Skip,
+}
+
+// 设备、FIFO、套接字等特殊文件的处理方式（对应 --devices 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DeviceAction {
+    // 照常打开读取（可能一直阻塞）
+    Read,
+    // 跳过
+    #[default]
+    Skip,
+}
+
+// 遍历选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkOptions {
+    // 是否展开目录
+    pub recursive: bool,
+    // 不递归时命令行上的目录如何处理
+    pub directories: DirectoryAction,
+    // 特殊文件如何处理
+    pub devices: DeviceAction,
+    // 不进入与起点目录不在同一个文件系统（设备）上的子目录，类似 find -xdev
+    pub one_file_system: bool,
+    // 是否按各目录中的 .ignore 文件跳过条目
+    pub ignore_dot: bool,
+    // 是否按各目录中的 .gitignore 文件跳过条目
+    pub ignore_vcs: bool,
+    // 是否遍历隐藏文件和目录（名字以 . 开头）
+    pub hidden: bool,
+}
+
+// 搜索前对文件排序的依据（对应 --sort / --sortr 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortBy {
+    // 按路径
+    Path,
+    // 按修改时间
+    Modified,
+    // 按创建时间
+    Created,
+    // 按文件大小
+    Size,
+}
+
+// 遍历产生的条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkItem {
+    // 要搜索的文件
+    File(PathBuf),
+    // 开始递归搜索的命令行目录
+    Recursing(PathBuf),
+    // 跳过的路径及原因
+    Skipped(PathBuf, SkipReason),
+}
+
+// 路径被跳过的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    // 目录（--directories skip）
+    Directory,
+    // 设备、FIFO 或套接字（--devices skip）
+    Device,
+    // 与起点不在同一个文件系统上的目录（--one-file-system）
+    OtherDevice,
+    // 隐藏文件或目录（没有 --hidden）
+    Hidden,
+    // 忽略文件中的规则
+    Ignored,
+    // 命令行上的通配符（--include、--exclude、--iglob）
+    Glob,
+    // 文件类型（--type / --type-not）
+    Type,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Directory => write!(f, "a directory, --directories skip"),
+            SkipReason::Device => write!(f, "a device, FIFO or socket, --devices skip"),
+            SkipReason::OtherDevice => write!(f, "on another file system"),
+            SkipReason::Hidden => write!(f, "hidden, --hidden to search it"),
+            SkipReason::Ignored => write!(f, "matched by an ignore file"),
+            SkipReason::Glob => write!(f, "excluded by --include/--exclude/--iglob"),
+            SkipReason::Type => write!(f, "excluded by --type/--type-not"),
+        }
+    }
+}
+
+// 正在展开的目录：目录的迭代器，它所属起点的设备号，以及目录中的忽略文件
+struct OpenDir {
+    path: PathBuf,
+    entries: fs::ReadDir,
+    device: Option<u64>,
+    // .ignore
+    dot: Option<IgnoreFile>,
+    // .gitignore
+    vcs: Option<IgnoreFile>,
+}
+
+// 目录遍历器
+pub struct Walker {
+    opts: WalkOptions,
+    // 还没有处理的命令行路径（倒序存放，从尾部取出）
+    roots: Vec<PathBuf>,
+    // 深度优先遍历的目录栈
+    stack: Vec<OpenDir>,
+    // git 的全局排除文件，规则相对于每个遍历起点
+    git_excludes: Option<Arc<IgnoreFile>>,
+    // 全局忽略文件，规则相对于每个遍历起点
+    global: Option<Arc<IgnoreFile>>,
+    // 命令行上的通配符，同样相对于每个遍历起点
+    overrides: Option<Arc<Overrides>>,
+    // 文件类型
+    types: Option<Arc<FileTypes>>,
+    // --one-file-system 取设备号的方式，测试中替换成不依赖真实挂载点的版本
+    device_of: DeviceLookup,
+}
+
+// 根据路径和它的元数据取所在设备的编号
+type DeviceLookup = fn(&Path, &fs::Metadata) -> Option<u64>;
+
+impl Walker {
+    pub fn new(roots: &[PathBuf], opts: WalkOptions) -> Walker {
+        Walker {
+            opts,
+            roots: roots.iter().rev().cloned().collect(),
+            stack: Vec::new(),
+            git_excludes: None,
+            global: None,
+            overrides: None,
+            types: None,
+            device_of,
+        }
+    }
+
+    // 替换取设备号的方式，用来在测试中模拟挂载点
+    #[cfg(test)]
+    fn with_device_lookup(mut self, lookup: DeviceLookup) -> Walker {
+        self.device_of = lookup;
+        self
+    }
+
+    // 按命令行上的通配符筛选（优先于所有忽略文件）
+    pub fn with_overrides(mut self, overrides: Arc<Overrides>) -> Walker {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    // 按文件类型筛选文件（在所有忽略规则之后）
+    pub fn with_types(mut self, types: Arc<FileTypes>) -> Walker {
+        self.types = Some(types);
+        self
+    }
+
+    // 使用 git 的全局排除文件（在 .gitignore 之后、全局忽略文件之前）
+    pub fn with_git_excludes(mut self, excludes: Arc<IgnoreFile>) -> Walker {
+        self.git_excludes = Some(excludes);
+        self
+    }
+
+    // 使用全局忽略文件（优先级最低）
+    pub fn with_global_ignore(mut self, global: Arc<IgnoreFile>) -> Walker {
+        self.global = Some(global);
+        self
+    }
+
+    // 进入一个目录，device 是它所属起点的设备号
+    // 忽略文件读不出来时报错，但目录照常进入，只是少了这个文件中的规则
+    fn push_dir(&mut self, path: &Path, device: Option<u64>) -> Result<()> {
+        let entries = fs::read_dir(path)
+            .with_context(|| format!("Failed to read directory {}", path.display()))?;
+        let load = |enabled: bool, name: &str| match enabled {
+            true => IgnoreFile::load(&path.join(name))
+                .with_context(|| format!("Failed to read {}", path.join(name).display())),
+            false => Ok(None),
+        };
+        let dot = load(self.opts.ignore_dot, ".ignore");
+        let vcs = load(self.opts.ignore_vcs, ".gitignore");
+        self.stack.push(OpenDir {
+            path: path.to_path_buf(),
+            entries,
+            device,
+            dot: dot.as_ref().ok().cloned().flatten(),
+            vcs: vcs.as_ref().ok().cloned().flatten(),
+        });
+        dot.and(vcs).map(|_| ())
+    }
+
+    // 遍历中遇到的 path 是否要跳过：命令行上的通配符优先，其次是隐藏文件，再次 .ignore、.gitignore、git 的全局排除文件、全局忽略文件，
+    // 同一层中更深的目录里的文件优先；第一个有结论的规则生效，没有被忽略的文件最后再按文件类型筛选
+    fn skipped(&self, path: &Path, is_dir: bool) -> Option<SkipReason> {
+        let relative = self
+            .stack
+            .first()
+            .and_then(|root| path.strip_prefix(&root.path).ok());
+        if let (Some(overrides), Some(relative)) = (&self.overrides, relative) {
+            match overrides.matched(relative, is_dir) {
+                Some(true) => return Some(SkipReason::Glob),
+                Some(false) => return None,
+                None => {}
+            }
+        }
+        if !self.opts.hidden && is_hidden(path) {
+            return Some(SkipReason::Hidden);
+        }
+        if self.ignored(path, relative, is_dir) {
+            return Some(SkipReason::Ignored);
+        }
+        let typed_out = !is_dir && self.types.as_ref().is_some_and(|types| types.skipped(path));
+        typed_out.then_some(SkipReason::Type)
+    }
+
+    // 按忽略文件判断 path 是否被忽略，root_relative 是相对于遍历起点的路径
+    fn ignored(&self, path: &Path, root_relative: Option<&Path>, is_dir: bool) -> bool {
+        let layers: [fn(&OpenDir) -> Option<&IgnoreFile>; 2] =
+            [|dir| dir.dot.as_ref(), |dir| dir.vcs.as_ref()];
+        for layer in layers {
+            for dir in self.stack.iter().rev() {
+                let Some(file) = layer(dir) else { continue };
+                let Ok(relative) = path.strip_prefix(&dir.path) else {
+                    continue;
+                };
+                if let Some(ignored) = file.matched(relative, is_dir) {
+                    return ignored;
+                }
+            }
+        }
+        let Some(relative) = root_relative else {
+            return false;
+        };
+        [&self.git_excludes, &self.global]
+            .into_iter()
+            .flatten()
+            .find_map(|file| file.matched(relative, is_dir))
+            .unwrap_or(false)
+    }
+
+    // 处理一个命令行路径：先根据元数据分类，再决定读取、展开还是跳过
+    // 分类只用 stat，不会打开文件，FIFO 之类的特殊文件不会在这里阻塞
+    fn root(&mut self, path: PathBuf) -> Result<Option<WalkItem>> {
+        if path.as_os_str() == "-" {
+            return Ok(Some(WalkItem::File(path)));
+        }
+        // 取不到元数据时当作文件交出，由打开文件的地方报告错误
+        let Ok(metadata) = fs::metadata(&path) else {
+            return Ok(Some(WalkItem::File(path)));
+        };
+        if metadata.is_file() {
+            return Ok(Some(WalkItem::File(path)));
+        }
+        if !metadata.is_dir() {
+            return Ok(Some(self.special(path)));
+        }
+
+        match (self.opts.recursive, self.opts.directories) {
+            (true, _) | (false, DirectoryAction::Recurse) => {}
+            (false, DirectoryAction::Skip) => {
+                return Ok(Some(WalkItem::Skipped(path, SkipReason::Directory)));
+            }
+            (false, DirectoryAction::Read) => bail!(
+                "{} is a directory (use -r to search it, or --directories skip to ignore it)",
+                path.display()
+            ),
+        }
+        let device = match self.opts.one_file_system {
+            true => (self.device_of)(&path, &metadata),
+            false => None,
+        };
+        self.push_dir(&path, device)?;
+        Ok(Some(WalkItem::Recursing(path)))
+    }
+
+    // 设备、FIFO、套接字按 --devices 处理
+    fn special(&self, path: PathBuf) -> WalkItem {
+        match self.opts.devices {
+            DeviceAction::Read => WalkItem::File(path),
+            DeviceAction::Skip => WalkItem::Skipped(path, SkipReason::Device),
+        }
+    }
+
+    // 处理栈顶目录中的下一个条目，目录读完时出栈
+    fn step(&mut self) -> Result<Option<WalkItem>> {
+        let Some(dir) = self.stack.last_mut() else {
+            return Ok(None);
+        };
+        let device = dir.device;
+        let Some(entry) = dir.entries.next() else {
+            self.stack.pop();
+            return Ok(None);
+        };
+        let entry = entry.context("Failed to read directory entry")?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("Failed to read file type of {}", path.display()))?;
+
+        if let Some(reason) = self.skipped(&path, file_type.is_dir()) {
+            return Ok(Some(WalkItem::Skipped(path, reason)));
+        }
+        if file_type.is_dir() {
+            if let Some(root_device) = device {
+                let metadata = entry
+                    .metadata()
+                    .with_context(|| format!("Failed to read metadata of {}", path.display()))?;
+                if !same_device(root_device, (self.device_of)(&path, &metadata)) {
+                    return Ok(Some(WalkItem::Skipped(path, SkipReason::OtherDevice)));
+                }
+            }
+            self.push_dir(&path, device)?;
+            return Ok(None);
+        }
+        // 目录中的符号链接不跟随
+        match (file_type.is_file(), file_type.is_symlink()) {
+            (true, _) => Ok(Some(WalkItem::File(path))),
+            (false, true) => Ok(None),
+            (false, false) => Ok(Some(self.special(path))),
+        }
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<WalkItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let result = match self.stack.is_empty() {
+                true => {
+                    let path = self.roots.pop()?;
+                    self.root(path)
+                }
+                false => self.step(),
+            };
+            match result {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+// 名字以 . 开头的文件和目录是隐藏的
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+}
+
+// 子目录是否和起点在同一个设备上；取不到子目录的设备号时不拦截
+fn same_device(root: u64, dir: Option<u64>) -> bool {
+    dir.is_none_or(|dir| dir == root)
+}
+
+// 路径所在设备的编号：Unix 上是 st_dev，Windows 上是卷序列号
+#[cfg(unix)]
+fn device_of(_path: &Path, metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+#[cfg(windows)]
+fn device_of(path: &Path, _metadata: &fs::Metadata) -> Option<u64> {
+    let handle = winapi_util::Handle::from_path_any(path).ok()?;
+    let info = winapi_util::file::information(&handle).ok()?;
+    Some(info.volume_serial_number())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn device_of(_path: &Path, _metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+// 单个文件的排序键，取不到的元数据（例如某些文件系统上的创建时间）为 None
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Path(PathBuf),
+    Time(Option<SystemTime>),
+    Size(Option<u64>),
+}
+
+impl SortKey {
+    fn of(path: &Path, sort: SortBy) -> SortKey {
+        let metadata = fs::metadata(path).ok();
+        match sort {
+            SortBy::Path => SortKey::Path(path.to_path_buf()),
+            SortBy::Modified => SortKey::Time(metadata.and_then(|m| m.modified().ok())),
+            SortBy::Created => SortKey::Time(metadata.and_then(|m| m.created().ok())),
+            SortBy::Size => SortKey::Size(metadata.map(|m| m.len())),
+        }
+    }
+
+    // 键中取不到的部分
+    fn missing(&self) -> bool {
+        matches!(self, SortKey::Time(None) | SortKey::Size(None))
+    }
+}
+
+// 对遍历得到的全部条目排序，reverse 为 true 时倒序（--sortr）
+// 取不到排序依据的文件不报错，无论正序倒序都排在最后，彼此之间保持遍历顺序
+// 文件以外的条目不参与排序，放在最前面（它们只用于 --debug 提示）
+pub fn sort_items(items: Vec<WalkItem>, sort: SortBy, reverse: bool) -> Vec<WalkItem> {
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for item in items {
+        match &item {
+            WalkItem::File(path) => files.push((SortKey::of(path, sort), item)),
+            WalkItem::Recursing(_) | WalkItem::Skipped(..) => skipped.push(item),
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| match (a.missing(), b.missing()) {
+        (false, false) if reverse => b.cmp(a),
+        (false, false) => a.cmp(b),
+        (missing_a, missing_b) => missing_a.cmp(&missing_b),
+    });
+    skipped
+        .into_iter()
+        .chain(files.into_iter().map(|(_, item)| item))
+        .collect()
+}
+
+// 展开命令行上的通配符（Windows 的 cmd.exe 不会替程序展开）
+// 只有最后一个路径分量含有 * 或 ? 、并且 exists 认为没有这个名字的文件时才展开，目录部分原样保留；
+// 与 --include 是同一套通配符（ignore::Overrides），只与文件名比较，开头的 # 和 ! 按字面处理。
+// 结果按路径排序，顺序是确定的。
+// 不需要展开、或者没有任何文件匹配时返回 None，调用方照常使用原来的参数（之后报告找不到文件）
+pub fn expand_wildcards(
+    arg: &Path,
+    exists: impl Fn(&Path) -> bool,
+    case_insensitive: bool,
+) -> Result<Option<Vec<PathBuf>>> {
+    let Some(name) = arg.file_name().map(|name| name.to_string_lossy()) else {
+        return Ok(None);
+    };
+    if !name.contains(['*', '?']) || exists(arg) {
+        return Ok(None);
+    }
+    let glob = match name.starts_with(['#', '!']) {
+        true => format!("\\{name}"),
+        false => name.to_string(),
+    };
+    let mut overrides = Overrides::default();
+    overrides
+        .add(&glob, case_insensitive)
+        .with_context(|| format!("invalid wildcard {}", arg.display()))?;
+    let dir = match arg.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(None),
+    };
+    let mut expanded: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| overrides.matched(Path::new(&entry.file_name()), false) == Some(false))
+        // 与 shell 一样，结果写成 参数中的目录 + 文件名，没有目录部分时就是文件名本身
+        .map(|entry| arg.with_file_name(entry.file_name()))
+        .collect();
+    if expanded.is_empty() {
+        return Ok(None);
+    }
+    expanded.sort();
+    Ok(Some(expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // root 下的 a、sub/b、mnt/c、mnt/deeper/d 四个文件
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["a", "sub/b", "mnt/c", "mnt/deeper/d"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        dir
+    }
+
+    // 模拟的挂载：路径中有 mnt 这一级的都在设备 2 上，其余在设备 1 上
+    fn mounted(path: &Path, _: &fs::Metadata) -> Option<u64> {
+        match path.components().any(|c| c.as_os_str() == "mnt") {
+            true => Some(2),
+            false => Some(1),
+        }
+    }
+
+    // 遍历 root，返回相对于 root 的文件和跳过的条目
+    fn walk(root: &Path, opts: WalkOptions, lookup: DeviceLookup) -> (Vec<String>, Vec<String>) {
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        let relative = |path: &Path| {
+            let path = path.strip_prefix(root).unwrap_or(path);
+            path.to_string_lossy().replace('\\', "/")
+        };
+        for item in Walker::new(&[root.to_path_buf()], opts).with_device_lookup(lookup) {
+            match item.unwrap() {
+                WalkItem::File(path) => files.push(relative(&path)),
+                WalkItem::Skipped(path, reason) => {
+                    skipped.push(format!("{} ({})", relative(&path), reason))
+                }
+                WalkItem::Recursing(_) => {}
+            }
+        }
+        files.sort();
+        (files, skipped)
+    }
+
+    fn recursive(one_file_system: bool) -> WalkOptions {
+        WalkOptions {
+            recursive: true,
+            one_file_system,
+            ..WalkOptions::default()
+        }
+    }
+
+    #[test]
+    fn one_file_system_skips_other_devices() {
+        let dir = tree();
+        let (files, skipped) = walk(dir.path(), recursive(true), mounted);
+        assert_eq!(files, ["a", "sub/b"]);
+        assert_eq!(skipped, ["mnt (on another file system)"]);
+    }
+
+    #[test]
+    fn without_one_file_system_every_device_is_searched() {
+        let dir = tree();
+        let (files, skipped) = walk(dir.path(), recursive(false), mounted);
+        assert_eq!(files, ["a", "mnt/c", "mnt/deeper/d", "sub/b"]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn starting_on_the_mounted_device() {
+        let dir = tree();
+        let (files, skipped) = walk(&dir.path().join("mnt"), recursive(true), mounted);
+        assert_eq!(files, ["c", "deeper/d"]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn unknown_devices_are_not_skipped() {
+        let dir = tree();
+        let (files, _) = walk(dir.path(), recursive(true), |path, _| {
+            path.components()
+                .all(|c| c.as_os_str() != "mnt")
+                .then_some(1)
+        });
+        assert_eq!(files, ["a", "mnt/c", "mnt/deeper/d", "sub/b"]);
+    }
+
+    #[test]
+    fn device_comparison() {
+        assert!(same_device(1, Some(1)));
+        assert!(!same_device(1, Some(2)));
+        assert!(same_device(1, None));
+    }
+
+    #[test]
+    fn real_tree_is_on_one_device() {
+        let dir = tree();
+        let (files, skipped) = walk(dir.path(), recursive(true), device_of);
+        assert_eq!(files, ["a", "mnt/c", "mnt/deeper/d", "sub/b"]);
+        assert!(skipped.is_empty());
+    }
+
+    // 隐藏的文件和目录（连同其中的文件）默认跳过，--hidden 时照常遍历
+    #[test]
+    fn hidden_entries_are_skipped_unless_asked_for() {
+        let dir = tree();
+        for file in [".env", ".config/c", "sub/.d"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        let (files, mut skipped) = walk(dir.path(), recursive(false), device_of);
+        skipped.sort();
+        assert_eq!(files, ["a", "mnt/c", "mnt/deeper/d", "sub/b"]);
+        assert_eq!(
+            skipped,
+            [
+                ".config (hidden, --hidden to search it)",
+                ".env (hidden, --hidden to search it)",
+                "sub/.d (hidden, --hidden to search it)"
+            ]
+        );
+        let opts = WalkOptions {
+            hidden: true,
+            ..recursive(false)
+        };
+        let (files, skipped) = walk(dir.path(), opts, device_of);
+        assert_eq!(
+            files,
+            [
+                ".config/c",
+                ".env",
+                "a",
+                "mnt/c",
+                "mnt/deeper/d",
+                "sub/.d",
+                "sub/b"
+            ]
+        );
+        assert!(skipped.is_empty());
+    }
+
+    // 命令行上的通配符选中的隐藏文件照常搜索，起点目录本身是隐藏的也照常展开
+    #[test]
+    fn globs_and_roots_are_not_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".root");
+        fs::create_dir_all(&root).unwrap();
+        for file in [".env", "a"] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+        let mut overrides = Overrides::default();
+        overrides.add(".env", false).unwrap();
+        overrides.add("a", false).unwrap();
+        let walker = Walker::new(std::slice::from_ref(&root), recursive(false))
+            .with_overrides(Arc::new(overrides));
+        let mut files: Vec<PathBuf> = walker
+            .filter_map(|item| match item.unwrap() {
+                WalkItem::File(path) => path.strip_prefix(&root).ok().map(Path::to_path_buf),
+                _ => None,
+            })
+            .collect();
+        files.sort();
+        assert_eq!(files, [PathBuf::from(".env"), PathBuf::from("a")]);
+    }
+
+    // dir 下的文件，返回展开结果中相对于 dir 的路径
+    fn expand(dir: &Path, arg: &str, exists: bool, case_insensitive: bool) -> Option<Vec<String>> {
+        let arg = dir.join(arg);
+        let expanded = expand_wildcards(&arg, |_| exists, case_insensitive).unwrap()?;
+        Some(
+            expanded
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect(),
+        )
+    }
+
+    fn logs() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "b.log",
+            "a.log",
+            "C.LOG",
+            "notes.txt",
+            "sub/d.log",
+            "#1.log",
+        ] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn wildcards_expand_to_sorted_file_names() {
+        let dir = logs();
+        assert_eq!(
+            expand(dir.path(), "*.log", false, false).unwrap(),
+            ["#1.log", "a.log", "b.log"]
+        );
+        assert_eq!(
+            expand(dir.path(), "?.log", false, true).unwrap(),
+            ["C.LOG", "a.log", "b.log"]
+        );
+        // 目录部分原样保留
+        assert_eq!(
+            expand(dir.path(), "sub/*.log", false, false).unwrap(),
+            ["sub/d.log"]
+        );
+        // 开头的 # 按字面处理，不是注释
+        assert_eq!(expand(dir.path(), "#*", false, false).unwrap(), ["#1.log"]);
+    }
+
+    #[test]
+    fn arguments_without_wildcards_or_matches_are_kept() {
+        let dir = logs();
+        assert_eq!(expand(dir.path(), "a.log", false, false), None);
+        assert_eq!(expand(dir.path(), "*.md", false, false), None);
+        assert_eq!(expand(dir.path(), "missing/*.log", false, false), None);
+    }
+
+    #[test]
+    fn an_existing_literal_name_is_not_expanded() {
+        let dir = logs();
+        // exists 说有一个名字就叫 *.log 的文件
+        assert_eq!(expand(dir.path(), "*.log", true, false), None);
+        // 真的有这样一个文件时（Windows 上不可能出现）也一样，用真实的检查
+        if cfg!(unix) {
+            let literal = dir.path().join("*.log");
+            fs::write(&literal, "x").unwrap();
+            assert_eq!(
+                expand_wildcards(&literal, Path::exists, false).unwrap(),
+                None
+            );
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// 浏览器中使用的 wasm-bindgen 包装（需要开启 wasm 功能）
+//
+// JS 中的用法：
+//     const searcher = new Searcher("err(or)?", '{"case_ignore": true}');
+//     const results = searcher.searchText(text); // [{lineNumber, line, matches: [{start, end, pattern}]}]
+// 选项是 Options 的 JSON 形式，字段名与 Rust 中相同，省略的字段取默认值；匹配区间是行内的字节位置。
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::search::{Match, Options, SearchResult};
+
+// 交给 JS 的一条结果，字段名按 JS 的习惯使用驼峰
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsResult {
+    line_number: usize,
+    line: String,
+    matches: Vec<Match>,
+}
+
+impl From<SearchResult> for JsResult {
+    fn from(result: SearchResult) -> Self {
+        JsResult {
+            line_number: result.line_number,
+            line: result.line,
+            matches: result.matches,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = Searcher)]
+pub struct Searcher {
+    searcher: crate::search::Searcher,
+}
+
+#[wasm_bindgen(js_class = Searcher)]
+impl Searcher {
+    // 编译模式，options_json 省略时使用默认选项；模式或选项有误时抛出异常
+    #[wasm_bindgen(constructor)]
+    pub fn new(pattern: &str, options_json: Option<String>) -> Result<Searcher, JsError> {
+        let searcher = compile(pattern, options_json.as_deref()).map_err(|e| JsError::new(&e))?;
+        Ok(Searcher { searcher })
+    }
+
+    // 搜索一段文本，返回选中的行组成的数组
+    #[wasm_bindgen(js_name = searchText)]
+    pub fn search_text(&self, text: &str) -> Result<JsValue, JsError> {
+        let results = search(&self.searcher, text).map_err(|e| JsError::new(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&results)?)
+    }
+}
+
+// JsError 只能在 wasm32 上构造，下面两个函数把错误先写成文字，在宿主机上也能测试
+
+// 读入 JSON 形式的选项并编译模式
+fn compile(pattern: &str, options_json: Option<&str>) -> Result<crate::search::Searcher, String> {
+    let opts: Options = match options_json {
+        Some(json) => serde_json::from_str(json).map_err(|e| format!("invalid options: {e}"))?,
+        None => Options::default(),
+    };
+    crate::search::Searcher::new(pattern, opts).map_err(|e| format!("{e:#}"))
+}
+
+fn search(searcher: &crate::search::Searcher, text: &str) -> Result<Vec<JsResult>, String> {
+    searcher
+        .search_str(text)
+        .map(|result| result.map(JsResult::from))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| format!("{e:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 结果按交给 JS 的样子写成 JSON
+    fn search_json(pattern: &str, options_json: Option<&str>, text: &str) -> serde_json::Value {
+        let searcher = compile(pattern, options_json).unwrap();
+        serde_json::to_value(search(&searcher, text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn results_use_camel_case() {
+        let results = search_json("b+", None, "abbc\nxyz\nb\n");
+        assert_eq!(
+            results,
+            serde_json::json!([
+                {"lineNumber": 1, "line": "abbc", "matches": [{"start": 1, "end": 3, "pattern": 0, "distance": 0}]},
+                {"lineNumber": 3, "line": "b", "matches": [{"start": 0, "end": 1, "pattern": 0, "distance": 0}]},
+            ])
+        );
+    }
+
+    #[test]
+    fn options_come_from_json() {
+        let results = search_json("error", Some(r#"{"case_ignore": true}"#), "ERROR\nok\n");
+        assert_eq!(results[0]["line"], "ERROR");
+        let results = search_json("error", Some("{}"), "ERROR\nok\n");
+        assert_eq!(results, serde_json::json!([]));
+    }
+
+    #[test]
+    fn bad_options_and_patterns_are_errors() {
+        let error = compile("a", Some("{")).err().unwrap();
+        assert!(error.starts_with("invalid options:"), "{error}");
+        let error = compile("a", Some(r#"{"case_ignore": 1}"#)).err().unwrap();
+        assert!(error.starts_with("invalid options:"), "{error}");
+        assert!(compile("(", None).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher, event::ModifyKind};
+
+// 没有事件时多久检查一次 Ctrl-C
+const POLL: Duration = Duration::from_millis(100);
+// 最后一个相关的事件之后安静这么久才重新搜索，编辑器保存时的一连串事件只触发一次
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// 一个被监视的命令行路径
+struct Root {
+    // 命令行上的写法，重新搜索时的路径都以它开头，输出与第一次搜索一致
+    given: PathBuf,
+    // 事件中的路径是绝对路径，用规范化之后的路径换算回 given 的写法
+    canonical: PathBuf,
+    is_dir: bool,
+}
+
+// --watch：监视命令行上的文件和目录，有变化时交出变化的路径
+// 目录递归监视；文件监视它所在的目录，编辑器先删除再重建文件时也能继续收到事件
+pub struct Watch {
+    _watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    roots: Vec<Root>,
+    // 不算变化的文件（规范化之后的路径），例如 --output 的输出文件
+    ignored: Vec<PathBuf>,
+    // Ctrl-C 时置位
+    stop: Arc<AtomicBool>,
+}
+
+// 一批变化
+#[derive(Debug, Default)]
+pub struct Changes {
+    // 包含变化的命令行路径，重新搜索时只遍历它们
+    pub roots: Vec<PathBuf>,
+    // 新建或修改过的文件
+    pub files: BTreeSet<PathBuf>,
+    // 新建或移进来的目录，其中的文件都要重新搜索
+    pub dirs: BTreeSet<PathBuf>,
+    // 被删除或移走的路径
+    pub removed: BTreeSet<PathBuf>,
+}
+
+impl Changes {
+    // 遍历到的文件是否要重新搜索
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path) || self.dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.dirs.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Watch {
+    pub fn new(paths: &[PathBuf], stop: Arc<AtomicBool>) -> Result<Watch> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .context("Failed to start watching for file changes")?;
+        let mut roots = Vec::new();
+        for given in paths {
+            let canonical = fs::canonicalize(given)
+                .with_context(|| format!("Failed to watch {}", given.display()))?;
+            let is_dir = canonical.is_dir();
+            let (target, mode) = match is_dir {
+                true => (canonical.as_path(), RecursiveMode::Recursive),
+                false => (
+                    canonical.parent().unwrap_or(Path::new("/")),
+                    RecursiveMode::NonRecursive,
+                ),
+            };
+            watcher
+                .watch(target, mode)
+                .with_context(|| format!("Failed to watch {}", given.display()))?;
+            roots.push(Root {
+                given: given.clone(),
+                canonical,
+                is_dir,
+            });
+        }
+        Ok(Watch {
+            _watcher: watcher,
+            events,
+            roots,
+            ignored: Vec::new(),
+            stop,
+        })
+    }
+
+    // 这个文件的变化不再交出；文件还不存在、无法规范化时不起作用
+    pub fn with_ignored(mut self, path: &Path) -> Watch {
+        self.ignored.extend(fs::canonicalize(path));
+        self
+    }
+
+    // 等待下一批变化，Ctrl-C 时返回 None
+    pub fn wait(&self) -> Option<Changes> {
+        let mut changes = Changes::default();
+        // 从最后一个相关的事件算起；不相关的事件（例如读取输出文件）不推迟重新搜索
+        let mut deadline: Option<Instant> = None;
+        loop {
+            if self.stop.load(Ordering::SeqCst) {
+                return None;
+            }
+            let timeout = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => POLL,
+            };
+            match self.events.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    if self.record(&mut changes, event) {
+                        deadline = Some(Instant::now() + DEBOUNCE);
+                    }
+                }
+                Ok(Err(e)) => eprintln!("mrustgrep: error while watching for changes: {e}"),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                // 只有目录属性变化之类的事件时继续等待
+                match changes.is_empty() {
+                    true => deadline = None,
+                    false => break,
+                }
+            }
+        }
+        // 同一个路径先删后建时以现在的状态为准
+        changes.removed.retain(|path| !path.exists());
+        changes.roots = self
+            .roots
+            .iter()
+            .filter(|root| {
+                changes
+                    .files
+                    .iter()
+                    .chain(&changes.dirs)
+                    .any(|path| path.starts_with(&root.given))
+            })
+            .map(|root| root.given.clone())
+            .collect();
+        Some(changes)
+    }
+
+    // 把一个事件中的路径记入 changes，返回是否有相关的路径
+    fn record(&self, changes: &mut Changes, event: Event) -> bool {
+        // 只读访问（打开、关闭文件）不算变化
+        if let EventKind::Access(_) = event.kind {
+            return false;
+        }
+        let appeared = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+        );
+        let mut relevant = false;
+        for path in &event.paths {
+            if self.ignored.contains(path) {
+                continue;
+            }
+            let Some(path) = self.given_form(path) else {
+                continue;
+            };
+            relevant = true;
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    // 目录自身的属性变化不需要重新搜索其中的文件
+                    if appeared {
+                        changes.dirs.insert(path);
+                    }
+                }
+                Ok(_) => {
+                    changes.files.insert(path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    changes.files.remove(&path);
+                    changes.dirs.remove(&path);
+                    changes.removed.insert(path);
+                }
+                Err(_) => {
+                    changes.files.insert(path);
+                }
+            }
+        }
+        relevant
+    }
+
+    // 把事件中的绝对路径换算成命令行上的写法，不属于任何命令行路径时返回 None
+    fn given_form(&self, path: &Path) -> Option<PathBuf> {
+        self.roots.iter().find_map(|root| match root.is_dir {
+            true => path
+                .strip_prefix(&root.canonical)
+                .ok()
+                .map(|relative| root.given.join(relative)),
+            false => (path == root.canonical).then(|| root.given.clone()),
+        })
+    }
+}
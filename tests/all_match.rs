@@ -0,0 +1,77 @@
+mod common;
+use common::Fixture;
+
+const LINES: &str = "foo bar baz\nfoo bar\nbaz foo\nbar\nnone\nbaz bar foo\n";
+
+fn patterns<'a>(extra: &[&'a str]) -> Vec<&'a str> {
+    let mut args = vec!["--all-match", "-e", "foo", "-e", "bar", "-e", "baz"];
+    args.extend_from_slice(extra);
+    args
+}
+
+#[test]
+fn only_lines_with_every_pattern() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&patterns(&[]), LINES);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:foo bar baz", "6:baz bar foo"]);
+}
+
+#[test]
+fn without_all_match_any_pattern_is_enough() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-c", "-e", "foo", "-e", "bar", "-e", "baz"], LINES);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "5\n");
+}
+
+#[test]
+fn two_of_three_is_not_enough() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&patterns(&[]), "foo bar\nbar baz\nfoo baz\n");
+    run.assert_code(1);
+    assert_eq!(run.stdout(), "");
+}
+
+#[test]
+fn invert_selects_lines_missing_a_pattern() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&patterns(&["-v"]), LINES);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:foo bar", "3:baz foo", "4:bar", "5:none"]);
+}
+
+#[test]
+fn highlighting_shows_every_pattern() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&patterns(&["--color=always"]), "foo bar baz\n");
+    run.assert_code(0);
+    let stdout = run.stdout();
+    for word in ["foo", "bar", "baz"] {
+        assert!(stdout.contains(&format!("m{word}\x1b[0m")), "{:?}", stdout);
+    }
+}
+
+#[test]
+fn only_matching_prints_the_union_in_order() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&patterns(&["-o"]), "baz x foo y bar\nfoo\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["baz", "foo", "bar"]);
+}
+
+#[test]
+fn files_with_matches_needs_every_pattern_somewhere() {
+    let fixture = Fixture::new();
+    fixture.write("dir/all", "foo\nbar\nbaz\n");
+    fixture.write("dir/two", "foo bar\n");
+    fixture.write("dir/one", "baz\n");
+    fixture.write("dir/none", "nothing\n");
+    let run = fixture.run(&patterns(&["-r", "-l", "--sort", "path", "dir"]));
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["dir/all"]);
+
+    let run = fixture.run(&patterns(&["-r", "-L", "--sort", "path", "dir"]));
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["dir/none", "dir/one", "dir/two"]);
+}
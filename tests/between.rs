@@ -0,0 +1,82 @@
+// --between START END：每条规则各有一个测试，规则见 --between 的帮助
+mod common;
+use common::Fixture;
+
+fn run(contents: &str, args: &[&str]) -> Vec<String> {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", contents);
+    let run = fixture.run(&[args, &["a.txt"]].concat());
+    run.assert_code(if run.stdout().is_empty() { 1 } else { 0 });
+    run.lines()
+}
+
+#[test]
+fn regions_include_both_ends() {
+    let lines = run("pre\nBEGIN\nx\nEND\npost\n", &["--between", "BEGIN", "END"]);
+    assert_eq!(lines, ["2:BEGIN", "3:x", "4:END"]);
+}
+
+#[test]
+fn start_inside_a_region_does_not_nest() {
+    let lines = run(
+        "BEGIN outer\nBEGIN inner\nx\nEND\nafter\nEND\n",
+        &["--between", "BEGIN", "END"],
+    );
+    // 第一个 END 就结束区间，之后的行直到下一个 START 都不选
+    assert_eq!(lines, ["1:BEGIN outer", "2:BEGIN inner", "3:x", "4:END"]);
+}
+
+#[test]
+fn a_line_matching_both_is_a_one_line_region() {
+    let lines = run(
+        "pre\nBEGIN END\nafter\nEND\n",
+        &["--between", "BEGIN", "END"],
+    );
+    assert_eq!(lines, ["2:BEGIN END"]);
+}
+
+#[test]
+fn an_unterminated_region_runs_to_the_end_of_input() {
+    let lines = run("pre\nBEGIN\nx\ny", &["--between", "BEGIN", "END"]);
+    assert_eq!(lines, ["2:BEGIN", "3:x", "4:y"]);
+}
+
+#[test]
+fn patterns_filter_region_lines() {
+    let contents = "x0\nBEGIN x\ny\nx1\nEND\nx2\n";
+    let lines = run(contents, &["--between", "BEGIN", "END", "-e", "x"]);
+    assert_eq!(lines, ["2:BEGIN x", "4:x1"]);
+    // -v 反转的是 -e 的筛选，区间之外的行仍然不选
+    let lines = run(contents, &["--between", "BEGIN", "END", "-v", "-e", "x"]);
+    assert_eq!(lines, ["3:y", "5:END"]);
+    let lines = run(contents, &["--between", "BEGIN", "END", "-e", "nowhere"]);
+    assert!(lines.is_empty());
+
+    let fixture = Fixture::new();
+    fixture.write("a.txt", contents);
+    fixture.write("patterns", "y\nEND\n");
+    let run = fixture.run(&["--between", "BEGIN", "END", "-f", "patterns", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:y", "5:END"]);
+}
+
+#[test]
+fn count_counts_selected_lines() {
+    let contents = "x0\nBEGIN x\ny\nx1\nEND\nx2\nBEGIN\n";
+    assert_eq!(run(contents, &["-c", "--between", "BEGIN", "END"]), ["5"]);
+    assert_eq!(
+        run(contents, &["-c", "--between", "BEGIN", "END", "-e", "x"]),
+        ["2"]
+    );
+}
+
+#[test]
+fn regions_restart_in_each_file() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "BEGIN\na\n");
+    fixture.write("b.txt", "b\nEND\n");
+    let run = fixture.run(&["--between", "BEGIN", "END", "a.txt", "b.txt"]);
+    run.assert_code(0);
+    // a.txt 中没有结束的区间不会延续到 b.txt
+    assert_eq!(run.lines(), ["a.txt:1:BEGIN", "a.txt:2:a"]);
+}
@@ -0,0 +1,79 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn fixed_strings_ignore_case_without_becoming_regex() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-i", "-F", "A.B (X)"], "a.b (x)\naxb (x)\nA.B (X)\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:a.b (x)", "3:A.B (X)"]);
+}
+
+#[test]
+fn literal_text_that_looks_like_a_flag_is_kept() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-i", "-F", "(?i)"], "x (?I) y\nxy\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:x (?I) y"]);
+}
+
+#[test]
+fn pattern_file_patterns_ignore_case() {
+    let fixture = Fixture::new();
+    fixture.write("patterns", "alpha\nbe.a\n");
+    let run = fixture.run_stdin(&["-i", "-f", "patterns"], "ALPHA\nBETA\ngamma\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:ALPHA", "2:BETA"]);
+
+    let run = fixture.run_stdin(&["-i", "-F", "-f", "patterns"], "ALPHA\nBETA\nBE.A\n");
+    assert_eq!(run.lines(), ["1:ALPHA", "3:BE.A"]);
+}
+
+#[test]
+fn debug_pattern_has_no_inline_flag() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--debug", "-i", "foo"], "FOO\n");
+    run.assert_code(0);
+    assert!(
+        run.stderr()
+            .contains("mrustgrep[debug]: final pattern: foo\n"),
+        "{}",
+        run.stderr()
+    );
+    assert!(run.stderr().contains("case-insensitive: true"));
+}
+
+#[test]
+fn error_position_is_unchanged_by_ignore_case() {
+    let fixture = Fixture::new();
+    let plain = fixture.run_stdin(&["x{2,1}"], "x\n");
+    let folded = fixture.run_stdin(&["-i", "x{2,1}"], "x\n");
+    folded.assert_code(2);
+    assert_eq!(plain.stderr(), folded.stderr());
+    assert!(folded.stderr().contains("    x{2,1}\n     ^^^^^\n"));
+}
+
+#[test]
+fn fixed_strings_use_full_unicode_folding() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-i", "-F", "straße"], "STRASSE\nstrase\nSTRAẞE\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:STRASSE", "3:STRAẞE"]);
+}
+
+#[test]
+fn many_literals_fold_beyond_ascii() {
+    let fixture = Fixture::new();
+    let input = "x \u{212a} y\nlong \u{17f}\nCAFÉ\nnone\n";
+    let run = fixture.run_stdin(&["-i", "-F", "-e", "k", "-e", "s", "-e", "café"], input);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:x \u{212a} y", "2:long \u{17f}", "3:CAFÉ"]);
+
+    let run = fixture.run_stdin(&["--debug", "-i", "-e", "k", "-e", "zz"], input);
+    assert!(
+        run.stderr().contains("engine: aho-corasick"),
+        "{}",
+        run.stderr()
+    );
+    assert_eq!(run.lines(), ["1:x \u{212a} y"]);
+}
@@ -0,0 +1,64 @@
+mod common;
+use common::Fixture;
+
+// 默认配色：匹配加粗红色，第 N 个分组在此基础上加下划线并轮换前景色
+const MATCH: &str = "\x1b[1;31m";
+const GROUP1: &str = "\x1b[1;4;32m";
+const GROUP2: &str = "\x1b[1;4;33m";
+const RESET: &str = "\x1b[0m";
+
+#[test]
+fn groups_are_styled_inside_the_match() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(
+        &["--color", "always", "--color-groups", "-N", r"(\w+)=(\S+)"],
+        "key=value\n",
+    );
+    run.assert_code(0);
+    assert_eq!(
+        run.stdout(),
+        format!("{GROUP1}key{RESET}{MATCH}={RESET}{GROUP2}value{RESET}\n")
+    );
+}
+
+#[test]
+fn groups_are_not_styled_without_the_flag() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--color", "always", "-N", r"(\w+)=(\S+)"], "key=value\n");
+    assert_eq!(run.stdout(), format!("{MATCH}key=value{RESET}\n"));
+}
+
+#[test]
+fn color_groups_without_color_prints_plain_text() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(
+        &["--color", "never", "--color-groups", "-N", r"(\w+)=(\S+)"],
+        "key=value\n",
+    );
+    assert_eq!(run.stdout(), "key=value\n");
+}
+
+#[test]
+fn group_colors_can_be_configured() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(
+        &[
+            "--color",
+            "always",
+            "--color-groups",
+            "--colors",
+            "group2:fg:blue",
+            "-N",
+            "-o",
+            r"(\w+)=(\S+)",
+        ],
+        "a=b\n",
+    );
+    run.assert_code(0);
+    let stdout = run.stdout();
+    assert!(
+        stdout.starts_with(&format!("{GROUP1}a{RESET}{MATCH}={RESET}")),
+        "{stdout:?}"
+    );
+    assert!(!stdout.contains(GROUP2), "{stdout:?}");
+}
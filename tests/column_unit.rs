@@ -0,0 +1,42 @@
+// --column-unit：多字节字符和组合字符出现在匹配之前时，字节列号和字符列号不同
+mod common;
+use common::Fixture;
+
+// é 两个字节，e 加上 U+0301 共三个字节两个字符，中 三个字节
+const INPUT: &str = "é e\u{301} 中 hit\nhit\n";
+
+#[test]
+fn columns_default_to_bytes() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--column", "-o", "hit"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["12:hit", "1:hit"]);
+    let run = fixture.run_stdin(&["--column", "--column-unit", "bytes", "-o", "hit"], INPUT);
+    assert_eq!(run.lines(), ["12:hit", "1:hit"]);
+}
+
+#[test]
+fn char_columns_count_each_scalar_value() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--column", "--column-unit", "chars", "-o", "hit"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["8:hit", "1:hit"]);
+    // 后面的匹配之前的内容同样按字符计数
+    let run = fixture.run_stdin(
+        &["--column", "--column-unit", "chars", "-o", "hit"],
+        "中中 hit hit\n",
+    );
+    assert_eq!(run.lines(), ["4:hit", "8:hit"]);
+}
+
+#[test]
+fn column_unit_does_not_change_byte_offsets() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(
+        &["-b", "--column", "--column-unit", "chars", "-o", "hit"],
+        INPUT,
+    );
+    run.assert_code(0);
+    // 列号在字节偏移之前；偏移仍然是字节
+    assert_eq!(run.lines(), ["8:11:hit", "1:15:hit"]);
+}
@@ -0,0 +1,151 @@
+// 集成测试共用的工具：在临时目录中准备输入文件，在与开发者环境隔离的条件下运行 mrustgrep
+// 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use tempfile::TempDir;
+
+// 一个测试用的临时目录：work 是运行命令的当前目录，home 是 HOME 和 XDG_CONFIG_HOME 所在的位置
+// 开发者自己的配置文件、git 全局排除文件和 MRUSTGREP_* 环境变量都不会影响测试
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Fixture {
+        let dir = tempfile::tempdir().expect("create temporary directory");
+        fs::create_dir_all(dir.path().join("work")).unwrap();
+        fs::create_dir_all(dir.path().join("home/.config")).unwrap();
+        Fixture { dir }
+    }
+
+    // 运行命令的当前目录
+    pub fn work(&self) -> PathBuf {
+        self.dir.path().join("work")
+    }
+
+    // HOME 目录
+    pub fn home(&self) -> PathBuf {
+        self.dir.path().join("home")
+    }
+
+    // 在 work 下写入一个文件（自动创建上级目录），返回它的完整路径
+    pub fn write(&self, relative: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.work().join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // 在 work 下创建一个目录
+    pub fn mkdir(&self, relative: &str) -> PathBuf {
+        let path = self.work().join(relative);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    // 隔离了环境的命令，还没有参数；stdin 默认是空的（不是终端）
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_mrustgrep"));
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("MRUSTGREP_") {
+                command.env_remove(&key);
+            }
+        }
+        command
+            .env_remove("NO_COLOR")
+            .env_remove("TERM")
+            .env("HOME", self.home())
+            .env("XDG_CONFIG_HOME", self.home().join(".config"))
+            .current_dir(self.work())
+            .stdin(Stdio::null());
+        command
+    }
+
+    // 运行 mrustgrep 并等待结束
+    pub fn run(&self, args: &[&str]) -> Run {
+        Run::from(self.command().args(args).output().expect("run mrustgrep"))
+    }
+
+    // 把 input 作为标准输入运行 mrustgrep
+    pub fn run_stdin(&self, args: &[&str], input: impl AsRef<[u8]>) -> Run {
+        run_with_stdin(self.command().args(args), input.as_ref())
+    }
+}
+
+// 给已经准备好的命令喂入标准输入，等待结束
+pub fn run_with_stdin(command: &mut Command, input: &[u8]) -> Run {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run mrustgrep");
+    // 输入比管道缓冲区大时由另一个线程写，避免与读输出互相等待
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || {
+        // 程序提前结束（例如 -q 找到匹配后）时写入会失败，这是正常的
+        let _ = stdin.write_all(&input);
+    });
+    let output = child.wait_with_output().expect("wait for mrustgrep");
+    writer.join().unwrap();
+    Run::from(output)
+}
+
+// 一次运行的结果
+pub struct Run {
+    pub output: Output,
+}
+
+impl From<Output> for Run {
+    fn from(output: Output) -> Run {
+        Run { output }
+    }
+}
+
+impl Run {
+    // 退出码，被信号结束时为 None
+    pub fn code(&self) -> Option<i32> {
+        self.output.status.code()
+    }
+
+    pub fn stdout(&self) -> String {
+        String::from_utf8_lossy(&self.output.stdout).into_owned()
+    }
+
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.output.stderr).into_owned()
+    }
+
+    // stdout 按行拆开
+    pub fn lines(&self) -> Vec<String> {
+        self.stdout().lines().map(str::to_string).collect()
+    }
+
+    // 断言退出码，失败时带上 stderr 方便排查
+    #[track_caller]
+    pub fn assert_code(&self, code: i32) -> &Run {
+        assert_eq!(
+            self.code(),
+            Some(code),
+            "unexpected exit status\nstdout:\n{}\nstderr:\n{}",
+            self.stdout(),
+            self.stderr()
+        );
+        self
+    }
+}
+
+// 路径中的目录分隔符统一成 /，方便在不同平台上比较输出
+pub fn slashes(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
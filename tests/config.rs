@@ -0,0 +1,137 @@
+// 配置文件：内容排在命令行参数之前，优先级是 环境变量 < 配置文件 < 命令行
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+
+// 一行 20 个字符，--max-columns 小于它时整行省略
+const INPUT: &str = "needle in a haystack\n";
+
+fn with_config(contents: &str) -> Fixture {
+    let fixture = Fixture::new();
+    let dir = fixture.home().join(".config/mrustgrep");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("config"), contents).unwrap();
+    fixture
+}
+
+#[test]
+fn xdg_config_file_supplies_defaults() {
+    let fixture = with_config("# always ignore case\n--ignore-case\n");
+    let run = fixture.run_stdin(&["NEEDLE"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:needle in a haystack"]);
+}
+
+#[test]
+fn command_line_overrides_config() {
+    let fixture = with_config("--ignore-case\n");
+    fixture
+        .run_stdin(&["--case-sensitive", "NEEDLE"], INPUT)
+        .assert_code(1);
+}
+
+#[test]
+fn smart_case_on_the_command_line_overrides_config_ignore_case() {
+    let fixture = with_config("--ignore-case\n");
+    // 模式中有大写字母，-S 区分大小写
+    fixture.run_stdin(&["-S", "Needle"], INPUT).assert_code(1);
+    fixture.run_stdin(&["-S", "needle"], INPUT).assert_code(0);
+    // 命令行上后给出的 -i 又覆盖了 -S
+    fixture
+        .run_stdin(&["-S", "-i", "Needle"], INPUT)
+        .assert_code(0);
+}
+
+#[test]
+fn config_overrides_environment() {
+    let fixture = with_config("--max-columns=5\n");
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("MRUSTGREP_MAX_COLUMNS", "100")
+            .arg("needle"),
+        INPUT.as_bytes(),
+    );
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:[Omitted long line with 1 matches]"]);
+
+    // 命令行又比配置文件优先
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("MRUSTGREP_MAX_COLUMNS", "5")
+            .args(["--max-columns=100", "needle"]),
+        INPUT.as_bytes(),
+    );
+    assert_eq!(run.lines(), ["1:needle in a haystack"]);
+}
+
+#[test]
+fn environment_applies_without_config() {
+    let fixture = Fixture::new();
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("MRUSTGREP_MAX_COLUMNS", "5")
+            .arg("needle"),
+        INPUT.as_bytes(),
+    );
+    assert_eq!(run.lines(), ["1:[Omitted long line with 1 matches]"]);
+}
+
+#[test]
+fn mrustgrep_config_wins_over_xdg_location() {
+    let fixture = with_config("--max-columns=5\n");
+    let explicit = fixture.write("my-config", "--ignore-case\n");
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("MRUSTGREP_CONFIG", &explicit)
+            .arg("NEEDLE"),
+        INPUT.as_bytes(),
+    );
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:needle in a haystack"]);
+}
+
+#[test]
+fn no_config_skips_the_file() {
+    let fixture = with_config("--ignore-case\n");
+    fixture
+        .run_stdin(&["--no-config", "NEEDLE"], INPUT)
+        .assert_code(1);
+}
+
+#[test]
+fn errors_name_the_file_and_line() {
+    let fixture = with_config("--heading\n# comment\nnot-a-flag\n");
+    let run = fixture.run_stdin(&["needle"], INPUT);
+    run.assert_code(2);
+    assert!(run.stderr().contains("line 3"), "{}", run.stderr());
+
+    // clap 不认识的参数同样指出来自配置文件的哪一行
+    let fixture = with_config("--heading\n--no-such-flag\n");
+    let run = fixture.run_stdin(&["needle"], INPUT);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("error in config file") && run.stderr().contains("line 2"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn missing_explicit_config_is_an_error() {
+    let fixture = Fixture::new();
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("MRUSTGREP_CONFIG", fixture.work().join("missing"))
+            .arg("needle"),
+        INPUT.as_bytes(),
+    );
+    run.assert_code(2);
+    assert!(run.stderr().contains("Failed to read config file"));
+}
@@ -0,0 +1,81 @@
+mod common;
+use common::Fixture;
+
+// 解析 --format csv 的输出：表头之后的每一行
+fn records(stdout: &[u8]) -> Vec<Vec<String>> {
+    let mut reader = csv::Reader::from_reader(stdout);
+    let headers = reader.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        ["path", "line", "column", "match_text", "line_text"]
+    );
+    reader
+        .records()
+        .map(|record| record.unwrap().iter().map(str::to_string).collect())
+        .collect()
+}
+
+fn row(fields: [&str; 5]) -> Vec<String> {
+    fields.iter().map(|field| field.to_string()).collect()
+}
+
+#[test]
+fn fields_with_commas_and_quotes_round_trip() {
+    let fixture = Fixture::new();
+    fixture.write("input", "a,b \"q\" hit\nplain hit hit\n");
+    let run = fixture.run(&["--format", "csv", "hit", "input"]);
+    run.assert_code(0);
+    assert_eq!(
+        records(&run.output.stdout),
+        [
+            row(["input", "1", "9", "hit", "a,b \"q\" hit"]),
+            row(["input", "2", "7", "hit", "plain hit hit"]),
+            row(["input", "2", "11", "hit", "plain hit hit"]),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn paths_with_newlines_round_trip() {
+    let fixture = Fixture::new();
+    fixture.write("odd,\nname \"x\"", "hit\n");
+    let run = fixture.run(&["--format", "csv", "hit", "odd,\nname \"x\""]);
+    run.assert_code(0);
+    assert_eq!(
+        records(&run.output.stdout),
+        [row(["odd,\nname \"x\"", "1", "1", "hit", "hit"])]
+    );
+}
+
+#[test]
+fn invalid_utf8_is_replaced() {
+    let fixture = Fixture::new();
+    fixture.write("input", b"x\xff hit\n");
+    let run = fixture.run(&["--format", "csv", "hit", "input"]);
+    run.assert_code(0);
+    assert_eq!(
+        records(&run.output.stdout),
+        [row(["input", "1", "6", "hit", "x\u{fffd} hit"])]
+    );
+}
+
+#[test]
+fn inverted_lines_have_no_match_columns() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--format", "csv", "-v", "hit"], "hit\nother\n");
+    run.assert_code(0);
+    assert_eq!(
+        records(&run.output.stdout),
+        [row(["(standard input)", "2", "", "", "other"])]
+    );
+}
+
+#[test]
+fn column_unit_applies_to_csv() {
+    let fixture = Fixture::new();
+    let args = ["--format", "csv", "--column-unit", "chars", "hit"];
+    let run = fixture.run_stdin(&args, "ééé hit\n");
+    run.assert_code(0);
+    assert_eq!(records(&run.output.stdout)[0][2], "5");
+}
@@ -0,0 +1,113 @@
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "plain\nx -v y\n-- z\n-e w\n-foo\n";
+
+#[test]
+fn flag_like_pattern_after_separator() {
+    let fixture = Fixture::new();
+    fixture.write("input", INPUT);
+    let run = fixture.run(&["--", "-v", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:x -v y"]);
+}
+
+#[test]
+fn separator_itself_as_pattern() {
+    let fixture = Fixture::new();
+    fixture.write("input", INPUT);
+    let run = fixture.run(&["--", "--", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:-- z"]);
+}
+
+#[test]
+fn regexp_option_takes_dash_patterns_literally() {
+    let fixture = Fixture::new();
+    fixture.write("input", INPUT);
+    let run = fixture.run(&["-e", "-e", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["4:-e w"]);
+
+    let run = fixture.run(&["-e", "-v", "-e", "--", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:x -v y", "3:-- z"]);
+}
+
+#[test]
+fn unknown_dash_word_is_a_pattern() {
+    let fixture = Fixture::new();
+    fixture.write("input", INPUT);
+    let run = fixture.run(&["--", "-foo", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["5:-foo"]);
+
+    // 不是任何选项的组合时不需要 --
+    fixture.write("other", "-zzz\n");
+    let run = fixture.run(&["-zzz", "other"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:-zzz"]);
+}
+
+#[test]
+fn help_mentions_the_separator() {
+    let fixture = Fixture::new();
+    let run = fixture.run(&["--help"]);
+    run.assert_code(0);
+    assert!(
+        run.stdout().contains("mrustgrep -- -v FILE"),
+        "{}",
+        run.stdout()
+    );
+}
+
+// 短参数紧跟着值（-m2、-A1、-C1、-j2）是参数而不是模式，不管放在模式之前还是之后
+#[test]
+fn attached_short_values_are_options() {
+    let fixture = Fixture::new();
+    fixture.write("input", "hit 1\nmiss\nhit 2\nhit 3\n");
+    for args in [
+        ["-m2", "hit", "input"],
+        ["hit", "-m2", "input"],
+        ["hit", "input", "-m2"],
+    ] {
+        let run = fixture.run(&args);
+        run.assert_code(0);
+        assert_eq!(run.lines(), ["1:hit 1", "3:hit 2"], "{args:?}");
+    }
+    for args in [["-A1", "miss", "input"], ["miss", "-A1", "input"]] {
+        let run = fixture.run(&args);
+        run.assert_code(0);
+        assert_eq!(run.lines(), ["2:miss", "3-hit 2"], "{args:?}");
+    }
+    for args in [["-C1", "miss", "input"], ["miss", "input", "-C1"]] {
+        let run = fixture.run(&args);
+        run.assert_code(0);
+        assert_eq!(run.lines(), ["1-hit 1", "2:miss", "3-hit 2"], "{args:?}");
+    }
+    for args in [["-c", "-j2", "hit", "input"], ["-c", "hit", "-j2", "input"]] {
+        let run = fixture.run(&args);
+        run.assert_code(0);
+        assert_eq!(run.lines(), ["3"], "{args:?}");
+    }
+    // 与前面的短参数合在一起，以及写成 -m=2，都是同样的意思
+    let run = fixture.run(&["-vm1", "hit", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:miss"]);
+    let run = fixture.run(&["-m=1", "hit", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:hit 1"]);
+}
+
+// 作为 -e 的值以及在 -- 之后时不拆开
+#[test]
+fn attached_values_are_not_split_inside_values() {
+    let fixture = Fixture::new();
+    fixture.write("input", "-m2\nhit\n");
+    let run = fixture.run(&["-e", "-m2", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:-m2"]);
+    let run = fixture.run(&["--", "-m2", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:-m2"]);
+}
@@ -0,0 +1,62 @@
+mod common;
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("src/a", "hit\n");
+    fixture.write("src/b", "hit\n");
+    fixture
+}
+
+#[test]
+fn overlapping_roots_search_each_file_once() {
+    let fixture = fixture();
+    let run = fixture.run(&["-r", "--sort", "path", "hit", "src", "src/"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["src/a:1:hit", "src/b:1:hit"]);
+    assert!(run.stderr().contains("Total matched lines: 2"));
+}
+
+#[test]
+fn repeated_arguments_are_searched_once() {
+    let fixture = fixture();
+    let run = fixture.run(&["hit", "src/a", "src/a", "./src/a"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["src/a:1:hit"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn hard_links_are_searched_once() {
+    let fixture = fixture();
+    std::fs::hard_link(fixture.work().join("src/a"), fixture.work().join("src/c")).unwrap();
+    let run = fixture.run(&["-c", "-r", "hit", "src"]);
+    run.assert_code(0);
+    // 两个名字中先走到的那个被搜索，另一个跳过
+    let mut lines = run.lines();
+    lines.sort();
+    assert!(
+        lines == ["src/a:1", "src/b:1"] || lines == ["src/b:1", "src/c:1"],
+        "{:?}",
+        lines
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_argument_is_the_same_file() {
+    let fixture = fixture();
+    std::os::unix::fs::symlink("src/a", fixture.work().join("link")).unwrap();
+    let run = fixture.run(&["hit", "src/a", "link"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["src/a:1:hit"]);
+}
+
+#[test]
+fn no_dedupe_searches_every_path() {
+    let fixture = fixture();
+    let run = fixture.run(&["--no-dedupe", "hit", "src/a", "src/a"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["src/a:1:hit", "src/a:1:hit"]);
+    assert!(run.stderr().contains("Total matched lines: 2"));
+}
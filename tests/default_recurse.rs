@@ -0,0 +1,89 @@
+// 没有路径参数、stdin 又是终端时默认递归搜索当前目录，与 -r 一样跳过忽略的、隐藏的和二进制文件
+// 需要一个伪终端，借用 util-linux 的 script 命令；没有它时跳过这些测试
+#![cfg(target_os = "linux")]
+mod common;
+use common::Fixture;
+
+use std::process::Command;
+
+// 在伪终端中运行 mrustgrep，stdin、stdout 和 stderr 都是终端；返回合在一起的输出（不含匹配总数那一行），
+// 没有 script 时返回 None
+fn run_on_terminal(fixture: &Fixture, args: &str) -> Option<String> {
+    let binary = env!("CARGO_BIN_EXE_mrustgrep");
+    let mut script = Command::new("script");
+    script
+        .args([
+            "-qec",
+            &format!("'{binary}' --color never {args}"),
+            "/dev/null",
+        ])
+        .current_dir(fixture.work());
+    // 与 Fixture::command 一样隔离开发者的环境
+    for (key, value) in fixture.command().get_envs() {
+        match value {
+            Some(value) => script.env(key, value),
+            None => script.env_remove(key),
+        };
+    }
+    match script.output() {
+        Ok(output) => Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.starts_with("Total matched lines"))
+                .map(|line| format!("{}\n", line.trim_end_matches('\r')))
+                .collect(),
+        ),
+        Err(_) => {
+            eprintln!("script is not available, skipping");
+            None
+        }
+    }
+}
+
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for path in ["a.txt", "ignored.txt", ".hidden.txt", "sub/b.txt"] {
+        fixture.write(path, "hit\n");
+    }
+    fixture.write("bin.dat", "hit\0\n");
+    fixture.write(".gitignore", "ignored.txt\n");
+    fixture
+}
+
+#[test]
+fn default_recursion_skips_like_recursive() {
+    let fixture = tree();
+    let Some(output) = run_on_terminal(&fixture, "-l --sort path hit") else {
+        return;
+    };
+    assert_eq!(output, "./a.txt\n./sub/b.txt\n");
+    let Some(output) = run_on_terminal(&fixture, "-l --sort path -uuu hit") else {
+        return;
+    };
+    assert_eq!(
+        output,
+        "./.hidden.txt\n./a.txt\n./bin.dat\n./ignored.txt\n./sub/b.txt\n"
+    );
+}
+
+#[test]
+fn debug_shows_the_chosen_mode() {
+    let fixture = tree();
+    let Some(output) = run_on_terminal(&fixture, "--debug -l hit") else {
+        return;
+    };
+    assert!(
+        output.contains(
+            "no path given: stdin is a terminal, searching the current directory recursively"
+        ),
+        "{output}"
+    );
+    assert!(
+        output.contains("skipping ./.hidden.txt (hidden, --hidden to search it)"),
+        "{output}"
+    );
+    assert!(
+        output.contains("skipping ./bin.dat (binary, -a/--text to search it)"),
+        "{output}"
+    );
+}
@@ -0,0 +1,107 @@
+// 环境变量提供的默认值：命令行总是优先，无效的值指出是哪个变量，--debug 说明来源
+mod common;
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one match\nnothing\n");
+    fixture.write("b.txt", "another match\n");
+    fixture
+}
+
+#[test]
+fn help_lists_every_variable() {
+    let run = Fixture::new().run(&["--help"]);
+    run.assert_code(0);
+    for var in [
+        "MRUSTGREP_CONFIG",
+        "MRUSTGREP_COLOR",
+        "MRUSTGREP_COLORS",
+        "MRUSTGREP_IGNORE",
+        "MRUSTGREP_MAX_COLUMNS",
+        "MRUSTGREP_THREADS",
+        "NO_COLOR",
+    ] {
+        assert!(run.stdout().contains(var), "{} missing from --help", var);
+    }
+}
+
+#[test]
+fn threads_from_environment() {
+    let fixture = fixture();
+    let sequential = fixture.run(&["match", "a.txt", "b.txt"]);
+    let run = fixture
+        .command()
+        .env("MRUSTGREP_THREADS", "4")
+        .args(["--debug", "match", "a.txt", "b.txt"])
+        .output()
+        .unwrap();
+    assert!(run.status.success());
+    assert_eq!(run.stdout, sequential.output.stdout);
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(stderr.contains("--threads = \"4\" (from environment variable MRUSTGREP_THREADS)"));
+    assert!(
+        stderr.contains("searching files on 4 threads"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn command_line_beats_environment() {
+    let fixture = fixture();
+    let run = fixture
+        .command()
+        .env("MRUSTGREP_THREADS", "4")
+        .args(["--debug", "-j", "1", "match", "a.txt", "b.txt"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert!(!stderr.contains("threads"), "{}", stderr);
+}
+
+#[test]
+fn invalid_value_names_the_variable() {
+    let fixture = fixture();
+    for (var, value) in [
+        ("MRUSTGREP_THREADS", "many"),
+        ("MRUSTGREP_MAX_COLUMNS", "wide"),
+        ("MRUSTGREP_COLOR", "sometimes"),
+    ] {
+        let run = fixture
+            .command()
+            .env(var, value)
+            .args(["match", "a.txt"])
+            .output()
+            .unwrap();
+        assert_eq!(run.status.code(), Some(2));
+        let stderr = String::from_utf8_lossy(&run.stderr);
+        assert!(
+            stderr.contains(&format!("invalid value in environment variable {}", var)),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn environment_threads_do_not_conflict_with_sequential_modes() {
+    let fixture = fixture();
+    let run = fixture
+        .command()
+        .env("MRUSTGREP_THREADS", "4")
+        .args(["--parallel-file", "match", "a.txt"])
+        .output()
+        .unwrap();
+    assert_eq!(run.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "1:one match\n");
+
+    // 命令行上显式给出时仍然是用法错误
+    let run = fixture.run(&["-j", "4", "--parallel-file", "match", "a.txt"]);
+    run.assert_code(2);
+    assert!(
+        run.stderr()
+            .contains("--threads cannot be used with --parallel-file")
+    );
+}
@@ -0,0 +1,88 @@
+// --field-match-separator / --field-context-separator：路径、行号、列号、字节位置和行内容之间的分隔符，
+// 匹配行默认是 :，上下文行默认是 -
+mod common;
+use common::Fixture;
+
+fn lines(args: &[&str]) -> Vec<String> {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "pre\nhit here\n");
+    let run = fixture.run(&[&["-B", "1"], args, &["hit", "a.txt"]].concat());
+    run.assert_code(0);
+    run.lines()
+}
+
+const CUSTOM: [&str; 4] = [
+    "--field-match-separator",
+    "=>",
+    "--field-context-separator",
+    "~",
+];
+
+#[test]
+fn defaults() {
+    assert_eq!(lines(&[]), ["1-pre", "2:hit here"]);
+    assert_eq!(lines(&["-H"]), ["a.txt-1-pre", "a.txt:2:hit here"]);
+    assert_eq!(
+        lines(&["-H", "-b", "--column"]),
+        ["a.txt-1-0-pre", "a.txt:2:1:4:hit here"]
+    );
+}
+
+#[test]
+fn custom_separators_without_a_path() {
+    assert_eq!(lines(&CUSTOM), ["1~pre", "2=>hit here"]);
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["-b"]].concat()),
+        ["1~0~pre", "2=>4=>hit here"]
+    );
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["--column"]].concat()),
+        ["1~pre", "2=>1=>hit here"]
+    );
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["-b", "--column"]].concat()),
+        ["1~0~pre", "2=>1=>4=>hit here"]
+    );
+}
+
+#[test]
+fn custom_separators_with_a_path() {
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["-H"]].concat()),
+        ["a.txt~1~pre", "a.txt=>2=>hit here"]
+    );
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["-H", "-b", "--column"]].concat()),
+        ["a.txt~1~0~pre", "a.txt=>2=>1=>4=>hit here"]
+    );
+    // 没有行号时只剩路径和行内容之间的分隔符
+    assert_eq!(
+        lines(&[&CUSTOM[..], &["-H", "-N"]].concat()),
+        ["a.txt~pre", "a.txt=>hit here"]
+    );
+}
+
+#[test]
+fn each_separator_only_applies_to_its_lines() {
+    assert_eq!(
+        lines(&["-H", "--field-match-separator", "="]),
+        ["a.txt-1-pre", "a.txt=2=hit here"]
+    );
+    assert_eq!(
+        lines(&["-H", "--field-context-separator", "="]),
+        ["a.txt=1=pre", "a.txt:2:hit here"]
+    );
+}
+
+#[test]
+fn empty_separators() {
+    assert_eq!(
+        lines(&[
+            "--field-match-separator",
+            "",
+            "--field-context-separator",
+            ""
+        ]),
+        ["1pre", "2hit here"]
+    );
+}
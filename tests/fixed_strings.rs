@@ -0,0 +1,40 @@
+// -F：模式是字面量；多个字面量模式用 Aho-Corasick 自动机，语义与正则的 | 相同
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "a.b\naxb\nq\nabcd\n";
+
+#[test]
+fn several_literals_use_aho_corasick() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--debug", "-F", "-e", "a.b", "-e", "q"], INPUT);
+    run.assert_code(0);
+    assert!(
+        run.stderr().contains("engine: aho-corasick"),
+        "{}",
+        run.stderr()
+    );
+    // . 是普通字符
+    assert_eq!(run.lines(), ["1:a.b", "3:q"]);
+}
+
+#[test]
+fn a_single_literal_is_an_escaped_regex() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--debug", "-F", "a.b"], INPUT);
+    run.assert_code(0);
+    assert!(run.stderr().contains("engine: regex"), "{}", run.stderr());
+    assert_eq!(run.lines(), ["1:a.b"]);
+}
+
+#[test]
+fn the_first_listed_literal_wins_at_the_same_start() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-F", "-o", "-e", "ab", "-e", "abc"], INPUT);
+    assert_eq!(run.lines(), ["ab"]);
+    let run = fixture.run_stdin(&["-F", "-o", "-e", "abc", "-e", "ab"], INPUT);
+    assert_eq!(run.lines(), ["abc"]);
+    // 与不加 -F 时的 ab|abc 相同
+    let run = fixture.run_stdin(&["-o", "-e", "ab", "-e", "abc"], INPUT);
+    assert_eq!(run.lines(), ["ab"]);
+}
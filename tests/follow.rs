@@ -0,0 +1,38 @@
+// --follow 的参数检查：只跟踪一个文件，不能与 -q、-c、-l、-L 一起使用
+mod common;
+use common::Fixture;
+
+#[test]
+fn quiet_and_per_file_reports_are_rejected() {
+    let fixture = Fixture::new();
+    fixture.write("app.log", "hit\n");
+    for flag in ["-q", "-c", "--count-matches", "-l", "-L"] {
+        let run = fixture.run(&["--follow", flag, "hit", "app.log"]);
+        run.assert_code(2);
+        assert!(
+            run.stderr().contains("cannot be used with"),
+            "{flag}: {}",
+            run.stderr()
+        );
+    }
+}
+
+#[test]
+fn exactly_one_file_is_required() {
+    let fixture = Fixture::new();
+    fixture.write("a.log", "hit\n");
+    fixture.write("b.log", "hit\n");
+    for args in [
+        &["--follow", "hit", "a.log", "b.log"][..],
+        &["--follow", "hit", "-"],
+        &["--follow", "hit"],
+    ] {
+        let run = fixture.run(args);
+        run.assert_code(2);
+        assert!(
+            run.stderr().contains("--follow requires exactly one file"),
+            "{args:?}: {}",
+            run.stderr()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+// -G/--glob-pattern：通配符翻译成正则之后，-i 和 -o 与正则模式的行为相同
+mod common;
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "Hello World\nyellow\nxxabcabc d\nfile[1].txt\n");
+    fixture
+}
+
+#[test]
+fn ignore_case_applies_to_wildcards_and_classes() {
+    let fixture = fixture();
+    let run = fixture.run(&["-G", "-i", "HEL*o", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:Hello World"]);
+    let run = fixture.run(&["-G", "-i", "[w]ORLD", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:Hello World"]);
+    // 不加 -i 时大小写不同不匹配
+    fixture.run(&["-G", "HEL*o", "a.txt"]).assert_code(1);
+}
+
+#[test]
+fn only_matching_prints_the_translated_match() {
+    let fixture = fixture();
+    let run = fixture.run(&["-G", "-o", "a?c", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["abc", "abc"]);
+    // * 尽量多地匹配
+    let run = fixture.run(&["-G", "-o", "a*c", "a.txt"]);
+    assert_eq!(run.lines(), ["abcabc"]);
+    let run = fixture.run(&["-G", "-o", r"file\[1\]", "a.txt"]);
+    assert_eq!(run.lines(), ["file[1]"]);
+    let run = fixture.run(&["-G", "-o", "l[!l]", "a.txt"]);
+    assert_eq!(run.lines(), ["lo", "ld", "lo", "le"]);
+}
+
+#[test]
+fn ignore_case_with_only_matching() {
+    let fixture = fixture();
+    let run = fixture.run(&["-G", "-o", "-i", "A*C", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["abcabc"]);
+    let run = fixture.run(&["-G", "-o", "-i", "?ELLO", "a.txt"]);
+    assert_eq!(run.lines(), ["Hello", "yello"]);
+}
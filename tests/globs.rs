@@ -0,0 +1,166 @@
+// 命令行上的通配符：--include、--exclude 和 --iglob 共用一组规则，按命令行上的顺序后面的优先
+mod common;
+use common::Fixture;
+
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for path in [
+        "main.rs",
+        "lib.RS",
+        "notes.md",
+        "src/util.rs",
+        "src/gen_table.rs",
+        "vendor/dep.rs",
+    ] {
+        fixture.write(path, "hit\n");
+    }
+    fixture
+}
+
+// 搜索到的文件，去掉遍历起点的 ./
+fn searched(fixture: &Fixture, args: &[&str]) -> Vec<String> {
+    let mut all = vec!["-r", "-l", "--sort", "path"];
+    all.extend(args);
+    all.push("hit");
+    let run = fixture.run(&all);
+    run.assert_code(0);
+    run.lines()
+        .iter()
+        .map(|line| line.replace('\\', "/").trim_start_matches("./").to_string())
+        .collect()
+}
+
+#[test]
+fn include_selects_matching_files_case_sensitively() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["--include", "*.rs"]),
+        [
+            "main.rs",
+            "src/gen_table.rs",
+            "src/util.rs",
+            "vendor/dep.rs"
+        ]
+    );
+    assert_eq!(
+        searched(&fixture, &["--include", "*.rs", "--include", "*.md"]),
+        [
+            "main.rs",
+            "notes.md",
+            "src/gen_table.rs",
+            "src/util.rs",
+            "vendor/dep.rs"
+        ]
+    );
+}
+
+#[test]
+fn exclude_skips_files_and_directories() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["--exclude", "vendor", "--exclude", "*.md"]),
+        ["lib.RS", "main.rs", "src/gen_table.rs", "src/util.rs"]
+    );
+}
+
+#[test]
+fn later_globs_win_in_command_line_order() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["--include", "*.rs", "--exclude", "gen_*"]),
+        ["main.rs", "src/util.rs", "vendor/dep.rs"]
+    );
+    assert_eq!(
+        searched(&fixture, &["--exclude", "gen_*", "--include", "*.rs"]),
+        [
+            "main.rs",
+            "src/gen_table.rs",
+            "src/util.rs",
+            "vendor/dep.rs"
+        ]
+    );
+}
+
+#[test]
+fn include_overrides_ignore_files() {
+    let fixture = tree();
+    fixture.write(".gitignore", "vendor/dep.rs\n");
+    assert_eq!(
+        searched(&fixture, &["--include", "dep.rs"]),
+        ["vendor/dep.rs"]
+    );
+}
+
+#[test]
+fn files_named_on_the_command_line_are_not_filtered() {
+    let fixture = tree();
+    let run = fixture.run(&["-l", "--include", "*.rs", "hit", "notes.md"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["notes.md"]);
+}
+
+#[test]
+fn invalid_globs_are_reported() {
+    let fixture = tree();
+    let run = fixture.run(&["-r", "--exclude", "", "hit"]);
+    run.assert_code(2);
+    assert!(run.stderr().contains("--exclude"), "{}", run.stderr());
+}
+
+// 大小写混杂的文件名在区分大小写的文件系统上并存，--iglob 全部选中，--include 只选中完全相同的
+#[cfg(unix)]
+#[test]
+fn iglob_matches_every_spelling_on_a_case_sensitive_file_system() {
+    let fixture = Fixture::new();
+    for path in [
+        "Makefile",
+        "makefile",
+        "GNUmakefile",
+        "README",
+        "readme",
+        "Docs/ReadMe",
+        "src/a.c",
+    ] {
+        fixture.write(path, "hit\n");
+    }
+    assert_eq!(
+        searched(&fixture, &["--iglob", "*makefile", "--iglob", "readme"]),
+        [
+            "Docs/ReadMe",
+            "GNUmakefile",
+            "Makefile",
+            "README",
+            "makefile",
+            "readme"
+        ]
+    );
+    assert_eq!(
+        searched(&fixture, &["--include", "*makefile", "--include", "readme"]),
+        ["GNUmakefile", "makefile", "readme"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn iglob_ignores_case_in_directory_components() {
+    let fixture = Fixture::new();
+    for path in ["Docs/a.md", "docs/b.md", "DOCS/c.md", "src/docs.md"] {
+        fixture.write(path, "hit\n");
+    }
+    assert_eq!(
+        searched(&fixture, &["--iglob", "docs/*.md"]),
+        ["DOCS/c.md", "Docs/a.md", "docs/b.md"]
+    );
+    assert_eq!(searched(&fixture, &["--iglob", "!DOCS/"]), ["src/docs.md"]);
+}
+
+#[test]
+fn iglob_negation_and_order_with_include() {
+    let fixture = tree();
+    // !GLOB 不区分大小写地跳过 .rs 和 .RS，之后的 --include 重新选中 gen_table.rs；
+    // 有了 --include，其余文件（notes.md）也被跳过
+    assert_eq!(
+        searched(&fixture, &["--iglob", "!*.RS", "--include", "gen_*.rs"]),
+        ["src/gen_table.rs"]
+    );
+}
@@ -0,0 +1,128 @@
+// 忽略文件各层之间的优先级：命令行通配符 > .ignore > .gitignore > git 的全局排除文件 > 全局忽略文件
+mod common;
+use common::Fixture;
+
+// 每个文件都含有 hit，列出被搜索到的文件
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for path in [
+        "a.txt",
+        "debug.log",
+        "keep.log",
+        "secret.txt",
+        "notes.tmp",
+        "scratch.bak",
+        "gen/out.txt",
+        "sub/sub.log",
+        "sub/other.log",
+    ] {
+        fixture.write(path, "hit\n");
+    }
+    fixture.write(".gitignore", "*.log\nsecret.txt\n");
+    // .ignore 重新包含 .gitignore 忽略的 keep.log
+    fixture.write(".ignore", "!keep.log\ngen/\n");
+    // 更深的目录中的 .gitignore 优先
+    fixture.write("sub/.gitignore", "!sub.log\n");
+    // 全局忽略文件优先级最低，不能重新包含 .gitignore 忽略的 secret.txt
+    fixture.write("../global-ignore", "*.tmp\n!secret.txt\n");
+    std::fs::create_dir_all(fixture.home().join(".config/git")).unwrap();
+    std::fs::write(fixture.home().join(".config/git/ignore"), "*.bak\n").unwrap();
+    fixture
+}
+
+fn searched(fixture: &Fixture, args: &[&str]) -> Vec<String> {
+    let mut all = vec!["-r", "-l", "--sort", "path"];
+    all.extend(args);
+    all.push("hit");
+    let run = fixture.run(&all);
+    run.assert_code(0);
+    // 没有给出路径时从 . 开始遍历
+    run.lines()
+        .iter()
+        .map(|line| line.trim_start_matches("./").to_string())
+        .collect()
+}
+
+#[test]
+fn layers_apply_in_order() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["--global-ignore-file", "../global-ignore"]),
+        ["a.txt", "keep.log", "sub/sub.log"]
+    );
+}
+
+#[test]
+fn global_ignore_file_comes_from_the_environment() {
+    let fixture = tree();
+    let run = fixture
+        .command()
+        .args(["-r", "-l", "--sort", "path", "hit"])
+        .env("MRUSTGREP_IGNORE", "../global-ignore")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(run.stdout).unwrap();
+    assert_eq!(stdout, "./a.txt\n./keep.log\n./sub/sub.log\n");
+}
+
+#[test]
+fn command_line_globs_beat_ignore_files() {
+    let fixture = tree();
+    // .ignore 重新包含了 keep.log，命令行再跳过它
+    assert_eq!(
+        searched(&fixture, &["--iglob", "!keep.log"]),
+        ["a.txt", "notes.tmp", "sub/sub.log"]
+    );
+    // .gitignore 忽略的文件可以由命令行通配符选中
+    assert_eq!(
+        searched(&fixture, &["--iglob", "secret.txt"]),
+        ["secret.txt"]
+    );
+}
+
+#[test]
+fn no_ignore_vcs_keeps_the_other_layers() {
+    let fixture = tree();
+    assert_eq!(
+        searched(
+            &fixture,
+            &[
+                "--no-ignore-vcs",
+                "--global-ignore-file",
+                "../global-ignore"
+            ]
+        ),
+        [
+            "a.txt",
+            "debug.log",
+            "keep.log",
+            "scratch.bak",
+            "secret.txt",
+            "sub/other.log",
+            "sub/sub.log"
+        ]
+    );
+}
+
+#[test]
+fn no_ignore_disables_every_layer() {
+    let fixture = tree();
+    let all = searched(
+        &fixture,
+        &["--no-ignore", "--global-ignore-file", "../global-ignore"],
+    );
+    assert_eq!(all.len(), 9, "{all:?}");
+    // 后面的 --ignore 撤销 --no-ignore
+    assert_eq!(
+        searched(&fixture, &["--no-ignore", "--ignore"]),
+        ["a.txt", "keep.log", "notes.tmp", "sub/sub.log"]
+    );
+}
+
+#[test]
+fn paths_on_the_command_line_are_never_ignored() {
+    let fixture = tree();
+    let run = fixture.run(&["-H", "hit", "debug.log", "gen/out.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["debug.log:1:hit", "gen/out.txt:1:hit"]);
+}
@@ -0,0 +1,141 @@
+mod common;
+use common::Fixture;
+use serde_json::{Value, json};
+
+// --json 输出的最后一个对象
+fn summary(stdout: &str) -> Value {
+    let last = stdout.lines().last().expect("a summary object");
+    serde_json::from_str::<Value>(last).unwrap()["summary"].take()
+}
+
+#[test]
+fn missing_file_does_not_stop_the_search() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "foo\n");
+    fixture.write("b.txt", "foo\n");
+    let run = fixture.run(&["foo", "a.txt", "missing.txt", "b.txt"]);
+    run.assert_code(2);
+    assert_eq!(run.lines(), ["a.txt:1:foo", "b.txt:1:foo"]);
+    let stderr = run.stderr();
+    assert!(
+        stderr.contains("mrustgrep: Failed to open missing.txt"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("1 input(s) could not be searched: missing.txt"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn no_messages_keeps_the_exit_status() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "foo\n");
+    let run = fixture.run(&["-s", "foo", "a.txt", "missing.txt"]);
+    run.assert_code(2);
+    assert_eq!(run.lines(), ["a.txt:1:foo"]);
+    assert!(!run.stderr().contains("missing.txt"), "{}", run.stderr());
+}
+
+#[test]
+fn json_summary_records_path_and_os_error() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "foo\n");
+    let run = fixture.run(&["-s", "--json", "foo", "a.txt", "missing.txt"]);
+    run.assert_code(2);
+    let errors = &summary(&run.stdout())["errors"];
+    assert_eq!(errors.as_array().map(Vec::len), Some(1), "{errors}");
+    assert_eq!(errors[0]["path"], "missing.txt");
+    // 操作系统错误的文字因平台而异，只检查它出现在完整消息的结尾
+    let os_error = errors[0]["os_error"].as_str().unwrap();
+    let message = errors[0]["message"].as_str().unwrap();
+    assert!(
+        message.starts_with("Failed to open missing.txt: "),
+        "{message}"
+    );
+    assert!(message.ends_with(os_error), "{message} / {os_error}");
+}
+
+#[test]
+fn summary_has_no_errors_when_everything_was_searched() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "foo\n");
+    let run = fixture.run(&["--json", "foo", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(summary(&run.stdout()), json!({"matched_lines": 1}));
+}
+
+// chmod 000 的文件和目录；root 不受权限位限制，这时跳过
+#[cfg(unix)]
+mod unreadable {
+    use super::*;
+    use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+    // 把 path 的权限改成 000，仍然能打开时（以 root 运行）返回 false
+    fn lock(path: &Path) -> bool {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = match path.is_dir() {
+            true => fs::read_dir(path).is_ok(),
+            false => fs::File::open(path).is_ok(),
+        };
+        if readable {
+            eprintln!(
+                "skipping: {} is still readable (running as root?)",
+                path.display()
+            );
+        }
+        !readable
+    }
+
+    // 测试结束时恢复权限，让临时目录可以删除
+    fn unlock(path: &Path) {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn unreadable_file_is_reported_and_skipped() {
+        let fixture = Fixture::new();
+        fixture.write("src/a.txt", "foo\n");
+        let locked = fixture.write("src/locked.txt", "foo\n");
+        fixture.write("src/z.txt", "foo\n");
+        if !lock(&locked) {
+            return;
+        }
+        let run = fixture.run(&["-r", "--sort", "path", "foo", "src"]);
+        let json = fixture.run(&["-r", "-s", "--json", "foo", "src"]);
+        unlock(&locked);
+
+        run.assert_code(2);
+        assert_eq!(run.lines(), ["src/a.txt:1:foo", "src/z.txt:1:foo"]);
+        let stderr = run.stderr();
+        assert!(stderr.contains("Failed to open src/locked.txt"), "{stderr}");
+        assert!(stderr.contains("Permission denied"), "{stderr}");
+
+        json.assert_code(2);
+        let errors = &summary(&json.stdout())["errors"];
+        assert_eq!(errors[0]["path"], "src/locked.txt", "{errors}");
+        let os_error = errors[0]["os_error"].as_str().unwrap();
+        assert!(os_error.starts_with("Permission denied"), "{os_error}");
+    }
+
+    #[test]
+    fn unreadable_directory_is_reported_and_skipped() {
+        let fixture = Fixture::new();
+        fixture.write("src/a.txt", "foo\n");
+        fixture.write("src/locked/b.txt", "foo\n");
+        let locked = fixture.work().join("src/locked");
+        if !lock(&locked) {
+            return;
+        }
+        let run = fixture.run(&["-r", "foo", "src"]);
+        unlock(&locked);
+
+        run.assert_code(2);
+        assert_eq!(run.lines(), ["src/a.txt:1:foo"]);
+        assert!(
+            run.stderr().contains("Permission denied"),
+            "{}",
+            run.stderr()
+        );
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn invert_with_only_matching_is_rejected() {
+    let fixture = Fixture::new();
+    for args in [
+        &["-v", "-o", "a"][..],
+        &["--invert-match", "--only-matching", "a"],
+    ] {
+        let run = fixture.run_stdin(args, "a\nb\n");
+        run.assert_code(2);
+        assert!(
+            run.stderr()
+                .contains("'--invert-match' cannot be used with '--only-matching'"),
+            "{}",
+            run.stderr()
+        );
+        assert_eq!(run.stdout(), "");
+    }
+}
+
+#[test]
+fn invert_selects_non_matching_lines() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-v", "a"], "a\nb\nab\nc\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:b", "4:c"]);
+}
+
+#[test]
+fn invert_exits_one_when_every_line_matches() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-v", "a"], "a\nba\n");
+    run.assert_code(1);
+    assert_eq!(run.stdout(), "");
+}
+
+#[test]
+fn only_matching_prints_each_match() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-o", "a."], "xabyac\nzz\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["ab", "ac"]);
+}
@@ -0,0 +1,135 @@
+mod common;
+use common::Fixture;
+use serde_json::{Value, json};
+
+// stdout 中的每一行都是一个 JSON 对象，最后一个是汇总，分开返回
+fn objects_and_summary(stdout: &str) -> (Vec<Value>, Value) {
+    let mut objects: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{line}: {e}")))
+        .collect();
+    let mut last = objects.pop().expect("a summary object");
+    (objects, last["summary"].take())
+}
+
+fn objects(stdout: &str) -> Vec<Value> {
+    objects_and_summary(stdout).0
+}
+
+#[test]
+fn json_prints_one_object_per_match() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "foo and foo\nbar\nquote \" and\ttab foo\n");
+    let run = fixture.run(&["--json", "foo", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(
+        objects(&run.stdout()),
+        [
+            json!({"path": "a.txt", "line_number": 1, "line": "foo and foo", "submatches": [{"start": 0, "end": 3}]}),
+            json!({"path": "a.txt", "line_number": 1, "line": "foo and foo", "submatches": [{"start": 8, "end": 11}]}),
+            json!({"path": "a.txt", "line_number": 3, "line": "quote \" and\ttab foo", "submatches": [{"start": 16, "end": 19}]}),
+        ]
+    );
+    // --format json 与 --json 相同
+    let same = fixture.run(&["--format", "json", "foo", "a.txt"]);
+    assert_eq!(same.stdout(), run.stdout());
+}
+
+#[test]
+fn inverted_lines_have_no_submatches() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--json", "-v", "foo"], "foo\nbar\n");
+    run.assert_code(0);
+    assert_eq!(
+        objects(&run.stdout()),
+        [json!({"path": "(standard input)", "line_number": 2, "line": "bar", "submatches": []})]
+    );
+}
+
+#[test]
+fn context_lines_are_not_printed() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--json", "-C", "1", "b"], "a\nb\nc\n");
+    run.assert_code(0);
+    let lines: Vec<Value> = objects(&run.stdout())
+        .into_iter()
+        .map(|object| object["line"].clone())
+        .collect();
+    assert_eq!(lines, ["b"]);
+}
+
+#[test]
+fn offsets_are_in_the_decoded_line() {
+    let fixture = Fixture::new();
+    fixture.write("latin1.txt", b"caf\xe9 foo\n");
+    let run = fixture.run(&["--json", "foo", "latin1.txt"]);
+    run.assert_code(0);
+    let object = &objects(&run.stdout())[0];
+    assert_eq!(object["line"], "caf\u{fffd} foo");
+    assert_eq!(object["submatches"], json!([{"start": 7, "end": 10}]));
+}
+
+#[test]
+fn json_conflicts_with_counting() {
+    let fixture = Fixture::new();
+    for flag in ["-c", "-l", "-L"] {
+        fixture
+            .run_stdin(&["--json", flag, "foo"], "foo\n")
+            .assert_code(2);
+    }
+}
+
+#[test]
+fn summary_carries_per_pattern_stats() {
+    let fixture = Fixture::new();
+    let input = "ERROR a\nWARN b\nERROR WARN\nok\n";
+    let run = fixture.run_stdin(
+        &["--json", "-e", "ERROR", "-e", "WARN", "-e", "FATAL"],
+        input,
+    );
+    run.assert_code(0);
+    let (objects, summary) = objects_and_summary(&run.stdout());
+    assert_eq!(objects.len(), 4);
+    assert_eq!(summary, json!({"matched_lines": 3}));
+
+    let run = fixture.run_stdin(
+        &[
+            "--json",
+            "--count-per-pattern",
+            "-e",
+            "ERROR",
+            "-e",
+            "WARN",
+            "-e",
+            "FATAL",
+        ],
+        input,
+    );
+    run.assert_code(0);
+    let (_, summary) = objects_and_summary(&run.stdout());
+    assert_eq!(
+        summary,
+        json!({
+            "matched_lines": 3,
+            "patterns": [
+                {"pattern": "ERROR", "lines": 2, "matches": 2},
+                {"pattern": "WARN", "lines": 2, "matches": 2},
+                {"pattern": "FATAL", "lines": 0, "matches": 0},
+            ],
+        })
+    );
+    // 文本形式仍然写到 stderr
+    assert!(
+        run.stderr().contains("FATAL: 0 lines, 0 matches"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn summary_is_printed_without_matches() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--json", "y"], "x\n");
+    run.assert_code(1);
+    assert_eq!(run.stdout(), "{\"summary\":{\"matched_lines\":0}}\n");
+}
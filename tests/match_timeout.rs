@@ -0,0 +1,45 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn timeout_does_not_change_results() {
+    let fixture = Fixture::new();
+    let input = format!("{}b\n{}\n", "a".repeat(5000), "a".repeat(5000));
+    let plain = fixture.run_stdin(&["-c", "(a|aa)+$"], &input);
+    let limited = fixture.run_stdin(&["--match-timeout", "1", "-c", "(a|aa)+$"], &input);
+    limited.assert_code(0);
+    assert_eq!(limited.stdout(), "1\n");
+    assert_eq!(plain.stdout(), limited.stdout());
+}
+
+#[test]
+fn debug_explains_that_the_timeout_is_ignored() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--debug", "--match-timeout", "20", "a"], "a\n");
+    run.assert_code(0);
+    assert!(
+        run.stderr().contains(
+            "mrustgrep[debug]: match timeout 20ms ignored: the regex engine runs in linear time"
+        ),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn help_says_the_timeout_has_no_effect() {
+    let fixture = Fixture::new();
+    let run = fixture.run(&["--help"]);
+    assert!(
+        run.stdout()
+            .contains("no effect with the current regex and literal engines")
+    );
+}
+
+#[test]
+fn timeout_must_be_a_number() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--match-timeout", "soon", "a"], "a\n");
+    run.assert_code(2);
+    assert!(run.stderr().contains("--match-timeout"), "{}", run.stderr());
+}
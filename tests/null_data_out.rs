@@ -0,0 +1,78 @@
+// --null-data-out：输出的每条记录以 NUL 结尾，记录中的换行原样保留
+mod common;
+use common::Fixture;
+
+// 按 NUL 拆开 stdout，最后一条记录之后也必须有 NUL
+fn records(stdout: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(stdout);
+    let text = text
+        .strip_suffix('\0')
+        .unwrap_or_else(|| panic!("output does not end with NUL: {text:?}"));
+    text.split('\0').map(str::to_string).collect()
+}
+
+#[test]
+fn embedded_newlines_survive_a_nul_splitting_consumer() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "key=one\nother\nkey=two\n");
+    let run = fixture.run(&[
+        "--null-data-out",
+        "-N",
+        "--replace",
+        "$1\n$2",
+        "(key)=(\\w+)",
+        "a.txt",
+    ]);
+    run.assert_code(0);
+    assert_eq!(records(&run.output.stdout), ["key\none", "key\ntwo"]);
+}
+
+#[test]
+fn context_lines_matches_and_separators_are_records() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "a\nhit 1\nb\nc\nd\nhit 2\n");
+    let run = fixture.run(&["--null-data-out", "-C", "1", "hit", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(
+        records(&run.output.stdout),
+        ["1-a", "2:hit 1", "3-b", "--", "5-d", "6:hit 2"]
+    );
+    let run = fixture.run(&["--null-data-out", "-o", "hit \\d", "a.txt"]);
+    assert_eq!(records(&run.output.stdout), ["hit 1", "hit 2"]);
+}
+
+#[test]
+fn heading_titles_are_records() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "hit\n");
+    fixture.write("b.txt", "hit\n");
+    let run = fixture.run(&[
+        "--null-data-out",
+        "--heading",
+        "-N",
+        "hit",
+        "a.txt",
+        "b.txt",
+    ]);
+    run.assert_code(0);
+    let records = records(&run.output.stdout);
+    assert!(records.contains(&"a.txt".to_string()), "{records:?}");
+    assert!(records.contains(&"b.txt".to_string()), "{records:?}");
+    assert!(
+        !records.iter().any(|record| record.contains('\n')),
+        "{records:?}"
+    );
+}
+
+#[test]
+fn counts_and_json_stay_newline_delimited() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "hit\nhit\n");
+    let run = fixture.run(&["--null-data-out", "-c", "hit", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "2\n");
+    let run = fixture.run(&["--null-data-out", "--json", "hit", "a.txt"]);
+    run.assert_code(0);
+    assert!(!run.stdout().contains('\0'), "{:?}", run.stdout());
+    assert!(run.lines().len() >= 2);
+}
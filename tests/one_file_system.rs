@@ -0,0 +1,12 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn same_device_tree_is_searched_normally() {
+    let fixture = Fixture::new();
+    fixture.write("dir/a", "hit\n");
+    fixture.write("dir/sub/b", "hit\n");
+    let run = fixture.run(&["-r", "--one-file-system", "--sort", "path", "hit", "dir"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["dir/a:1:hit", "dir/sub/b:1:hit"]);
+}
@@ -0,0 +1,44 @@
+// -i、-c、-o、-n、-N 以及它们之间的互斥和覆盖
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "Foo\nbar foo\n";
+
+fn lines(args: &[&str]) -> Vec<String> {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(args, INPUT);
+    run.assert_code(0);
+    run.lines()
+}
+
+#[test]
+fn ignore_case_only_matching_and_line_numbers() {
+    assert_eq!(lines(&["-i", "-o", "-n", "foo"]), ["1:Foo", "2:foo"]);
+    assert_eq!(lines(&["--ignore-case", "foo"]), ["1:Foo", "2:bar foo"]);
+}
+
+#[test]
+fn count_wins_over_only_matching() {
+    assert_eq!(lines(&["-c", "foo"]), ["1"]);
+    assert_eq!(lines(&["-i", "-c", "-o", "foo"]), ["2"]);
+}
+
+#[test]
+fn last_line_number_flag_wins() {
+    assert_eq!(lines(&["-n", "-N", "foo"]), ["bar foo"]);
+    assert_eq!(lines(&["-N", "-n", "foo"]), ["2:bar foo"]);
+    assert_eq!(lines(&["-o", "-N", "foo"]), ["foo"]);
+    assert_eq!(lines(&["--no-line-number", "foo"]), ["bar foo"]);
+}
+
+#[test]
+fn only_matching_conflicts_with_invert() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-o", "-v", "foo"], INPUT);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("cannot be used with"),
+        "{}",
+        run.stderr()
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn prints_the_whole_paragraph() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--paragraph", "hit"], "a1\na2 hit\na3\n\nb1\nb2\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1-a1", "2:a2 hit", "3-a3"]);
+}
+
+#[test]
+fn last_paragraph_without_trailing_blank_line() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--paragraph", "hit"], "a\n\nb1 hit\nb2");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:b1 hit", "4-b2"]);
+}
+
+#[test]
+fn adjacent_matching_paragraphs_are_separated() {
+    let fixture = Fixture::new();
+    let input = "a hit\n\nb hit\nb2\n\nc\n\nd hit\n";
+    let run = fixture.run_stdin(&["--paragraph", "hit"], input);
+    run.assert_code(0);
+    assert_eq!(
+        run.lines(),
+        ["1:a hit", "--", "3:b hit", "4-b2", "--", "8:d hit"]
+    );
+}
+
+#[test]
+fn several_matches_in_one_paragraph_print_it_once() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--paragraph", "hit"], "x\nhit 1\nhit 2\n\ny\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1-x", "2:hit 1", "3:hit 2"]);
+}
+
+#[test]
+fn whitespace_only_lines_end_a_paragraph() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--paragraph", "hit"], "a\n \t\nb hit\n\n\nc\n");
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:b hit"]);
+}
+
+#[test]
+fn no_match_exits_one() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--paragraph", "hit"], "a\n\nb\n");
+    run.assert_code(1);
+    assert_eq!(run.stdout(), "");
+}
@@ -0,0 +1,60 @@
+// --parallel-file：结果与顺序搜索相同；-q 和上下文参数时退回顺序搜索
+mod common;
+use common::Fixture;
+
+// 几千行、匹配分散在整个文件中，足以切成多块
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let contents: String = (0..5000)
+        .map(|i| match i % 97 {
+            0 => format!("{i} hit\n"),
+            _ => format!("{i} filler line\n"),
+        })
+        .collect();
+    fixture.write("big.txt", contents);
+    fixture
+}
+
+#[test]
+fn results_match_the_sequential_search() {
+    let fixture = fixture();
+    let sequential = fixture.run(&["hit", "big.txt"]);
+    sequential.assert_code(0);
+    let parallel = fixture.run(&["--parallel-file", "hit", "big.txt"]);
+    parallel.assert_code(0);
+    assert_eq!(parallel.stdout(), sequential.stdout());
+    assert_eq!(parallel.lines()[1], "98:97 hit");
+    let parallel = fixture.run(&["--parallel-file", "-m", "3", "hit", "big.txt"]);
+    assert_eq!(parallel.lines(), ["1:0 hit", "98:97 hit", "195:194 hit"]);
+}
+
+#[test]
+fn quiet_searches_sequentially() {
+    let fixture = fixture();
+    let run = fixture.run(&["--debug", "-q", "--parallel-file", "hit", "big.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "");
+    assert!(
+        run.stderr()
+            .contains("big.txt: searching sequentially (-q)"),
+        "{}",
+        run.stderr()
+    );
+    let run = fixture.run(&["-q", "--parallel-file", "nowhere", "big.txt"]);
+    run.assert_code(1);
+    assert_eq!(run.stdout(), "");
+}
+
+#[test]
+fn context_searches_sequentially() {
+    let fixture = fixture();
+    let sequential = fixture.run(&["-C", "1", "hit", "big.txt"]);
+    let run = fixture.run(&["--debug", "-C", "1", "--parallel-file", "hit", "big.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), sequential.stdout());
+    assert!(
+        run.stderr().contains("searching sequentially (-A/-B/-C)"),
+        "{}",
+        run.stderr()
+    );
+}
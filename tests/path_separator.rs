@@ -0,0 +1,80 @@
+// --path-separator：只改变输出中的路径，前缀、标题、-l、JSON 和错误消息中的路径都一样替换
+mod common;
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a/b/c.txt", "hit\nctx\n");
+    fixture
+}
+
+#[test]
+fn prefixes_use_the_separator() {
+    let fixture = fixture();
+    let run = fixture.run(&["-r", "--path-separator", "\\", "-A", "1", "hit", "a"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["a\\b\\c.txt:1:hit", "a\\b\\c.txt-2-ctx"]);
+    // 没有给出路径时遍历 ./，开头的 . 之后也替换
+    let run = fixture.run(&["-r", "--path-separator", "\\", "hit"]);
+    assert_eq!(run.lines(), [".\\a\\b\\c.txt:1:hit"]);
+    let run = fixture.run(&["-r", "-c", "--path-separator", "|", "hit", "a"]);
+    assert_eq!(run.lines(), ["a|b|c.txt:1"]);
+}
+
+#[test]
+fn headings_and_file_lists_use_the_separator() {
+    let fixture = fixture();
+    let run = fixture.run(&["-r", "--heading", "--path-separator", "\\", "hit", "a"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["a\\b\\c.txt", "1:hit"]);
+    let run = fixture.run(&["-r", "-l", "--path-separator", "\\", "hit", "a"]);
+    assert_eq!(run.lines(), ["a\\b\\c.txt"]);
+    let run = fixture.run(&["-r", "-L", "--path-separator", "\\", "nowhere", "a"]);
+    assert_eq!(run.lines(), ["a\\b\\c.txt"]);
+}
+
+#[test]
+fn json_paths_use_the_separator() {
+    let fixture = fixture();
+    let run = fixture.run(&["-r", "--json", "--path-separator", "\\", "hit", "a"]);
+    run.assert_code(0);
+    let record: serde_json::Value = serde_json::from_str(&run.lines()[0]).unwrap();
+    assert_eq!(record["path"], "a\\b\\c.txt");
+}
+
+#[test]
+fn error_messages_use_the_separator() {
+    let fixture = fixture();
+    let run = fixture.run(&[
+        "--path-separator",
+        "\\",
+        "hit",
+        "a/b/c.txt",
+        "a/b/missing.txt",
+    ]);
+    run.assert_code(2);
+    assert_eq!(run.lines(), ["a\\b\\c.txt:1:hit"]);
+    let stderr = run.stderr();
+    assert!(
+        stderr.contains("Failed to open a\\b\\missing.txt"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("could not be searched: a\\b\\missing.txt"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn separator_must_be_one_character() {
+    let fixture = fixture();
+    for value in ["", "ab"] {
+        let run = fixture.run(&["--path-separator", value, "hit", "a/b/c.txt"]);
+        run.assert_code(2);
+        assert!(
+            run.stderr().contains("expected a single character"),
+            "{value:?}: {}",
+            run.stderr()
+        );
+    }
+}
@@ -0,0 +1,46 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn positional_pattern_error_points_at_user_text() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-i", "a(b"], "x\n");
+    run.assert_code(2);
+    assert_eq!(
+        run.stderr(),
+        "Application error in the pattern argument:\n\
+         regex parse error:\n    a(b\n     ^\nerror: unclosed group\n"
+    );
+}
+
+#[test]
+fn regexp_error_names_the_pattern_number() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["-e", "foo", "-e", "b("], "x\n");
+    run.assert_code(2);
+    assert!(
+        run.stderr()
+            .starts_with("Application error in -e pattern #2:\n"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn pattern_file_error_names_the_file_line() {
+    let fixture = Fixture::new();
+    fixture.write("patterns", "ok\n[z-\n");
+    let run = fixture.run_stdin(&["-f", "patterns"], "x\n");
+    run.assert_code(2);
+    assert!(
+        run.stderr()
+            .starts_with("Application error in patterns line 2:\n"),
+        "{}",
+        run.stderr()
+    );
+    assert!(
+        run.stderr().contains("    [z-\n    ^\n"),
+        "{}",
+        run.stderr()
+    );
+}
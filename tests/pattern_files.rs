@@ -0,0 +1,60 @@
+// -e 和 -f：可以重复给出，所有模式一起编译，任何一个匹配即可
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "a.b\naxb\nq\nabcd\nnone\n";
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("in.txt", INPUT);
+    fixture
+}
+
+#[test]
+fn repeated_regexps_match_any_of_them() {
+    let fixture = fixture();
+    let run = fixture.run(&["-e", "q", "-e", "^none", "in.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:q", "5:none"]);
+}
+
+#[test]
+fn pattern_files_combine_with_regexps() {
+    let fixture = fixture();
+    fixture.write("one", "q\n");
+    fixture.write("two", "none\nbcd\n");
+    let run = fixture.run(&["-f", "one", "-f", "two", "in.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:q", "4:abcd", "5:none"]);
+    let run = fixture.run(&["-f", "one", "-e", "axb", "in.txt"]);
+    assert_eq!(run.lines(), ["2:axb", "3:q"]);
+}
+
+#[test]
+fn an_empty_pattern_file_matches_nothing() {
+    let fixture = fixture();
+    fixture.write("empty", "");
+    let run = fixture.run(&["-f", "empty", "in.txt"]);
+    run.assert_code(1);
+    assert!(run.stdout().is_empty());
+    // 与其他模式一起给出时不影响它们
+    let run = fixture.run(&["-f", "empty", "-e", "q", "in.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["3:q"]);
+}
+
+#[test]
+fn comments_blank_lines_and_continuations() {
+    let fixture = fixture();
+    fixture.write(
+        "patterns",
+        "# comment\n\n   # indented comment\nax\\\nb\nq\n",
+    );
+    let run = fixture.run(&["--file-pattern-comments", "-f", "patterns", "in.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:axb", "3:q"]);
+    // 不加 --file-pattern-comments 时每一行都是模式，结尾的 \ 是不完整的转义
+    let run = fixture.run(&["-f", "patterns", "in.txt"]);
+    run.assert_code(2);
+    assert!(run.stderr().contains("patterns line 4"), "{}", run.stderr());
+}
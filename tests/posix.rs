@@ -0,0 +1,40 @@
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "a+b\naab\n(ab)\nab\na{x}\n";
+
+#[test]
+fn basic_syntax_needs_backslashes() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--posix-basic", "a+b"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["1:a+b"]);
+    let run = fixture.run_stdin(&["--posix-basic", r"a\+b"], INPUT);
+    assert_eq!(run.lines(), ["2:aab", "3:(ab)", "4:ab"]);
+    let run = fixture.run_stdin(&["--posix-basic", "(ab)"], INPUT);
+    assert_eq!(run.lines(), ["3:(ab)"]);
+}
+
+#[test]
+fn extended_syntax_matches_like_egrep() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--posix-extended", "^a+b$"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2:aab", "4:ab"]);
+    // 不是区间的 { 是普通字符
+    let run = fixture.run_stdin(&["--posix-extended", "a{x}"], INPUT);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["5:a{x}"]);
+}
+
+#[test]
+fn back_references_are_pattern_errors() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--posix-basic", r"\(a\)\1"], INPUT);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("back-references are not supported"),
+        "{}",
+        run.stderr()
+    );
+}
@@ -0,0 +1,75 @@
+// 前缀（文件名、行号、列号、字节位置）与主体（整行或每个匹配）互相独立
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "foo bar foo\nbaz\nxfoo\n";
+
+fn run(args: &[&str]) -> Vec<String> {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", INPUT);
+    let mut args = args.to_vec();
+    args.extend(["foo", "a.txt"]);
+    let run = fixture.run(&args);
+    run.assert_code(0);
+    run.lines()
+}
+
+#[test]
+fn only_matching_with_line_numbers() {
+    assert_eq!(run(&["-o", "-n"]), ["1:foo", "1:foo", "3:foo"]);
+}
+
+#[test]
+fn only_matching_with_filename_and_byte_offset() {
+    // -b 是每个匹配的起点在文件中的位置
+    assert_eq!(
+        run(&["-o", "-H", "-b"]),
+        ["a.txt:0:foo", "a.txt:8:foo", "a.txt:17:foo"]
+    );
+}
+
+#[test]
+fn line_numbers_alone() {
+    assert_eq!(run(&["-n"]), ["1:foo bar foo", "3:xfoo"]);
+    // 整行时行号默认就有，-N 去掉
+    assert_eq!(run(&[]), ["1:foo bar foo", "3:xfoo"]);
+    assert_eq!(run(&["-N"]), ["foo bar foo", "xfoo"]);
+}
+
+#[test]
+fn all_prefixes_keep_their_order() {
+    assert_eq!(
+        run(&["-o", "-H", "-n", "--column", "-b"]),
+        ["a.txt:1:1:0:foo", "a.txt:1:9:8:foo", "a.txt:3:2:17:foo"]
+    );
+    assert_eq!(
+        run(&["-H", "-n", "--column", "-b"]),
+        ["a.txt:1:1:0:foo bar foo", "a.txt:3:2:16:xfoo"]
+    );
+}
+
+#[test]
+fn byte_offset_and_column_together() {
+    // 整行时列号是行中第一个匹配的位置，-b 是行首的位置
+    assert_eq!(
+        run(&["-b", "--column"]),
+        ["1:1:0:foo bar foo", "3:2:16:xfoo"]
+    );
+    // -o 时两者都是每个匹配自己的位置
+    assert_eq!(
+        run(&["-b", "--column", "-o"]),
+        ["1:0:foo", "9:8:foo", "2:17:foo"]
+    );
+    // 没有匹配的行（-v、上下文）只有字节位置
+    assert_eq!(run(&["-b", "--column", "-v"]), ["2:12:baz"]);
+    assert_eq!(
+        run(&["-b", "--column", "-A", "1"]),
+        ["1:1:0:foo bar foo", "2-12-baz", "3:2:16:xfoo"]
+    );
+}
+
+#[test]
+fn counting_modes_ignore_prefixes() {
+    assert_eq!(run(&["-c", "-n", "-b"]), ["2"]);
+    assert_eq!(run(&["-l", "-n", "-o"]), ["a.txt"]);
+}
@@ -0,0 +1,114 @@
+// --pre 和 --pre-glob：只有通配符选中的文件经过预处理命令，其余文件直接搜索
+mod common;
+use common::Fixture;
+
+#[test]
+fn pre_glob_requires_pre() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "hit\n");
+    let run = fixture.run(&["--pre-glob", "*.txt", "hit", "a.txt"]);
+    run.assert_code(2);
+    assert!(run.stderr().contains("--pre"), "{}", run.stderr());
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::common::Fixture;
+    use std::os::unix::fs::PermissionsExt;
+
+    // 把内容转成大写的预处理命令，用完整路径给出
+    fn fixture() -> (Fixture, String) {
+        let fixture = Fixture::new();
+        let script = fixture.write("../upper.sh", "#!/bin/sh\ntr a-z A-Z < \"$1\"\n");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        fixture.write("doc.pdf", "hit\n");
+        fixture.write("docs/deep.pdf", "hit\n");
+        fixture.write("plain.txt", "hit\n");
+        (fixture, script.display().to_string())
+    }
+
+    #[test]
+    fn only_files_matching_pre_glob_are_preprocessed() {
+        let (fixture, script) = fixture();
+        let run = fixture.run(&[
+            "-r",
+            "--sort",
+            "path",
+            "-N",
+            "--pre",
+            &script,
+            "--pre-glob",
+            "*.pdf",
+            "-i",
+            "hit",
+        ]);
+        run.assert_code(0);
+        assert_eq!(
+            run.lines(),
+            ["./doc.pdf:HIT", "./docs/deep.pdf:HIT", "./plain.txt:hit"]
+        );
+    }
+
+    #[test]
+    fn pre_glob_uses_include_semantics() {
+        let (fixture, script) = fixture();
+        // 含 / 的通配符与整个路径比较，! 排除前面选中的文件
+        let run = fixture.run(&[
+            "-r",
+            "--sort",
+            "path",
+            "-N",
+            "--pre",
+            &script,
+            "--pre-glob",
+            "docs/*.pdf",
+            "-i",
+            "hit",
+        ]);
+        assert_eq!(
+            run.lines(),
+            ["./doc.pdf:hit", "./docs/deep.pdf:HIT", "./plain.txt:hit"]
+        );
+        let run = fixture.run(&[
+            "-r",
+            "--sort",
+            "path",
+            "-N",
+            "--pre",
+            &script,
+            "--pre-glob",
+            "*",
+            "--pre-glob",
+            "!*.txt",
+            "-i",
+            "hit",
+        ]);
+        assert_eq!(
+            run.lines(),
+            ["./doc.pdf:HIT", "./docs/deep.pdf:HIT", "./plain.txt:hit"]
+        );
+    }
+
+    #[test]
+    fn debug_reports_whether_the_preprocessor_ran() {
+        let (fixture, script) = fixture();
+        let run = fixture.run(&[
+            "--debug",
+            "--pre",
+            &script,
+            "--pre-glob",
+            "*.pdf",
+            "-i",
+            "hit",
+            "doc.pdf",
+            "plain.txt",
+        ]);
+        run.assert_code(0);
+        let stderr = run.stderr();
+        assert!(stderr.contains("doc.pdf: running preprocessor"), "{stderr}");
+        assert!(
+            stderr.contains("plain.txt: no --pre-glob matched, searching directly"),
+            "{stderr}"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// -q：不输出任何内容，只用退出码说明有没有选中的行
+mod common;
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "ok\nhit\n");
+    fixture.write("b.txt", "ok\n");
+    fixture
+}
+
+#[track_caller]
+fn assert_silent(run: &common::Run) {
+    assert_eq!(run.stdout(), "");
+    assert_eq!(run.stderr(), "");
+}
+
+#[test]
+fn exit_status_tells_whether_anything_matched() {
+    let fixture = fixture();
+    assert_silent(fixture.run(&["-q", "hit", "a.txt", "b.txt"]).assert_code(0));
+    assert_silent(fixture.run(&["--quiet", "hit", "b.txt"]).assert_code(1));
+    assert_silent(
+        fixture
+            .run(&["--silent", "-v", "ok", "a.txt"])
+            .assert_code(0),
+    );
+    assert_silent(fixture.run_stdin(&["-q", "hit"], "hit\n").assert_code(0));
+    assert_silent(fixture.run(&["-q", "-r", "hit"]).assert_code(0));
+}
+
+#[test]
+fn search_stops_at_the_first_selected_line() {
+    let fixture = fixture();
+    // 找到匹配之后不再打开后面的输入，所以不会报告它不存在
+    assert_silent(
+        fixture
+            .run(&["-q", "hit", "a.txt", "missing.txt"])
+            .assert_code(0),
+    );
+    let run = fixture.run(&["--debug", "-q", "hit", "a.txt", "b.txt"]);
+    run.assert_code(0);
+    assert!(
+        run.stderr()
+            .contains("-q: a.txt: found a selected line, stopping"),
+        "{}",
+        run.stderr()
+    );
+    assert!(
+        !run.stderr().contains("searching b.txt"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn a_match_wins_over_earlier_errors() {
+    let fixture = fixture();
+    // 与 grep -q 一样：有匹配就返回 0，出错的输入照常报告
+    let run = fixture.run(&["-q", "hit", "missing.txt", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "");
+    assert!(run.stderr().contains("missing.txt"), "{}", run.stderr());
+    fixture
+        .run(&["-q", "hit", "missing.txt", "b.txt"])
+        .assert_code(2);
+    assert_silent(
+        fixture
+            .run(&["-q", "-s", "hit", "missing.txt", "b.txt"])
+            .assert_code(2),
+    );
+}
+
+#[test]
+fn quiet_overrides_counting_and_listing() {
+    let fixture = fixture();
+    for flag in ["-c", "--count-matches", "-l", "-L", "--json"] {
+        let run = fixture.run(&["-q", flag, "hit", "a.txt", "b.txt"]);
+        assert_silent(run.assert_code(0));
+    }
+    for flag in ["-c", "-l"] {
+        assert_silent(fixture.run(&["-q", flag, "hit", "b.txt"]).assert_code(1));
+    }
+}
+
+#[test]
+fn quiet_works_with_threads() {
+    let fixture = fixture();
+    let run = fixture.run(&["-q", "-j", "4", "-r", "hit"]);
+    assert_silent(run.assert_code(0));
+}
+
+#[test]
+fn write_is_rejected() {
+    let fixture = fixture();
+    let run = fixture.run(&["-q", "--write", "--replace", "x", "hit", "a.txt"]);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("cannot be used with"),
+        "{}",
+        run.stderr()
+    );
+}
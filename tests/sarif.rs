@@ -0,0 +1,102 @@
+// --format sarif：输出按 SARIF 2.1.0 的 JSON Schema（tests/data 中 OASIS 发布的版本）校验，
+// 位置相对于 git 仓库的根目录，重复给出的模式只有一条规则。
+// OASIS 原版中两处 language 的 pattern 多了一个 "]"，不是合法的正则，Schema 本身通不过
+// draft-07 元 Schema 的校验；tests/data 中的副本把它改成了 "^[a-zA-Z]{2}(-[a-zA-Z]{2})?$"
+mod common;
+use common::Fixture;
+
+use serde_json::Value;
+
+// 在 work/sub 中运行，work 是仓库的根目录
+fn sarif(fixture: &Fixture, args: &[&str]) -> Value {
+    let output = fixture
+        .command()
+        .current_dir(fixture.work().join("sub"))
+        .args(["--format", "sarif"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let log: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let schema: Value = serde_json::from_str(include_str!("data/sarif-schema-2.1.0.json")).unwrap();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    let errors: Vec<String> = validator
+        .iter_errors(&log)
+        .map(|e| format!("{} at {}", e, e.instance_path()))
+        .collect();
+    assert!(errors.is_empty(), "{errors:#?}\n{log:#}");
+    log
+}
+
+fn repo() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.mkdir(".git");
+    fixture.write("sub/x.txt", "say \"foo\"\tto é bar\nnothing\n");
+    fixture.write("top.txt", "foo\n");
+    fixture
+}
+
+#[test]
+fn output_validates_against_the_schema() {
+    let fixture = repo();
+    let log = sarif(&fixture, &["-e", "foo", "-e", "b.r", "x.txt", "../top.txt"]);
+    let run = &log["runs"][0];
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    // 列号按码位计，endColumn 不在匹配内
+    assert_eq!(
+        results[1]["locations"][0]["physicalLocation"]["region"],
+        serde_json::json!({"startLine": 1, "startColumn": 16, "endColumn": 19})
+    );
+    assert_eq!(results[1]["ruleId"], "b.r");
+    assert_eq!(results[1]["ruleIndex"], 1);
+    assert_eq!(results[0]["message"]["text"], "say \"foo\"\tto é bar");
+
+    // 反向匹配的结果没有列号，也不属于任何规则
+    sarif(&fixture, &["-v", "foo", "x.txt"]);
+}
+
+#[test]
+fn uris_are_relative_to_the_repository() {
+    let fixture = repo();
+    let log = sarif(&fixture, &["foo", "x.txt", "../top.txt"]);
+    let run = &log["runs"][0];
+    let uris: Vec<&Value> = run["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| &result["locations"][0]["physicalLocation"]["artifactLocation"])
+        .collect();
+    assert_eq!(
+        uris,
+        [
+            &serde_json::json!({"uri": "sub/x.txt", "uriBaseId": "%SRCROOT%"}),
+            &serde_json::json!({"uri": "top.txt", "uriBaseId": "%SRCROOT%"}),
+        ]
+    );
+    let root = run["originalUriBaseIds"]["%SRCROOT%"]["uri"]
+        .as_str()
+        .unwrap();
+    assert!(
+        root.starts_with("file:///") && root.ends_with("/work/"),
+        "{root}"
+    );
+}
+
+#[test]
+fn repeated_patterns_are_one_rule() {
+    let fixture = repo();
+    let log = sarif(&fixture, &["-e", "foo", "-e", "foo", "-e", "bar", "x.txt"]);
+    let rules = log["runs"][0]["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap();
+    let ids: Vec<&str> = rules
+        .iter()
+        .map(|rule| rule["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["foo", "bar"]);
+    for result in log["runs"][0]["results"].as_array().unwrap() {
+        let index = result["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(rules[index]["id"], result["ruleId"]);
+    }
+}
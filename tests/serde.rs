@@ -0,0 +1,102 @@
+// 开启 serde 功能时选项、结果和统计能经 JSON 原样存取：
+//     cargo test --features serde --test serde
+#![cfg(feature = "serde")]
+
+use mrustgrep::search::{Body, Match, Options, OutputFormat, SearchResult, SearchStats, Searcher};
+use std::time::Duration;
+
+// 序列化后再读回来
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("cannot read back {json}: {e}"))
+}
+
+#[test]
+fn options_round_trip() {
+    let opts = Options {
+        show_line_number: true,
+        case_ignore: true,
+        match_only: true,
+        max_line_length: Some(80),
+        ..Options::default()
+    };
+    let json = serde_json::to_value(&opts).unwrap();
+    let back = round_trip(&opts);
+    assert_eq!(serde_json::to_value(&back).unwrap(), json);
+    assert!(back.show_line_number && back.case_ignore && back.match_only);
+    assert_eq!(back.max_line_length, Some(80));
+}
+
+#[test]
+fn missing_option_fields_take_defaults() {
+    // 以前保存的选项缺少后来增加的字段
+    let opts: Options = serde_json::from_str(r#"{"case_ignore": true}"#).unwrap();
+    assert!(opts.case_ignore);
+    assert!(!opts.count_only);
+    assert_eq!(OutputFormat::from(&opts).body, Body::Line);
+}
+
+#[test]
+fn results_round_trip() {
+    let opts = Options {
+        case_ignore: true,
+        ..Options::default()
+    };
+    let searcher = Searcher::new("foo", opts).unwrap();
+    let results = searcher
+        .search_str("a foo\nbar\nFOO foo\n")
+        .collect::<anyhow::Result<Vec<SearchResult>>>()
+        .unwrap();
+    let back: Vec<SearchResult> = round_trip(&results);
+    assert_eq!(back.len(), 2);
+    for (result, back) in results.iter().zip(&back) {
+        assert_eq!(back.line_number, result.line_number);
+        assert_eq!(back.byte_offset, result.byte_offset);
+        assert_eq!(back.byte_len, result.byte_len);
+        assert_eq!(back.line, result.line);
+        assert_eq!(back.matches, result.matches);
+        assert_eq!(back.context, result.context);
+    }
+    assert_eq!(
+        back[1].matches,
+        [
+            Match {
+                start: 0,
+                end: 3,
+                pattern: 0,
+                distance: 0
+            },
+            Match {
+                start: 4,
+                end: 7,
+                pattern: 0,
+                distance: 0
+            },
+        ]
+    );
+}
+
+#[test]
+fn match_without_distance_reads_as_exact() {
+    let found: Match = serde_json::from_str(r#"{"start": 1, "end": 4, "pattern": 0}"#).unwrap();
+    assert_eq!(found.distance, 0);
+}
+
+#[test]
+fn stats_and_format_round_trip() {
+    let stats = SearchStats {
+        lines_read: 10,
+        bytes_read: 120,
+        lines_matched: 2,
+        matches: 3,
+        elapsed: Some(Duration::from_millis(15)),
+    };
+    assert_eq!(round_trip(&stats), stats);
+    let format = OutputFormat {
+        line_number: true,
+        byte_offset: false,
+        column: true,
+        body: Body::Snippet(5),
+    };
+    assert_eq!(round_trip(&format), format);
+}
@@ -0,0 +1,52 @@
+// 终端探测推导出的默认行为：输出到管道时默认不带颜色，显式的 --color 总是优先
+mod common;
+
+use common::Fixture;
+
+const INPUT: &str = "alpha\nbeta\nalphabet\n";
+
+#[test]
+fn piped_output_has_no_escapes_by_default() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["alpha"], INPUT);
+    run.assert_code(0);
+    assert!(!run.output.stdout.contains(&0x1b), "{:?}", run.stdout());
+    assert_eq!(run.lines(), ["1:alpha", "3:alphabet"]);
+}
+
+#[test]
+fn color_always_wins_over_a_pipe() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(&["--color=always", "alpha"], INPUT);
+    run.assert_code(0);
+    assert!(run.output.stdout.contains(&0x1b));
+}
+
+#[test]
+fn no_color_does_not_override_color_always() {
+    let fixture = Fixture::new();
+    let run = common::run_with_stdin(
+        fixture
+            .command()
+            .env("NO_COLOR", "1")
+            .args(["--color=always", "alpha"]),
+        INPUT.as_bytes(),
+    );
+    assert!(run.output.stdout.contains(&0x1b));
+}
+
+#[test]
+fn dumb_terminal_and_no_color_keep_auto_plain() {
+    let fixture = Fixture::new();
+    for (key, value) in [("TERM", "dumb"), ("NO_COLOR", "1")] {
+        let run = common::run_with_stdin(
+            fixture
+                .command()
+                .env(key, value)
+                .args(["--color=auto", "alpha"]),
+            INPUT.as_bytes(),
+        );
+        run.assert_code(0);
+        assert!(!run.output.stdout.contains(&0x1b));
+    }
+}
@@ -0,0 +1,139 @@
+// -j/--threads：文件交给工作线程提前搜索，输出与顺序搜索完全相同
+mod common;
+use common::Fixture;
+
+// 几十个大小不一的文件，后面的文件往往比前面的先搜索完
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for n in 0..40 {
+        let contents = format!(
+            "hit {n}\n{}miss\nhit again\n",
+            "filler\n".repeat((40 - n) * 200)
+        );
+        fixture.write(&format!("src/d{}/f{n:02}.txt", n % 3), &contents);
+    }
+    fixture
+}
+
+fn same_as_sequential(fixture: &Fixture, args: &[&str]) {
+    let sequential = fixture.run(&[&["-j", "1"], args].concat());
+    for threads in ["4", "0"] {
+        let parallel = fixture.run(&[&["-j", threads], args].concat());
+        assert_eq!(
+            parallel.output.status, sequential.output.status,
+            "-j {threads} {args:?}"
+        );
+        assert_eq!(
+            parallel.stdout(),
+            sequential.stdout(),
+            "-j {threads} {args:?}"
+        );
+        assert_eq!(
+            parallel.stderr(),
+            sequential.stderr(),
+            "-j {threads} {args:?}"
+        );
+    }
+}
+
+#[test]
+fn output_is_in_walk_order() {
+    let fixture = tree();
+    same_as_sequential(&fixture, &["-r", "hit", "src"]);
+    same_as_sequential(&fixture, &["-r", "--sort", "path", "hit", "src"]);
+    same_as_sequential(&fixture, &["-r", "--heading", "-A", "1", "again", "src"]);
+    same_as_sequential(&fixture, &["-r", "-m", "1", "hit", "src"]);
+}
+
+#[test]
+fn counts_and_file_lists_are_in_walk_order() {
+    let fixture = tree();
+    same_as_sequential(&fixture, &["-r", "-c", "hit", "src"]);
+    same_as_sequential(&fixture, &["-r", "-l", "again", "src"]);
+    same_as_sequential(&fixture, &["-r", "-L", "again", "src"]);
+    same_as_sequential(&fixture, &["-r", "-c", "nowhere", "src"]);
+}
+
+#[test]
+fn errors_are_reported_in_place() {
+    let fixture = tree();
+    let args = ["hit", "src/d0/f00.txt", "missing.txt", "src/d1/f01.txt"];
+    same_as_sequential(&fixture, &args);
+    let run = fixture.run(&[&["-j", "4"], &args[..]].concat());
+    run.assert_code(2);
+    assert_eq!(
+        run.lines(),
+        [
+            "src/d0/f00.txt:1:hit 0",
+            "src/d0/f00.txt:8003:hit again",
+            "src/d1/f01.txt:1:hit 1",
+            "src/d1/f01.txt:7803:hit again",
+        ]
+    );
+    assert!(run.stderr().contains("missing.txt"), "{}", run.stderr());
+}
+
+#[test]
+fn max_total_matches_stops_the_search() {
+    let fixture = tree();
+    same_as_sequential(
+        &fixture,
+        &[
+            "-r",
+            "--sort",
+            "path",
+            "--max-total-matches",
+            "5",
+            "hit",
+            "src",
+        ],
+    );
+    let run = fixture.run(&[
+        "-j",
+        "4",
+        "-r",
+        "--sort",
+        "path",
+        "--max-total-matches",
+        "5",
+        "hit",
+        "src",
+    ]);
+    run.assert_code(0);
+    assert_eq!(run.lines().len(), 5);
+}
+
+#[test]
+fn zero_means_one_thread_per_cpu() {
+    let fixture = tree();
+    let run = fixture.run(&["--debug", "-j", "0", "-r", "hit", "src"]);
+    run.assert_code(0);
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    assert!(
+        run.stderr()
+            .contains(&format!("searching files on {cpus} threads")),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn sequential_modes_reject_threads() {
+    let fixture = tree();
+    for flags in [
+        &["--follow"][..],
+        &["--write", "--replace", "x"],
+        &["--parallel-file"],
+        &["--binary-offsets"],
+    ] {
+        let flag = flags[0];
+        let run = fixture.run(&[&["-j", "2"], flags, &["hit", "src/d0/f00.txt"]].concat());
+        run.assert_code(2);
+        assert!(
+            run.stderr()
+                .contains(&format!("--threads cannot be used with {flag}")),
+            "{flag}: {}",
+            run.stderr()
+        );
+    }
+}
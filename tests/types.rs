@@ -0,0 +1,107 @@
+// --type 和 --type-not：选中的类型减去排除的类型，再与 --include/--exclude 组合
+mod common;
+use common::Fixture;
+
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for path in [
+        "src/main.rs",
+        "src/a.c",
+        "src/a.cpp",
+        "src/a.h",
+        "docs/guide.md",
+        "tests/fixtures/data.json",
+        "Cargo.toml",
+        "Makefile",
+    ] {
+        fixture.write(path, "hit\n");
+    }
+    fixture
+}
+
+fn searched(fixture: &Fixture, args: &[&str]) -> Vec<String> {
+    let mut all = vec!["-r", "-l", "--sort", "path"];
+    all.extend(args);
+    all.push("hit");
+    fixture
+        .run(&all)
+        .lines()
+        .iter()
+        .map(|line| line.replace('\\', "/").trim_start_matches("./").to_string())
+        .collect()
+}
+
+#[test]
+fn type_not_skips_the_named_types() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["--type-not", "md", "--type-not", "json"]),
+        [
+            "Cargo.toml",
+            "Makefile",
+            "src/a.c",
+            "src/a.cpp",
+            "src/a.h",
+            "src/main.rs"
+        ]
+    );
+}
+
+#[test]
+fn a_file_of_both_a_selected_and_an_excluded_type_is_skipped() {
+    let fixture = tree();
+    // a.h 属于 c 和 cpp
+    assert_eq!(searched(&fixture, &["-t", "cpp", "-T", "c"]), ["src/a.cpp"]);
+    assert_eq!(searched(&fixture, &["-t", "cpp"]), ["src/a.cpp", "src/a.h"]);
+}
+
+#[test]
+fn include_and_exclude_compose_with_types() {
+    let fixture = tree();
+    // --include 明确包含的文件不受类型限制
+    assert_eq!(
+        searched(&fixture, &["-T", "md", "--include", "*.md"]),
+        ["docs/guide.md"]
+    );
+    // --exclude 在类型选中的文件中再去掉一部分
+    assert_eq!(
+        searched(&fixture, &["-t", "c", "-t", "cpp", "--exclude", "*.h"]),
+        ["src/a.c", "src/a.cpp"]
+    );
+    assert_eq!(
+        searched(&fixture, &["-T", "json", "--exclude", "src"]),
+        ["Cargo.toml", "Makefile", "docs/guide.md"]
+    );
+}
+
+#[test]
+fn unknown_types_are_an_error_listing_the_available_ones() {
+    let fixture = tree();
+    let run = fixture.run(&["-r", "--type-not", "markdown", "hit"]);
+    run.assert_code(2);
+    let stderr = run.stderr();
+    assert!(
+        stderr.contains("unknown file type \"markdown\""),
+        "{stderr}"
+    );
+    assert!(stderr.contains("available: c, cpp,"), "{stderr}");
+}
+
+#[test]
+fn completions_offer_the_type_names() {
+    let fixture = tree();
+    let run = fixture.run(&["--generate-completions", "bash"]);
+    run.assert_code(0);
+    let script = run.stdout();
+    for option in ["--type", "--type-not"] {
+        let case = script
+            .split(&format!("                {option})\n"))
+            .nth(1)
+            .unwrap_or_else(|| panic!("no completion for {option}"));
+        let reply = case.lines().next().unwrap();
+        assert!(
+            reply.contains("compgen -W \"c cpp ") && reply.contains(" rust "),
+            "{option}: {reply}"
+        );
+    }
+}
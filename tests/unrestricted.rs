@@ -0,0 +1,160 @@
+// 递归时默认跳过的隐藏文件和二进制文件，--hidden、-a/--text，以及 -u/-uu/-uuu 的展开
+mod common;
+use common::Fixture;
+
+// 每个文件都含有 hit
+fn tree() -> Fixture {
+    let fixture = Fixture::new();
+    for path in ["a.txt", "ignored.txt", ".hidden.txt", ".dir/b.txt"] {
+        fixture.write(path, "hit\n");
+    }
+    fixture.write("bin.dat", "hit\0\x01\n");
+    fixture.write(".gitignore", "ignored.txt\n");
+    fixture
+}
+
+// 列出被搜索到的文件
+fn searched(fixture: &Fixture, args: &[&str]) -> Vec<String> {
+    let mut all = vec!["-r", "-l", "--sort", "path"];
+    all.extend(args);
+    all.push("hit");
+    let run = fixture.run(&all);
+    run.assert_code(0);
+    run.lines()
+        .iter()
+        .map(|line| line.trim_start_matches("./").to_string())
+        .collect()
+}
+
+#[test]
+fn hidden_and_binary_files_are_skipped_by_default() {
+    let fixture = tree();
+    assert_eq!(searched(&fixture, &[]), ["a.txt"]);
+    // -j 时工作线程上搜索的文件同样跳过二进制文件
+    assert_eq!(searched(&fixture, &["-j", "2"]), ["a.txt"]);
+    assert_eq!(
+        searched(&fixture, &["--hidden"]),
+        [".dir/b.txt", ".hidden.txt", "a.txt"]
+    );
+    assert_eq!(searched(&fixture, &["-a"]), ["a.txt", "bin.dat"]);
+    assert_eq!(searched(&fixture, &["--hidden", "--no-hidden"]), ["a.txt"]);
+    assert_eq!(searched(&fixture, &["--text", "--no-text"]), ["a.txt"]);
+}
+
+#[test]
+fn skipped_files_show_up_under_debug() {
+    let fixture = tree();
+    let run = fixture.run(&["-r", "--debug", "hit"]);
+    run.assert_code(0);
+    assert!(
+        run.stderr()
+            .contains("skipping ./.dir (hidden, --hidden to search it)"),
+        "{}",
+        run.stderr()
+    );
+    assert!(
+        run.stderr()
+            .contains("skipping ./bin.dat (binary, -a/--text to search it)"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn hidden_paths_on_the_command_line_are_searched() {
+    let fixture = tree();
+    let run = fixture.run(&["-H", "hit", ".hidden.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), [".hidden.txt:1:hit"]);
+}
+
+// 命令行上给出的二进制文件和标准输入照常搜索，逐行输出时只报告一行，-c 等照常计数
+#[test]
+fn named_binary_inputs_report_a_match() {
+    let fixture = tree();
+    for args in [&["hit", "bin.dat"][..], &["-j", "2", "hit", "bin.dat"]] {
+        let run = fixture.run(args);
+        run.assert_code(0);
+        assert_eq!(
+            run.lines(),
+            [r#"binary file matches (found "\0" byte around offset 3)"#],
+            "{args:?}"
+        );
+    }
+    let run = fixture.run(&["-C1", "hit", "a.txt", "bin.dat"]);
+    run.assert_code(0);
+    assert_eq!(
+        run.lines(),
+        [
+            "a.txt:1:hit",
+            r#"bin.dat: binary file matches (found "\0" byte around offset 3)"#
+        ]
+    );
+    fixture.run(&["miss", "bin.dat"]).assert_code(1);
+
+    let input = "a\0b\nhit\nhit\n";
+    let run = fixture.run_stdin(&["hit"], input);
+    run.assert_code(0);
+    assert_eq!(
+        run.lines(),
+        [r#"binary file matches (found "\0" byte around offset 1)"#]
+    );
+    let run = fixture.run_stdin(&["-c", "hit"], input);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["2"]);
+    let run = fixture.run_stdin(&["-a", "hit"], input);
+    assert_eq!(run.lines(), ["2:hit", "3:hit"]);
+}
+
+#[test]
+fn unrestricted_expands_step_by_step() {
+    let fixture = tree();
+    assert_eq!(searched(&fixture, &["-u"]), ["a.txt", "ignored.txt"]);
+    assert_eq!(
+        searched(&fixture, &["-uu"]),
+        [".dir/b.txt", ".hidden.txt", "a.txt", "ignored.txt"]
+    );
+    assert_eq!(
+        searched(&fixture, &["-uuu"]),
+        [
+            ".dir/b.txt",
+            ".hidden.txt",
+            "a.txt",
+            "bin.dat",
+            "ignored.txt"
+        ]
+    );
+    let run = fixture.run(&["-r", "--debug", "-uuu", "hit"]);
+    assert!(
+        run.stderr()
+            .contains("mrustgrep[debug]: -uuu expands to --no-ignore --hidden --text\n"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[test]
+fn explicit_flags_after_unrestricted_win() {
+    let fixture = tree();
+    assert_eq!(
+        searched(&fixture, &["-uuu", "--no-hidden", "--no-text"]),
+        ["a.txt", "ignored.txt"]
+    );
+    // 反向开关在 -u 之前时不起作用
+    assert_eq!(
+        searched(&fixture, &["--no-hidden", "-uu"]),
+        [".dir/b.txt", ".hidden.txt", "a.txt", "ignored.txt"]
+    );
+    assert_eq!(
+        searched(&fixture, &["-uu", "--ignore"]),
+        [".dir/b.txt", ".hidden.txt", "a.txt"]
+    );
+    let run = fixture.run(&["-r", "--debug", "-uu", "--no-hidden", "hit"]);
+    assert!(
+        run.stderr().contains(
+            "mrustgrep[debug]: -uu expands to --no-ignore --hidden, overridden by a later --no-hidden\n"
+        ),
+        "{}",
+        run.stderr()
+    );
+}
@@ -0,0 +1,41 @@
+// 浏览器包装在 wasm32 上的测试，需要 wasm-bindgen-test-runner（cargo install wasm-bindgen-cli）和 node：
+//     CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//         cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use mrustgrep::wasm::Searcher;
+use serde_json::{Value, json};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// searchText 交给 JS 的数组，读回成 JSON 比较
+fn search(searcher: &Searcher, text: &str) -> Value {
+    let results = searcher.search_text(text).unwrap();
+    serde_wasm_bindgen::from_value(results).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn search_text_returns_selected_lines() {
+    let searcher = Searcher::new("err(or)?", None).unwrap();
+    let results = search(&searcher, "an error\nfine\nerr\n");
+    assert_eq!(
+        results,
+        json!([
+            {"lineNumber": 1, "line": "an error", "matches": [{"start": 3, "end": 8, "pattern": 0, "distance": 0}]},
+            {"lineNumber": 3, "line": "err", "matches": [{"start": 0, "end": 3, "pattern": 0, "distance": 0}]},
+        ])
+    );
+}
+
+#[wasm_bindgen_test]
+fn options_are_json() {
+    let searcher = Searcher::new("error", Some(r#"{"case_ignore": true}"#.into())).unwrap();
+    assert_eq!(search(&searcher, "ERROR\nok\n")[0]["line"], "ERROR");
+    let searcher = Searcher::new("ok", Some(r#"{"invert_match": true}"#.into())).unwrap();
+    assert_eq!(search(&searcher, "ERROR\nok\n")[0]["line"], "ERROR");
+}
+
+#[wasm_bindgen_test]
+fn bad_patterns_and_options_throw() {
+    assert!(Searcher::new("(", None).is_err());
+    assert!(Searcher::new("a", Some("{".into())).is_err());
+}
@@ -0,0 +1,89 @@
+// --watch：参数检查，以及 --output 时每一轮都追加到同一个输出文件中
+mod common;
+use common::Fixture;
+
+#[test]
+fn quiet_and_stdin_are_rejected() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "hit\n");
+    let run = fixture.run(&["--watch", "-q", "hit", "a.txt"]);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("cannot be used with"),
+        "{}",
+        run.stderr()
+    );
+    let run = fixture.run(&["--watch", "hit", "-"]);
+    run.assert_code(2);
+    assert!(
+        run.stderr().contains("--watch cannot watch stdin"),
+        "{}",
+        run.stderr()
+    );
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::common::Fixture;
+    use std::{
+        fs,
+        path::Path,
+        process::{Command, Stdio},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    // 等到 path 的内容满足 done，最多等 10 秒，返回最后读到的内容
+    // 这里不断读取输出文件，产生的访问事件也不能推迟 --watch 的下一轮
+    fn wait_for(path: &Path, done: impl Fn(&str) -> bool) -> String {
+        let started = Instant::now();
+        loop {
+            let contents = fs::read_to_string(path).unwrap_or_default();
+            if done(&contents) || started.elapsed() > Duration::from_secs(10) {
+                return contents;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    // 在 out 指定的位置写结果，修改一次被监视的文件，然后用 SIGINT 结束
+    fn watch_round_trip(out: &str) {
+        let fixture = Fixture::new();
+        fixture.write("src/a.txt", "hit 1\n");
+        let out = fixture.work().join(out);
+        let child = fixture
+            .command()
+            .args(["--watch", "-r", "-N", "--output"])
+            .arg(&out)
+            .args(["hit", "src"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        wait_for(&out, |contents| contents.contains("hit 1"));
+        fixture.write("src/a.txt", "hit 1\nhit 2\n");
+        let contents = wait_for(&out, |contents| contents.contains("hit 2"));
+        Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        // 第一轮的结果没有被后面的一轮截断，分隔行也写在输出文件中
+        assert_eq!(
+            contents,
+            "src/a.txt:hit 1\n--- changed: src/a.txt ---\nsrc/a.txt:hit 1\nsrc/a.txt:hit 2\n"
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    }
+
+    #[test]
+    fn every_round_goes_to_the_same_output_file() {
+        watch_round_trip("../out.txt");
+    }
+
+    #[test]
+    fn an_output_file_inside_the_watched_tree_does_not_retrigger() {
+        watch_round_trip("src/out.txt");
+    }
+}
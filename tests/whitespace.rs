@@ -0,0 +1,40 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn trailing_spaces_and_tabs_are_kept() {
+    let fixture = Fixture::new();
+    fixture.write("input", "a \nb\t\nc\n");
+    let run = fixture.run(&["\\s$", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "1:a \n2:b\t\n");
+}
+
+#[test]
+fn only_the_crlf_terminator_is_stripped() {
+    let fixture = Fixture::new();
+    fixture.write("input", "a \r\nb\r\n");
+    let run = fixture.run(&["a", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.output.stdout, b"1:a \n");
+}
+
+#[test]
+fn highlight_covers_the_trailing_whitespace() {
+    let fixture = Fixture::new();
+    fixture.write("input", "ab  \n");
+    let run = fixture.run(&["--color=always", " +$", "input"]);
+    run.assert_code(0);
+    // 高亮包住行尾的两个空格，之后直接是换行
+    let stdout = run.stdout();
+    assert_eq!(stdout.split_once(':').unwrap().1, "ab\x1b[1;31m  \x1b[0m\n");
+}
+
+#[test]
+fn only_matching_keeps_matched_whitespace() {
+    let fixture = Fixture::new();
+    fixture.write("input", "x\t \n");
+    let run = fixture.run(&["-o", "\\s+$", "input"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout(), "\t \n");
+}
@@ -0,0 +1,53 @@
+// -w 和 --word-boundary：é、日 是否算单词字符决定了结果
+mod common;
+use common::Fixture;
+
+const INPUT: &str = "naïve\nna_1\nna1\nna-1\n日本語\n日本 語\n";
+
+fn selected(args: &[&str]) -> Vec<String> {
+    let fixture = Fixture::new();
+    let mut all = vec!["-N"];
+    all.extend(args);
+    fixture.run_stdin(&all, INPUT).lines()
+}
+
+#[test]
+fn word_boundaries_are_unicode_by_default() {
+    assert_eq!(selected(&["-w", "na"]), ["na-1"]);
+    assert_eq!(selected(&["-w", "日本"]), ["日本 語"]);
+    assert_eq!(
+        selected(&["-w", "--word-boundary", "unicode", "na"]),
+        ["na-1"]
+    );
+}
+
+#[test]
+fn ascii_word_boundaries_split_at_non_ascii_letters() {
+    assert_eq!(
+        selected(&["-w", "--word-boundary", "ascii", "na"]),
+        ["naïve", "na-1"]
+    );
+    assert_eq!(
+        selected(&["-w", "--word-boundary", "ascii", "日本"]),
+        ["日本語", "日本 語"]
+    );
+}
+
+#[test]
+fn fixed_strings_and_several_patterns_are_wrapped_each() {
+    assert_eq!(
+        selected(&["-w", "-F", "-e", "na-1", "-e", "日本"]),
+        ["na-1", "日本 語"]
+    );
+}
+
+#[test]
+fn only_matching_reports_the_word_itself() {
+    let fixture = Fixture::new();
+    let run = fixture.run_stdin(
+        &["-w", "-o", "--word-boundary", "ascii", "caf"],
+        "café caf cafe\n",
+    );
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["caf", "caf"]);
+}
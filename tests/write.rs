@@ -0,0 +1,41 @@
+// --write：把 --replace 的结果写回文件；与不输出整行或只输出部分行的参数冲突
+mod common;
+use common::Fixture;
+
+#[test]
+fn replacements_are_written_back() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "pre\nhit here\n");
+    let run = fixture.run(&["--write", "--replace", "X", "hit", "a.txt"]);
+    run.assert_code(0);
+    assert_eq!(run.lines(), ["a.txt: 1 replacement(s) on 1 line(s)"]);
+    assert_eq!(
+        std::fs::read_to_string(fixture.work().join("a.txt")).unwrap(),
+        "pre\nX here\n"
+    );
+}
+
+#[test]
+fn context_options_conflict() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "pre\nhit here\n");
+    for (args, name) in [
+        (["-A", "1"], "--after-context"),
+        (["-B", "1"], "--before-context"),
+        (["-C", "1"], "--context"),
+    ] {
+        let run =
+            fixture.run(&[&["--write", "--replace", "X"], &args[..], &["hit", "a.txt"]].concat());
+        run.assert_code(2);
+        assert!(
+            run.stderr().contains("cannot be used with") && run.stderr().contains(name),
+            "{args:?}: {}",
+            run.stderr()
+        );
+    }
+    // 文件没有被改写
+    assert_eq!(
+        std::fs::read_to_string(fixture.work().join("a.txt")).unwrap(),
+        "pre\nhit here\n"
+    );
+}